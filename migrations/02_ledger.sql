@@ -0,0 +1,39 @@
+-- Create ledger_entries table holding the double-entry postings behind every transaction
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts(id),
+    system_account VARCHAR(50),
+    entry_type VARCHAR(6) NOT NULL,
+    amount BIGINT NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK ((account_id IS NULL) <> (system_account IS NULL)),
+    CHECK (entry_type IN ('debit', 'credit')),
+    CHECK (amount > 0)
+);
+
+-- Backfill postings for transactions completed before the ledger existed
+INSERT INTO ledger_entries (transaction_id, account_id, system_account, entry_type, amount, currency, created_at)
+SELECT id, NULL, 'deposit_clearing', 'debit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'deposit'
+UNION ALL
+SELECT id, destination_account_id, NULL, 'credit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'deposit'
+UNION ALL
+SELECT id, source_account_id, NULL, 'debit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'withdrawal'
+UNION ALL
+SELECT id, NULL, 'withdrawal_clearing', 'credit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'withdrawal'
+UNION ALL
+SELECT id, source_account_id, NULL, 'debit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'transfer'
+UNION ALL
+SELECT id, destination_account_id, NULL, 'credit', amount, currency, updated_at
+FROM transactions WHERE status = 'completed' AND transaction_type = 'transfer';
+
+-- Create indices
+CREATE INDEX idx_ledger_entries_transaction_id ON ledger_entries(transaction_id);
+CREATE INDEX idx_ledger_entries_account_id ON ledger_entries(account_id);
+CREATE INDEX idx_ledger_entries_system_account ON ledger_entries(system_account);
@@ -0,0 +1,15 @@
+-- Create idempotency_keys table storing the first response for each client-supplied key
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_path VARCHAR(255) NOT NULL,
+    request_body JSONB NOT NULL,
+    response_status SMALLINT,
+    response_body JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (user_id, idempotency_key)
+);
+
+-- Create indices
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
@@ -0,0 +1,21 @@
+-- Track funds reserved by pending authorizations
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS held_balance BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD CONSTRAINT accounts_held_balance_check CHECK (held_balance >= 0);
+
+-- Create transaction_holds table for authorize/capture/void flows
+CREATE TABLE IF NOT EXISTS transaction_holds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transactions(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    amount BIGINT NOT NULL,
+    captured_amount BIGINT,
+    status VARCHAR(20) NOT NULL DEFAULT 'active',
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (amount > 0)
+);
+
+-- Create indices
+CREATE INDEX idx_transaction_holds_account_id ON transaction_holds(account_id);
+CREATE INDEX idx_transaction_holds_status_expires_at ON transaction_holds(status, expires_at);
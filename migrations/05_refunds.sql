@@ -0,0 +1,8 @@
+-- Link refunds to the transaction they refund and track refunded totals
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS parent_transaction_id UUID REFERENCES transactions(id);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS refunded_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE transactions ADD CONSTRAINT transactions_refunded_amount_check
+    CHECK (refunded_amount >= 0 AND refunded_amount <= amount);
+
+-- Create indices
+CREATE INDEX idx_transactions_parent_transaction_id ON transactions(parent_transaction_id);
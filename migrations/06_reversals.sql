@@ -0,0 +1,2 @@
+-- Give users a role so operators can perform privileged actions
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'user';
@@ -0,0 +1,46 @@
+-- Exchange rates for cross-currency transfers. A rate converts one unit of the
+-- base currency into the quote currency and applies within its validity window.
+CREATE TABLE IF NOT EXISTS fx_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    base_currency VARCHAR(3) NOT NULL,
+    quote_currency VARCHAR(3) NOT NULL,
+    rate NUMERIC(24, 10) NOT NULL,
+    spread_bps INTEGER NOT NULL DEFAULT 0,
+    valid_from TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    valid_to TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (base_currency <> quote_currency),
+    CHECK (rate > 0),
+    CHECK (spread_bps >= 0 AND spread_bps < 10000),
+    CHECK (valid_to IS NULL OR valid_to > valid_from)
+);
+
+-- Quotes lock a rate for a user and amount until they expire or are used
+CREATE TABLE IF NOT EXISTS fx_quotes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id),
+    fx_rate_id UUID NOT NULL REFERENCES fx_rates(id),
+    source_currency VARCHAR(3) NOT NULL,
+    destination_currency VARCHAR(3) NOT NULL,
+    source_amount BIGINT NOT NULL,
+    destination_amount BIGINT NOT NULL,
+    rate NUMERIC(24, 10) NOT NULL,
+    spread_bps INTEGER NOT NULL,
+    spread_amount BIGINT NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (source_amount > 0 AND destination_amount > 0)
+);
+
+-- Record the destination leg of cross-currency transfers
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS destination_amount BIGINT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS destination_currency VARCHAR(3);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fx_rate NUMERIC(24, 10);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fx_spread_bps INTEGER;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fx_spread_amount BIGINT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS fx_quote_id UUID REFERENCES fx_quotes(id);
+
+-- Create indices
+CREATE INDEX idx_fx_rates_pair_valid_from ON fx_rates(base_currency, quote_currency, valid_from DESC);
+CREATE INDEX idx_fx_quotes_user_id ON fx_quotes(user_id);
@@ -0,0 +1,41 @@
+-- Standing orders that repeat a transfer on a cron or fixed-interval schedule
+CREATE TABLE IF NOT EXISTS scheduled_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id),
+    source_account_id UUID NOT NULL REFERENCES accounts(id),
+    destination_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount BIGINT NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    cron_expression VARCHAR(100),
+    interval_seconds BIGINT,
+    start_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    end_at TIMESTAMP WITH TIME ZONE,
+    max_occurrences INTEGER,
+    occurrences INTEGER NOT NULL DEFAULT 0,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'active',
+    next_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (amount > 0),
+    CHECK ((cron_expression IS NULL) <> (interval_seconds IS NULL)),
+    CHECK (interval_seconds IS NULL OR interval_seconds > 0),
+    CHECK (max_occurrences IS NULL OR max_occurrences > 0)
+);
+
+-- Outcome of every attempt to execute a scheduled transfer
+CREATE TABLE IF NOT EXISTS scheduled_transfer_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    scheduled_transfer_id UUID NOT NULL REFERENCES scheduled_transfers(id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Create indices
+CREATE INDEX idx_scheduled_transfers_user_id ON scheduled_transfers(user_id);
+CREATE INDEX idx_scheduled_transfers_status_next_run_at ON scheduled_transfers(status, next_run_at);
+CREATE INDEX idx_scheduled_transfer_runs_scheduled_transfer_id ON scheduled_transfer_runs(scheduled_transfer_id);
@@ -0,0 +1,24 @@
+-- Transactional outbox. Domain events are written in the same database
+-- transaction as the change they describe and published afterwards by the
+-- relay. The id orders events; the row locks taken on each aggregate mean
+-- events for one aggregate always commit in id order.
+CREATE TABLE IF NOT EXISTS outbox_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_id UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+    aggregate_type VARCHAR(50) NOT NULL,
+    aggregate_id UUID NOT NULL,
+    -- Users the event concerns, i.e. the owners of the accounts involved
+    user_ids UUID[] NOT NULL,
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_outbox_events_unpublished ON outbox_events(id) WHERE published_at IS NULL;
+CREATE INDEX idx_outbox_events_published_at ON outbox_events(published_at);
+CREATE INDEX idx_outbox_events_aggregate ON outbox_events(aggregate_type, aggregate_id, id);
+
+-- Webhook deliveries are now created from outbox events, which may be relayed
+-- more than once, so each event is delivered to an endpoint at most once.
+CREATE UNIQUE INDEX idx_webhook_deliveries_endpoint_event ON webhook_deliveries(endpoint_id, event_id);
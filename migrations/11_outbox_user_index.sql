@@ -0,0 +1,2 @@
+-- The event stream replays a user's events from the outbox on reconnect.
+CREATE INDEX idx_outbox_events_user_ids ON outbox_events USING GIN (user_ids);
@@ -0,0 +1,5 @@
+-- Orders a transaction's events for replay. created_at cannot, since events
+-- written in one database transaction share the same timestamp.
+ALTER TABLE transaction_events ADD COLUMN sequence_number BIGSERIAL;
+
+CREATE INDEX idx_transaction_events_sequence ON transaction_events(transaction_id, sequence_number);
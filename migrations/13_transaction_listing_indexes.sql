@@ -0,0 +1,4 @@
+-- Transaction listings page through each account's transactions in
+-- (created_at, id) order.
+CREATE INDEX idx_transactions_source_created ON transactions(source_account_id, created_at, id);
+CREATE INDEX idx_transactions_destination_created ON transactions(destination_account_id, created_at, id);
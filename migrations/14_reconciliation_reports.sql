@@ -0,0 +1,39 @@
+-- Saved results of reconciliation runs, which check every account's stored
+-- balances against its transaction history
+CREATE TABLE IF NOT EXISTS reconciliation_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    accounts_checked BIGINT NOT NULL,
+    discrepancy_count INTEGER NOT NULL,
+    orphaned_transaction_count INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_reconciliation_reports_created_at ON reconciliation_reports(created_at);
+
+-- An account whose stored balance or held balance disagreed with what its
+-- transactions and holds add up to
+CREATE TABLE IF NOT EXISTS reconciliation_discrepancies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    report_id UUID NOT NULL REFERENCES reconciliation_reports(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    currency VARCHAR(3) NOT NULL,
+    stored_balance BIGINT NOT NULL,
+    expected_balance BIGINT NOT NULL,
+    stored_held_balance BIGINT NOT NULL,
+    expected_held_balance BIGINT NOT NULL
+);
+
+CREATE INDEX idx_reconciliation_discrepancies_report_id ON reconciliation_discrepancies(report_id);
+
+-- A pending transaction that was left unfinished
+CREATE TABLE IF NOT EXISTS reconciliation_orphaned_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    report_id UUID NOT NULL REFERENCES reconciliation_reports(id) ON DELETE CASCADE,
+    transaction_id UUID NOT NULL REFERENCES transactions(id),
+    hold_status VARCHAR(20),
+    reason TEXT NOT NULL
+);
+
+CREATE INDEX idx_reconciliation_orphaned_transactions_report_id ON reconciliation_orphaned_transactions(report_id);
@@ -0,0 +1,34 @@
+-- Settlement files received from the bank, one row per import
+CREATE TABLE IF NOT EXISTS settlement_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    file_name TEXT NOT NULL,
+    format VARCHAR(20) NOT NULL,
+    imported_by UUID REFERENCES users(id),
+    line_count INTEGER NOT NULL,
+    matched_count INTEGER NOT NULL,
+    unmatched_count INTEGER NOT NULL,
+    mismatched_count INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Every line of an imported file and how it compared with our transactions
+CREATE TABLE IF NOT EXISTS settlement_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    import_id UUID NOT NULL REFERENCES settlement_imports(id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    reference TEXT NOT NULL,
+    bank_reference TEXT,
+    amount BIGINT NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    value_date DATE NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    reasons TEXT[] NOT NULL DEFAULT '{}',
+    UNIQUE(import_id, line_number)
+);
+
+CREATE INDEX idx_settlement_lines_transaction_id ON settlement_lines(transaction_id);
+
+-- Transactions the bank has confirmed, and the line that confirmed them
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS settled_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS settlement_line_id UUID REFERENCES settlement_lines(id);
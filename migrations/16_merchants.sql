@@ -0,0 +1,42 @@
+-- Businesses that take payments, each owned by a user
+CREATE TABLE IF NOT EXISTS merchants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id),
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_merchants_user_id ON merchants(user_id);
+
+-- Every merchant has one settlement account that payments are made into. It
+-- belongs to the merchant's owner but does not count towards their one
+-- personal account per currency.
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS merchant_id UUID REFERENCES merchants(id);
+ALTER TABLE accounts DROP CONSTRAINT IF EXISTS accounts_user_id_currency_key;
+CREATE UNIQUE INDEX idx_accounts_user_id_currency ON accounts(user_id, currency) WHERE merchant_id IS NULL;
+CREATE UNIQUE INDEX idx_accounts_merchant_id ON accounts(merchant_id) WHERE merchant_id IS NOT NULL;
+
+-- A merchant's request to be paid, which a customer confirms from one of
+-- their accounts
+CREATE TABLE IF NOT EXISTS payment_intents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    merchant_id UUID NOT NULL REFERENCES merchants(id),
+    destination_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount BIGINT NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    client_secret VARCHAR(100) NOT NULL,
+    status VARCHAR(30) NOT NULL DEFAULT 'requires_confirmation',
+    customer_user_id UUID REFERENCES users(id),
+    source_account_id UUID REFERENCES accounts(id),
+    transaction_id UUID REFERENCES transactions(id),
+    last_error TEXT,
+    cancellation_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (amount > 0)
+);
+
+CREATE INDEX idx_payment_intents_merchant_id ON payment_intents(merchant_id, created_at);
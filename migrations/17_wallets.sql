@@ -0,0 +1,8 @@
+-- A user can hold several accounts, or wallets, in the same currency, told
+-- apart by their label
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS label VARCHAR(50) NOT NULL DEFAULT 'Main';
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS purpose VARCHAR(20) NOT NULL DEFAULT 'general';
+
+DROP INDEX IF EXISTS idx_accounts_user_id_currency;
+CREATE UNIQUE INDEX idx_accounts_user_id_currency_label
+    ON accounts(user_id, currency, LOWER(label)) WHERE merchant_id IS NULL;
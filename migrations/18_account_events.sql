@@ -0,0 +1,17 @@
+-- Every change of an account's status, with who made it and why
+CREATE TABLE IF NOT EXISTS account_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    previous_status VARCHAR(20) NOT NULL,
+    new_status VARCHAR(20) NOT NULL,
+    reason TEXT,
+    performed_by UUID REFERENCES users(id),
+    -- The transfer that emptied the account when it was closed
+    sweep_transaction_id UUID REFERENCES transactions(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_events_account_id ON account_events(account_id, created_at);
+
+ALTER TABLE accounts ADD CONSTRAINT accounts_status_check
+    CHECK (status IN ('active', 'suspended', 'closed'));
@@ -0,0 +1,26 @@
+-- Limits on outgoing money. The single 'default' row applies to every account
+-- without limits of its own; unset limits do not apply.
+CREATE TABLE IF NOT EXISTS spending_limits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    scope VARCHAR(10) NOT NULL,
+    -- The account or user the limits belong to, unset for the default
+    subject_id UUID,
+    max_transaction_amount BIGINT,
+    daily_amount BIGINT,
+    monthly_amount BIGINT,
+    hourly_count BIGINT,
+    updated_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (scope IN ('default', 'account', 'user')),
+    CHECK ((scope = 'default') = (subject_id IS NULL))
+);
+
+CREATE UNIQUE INDEX idx_spending_limits_default ON spending_limits(scope) WHERE subject_id IS NULL;
+CREATE UNIQUE INDEX idx_spending_limits_subject ON spending_limits(scope, subject_id) WHERE subject_id IS NOT NULL;
+
+INSERT INTO spending_limits (scope) VALUES ('default');
+
+-- Counting outgoing money per account and per user over recent windows
+CREATE INDEX IF NOT EXISTS idx_transactions_source_created_at
+    ON transactions(source_account_id, created_at) WHERE source_account_id IS NOT NULL;
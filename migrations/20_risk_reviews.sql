@@ -0,0 +1,17 @@
+-- Transfers the risk engine held for review, and what an operator decided.
+-- The assessment is also recorded in the transaction's events.
+CREATE TABLE IF NOT EXISTS risk_reviews (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transactions(id),
+    assessment JSONB NOT NULL,
+    -- Approving an authorization leaves it to be captured rather than completing it
+    authorize_only BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(10) NOT NULL DEFAULT 'open',
+    decided_by UUID REFERENCES users(id),
+    note TEXT,
+    decided_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (status IN ('open', 'approved', 'rejected', 'expired'))
+);
+
+CREATE INDEX idx_risk_reviews_status ON risk_reviews(status, created_at);
@@ -0,0 +1,33 @@
+-- Screened against watchlist entries' dates of birth when both are known
+ALTER TABLE users ADD COLUMN IF NOT EXISTS date_of_birth DATE;
+
+-- Watchlist hits on registrations and transfers, for compliance review. A
+-- cleared case stops the same person being flagged against the same entry.
+CREATE TABLE IF NOT EXISTS screening_cases (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subject VARCHAR(20) NOT NULL,
+    -- The person screened: the registering email, and the user once there is one
+    user_id UUID REFERENCES users(id),
+    email VARCHAR(255),
+    screened_name VARCHAR(255) NOT NULL,
+    date_of_birth DATE,
+    entry_id VARCHAR(255) NOT NULL,
+    watchlist_match JSONB NOT NULL,
+    action VARCHAR(10) NOT NULL,
+    -- The transfer held by the hit
+    transaction_id UUID REFERENCES transactions(id),
+    authorize_only BOOLEAN NOT NULL DEFAULT FALSE,
+    -- The risk engine's view of the held transfer, handed to a risk review on clearing
+    risk_assessment JSONB,
+    status VARCHAR(10) NOT NULL DEFAULT 'open',
+    decided_by UUID REFERENCES users(id),
+    note TEXT,
+    decided_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (subject IN ('registration', 'transfer')),
+    CHECK (action IN ('blocked', 'held')),
+    CHECK (status IN ('open', 'cleared', 'confirmed'))
+);
+
+CREATE INDEX idx_screening_cases_status ON screening_cases(status, created_at);
+CREATE INDEX idx_screening_cases_entry ON screening_cases(entry_id) WHERE status = 'cleared';
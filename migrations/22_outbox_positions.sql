@@ -0,0 +1,14 @@
+-- Orders events for stream clients resuming after a reconnect. The id cannot,
+-- since ids are assigned on insert but rows become visible on commit, so an
+-- event with a lower id can commit after one with a higher id has been read.
+-- The relay numbers committed events one batch at a time instead.
+CREATE SEQUENCE outbox_events_position_seq;
+
+ALTER TABLE outbox_events ADD COLUMN position BIGINT UNIQUE;
+
+-- Existing events keep their id as their position, so resume points clients
+-- already hold stay valid
+UPDATE outbox_events SET position = id;
+SELECT setval('outbox_events_position_seq', COALESCE((SELECT MAX(id) FROM outbox_events), 0) + 1, false);
+
+CREATE INDEX idx_outbox_events_unpositioned ON outbox_events(id) WHERE position IS NULL;
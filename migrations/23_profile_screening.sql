@@ -0,0 +1,4 @@
+-- Name changes on an existing profile are screened like registrations
+ALTER TABLE screening_cases DROP CONSTRAINT IF EXISTS screening_cases_subject_check;
+ALTER TABLE screening_cases ADD CONSTRAINT screening_cases_subject_check
+    CHECK (subject IN ('registration', 'profile', 'transfer'));
@@ -1,6 +1,9 @@
 use crate::{
     config::Config,
-    handlers::accounts::{create_account, get_account, list_accounts},
+    handlers::accounts::{
+        close_account, create_account, get_account, get_account_activity, get_account_events,
+        get_account_ledger, get_account_limits, get_account_statement, list_accounts, move_funds,
+    },
 };
 use axum::{
     Router,
@@ -11,5 +14,12 @@ pub fn create_router() -> Router<Config> {
     Router::new()
         .route("/", post(create_account))
         .route("/", get(list_accounts))
+        .route("/moves", post(move_funds))
         .route("/{:id}", get(get_account))
+        .route("/{:id}/ledger", get(get_account_ledger))
+        .route("/{:id}/activity", get(get_account_activity))
+        .route("/{:id}/statement", get(get_account_statement))
+        .route("/{:id}/events", get(get_account_events))
+        .route("/{:id}/limits", get(get_account_limits))
+        .route("/{:id}/close", post(close_account))
 }
@@ -0,0 +1,41 @@
+use crate::{
+    config::Config,
+    handlers::admin::{
+        approve_risk_review, clear_screening_case, confirm_screening_case, create_fx_rate, get_default_limits,
+        get_reconciliation_report, get_risk_review, get_screening_case, get_settlement_import, get_subject_limits,
+        get_watchlist, import_fx_rates, import_settlement, list_risk_reviews, list_screening_cases, reconcile,
+        reject_risk_review, reload_watchlist, reverse_transaction, set_default_limits, set_subject_limits,
+        suspend_account, unsuspend_account,
+    },
+};
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/transactions/{:id}/reverse", post(reverse_transaction))
+        .route("/accounts/{:id}/suspend", post(suspend_account))
+        .route("/accounts/{:id}/unsuspend", post(unsuspend_account))
+        .route("/limits/default", get(get_default_limits))
+        .route("/limits/default", put(set_default_limits))
+        .route("/limits/{:scope}/{:id}", get(get_subject_limits))
+        .route("/limits/{:scope}/{:id}", put(set_subject_limits))
+        .route("/risk/reviews", get(list_risk_reviews))
+        .route("/risk/reviews/{:id}", get(get_risk_review))
+        .route("/risk/reviews/{:id}/approve", post(approve_risk_review))
+        .route("/risk/reviews/{:id}/reject", post(reject_risk_review))
+        .route("/screening/cases", get(list_screening_cases))
+        .route("/screening/cases/{:id}", get(get_screening_case))
+        .route("/screening/cases/{:id}/clear", post(clear_screening_case))
+        .route("/screening/cases/{:id}/confirm", post(confirm_screening_case))
+        .route("/watchlist", get(get_watchlist))
+        .route("/watchlist/reload", post(reload_watchlist))
+        .route("/fx/rates", post(create_fx_rate))
+        .route("/fx/rates/import", post(import_fx_rates))
+        .route("/reconciliation", post(reconcile))
+        .route("/reconciliation/{:id}", get(get_reconciliation_report))
+        .route("/settlements/import", post(import_settlement))
+        .route("/settlements/{:id}", get(get_settlement_import))
+}
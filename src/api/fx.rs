@@ -0,0 +1,14 @@
+use crate::{
+    config::Config,
+    handlers::fx::{create_quote, list_rates},
+};
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/rates", get(list_rates))
+        .route("/quotes", post(create_quote))
+}
@@ -0,0 +1,15 @@
+use crate::{
+    config::Config,
+    handlers::merchants::{create_merchant, get_merchant, list_merchants},
+};
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/", post(create_merchant))
+        .route("/", get(list_merchants))
+        .route("/{:id}", get(get_merchant))
+}
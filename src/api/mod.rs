@@ -1,7 +1,14 @@
 mod accounts;
+mod admin;
 mod auth;
+mod fx;
+mod merchants;
+mod payment_intents;
 mod transactions;
+mod stream;
+mod transfers;
 mod users;
+mod webhooks;
 
 use axum::{Router, routing::get};
 use crate::config::Config;
@@ -12,6 +19,13 @@ pub fn create_router() -> Router<Config> {
         .nest("/api/users", users::create_router())
         .nest("/api/accounts", accounts::create_router())
         .nest("/api/transactions", transactions::create_router())
+        .nest("/api/transfers", transfers::create_router())
+        .nest("/api/fx", fx::create_router())
+        .nest("/api/webhooks", webhooks::create_router())
+        .nest("/api/merchants", merchants::create_router())
+        .nest("/api/payment_intents", payment_intents::create_router())
+        .nest("/api/stream", stream::create_router())
+        .nest("/api/admin", admin::create_router())
         .route("/api/health", get(health_check))
 }
 
@@ -0,0 +1,18 @@
+use crate::{
+    config::Config,
+    handlers::payment_intents::{
+        cancel_payment_intent, confirm_payment_intent, create_payment_intent, get_payment_intent,
+    },
+};
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/", post(create_payment_intent))
+        .route("/{:id}", get(get_payment_intent))
+        .route("/{:id}/confirm", post(confirm_payment_intent))
+        .route("/{:id}/cancel", post(cancel_payment_intent))
+}
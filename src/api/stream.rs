@@ -0,0 +1,6 @@
+use crate::{config::Config, handlers::stream::stream};
+use axum::{Router, routing::get};
+
+pub fn create_router() -> Router<Config> {
+    Router::new().route("/", get(stream))
+}
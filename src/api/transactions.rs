@@ -1,6 +1,10 @@
 use crate::{
     config::Config,
-    handlers::transactions::{create_transaction, get_transaction, list_transactions},
+    handlers::transactions::{
+        capture_transaction, create_refund, create_transaction, get_transaction,
+        get_transaction_history, list_refunds,
+        list_transactions, transaction_events, void_transaction,
+    },
 };
 use axum::{
     Router,
@@ -12,4 +16,10 @@ pub fn create_router() -> Router<Config> {
         .route("/", post(create_transaction))
         .route("/", get(list_transactions))
         .route("/{:id}", get(get_transaction))
+        .route("/{:id}/history", get(get_transaction_history))
+        .route("/{:id}/events", get(transaction_events))
+        .route("/{:id}/capture", post(capture_transaction))
+        .route("/{:id}/void", post(void_transaction))
+        .route("/{:id}/refunds", post(create_refund))
+        .route("/{:id}/refunds", get(list_refunds))
 }
@@ -0,0 +1,21 @@
+use crate::{
+    config::Config,
+    handlers::scheduled_transfers::{
+        cancel_scheduled_transfer, create_scheduled_transfer, get_scheduled_transfer,
+        list_scheduled_transfer_runs, list_scheduled_transfers, update_scheduled_transfer,
+    },
+};
+use axum::{
+    Router,
+    routing::{delete, get, post, put},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/scheduled", post(create_scheduled_transfer))
+        .route("/scheduled", get(list_scheduled_transfers))
+        .route("/scheduled/{:id}", get(get_scheduled_transfer))
+        .route("/scheduled/{:id}", put(update_scheduled_transfer))
+        .route("/scheduled/{:id}", delete(cancel_scheduled_transfer))
+        .route("/scheduled/{:id}/runs", get(list_scheduled_transfer_runs))
+}
@@ -0,0 +1,23 @@
+use crate::{
+    config::Config,
+    handlers::webhooks::{
+        create_endpoint, delete_endpoint, get_delivery, get_endpoint, list_deliveries, list_endpoints,
+        redeliver, update_endpoint,
+    },
+};
+use axum::{
+    Router,
+    routing::{delete, get, post, put},
+};
+
+pub fn create_router() -> Router<Config> {
+    Router::new()
+        .route("/endpoints", post(create_endpoint))
+        .route("/endpoints", get(list_endpoints))
+        .route("/endpoints/{:id}", get(get_endpoint))
+        .route("/endpoints/{:id}", put(update_endpoint))
+        .route("/endpoints/{:id}", delete(delete_endpoint))
+        .route("/endpoints/{:id}/deliveries", get(list_deliveries))
+        .route("/deliveries/{:id}", get(get_delivery))
+        .route("/deliveries/{:id}/redeliver", post(redeliver))
+}
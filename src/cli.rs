@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::settlement::SettlementMapping;
+use crate::services::{reconciliation_service, settlement_service};
+
+const USAGE: &str = "Usage:
+  payments-backend                                     run the server
+  payments-backend reconcile [--persist]               check balances against transactions
+  payments-backend import-settlement <file> <mapping>  match a bank settlement file";
+
+/// Runs a one-off command and returns the process exit code. Commands print
+/// their report as JSON and exit with 1 if it found anything that needs
+/// attention, so they can be run from cron or CI, and with 2 if they failed.
+pub async fn run(command: &str, options: &[String], db: &Database, config: &Config) -> i32 {
+    let outcome = match (command, options) {
+        ("reconcile", []) => reconcile(db, config, false).await,
+        ("reconcile", [flag]) if flag == "--persist" => reconcile(db, config, true).await,
+        ("import-settlement", [file, mapping]) => import_settlement(db, file, mapping).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match outcome {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("{} failed: {}", command, e);
+            2
+        }
+    }
+}
+
+fn print_report<T: Serialize>(report: &T) {
+    println!("{}", serde_json::to_string_pretty(report).unwrap());
+}
+
+async fn reconcile(db: &Database, config: &Config, persist: bool) -> Result<bool, String> {
+    let report = reconciliation_service::reconcile(db, config.reconciliation_pending_age, persist)
+        .await
+        .map_err(|e| e.to_string())?;
+    print_report(&report);
+
+    Ok(report.is_clean())
+}
+
+/// Imports a settlement file using a mapping read from a JSON file.
+async fn import_settlement(db: &Database, file: &str, mapping_file: &str) -> Result<bool, String> {
+    let content = std::fs::read_to_string(file).map_err(|e| format!("Cannot read {}: {}", file, e))?;
+    let mapping = std::fs::read_to_string(mapping_file)
+        .map_err(|e| format!("Cannot read {}: {}", mapping_file, e))?;
+    let mapping: SettlementMapping =
+        serde_json::from_str(&mapping).map_err(|e| format!("Invalid mapping in {}: {}", mapping_file, e))?;
+
+    let file_name = Path::new(file)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(file);
+    let report = settlement_service::import(db, file_name, &mapping, &content, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    print_report(&report);
+
+    Ok(report.is_clean())
+}
@@ -2,12 +2,33 @@ use dotenv::dotenv;
 use serde::Deserialize;
 use std::env;
 
+use crate::models::outbox::{parse_event_sinks, EventSinkKind};
+use crate::models::risk::RiskPolicy;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
     pub jwt_expiration: i64,
     pub port: u16,
+    pub idempotency_key_ttl: i64,
+    pub hold_expiration: i64,
+    pub hold_expiry_interval: u64,
+    pub fx_quote_ttl: i64,
+    pub scheduled_transfer_interval: u64,
+    pub scheduled_transfer_retry_delay: i64,
+    pub scheduled_transfer_max_failures: i32,
+    pub webhook_delivery_interval: u64,
+    pub webhook_max_attempts: i32,
+    pub webhook_retry_base_delay: i64,
+    pub webhook_allowed_hosts: Vec<String>,
+    pub outbox_sinks: Vec<EventSinkKind>,
+    pub outbox_relay_interval: u64,
+    pub outbox_retention: i64,
+    pub reconciliation_pending_age: i64,
+    pub risk_policy: RiskPolicy,
+    pub watchlist_path: Option<String>,
+    pub watchlist_match_threshold: f64,
 }
 
 impl Config {
@@ -24,12 +45,103 @@ impl Config {
             .unwrap_or_else(|_| "3002".to_string())
             .parse::<u16>()
             .expect("PORT must be a valid integer");
+        let idempotency_key_ttl = env::var("IDEMPOTENCY_KEY_TTL")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<i64>()
+            .expect("IDEMPOTENCY_KEY_TTL must be a valid integer");
+        let hold_expiration = env::var("HOLD_EXPIRATION")
+            .unwrap_or_else(|_| "604800".to_string())
+            .parse::<i64>()
+            .expect("HOLD_EXPIRATION must be a valid integer");
+        let hold_expiry_interval = env::var("HOLD_EXPIRY_INTERVAL")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("HOLD_EXPIRY_INTERVAL must be a valid integer");
+        let fx_quote_ttl = env::var("FX_QUOTE_TTL")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()
+            .expect("FX_QUOTE_TTL must be a valid integer");
+        let scheduled_transfer_interval = env::var("SCHEDULED_TRANSFER_INTERVAL")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("SCHEDULED_TRANSFER_INTERVAL must be a valid integer");
+        let scheduled_transfer_retry_delay = env::var("SCHEDULED_TRANSFER_RETRY_DELAY")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<i64>()
+            .expect("SCHEDULED_TRANSFER_RETRY_DELAY must be a valid integer");
+        let scheduled_transfer_max_failures = env::var("SCHEDULED_TRANSFER_MAX_FAILURES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<i32>()
+            .expect("SCHEDULED_TRANSFER_MAX_FAILURES must be a valid integer");
+        let webhook_delivery_interval = env::var("WEBHOOK_DELIVERY_INTERVAL")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u64>()
+            .expect("WEBHOOK_DELIVERY_INTERVAL must be a valid integer");
+        let webhook_max_attempts = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse::<i32>()
+            .expect("WEBHOOK_MAX_ATTEMPTS must be a valid integer");
+        let webhook_retry_base_delay = env::var("WEBHOOK_RETRY_BASE_DELAY")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()
+            .expect("WEBHOOK_RETRY_BASE_DELAY must be a valid integer");
+        let webhook_allowed_hosts = env::var("WEBHOOK_ALLOWED_HOSTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|host| host.trim().to_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        let outbox_sinks = parse_event_sinks(
+            &env::var("OUTBOX_SINKS").unwrap_or_else(|_| "broadcast,webhooks".to_string()),
+        )
+        .expect("OUTBOX_SINKS must be a comma-separated list of broadcast, webhooks and stdout");
+        let outbox_relay_interval = env::var("OUTBOX_RELAY_INTERVAL")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<u64>()
+            .expect("OUTBOX_RELAY_INTERVAL must be a valid integer");
+        let outbox_retention = env::var("OUTBOX_RETENTION")
+            .unwrap_or_else(|_| "604800".to_string())
+            .parse::<i64>()
+            .expect("OUTBOX_RETENTION must be a valid integer");
+        let reconciliation_pending_age = env::var("RECONCILIATION_PENDING_AGE")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<i64>()
+            .expect("RECONCILIATION_PENDING_AGE must be a valid integer");
+        let risk_policy = match env::var("RISK_RULES_PATH") {
+            Ok(path) => RiskPolicy::load(&path).unwrap_or_else(|e| panic!("RISK_RULES_PATH is invalid: {}", e)),
+            Err(_) => RiskPolicy::default(),
+        };
+        let watchlist_path = env::var("WATCHLIST_PATH").ok();
+        let watchlist_match_threshold = env::var("WATCHLIST_MATCH_THRESHOLD")
+            .unwrap_or_else(|_| "0.92".to_string())
+            .parse::<f64>()
+            .ok()
+            .filter(|threshold| (0.0..=1.0).contains(threshold))
+            .expect("WATCHLIST_MATCH_THRESHOLD must be a number between 0 and 1");
 
         Self {
             database_url,
             jwt_secret,
             jwt_expiration,
             port,
+            idempotency_key_ttl,
+            hold_expiration,
+            hold_expiry_interval,
+            fx_quote_ttl,
+            scheduled_transfer_interval,
+            scheduled_transfer_retry_delay,
+            scheduled_transfer_max_failures,
+            webhook_delivery_interval,
+            webhook_max_attempts,
+            webhook_retry_base_delay,
+            webhook_allowed_hosts,
+            outbox_sinks,
+            outbox_relay_interval,
+            outbox_retention,
+            reconciliation_pending_age,
+            risk_policy,
+            watchlist_path,
+            watchlist_match_threshold,
         }
     }
 }
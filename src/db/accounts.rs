@@ -1,47 +1,110 @@
-use crate::models::account::{Account, AccountStatus, CreateAccountRequest};
+use crate::db::{outbox, transactions};
+use crate::models::account::{
+    Account, AccountEvent, AccountPurpose, AccountStatus, CloseAccountRequest, CreateAccountRequest,
+};
+use crate::models::transaction::{CreateTransactionRequest, TransactionType};
 use crate::utils::error::AppError;
 use deadpool_postgres::Client;
+use tokio_postgres::Row;
 use uuid::Uuid;
 
+const SETTLEMENT_ACCOUNT_LABEL: &str = "Settlement";
+
+fn account_from_row(row: &Row) -> Result<Account, AppError> {
+    let status = AccountStatus::try_from(row.get::<_, &str>("status")).map_err(AppError::Internal)?;
+
+    Ok(Account {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        balance: row.get("balance"),
+        held_balance: row.get("held_balance"),
+        currency: row.get("currency"),
+        label: row.get("label"),
+        purpose: AccountPurpose::from(row.get::<_, &str>("purpose")),
+        status,
+        merchant_id: row.get("merchant_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
 pub async fn create_account(
-    client: &Client,
+    client: &mut Client,
     user_id: Uuid,
     data: &CreateAccountRequest,
 ) -> Result<Account, AppError> {
-    // Check if the user already has an account with this currency
-    let existing = client
+    let tx = client.transaction().await?;
+
+    // Check if the user already has an account with this currency and label
+    let existing = tx
         .query_opt(
-            "SELECT id FROM accounts WHERE user_id = $1 AND currency = $2",
-            &[&user_id, &data.currency],
+            "SELECT id FROM accounts
+             WHERE user_id = $1 AND currency = $2 AND LOWER(label) = LOWER($3) AND merchant_id IS NULL",
+            &[&user_id, &data.currency, &data.label()],
         )
         .await?;
 
     if existing.is_some() {
         return Err(AppError::BadRequest(format!(
-            "User already has an account in {} currency",
-            data.currency
+            "User already has an account in {} currency labelled '{}'",
+            data.currency,
+            data.label()
         )));
     }
 
-    // Create the account
+    let account = insert_account(&tx, user_id, &data.currency, data.label(), data.purpose, None).await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// Opens the account a merchant's payments are made into, owned by the
+/// merchant's user alongside their personal accounts.
+pub async fn create_settlement_account<T>(
+    client: &T,
+    user_id: Uuid,
+    merchant_id: Uuid,
+    currency: &str,
+) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    insert_account(
+        client,
+        user_id,
+        currency,
+        SETTLEMENT_ACCOUNT_LABEL,
+        AccountPurpose::General,
+        Some(merchant_id),
+    )
+    .await
+}
+
+async fn insert_account<T>(
+    client: &T,
+    user_id: Uuid,
+    currency: &str,
+    label: &str,
+    purpose: AccountPurpose,
+    merchant_id: Option<Uuid>,
+) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
     let row = client
         .query_one(
-            "INSERT INTO accounts (user_id, currency) 
-             VALUES ($1, $2) 
-             RETURNING id, user_id, balance, currency, status, created_at, updated_at",
-            &[&user_id, &data.currency],
+            "INSERT INTO accounts (user_id, currency, label, purpose, merchant_id) 
+             VALUES ($1, $2, $3, $4, $5) 
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
+            &[&user_id, &currency, &label, &purpose.to_string(), &merchant_id],
         )
         .await?;
+    let account = account_from_row(&row)?;
 
-    Ok(Account {
-        id: row.get("id"),
-        user_id: row.get("user_id"),
-        balance: row.get("balance"),
-        currency: row.get("currency"),
-        status: AccountStatus::from(row.get::<_, &str>("status")),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    outbox::record_account_event(client, "account.created", &account).await?;
+
+    Ok(account)
 }
 
 pub async fn get_account<T>(client: &T, account_id: Uuid) -> Result<Account, AppError>
@@ -50,7 +113,7 @@ where
 {
     let row = client
         .query_opt(
-            "SELECT id, user_id, balance, currency, status, created_at, updated_at 
+            "SELECT id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at 
              FROM accounts 
              WHERE id = $1",
             &[&account_id],
@@ -58,21 +121,13 @@ where
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Account not found: {}", account_id)))?;
 
-    Ok(Account {
-        id: row.get("id"),
-        user_id: row.get("user_id"),
-        balance: row.get("balance"),
-        currency: row.get("currency"),
-        status: AccountStatus::from(row.get::<_, &str>("status")),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    account_from_row(&row)
 }
 
 pub async fn get_user_accounts(client: &Client, user_id: Uuid) -> Result<Vec<Account>, AppError> {
     let rows = client
         .query(
-            "SELECT id, user_id, balance, currency, status, created_at, updated_at 
+            "SELECT id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at 
              FROM accounts 
              WHERE user_id = $1
              ORDER BY created_at",
@@ -80,23 +135,87 @@ pub async fn get_user_accounts(client: &Client, user_id: Uuid) -> Result<Vec<Acc
         )
         .await?;
 
+    rows.iter().map(account_from_row).collect()
+}
+
+pub async fn update_balance<T>(
+    client: &T,
+    account_id: Uuid,
+    amount: i64,
+) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "UPDATE accounts 
+             SET balance = balance + $1, updated_at = NOW() 
+             WHERE id = $2 
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
+            &[&amount, &account_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account not found: {}", account_id)))?;
+
+    let account = account_from_row(&row)?;
+    outbox::record_account_event(client, "account.balance_updated", &account).await?;
+
+    Ok(account)
+}
+
+/// The distinct owners of the given accounts.
+pub async fn get_owner_ids<T>(client: &T, account_ids: &[Uuid]) -> Result<Vec<Uuid>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT DISTINCT user_id FROM accounts WHERE id = ANY($1)",
+            &[&account_ids],
+        )
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get("user_id")).collect())
+}
+
+/// Locks the given accounts with `SELECT ... FOR UPDATE` for the rest of the
+/// surrounding database transaction. Rows are always locked in ascending id
+/// order so that concurrent transactions touching the same accounts cannot
+/// deadlock each other.
+pub async fn lock_accounts<T>(client: &T, account_ids: &[Uuid]) -> Result<Vec<Account>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let mut ids = account_ids.to_vec();
+    ids.sort();
+    ids.dedup();
+
+    let rows = client
+        .query(
+            "SELECT id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at 
+             FROM accounts 
+             WHERE id = ANY($1)
+             ORDER BY id
+             FOR UPDATE",
+            &[&ids],
+        )
+        .await?;
+
     let accounts = rows
         .iter()
-        .map(|row| Account {
-            id: row.get("id"),
-            user_id: row.get("user_id"),
-            balance: row.get("balance"),
-            currency: row.get("currency"),
-            status: AccountStatus::from(row.get::<_, &str>("status")),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        })
-        .collect();
+        .map(account_from_row)
+        .collect::<Result<Vec<Account>, AppError>>()?;
+
+    if let Some(missing) = ids.iter().find(|id| !accounts.iter().any(|a| a.id == **id)) {
+        return Err(AppError::NotFound(format!("Account not found: {}", missing)));
+    }
 
     Ok(accounts)
 }
 
-pub async fn update_balance<T>(
+/// Subtracts `amount` from the account balance only if the available balance covers it,
+/// so a debit can never drive an account negative even without a prior lock.
+pub async fn debit_balance<T>(
     client: &T,
     account_id: Uuid,
     amount: i64,
@@ -107,21 +226,297 @@ where
     let row = client
         .query_opt(
             "UPDATE accounts 
-             SET balance = balance + $1, updated_at = NOW() 
+             SET balance = balance - $1, updated_at = NOW() 
+             WHERE id = $2 AND balance - held_balance >= $1
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
+            &[&amount, &account_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::InsufficientFunds(format!(
+                "account {} cannot cover a debit of {}",
+                account_id, amount
+            ))
+        })?;
+
+    let account = account_from_row(&row)?;
+    outbox::record_account_event(client, "account.balance_updated", &account).await?;
+
+    Ok(account)
+}
+
+/// Reserves `amount` of the available balance for a pending authorization.
+pub async fn place_hold<T>(client: &T, account_id: Uuid, amount: i64) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "UPDATE accounts 
+             SET held_balance = held_balance + $1, updated_at = NOW() 
+             WHERE id = $2 AND balance - held_balance >= $1
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
+            &[&amount, &account_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::InsufficientFunds(format!(
+                "account {} cannot cover a hold of {}",
+                account_id, amount
+            ))
+        })?;
+
+    let account = account_from_row(&row)?;
+    outbox::record_account_event(client, "account.balance_updated", &account).await?;
+
+    Ok(account)
+}
+
+/// Returns a previously held amount to the available balance.
+pub async fn release_hold<T>(client: &T, account_id: Uuid, amount: i64) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "UPDATE accounts 
+             SET held_balance = held_balance - $1, updated_at = NOW() 
              WHERE id = $2 
-             RETURNING id, user_id, balance, currency, status, created_at, updated_at",
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
             &[&amount, &account_id],
         )
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Account not found: {}", account_id)))?;
 
-    Ok(Account {
-        id: row.get("id"),
-        user_id: row.get("user_id"),
-        balance: row.get("balance"),
-        currency: row.get("currency"),
-        status: AccountStatus::from(row.get::<_, &str>("status")),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    let account = account_from_row(&row)?;
+    outbox::record_account_event(client, "account.balance_updated", &account).await?;
+
+    Ok(account)
+}
+
+/// Refuses to take money out of an account that is not active.
+pub fn ensure_can_debit(account: &Account) -> Result<(), AppError> {
+    if !account.status.can_debit() {
+        return Err(AppError::Conflict(format!(
+            "Account {} is {} and cannot be debited",
+            account.id, account.status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Refuses to pay money into a closed account.
+pub fn ensure_can_credit(account: &Account) -> Result<(), AppError> {
+    if !account.status.can_credit() {
+        return Err(AppError::Conflict(format!(
+            "Account {} is {} and cannot be credited",
+            account.id, account.status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Suspends an active account, or lifts the suspension of a suspended one,
+/// on an operator's behalf.
+pub async fn change_status(
+    client: &mut Client,
+    operator_id: Uuid,
+    account_id: Uuid,
+    status: AccountStatus,
+    reason: &str,
+) -> Result<Account, AppError> {
+    let tx = client.transaction().await?;
+
+    let account = lock_accounts(&tx, &[account_id]).await?.remove(0);
+    let account = record_status_change(&tx, &account, status, Some(reason), Some(operator_id), None).await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// Closes an active account. Any remaining balance is first transferred to
+/// `sweep_account_id`, another of the user's accounts, in the same database
+/// transaction, so the account is never closed with money in it. Overdrawn
+/// accounts, and accounts with pending authorizations, cannot be closed until
+/// the overdraft is settled or the authorizations are captured, voided or
+/// expire.
+pub async fn close_account(
+    client: &mut Client,
+    user_id: Uuid,
+    account_id: Uuid,
+    data: &CloseAccountRequest,
+) -> Result<Account, AppError> {
+    let mut tx = client.transaction().await?;
+
+    let account = lock_accounts(&tx, &[account_id]).await?.remove(0);
+    if account.user_id != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+    if !account.status.allowed_transitions().contains(&AccountStatus::Closed) {
+        return Err(invalid_transition(&account, &AccountStatus::Closed));
+    }
+    if account.held_balance > 0 {
+        return Err(AppError::Conflict(
+            "Account has pending authorizations and cannot be closed".to_string(),
+        ));
+    }
+
+    let sweep_transaction_id = match (account.balance, data.sweep_account_id) {
+        (0, _) => None,
+        // Reversals can leave an account below zero, and there is nothing to sweep
+        (balance, _) if balance < 0 => {
+            return Err(AppError::Conflict(format!(
+                "Account is overdrawn by {} {}, settle the overdraft first",
+                -balance, account.currency
+            )));
+        }
+        (_, None) => {
+            return Err(AppError::BadRequest(format!(
+                "Account still holds {} {}, name an account to sweep it to",
+                account.balance, account.currency
+            )));
+        }
+        (_, Some(sweep_account_id)) => {
+            let sweep_account = get_account(&tx, sweep_account_id).await?;
+            if sweep_account.user_id != user_id {
+                return Err(AppError::Forbidden(
+                    "You do not have permission to access this account".to_string(),
+                ));
+            }
+            if sweep_account.id == account.id {
+                return Err(AppError::BadRequest(
+                    "Cannot sweep an account's balance into itself".to_string(),
+                ));
+            }
+            if sweep_account.currency != account.currency {
+                return Err(AppError::BadRequest(format!(
+                    "Cannot sweep a {} balance into a {} account",
+                    account.currency, sweep_account.currency
+                )));
+            }
+
+            let request = CreateTransactionRequest {
+                source_account_id: Some(account.id),
+                destination_account_id: Some(sweep_account.id),
+                amount: account.balance,
+                currency: account.currency.clone(),
+                transaction_type: TransactionType::Transfer.to_string(),
+                description: Some(format!("Balance sweep on closing account {}", account.id)),
+                authorize_only: false,
+                fx_quote_id: None,
+            };
+            // Neither screened nor limited: both only cover money going to another user
+            Some(transactions::create_transaction(&mut tx, user_id, &request).await?.id)
+        }
+    };
+
+    let account = get_account(&tx, account_id).await?;
+    let account = record_status_change(
+        &tx,
+        &account,
+        AccountStatus::Closed,
+        data.reason.as_deref(),
+        Some(user_id),
+        sweep_transaction_id,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// The account's status changes, oldest first.
+pub async fn get_account_events(client: &Client, account_id: Uuid) -> Result<Vec<AccountEvent>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM account_events WHERE account_id = $1 ORDER BY created_at, id",
+            &[&account_id],
+        )
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let status = |column: &str| {
+                AccountStatus::try_from(row.get::<_, &str>(column)).map_err(AppError::Internal)
+            };
+
+            Ok(AccountEvent {
+                id: row.get("id"),
+                account_id: row.get("account_id"),
+                previous_status: status("previous_status")?,
+                new_status: status("new_status")?,
+                reason: row.get("reason"),
+                performed_by: row.get("performed_by"),
+                sweep_transaction_id: row.get("sweep_transaction_id"),
+                created_at: row.get("created_at"),
+            })
+        })
+        .collect()
+}
+
+fn invalid_transition(account: &Account, status: &AccountStatus) -> AppError {
+    AppError::Conflict(format!(
+        "Account cannot move from {} to {}",
+        account.status, status
+    ))
+}
+
+/// Moves a locked account to `status`, recording the change in its history
+/// and publishing `account.<status>`.
+async fn record_status_change<T>(
+    client: &T,
+    account: &Account,
+    status: AccountStatus,
+    reason: Option<&str>,
+    performed_by: Option<Uuid>,
+    sweep_transaction_id: Option<Uuid>,
+) -> Result<Account, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    if !account.status.allowed_transitions().contains(&status) {
+        return Err(invalid_transition(account, &status));
+    }
+
+    let row = client
+        .query_one(
+            "UPDATE accounts
+             SET status = $1, updated_at = NOW()
+             WHERE id = $2
+             RETURNING id, user_id, balance, held_balance, currency, label, purpose, status, merchant_id, created_at, updated_at",
+            &[&status.to_string(), &account.id],
+        )
+        .await?;
+    let updated = account_from_row(&row)?;
+
+    client
+        .execute(
+            "INSERT INTO account_events
+             (account_id, previous_status, new_status, reason, performed_by, sweep_transaction_id)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[
+                &account.id,
+                &account.status.to_string(),
+                &status.to_string(),
+                &reason,
+                &performed_by,
+                &sweep_transaction_id,
+            ],
+        )
+        .await?;
+
+    let event_type = match status {
+        AccountStatus::Active => "account.unsuspended",
+        AccountStatus::Suspended => "account.suspended",
+        AccountStatus::Closed => "account.closed",
+    };
+    outbox::record_account_event(client, event_type, &updated).await?;
+
+    Ok(updated)
 }
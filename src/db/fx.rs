@@ -0,0 +1,213 @@
+use crate::models::fx::{convert, CreateFxRateRequest, FxQuote, FxRate};
+use crate::utils::error::AppError;
+use deadpool_postgres::Client;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Parses a NUMERIC column that was selected as text.
+pub(crate) fn parse_rate(value: &str) -> Result<Decimal, AppError> {
+    Decimal::from_str(value)
+        .map_err(|e| AppError::Internal(format!("Invalid stored rate '{}': {}", value, e)))
+}
+
+fn fx_rate_from_row(row: &Row) -> Result<FxRate, AppError> {
+    Ok(FxRate {
+        id: row.get("id"),
+        base_currency: row.get("base_currency"),
+        quote_currency: row.get("quote_currency"),
+        rate: parse_rate(row.get("rate"))?,
+        spread_bps: row.get("spread_bps"),
+        valid_from: row.get("valid_from"),
+        valid_to: row.get("valid_to"),
+        created_at: row.get("created_at"),
+    })
+}
+
+fn fx_quote_from_row(row: &Row) -> Result<FxQuote, AppError> {
+    Ok(FxQuote {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        fx_rate_id: row.get("fx_rate_id"),
+        source_currency: row.get("source_currency"),
+        destination_currency: row.get("destination_currency"),
+        source_amount: row.get("source_amount"),
+        destination_amount: row.get("destination_amount"),
+        rate: parse_rate(row.get("rate"))?,
+        spread_bps: row.get("spread_bps"),
+        spread_amount: row.get("spread_amount"),
+        transaction_id: row.get("transaction_id"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+pub async fn create_rate<T>(client: &T, data: &CreateFxRateRequest) -> Result<FxRate, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "INSERT INTO fx_rates (base_currency, quote_currency, rate, spread_bps, valid_from, valid_to)
+             VALUES ($1, $2, $3::TEXT::NUMERIC, $4, COALESCE($5, NOW()), $6)
+             RETURNING id, base_currency, quote_currency, rate::TEXT AS rate, spread_bps,
+                       valid_from, valid_to, created_at",
+            &[
+                &data.base_currency,
+                &data.quote_currency,
+                &data.rate.to_string(),
+                &data.spread_bps,
+                &data.valid_from,
+                &data.valid_to,
+            ],
+        )
+        .await?;
+
+    fx_rate_from_row(&row)
+}
+
+/// Inserts a batch of rates atomically, so a bad row leaves nothing loaded.
+pub async fn create_rates(client: &mut Client, rates: &[CreateFxRateRequest]) -> Result<Vec<FxRate>, AppError> {
+    let tx = client.transaction().await?;
+
+    let mut created = Vec::with_capacity(rates.len());
+    for rate in rates {
+        created.push(create_rate(&tx, rate).await?);
+    }
+
+    tx.commit().await?;
+
+    Ok(created)
+}
+
+/// The rate currently in force for every currency pair.
+pub async fn get_current_rates(client: &Client) -> Result<Vec<FxRate>, AppError> {
+    let rows = client
+        .query(
+            "SELECT DISTINCT ON (base_currency, quote_currency)
+                    id, base_currency, quote_currency, rate::TEXT AS rate, spread_bps,
+                    valid_from, valid_to, created_at
+             FROM fx_rates
+             WHERE valid_from <= NOW() AND (valid_to IS NULL OR valid_to > NOW())
+             ORDER BY base_currency, quote_currency, valid_from DESC, created_at DESC",
+            &[],
+        )
+        .await?;
+
+    rows.iter().map(fx_rate_from_row).collect()
+}
+
+/// Finds the rate in force for converting between two currencies, preferring
+/// a rate quoted in that direction over the inverse of the opposite pair.
+pub async fn find_current_rate<T>(
+    client: &T,
+    source_currency: &str,
+    destination_currency: &str,
+) -> Result<FxRate, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT id, base_currency, quote_currency, rate::TEXT AS rate, spread_bps,
+                    valid_from, valid_to, created_at
+             FROM fx_rates
+             WHERE ((base_currency = $1 AND quote_currency = $2)
+                    OR (base_currency = $2 AND quote_currency = $1))
+               AND valid_from <= NOW() AND (valid_to IS NULL OR valid_to > NOW())
+             ORDER BY base_currency = $1 DESC, valid_from DESC, created_at DESC
+             LIMIT 1",
+            &[&source_currency, &destination_currency],
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "No exchange rate available from {} to {}",
+                source_currency, destination_currency
+            ))
+        })?;
+
+    fx_rate_from_row(&row)
+}
+
+/// Locks the current rate for a conversion until `ttl_seconds` from now.
+pub async fn create_quote(
+    client: &Client,
+    user_id: Uuid,
+    source_currency: &str,
+    destination_currency: &str,
+    amount: i64,
+    ttl_seconds: i64,
+) -> Result<FxQuote, AppError> {
+    let rate = find_current_rate(client, source_currency, destination_currency).await?;
+    let conversion = convert(
+        amount,
+        source_currency,
+        destination_currency,
+        rate.rate_from(source_currency),
+        rate.spread_bps,
+    )
+    .map_err(AppError::BadRequest)?;
+
+    let row = client
+        .query_one(
+            "INSERT INTO fx_quotes
+             (user_id, fx_rate_id, source_currency, destination_currency, source_amount,
+              destination_amount, rate, spread_bps, spread_amount, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7::TEXT::NUMERIC, $8, $9, NOW() + make_interval(secs => $10))
+             RETURNING id, user_id, fx_rate_id, source_currency, destination_currency, source_amount,
+                       destination_amount, rate::TEXT AS rate, spread_bps, spread_amount,
+                       transaction_id, expires_at, created_at",
+            &[
+                &user_id,
+                &rate.id,
+                &source_currency,
+                &destination_currency,
+                &amount,
+                &conversion.destination_amount,
+                &conversion.rate.to_string(),
+                &conversion.spread_bps,
+                &conversion.spread_amount,
+                &(ttl_seconds as f64),
+            ],
+        )
+        .await?;
+
+    fx_quote_from_row(&row)
+}
+
+/// Loads and locks one of a user's quotes so it can only be used once.
+pub async fn lock_quote<T>(client: &T, user_id: Uuid, quote_id: Uuid) -> Result<FxQuote, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT id, user_id, fx_rate_id, source_currency, destination_currency, source_amount,
+                    destination_amount, rate::TEXT AS rate, spread_bps, spread_amount,
+                    transaction_id, expires_at, created_at
+             FROM fx_quotes
+             WHERE id = $1 AND user_id = $2
+             FOR UPDATE",
+            &[&quote_id, &user_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("FX quote not found: {}", quote_id)))?;
+
+    fx_quote_from_row(&row)
+}
+
+pub async fn mark_quote_used<T>(client: &T, quote_id: Uuid, transaction_id: Uuid) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "UPDATE fx_quotes SET transaction_id = $1 WHERE id = $2",
+            &[&transaction_id, &quote_id],
+        )
+        .await?;
+
+    Ok(())
+}
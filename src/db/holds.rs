@@ -0,0 +1,112 @@
+use crate::models::hold::{HoldStatus, TransactionHold};
+use crate::utils::error::AppError;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn hold_from_row(row: &Row) -> Result<TransactionHold, AppError> {
+    Ok(TransactionHold {
+        id: row.get("id"),
+        transaction_id: row.get("transaction_id"),
+        account_id: row.get("account_id"),
+        amount: row.get("amount"),
+        captured_amount: row.get("captured_amount"),
+        status: HoldStatus::try_from(row.get::<_, &str>("status")).map_err(AppError::Internal)?,
+        expires_at: row.get("expires_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+pub async fn create_hold<T>(
+    client: &T,
+    transaction_id: Uuid,
+    account_id: Uuid,
+    amount: i64,
+    ttl_seconds: i64,
+) -> Result<TransactionHold, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "INSERT INTO transaction_holds (transaction_id, account_id, amount, expires_at) 
+             VALUES ($1, $2, $3, NOW() + make_interval(secs => $4)) 
+             RETURNING id, transaction_id, account_id, amount, captured_amount, status, 
+                     expires_at, created_at, updated_at",
+            &[&transaction_id, &account_id, &amount, &(ttl_seconds as f64)],
+        )
+        .await?;
+
+    hold_from_row(&row)
+}
+
+/// Loads and locks the hold belonging to a transaction.
+pub async fn lock_hold_for_transaction<T>(
+    client: &T,
+    transaction_id: Uuid,
+) -> Result<TransactionHold, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT id, transaction_id, account_id, amount, captured_amount, status, 
+                    expires_at, created_at, updated_at 
+             FROM transaction_holds 
+             WHERE transaction_id = $1 
+             FOR UPDATE",
+            &[&transaction_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "No authorization found for transaction: {}",
+                transaction_id
+            ))
+        })?;
+
+    hold_from_row(&row)
+}
+
+/// Locks a batch of active holds whose expiry has passed, skipping any that
+/// are being captured or voided concurrently.
+pub async fn lock_expired_holds<T>(client: &T, limit: i64) -> Result<Vec<TransactionHold>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT id, transaction_id, account_id, amount, captured_amount, status, 
+                    expires_at, created_at, updated_at 
+             FROM transaction_holds 
+             WHERE status = 'active' AND expires_at <= NOW() 
+             ORDER BY expires_at 
+             LIMIT $1 
+             FOR UPDATE SKIP LOCKED",
+            &[&limit],
+        )
+        .await?;
+
+    rows.iter().map(hold_from_row).collect()
+}
+
+pub async fn update_hold_status<T>(
+    client: &T,
+    hold_id: Uuid,
+    status: &HoldStatus,
+    captured_amount: Option<i64>,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "UPDATE transaction_holds 
+             SET status = $1, captured_amount = $2, updated_at = NOW() 
+             WHERE id = $3",
+            &[&status.to_string(), &captured_amount, &hold_id],
+        )
+        .await?;
+
+    Ok(())
+}
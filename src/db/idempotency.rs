@@ -0,0 +1,99 @@
+use crate::models::idempotency::{IdempotencyOutcome, StoredResponse};
+use crate::utils::error::AppError;
+use uuid::Uuid;
+
+/// Claims an idempotency key for a request. A new key is recorded as in
+/// progress; a known key either replays the stored response or is rejected if
+/// the request differs.
+///
+/// Call this inside the transaction that processes the request and store the
+/// response with `complete` before committing. A concurrent request with the
+/// same key then waits for the first to finish and replays its response, and
+/// a claim whose transaction rolls back disappears with it.
+pub async fn begin<T>(
+    client: &T,
+    user_id: Uuid,
+    key: &str,
+    request_path: &str,
+    request_body: &serde_json::Value,
+    ttl_seconds: i64,
+) -> Result<IdempotencyOutcome, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    // Expired keys can be reused, as can claims committed without a response
+    client
+        .execute(
+            "DELETE FROM idempotency_keys 
+             WHERE user_id = $1 AND idempotency_key = $2
+               AND (expires_at <= NOW() OR response_status IS NULL)",
+            &[&user_id, &key],
+        )
+        .await?;
+
+    let inserted = client
+        .query_opt(
+            "INSERT INTO idempotency_keys (user_id, idempotency_key, request_path, request_body, expires_at) 
+             VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5)) 
+             ON CONFLICT (user_id, idempotency_key) DO NOTHING 
+             RETURNING idempotency_key",
+            &[&user_id, &key, &request_path, request_body, &(ttl_seconds as f64)],
+        )
+        .await?;
+
+    if inserted.is_some() {
+        return Ok(IdempotencyOutcome::Started);
+    }
+
+    let row = client
+        .query_opt(
+            "SELECT request_path, request_body, response_status, response_body 
+             FROM idempotency_keys 
+             WHERE user_id = $1 AND idempotency_key = $2",
+            &[&user_id, &key],
+        )
+        .await?
+        .ok_or_else(|| {
+            AppError::Conflict("Idempotency key was released concurrently, please retry".to_string())
+        })?;
+
+    let stored_path: String = row.get("request_path");
+    let stored_body: serde_json::Value = row.get("request_body");
+    if stored_path != request_path || &stored_body != request_body {
+        return Err(AppError::Conflict(
+            "Idempotency key has already been used with a different request payload".to_string(),
+        ));
+    }
+
+    match row.get::<_, Option<i16>>("response_status") {
+        Some(status) => Ok(IdempotencyOutcome::Replay(StoredResponse {
+            status: status as u16,
+            body: row.get::<_, Option<serde_json::Value>>("response_body").unwrap_or_default(),
+        })),
+        None => Err(AppError::Conflict(
+            "A request with this idempotency key is still being processed".to_string(),
+        )),
+    }
+}
+
+/// Stores the response for a claimed key so that retries can replay it.
+pub async fn complete<T>(
+    client: &T,
+    user_id: Uuid,
+    key: &str,
+    response: &StoredResponse,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "UPDATE idempotency_keys 
+             SET response_status = $3, response_body = $4 
+             WHERE user_id = $1 AND idempotency_key = $2",
+            &[&user_id, &key, &(response.status as i16), &response.body],
+        )
+        .await?;
+
+    Ok(())
+}
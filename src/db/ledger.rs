@@ -0,0 +1,203 @@
+use crate::db::accounts;
+use crate::models::ledger::{ensure_balanced, EntryType, LedgerAccount, LedgerEntry, Posting};
+use crate::models::statement::StatementEntry;
+use crate::models::transaction::TransactionType;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use tokio_postgres::Portal;
+use uuid::Uuid;
+
+/// Posts a balanced set of ledger entries for a transaction and applies the
+/// resulting movements to the customer account balances. Debits are rejected
+/// if they would overdraw the account.
+pub async fn post_entries<T>(
+    client: &T,
+    transaction_id: Uuid,
+    postings: &[Posting],
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    post(client, transaction_id, postings, false).await
+}
+
+/// Posts entries that unwind an earlier movement. Unlike `post_entries` these
+/// are applied even if they leave a customer account with a negative balance.
+pub async fn post_compensating_entries<T>(
+    client: &T,
+    transaction_id: Uuid,
+    postings: &[Posting],
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    post(client, transaction_id, postings, true).await
+}
+
+async fn post<T>(
+    client: &T,
+    transaction_id: Uuid,
+    postings: &[Posting],
+    allow_overdraft: bool,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    ensure_balanced(postings).map_err(AppError::Internal)?;
+
+    for posting in postings {
+        let (account_id, system_account) = match posting.account {
+            LedgerAccount::Customer(account_id) => (Some(account_id), None),
+            LedgerAccount::System(system_account) => (None, Some(system_account.as_str())),
+        };
+
+        client
+            .execute(
+                "INSERT INTO ledger_entries
+                 (transaction_id, account_id, system_account, entry_type, amount, currency)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &transaction_id,
+                    &account_id,
+                    &system_account,
+                    &posting.entry_type.to_string(),
+                    &posting.amount,
+                    &posting.currency,
+                ],
+            )
+            .await?;
+
+        if let Some(account_id) = account_id {
+            match posting.entry_type {
+                EntryType::Debit if allow_overdraft => {
+                    accounts::update_balance(client, account_id, -posting.amount).await?;
+                }
+                EntryType::Debit => {
+                    accounts::debit_balance(client, account_id, posting.amount).await?;
+                }
+                EntryType::Credit => {
+                    accounts::update_balance(client, account_id, posting.amount).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn get_account_entries<T>(client: &T, account_id: Uuid) -> Result<Vec<LedgerEntry>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT id, transaction_id, account_id, system_account, entry_type, amount, currency, created_at
+             FROM ledger_entries
+             WHERE account_id = $1
+             ORDER BY created_at, id",
+            &[&account_id],
+        )
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(LedgerEntry {
+                id: row.get("id"),
+                transaction_id: row.get("transaction_id"),
+                account_id: row.get("account_id"),
+                system_account: row.get("system_account"),
+                entry_type: EntryType::try_from(row.get::<_, &str>("entry_type")).map_err(AppError::Internal)?,
+                amount: row.get("amount"),
+                currency: row.get("currency"),
+                created_at: row.get("created_at"),
+            })
+        })
+        .collect()
+}
+
+/// Derives an account balance from its ledger entries alone.
+pub async fn get_ledger_balance<T>(client: &T, account_id: Uuid) -> Result<i64, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "SELECT COALESCE(SUM(CASE WHEN entry_type = 'credit' THEN amount ELSE -amount END), 0)::BIGINT AS balance
+             FROM ledger_entries
+             WHERE account_id = $1",
+            &[&account_id],
+        )
+        .await?;
+
+    Ok(row.get("balance"))
+}
+
+/// The account's balance from the entries posted before `at`.
+pub async fn get_balance_before<T>(client: &T, account_id: Uuid, at: DateTime<Utc>) -> Result<i64, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "SELECT COALESCE(SUM(CASE WHEN entry_type = 'credit' THEN amount ELSE -amount END), 0)::BIGINT AS balance
+             FROM ledger_entries
+             WHERE account_id = $1 AND created_at < $2",
+            &[&account_id, &at],
+        )
+        .await?;
+
+    Ok(row.get("balance"))
+}
+
+/// Opens a portal over the account's entries posted in `[from, to)`, oldest
+/// first, so a statement can read them in batches with
+/// `fetch_statement_entries` instead of all at once.
+pub async fn bind_statement_entries(
+    tx: &tokio_postgres::Transaction<'_>,
+    account_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Portal, AppError> {
+    let portal = tx
+        .bind(
+            "SELECT l.id, l.transaction_id, l.entry_type, l.amount, l.created_at,
+                    t.transaction_type, t.description
+             FROM ledger_entries l
+             JOIN transactions t ON t.id = l.transaction_id
+             WHERE l.account_id = $1 AND l.created_at >= $2 AND l.created_at < $3
+             ORDER BY l.created_at, l.id",
+            &[&account_id, &from, &to],
+        )
+        .await?;
+
+    Ok(portal)
+}
+
+/// The next `max_rows` entries from a portal opened by
+/// `bind_statement_entries`; fewer means the portal is exhausted.
+pub async fn fetch_statement_entries(
+    tx: &tokio_postgres::Transaction<'_>,
+    portal: &Portal,
+    max_rows: i32,
+) -> Result<Vec<StatementEntry>, AppError> {
+    let rows = tx.query_portal(portal, max_rows).await?;
+
+    rows.iter()
+        .map(|row| {
+            let entry_type = EntryType::try_from(row.get::<_, &str>("entry_type")).map_err(AppError::Internal)?;
+            let amount: i64 = row.get("amount");
+            Ok(StatementEntry {
+                entry_id: row.get("id"),
+                transaction_id: row.get("transaction_id"),
+                transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
+                description: row.get("description"),
+                entry_type,
+                amount: match entry_type {
+                    EntryType::Credit => amount,
+                    EntryType::Debit => -amount,
+                },
+                created_at: row.get("created_at"),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,240 @@
+use crate::db::accounts;
+use crate::models::account::Account;
+use crate::models::limit::{
+    LimitExceeded, LimitScope, LimitUsage, LimitWindows, SpendingLimitSet, SpendingLimits,
+};
+use crate::models::transaction::{CreateTransactionRequest, TransactionType};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn limits_from_row(row: &Row) -> SpendingLimits {
+    SpendingLimits {
+        max_transaction_amount: row.get("max_transaction_amount"),
+        daily_amount: row.get("daily_amount"),
+        monthly_amount: row.get("monthly_amount"),
+        hourly_count: row.get("hourly_count"),
+    }
+}
+
+fn limit_set_from_row(row: &Row, scope: LimitScope) -> SpendingLimitSet {
+    SpendingLimitSet {
+        scope,
+        subject_id: row.get("subject_id"),
+        limits: limits_from_row(row),
+        updated_by: row.get("updated_by"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// The limits stored for `subject_id`, or for the default when it is unset.
+/// Subjects without any stored limits have none.
+pub async fn get_limits<T>(
+    client: &T,
+    scope: LimitScope,
+    subject_id: Option<Uuid>,
+) -> Result<SpendingLimitSet, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT * FROM spending_limits WHERE scope = $1 AND subject_id IS NOT DISTINCT FROM $2",
+            &[&scope.to_string(), &subject_id],
+        )
+        .await?;
+
+    Ok(match row {
+        Some(row) => limit_set_from_row(&row, scope),
+        None => SpendingLimitSet {
+            scope,
+            subject_id,
+            limits: SpendingLimits::default(),
+            updated_by: None,
+            updated_at: Utc::now(),
+        },
+    })
+}
+
+/// Replaces the limits for `subject_id`, or the default when it is unset.
+pub async fn set_limits<T>(
+    client: &T,
+    scope: LimitScope,
+    subject_id: Option<Uuid>,
+    limits: &SpendingLimits,
+    updated_by: Uuid,
+) -> Result<SpendingLimitSet, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = match subject_id {
+        None => {
+            client
+                .query_one(
+                    "UPDATE spending_limits
+                     SET max_transaction_amount = $2, daily_amount = $3, monthly_amount = $4,
+                         hourly_count = $5, updated_by = $6, updated_at = NOW()
+                     WHERE scope = $1 AND subject_id IS NULL
+                     RETURNING *",
+                    &[
+                        &scope.to_string(),
+                        &limits.max_transaction_amount,
+                        &limits.daily_amount,
+                        &limits.monthly_amount,
+                        &limits.hourly_count,
+                        &updated_by,
+                    ],
+                )
+                .await?
+        }
+        Some(subject_id) => {
+            client
+                .query_one(
+                    "INSERT INTO spending_limits
+                     (scope, subject_id, max_transaction_amount, daily_amount, monthly_amount, hourly_count, updated_by)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)
+                     ON CONFLICT (scope, subject_id) WHERE subject_id IS NOT NULL
+                     DO UPDATE SET max_transaction_amount = EXCLUDED.max_transaction_amount,
+                                   daily_amount = EXCLUDED.daily_amount,
+                                   monthly_amount = EXCLUDED.monthly_amount,
+                                   hourly_count = EXCLUDED.hourly_count,
+                                   updated_by = EXCLUDED.updated_by,
+                                   updated_at = NOW()
+                     RETURNING *",
+                    &[
+                        &scope.to_string(),
+                        &subject_id,
+                        &limits.max_transaction_amount,
+                        &limits.daily_amount,
+                        &limits.monthly_amount,
+                        &limits.hourly_count,
+                        &updated_by,
+                    ],
+                )
+                .await?
+        }
+    };
+
+    Ok(limit_set_from_row(&row, scope))
+}
+
+/// The limits that apply to an account: its own, falling back to the default
+/// for any it does not set.
+pub async fn get_account_limits<T>(client: &T, account_id: Uuid) -> Result<SpendingLimits, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let own = get_limits(client, LimitScope::Account, Some(account_id)).await?;
+    let default = get_limits(client, LimitScope::Default, None).await?;
+
+    Ok(own.limits.or(default.limits))
+}
+
+/// Outgoing money counted against an account's limits, or, for the user
+/// scope, against its owner's limits in the account's currency. Withdrawals
+/// and transfers count while pending, under review or completed; transfers between the
+/// user's own accounts never count, since the money stays with them.
+pub async fn get_usage<T>(
+    client: &T,
+    scope: LimitScope,
+    account: &Account,
+    windows: &LimitWindows,
+) -> Result<LimitUsage, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let (subject_filter, subject_id) = match scope {
+        LimitScope::User => ("s.user_id = $1", account.user_id),
+        _ => ("t.source_account_id = $1", account.id),
+    };
+    let row = client
+        .query_one(
+            &format!(
+                "SELECT COALESCE(SUM(t.amount) FILTER (WHERE t.created_at >= $4), 0)::BIGINT AS daily_amount,
+                        COALESCE(SUM(t.amount) FILTER (WHERE t.created_at >= $5), 0)::BIGINT AS monthly_amount,
+                        COUNT(*) FILTER (WHERE t.created_at > $6) AS hourly_count,
+                        MIN(t.created_at) FILTER (WHERE t.created_at > $6) AS oldest_in_hour
+                 FROM transactions t
+                 JOIN accounts s ON s.id = t.source_account_id
+                 LEFT JOIN accounts d ON d.id = t.destination_account_id
+                 WHERE {} AND t.currency = $2
+                   AND t.transaction_type IN ('withdrawal', 'transfer')
+                   AND t.status IN ('pending', 'under_review', 'completed')
+                   AND (d.id IS NULL OR d.user_id <> s.user_id)
+                   AND t.created_at >= $3",
+                subject_filter
+            ),
+            &[
+                &subject_id,
+                &account.currency,
+                &windows.earliest(),
+                &windows.day_start,
+                &windows.month_start,
+                &windows.hour_start,
+            ],
+        )
+        .await?;
+
+    Ok(LimitUsage {
+        daily_amount: row.get("daily_amount"),
+        monthly_amount: row.get("monthly_amount"),
+        hourly_count: row.get("hourly_count"),
+        oldest_in_hour: row.get("oldest_in_hour"),
+    })
+}
+
+/// Refuses a withdrawal or transfer that would break the source account's
+/// limits or its owner's. Runs after the request's accounts are locked, so
+/// debits from the same account are checked one at a time; the owner's row is
+/// locked as well when they have limits of their own, which does the same for
+/// debits from their other accounts.
+pub async fn enforce<T>(
+    client: &T,
+    data: &CreateTransactionRequest,
+    transaction_type: &TransactionType,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let (TransactionType::Withdrawal | TransactionType::Transfer, Some(source_account_id)) =
+        (transaction_type, data.source_account_id)
+    else {
+        return Ok(());
+    };
+
+    let source = accounts::get_account(client, source_account_id).await?;
+    if let Some(destination_account_id) = data.destination_account_id {
+        let destination = accounts::get_account(client, destination_account_id).await?;
+        if destination.user_id == source.user_id {
+            return Ok(());
+        }
+    }
+
+    let windows = LimitWindows::at(Utc::now());
+
+    let account_limits = get_account_limits(client, source.id).await?;
+    if !account_limits.is_empty() {
+        let usage = get_usage(client, LimitScope::Account, &source, &windows).await?;
+        usage
+            .check(&account_limits, &windows, data.amount)
+            .map_err(|exceeded| {
+                AppError::LimitExceeded(LimitExceeded::new(LimitScope::Account, &source.currency, exceeded))
+            })?;
+    }
+
+    let user_limits = get_limits(client, LimitScope::User, Some(source.user_id)).await?.limits;
+    if !user_limits.is_empty() {
+        client
+            .execute("SELECT id FROM users WHERE id = $1 FOR UPDATE", &[&source.user_id])
+            .await?;
+        let usage = get_usage(client, LimitScope::User, &source, &windows).await?;
+        usage
+            .check(&user_limits, &windows, data.amount)
+            .map_err(|exceeded| {
+                AppError::LimitExceeded(LimitExceeded::new(LimitScope::User, &source.currency, exceeded))
+            })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::db::accounts;
+use crate::models::merchant::{CreateMerchantRequest, Merchant};
+use crate::utils::error::AppError;
+use deadpool_postgres::Client;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+const MERCHANT_COLUMNS: &str = "m.id, m.user_id, m.name, a.id AS settlement_account_id, a.currency, m.created_at, m.updated_at";
+
+fn merchant_from_row(row: &Row) -> Merchant {
+    Merchant {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        name: row.get("name"),
+        settlement_account_id: row.get("settlement_account_id"),
+        currency: row.get("currency"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Creates a merchant together with its settlement account.
+pub async fn create_merchant(
+    client: &mut Client,
+    user_id: Uuid,
+    data: &CreateMerchantRequest,
+) -> Result<Merchant, AppError> {
+    let tx = client.transaction().await?;
+
+    let row = tx
+        .query_one(
+            "INSERT INTO merchants (user_id, name) VALUES ($1, $2) RETURNING id",
+            &[&user_id, &data.name],
+        )
+        .await?;
+    let merchant_id: Uuid = row.get("id");
+
+    accounts::create_settlement_account(&tx, user_id, merchant_id, &data.currency).await?;
+    let merchant = get_merchant(&tx, merchant_id).await?;
+
+    tx.commit().await?;
+
+    Ok(merchant)
+}
+
+pub async fn get_merchant<T>(client: &T, merchant_id: Uuid) -> Result<Merchant, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            &format!(
+                "SELECT {} FROM merchants m JOIN accounts a ON a.merchant_id = m.id WHERE m.id = $1",
+                MERCHANT_COLUMNS
+            ),
+            &[&merchant_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Merchant not found: {}", merchant_id)))?;
+
+    Ok(merchant_from_row(&row))
+}
+
+pub async fn get_user_merchants(client: &Client, user_id: Uuid) -> Result<Vec<Merchant>, AppError> {
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {} FROM merchants m JOIN accounts a ON a.merchant_id = m.id
+                 WHERE m.user_id = $1
+                 ORDER BY m.created_at",
+                MERCHANT_COLUMNS
+            ),
+            &[&user_id],
+        )
+        .await?;
+
+    Ok(rows.iter().map(merchant_from_row).collect())
+}
@@ -6,6 +6,20 @@ pub mod users;
 pub mod accounts;
 pub mod transactions;
 pub mod decimal;
+pub mod ledger;
+pub mod idempotency;
+pub mod holds;
+pub mod fx;
+pub mod scheduled_transfers;
+pub mod webhooks;
+pub mod outbox;
+pub mod reconciliation;
+pub mod settlements;
+pub mod merchants;
+pub mod payment_intents;
+pub mod limits;
+pub mod risk;
+pub mod screening;
 
 #[derive(Clone)]
 pub struct Database {
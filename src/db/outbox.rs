@@ -0,0 +1,179 @@
+use crate::models::account::Account;
+use crate::models::outbox::{AggregateType, OutboxEvent};
+use crate::utils::error::AppError;
+use serde_json::json;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Advisory lock held by the relay while it publishes a batch, so that only
+/// one relay publishes at a time and each aggregate's events go out in order.
+const RELAY_LOCK_KEY: i64 = 0x6f75_7462_6f78;
+
+fn outbox_event_from_row(row: &Row) -> Result<OutboxEvent, AppError> {
+    Ok(OutboxEvent {
+        id: row.get("id"),
+        position: row.get("position"),
+        event_id: row.get("event_id"),
+        aggregate_type: AggregateType::try_from(row.get::<_, &str>("aggregate_type")).map_err(AppError::Internal)?,
+        aggregate_id: row.get("aggregate_id"),
+        user_ids: row.get("user_ids"),
+        event_type: row.get("event_type"),
+        payload: row.get("payload"),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Records a domain event. Must run inside the database transaction that made
+/// the change, so the event exists if and only if the change commits.
+pub async fn record<T>(
+    client: &T,
+    aggregate_type: AggregateType,
+    aggregate_id: Uuid,
+    user_ids: &[Uuid],
+    event_type: &str,
+    payload: serde_json::Value,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "INSERT INTO outbox_events (aggregate_type, aggregate_id, user_ids, event_type, payload)
+             VALUES ($1, $2, $3, $4, $5)",
+            &[&aggregate_type.to_string(), &aggregate_id, &user_ids, &event_type, &payload],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Records an event carrying a snapshot of an account after a change.
+pub async fn record_account_event<T>(client: &T, event_type: &str, account: &Account) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let payload = json!({
+        "account": account,
+        "available_balance": account.available_balance(),
+    });
+
+    record(
+        client,
+        AggregateType::Account,
+        account.id,
+        &[account.user_id],
+        event_type,
+        payload,
+    )
+    .await
+}
+
+/// Takes the relay lock for the rest of the database transaction, returning
+/// false if another relay holds it.
+pub async fn try_lock_relay<T>(client: &T) -> Result<bool, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one("SELECT pg_try_advisory_xact_lock($1) AS locked", &[&RELAY_LOCK_KEY])
+        .await?;
+
+    Ok(row.get("locked"))
+}
+
+/// Numbers up to `limit` committed events that have no position yet, in id
+/// order, returning how many were. Run under the relay lock and committed
+/// before the events are published, so positions follow the order events
+/// became visible and nobody sees an event before its position is durable.
+pub async fn assign_positions<T>(client: &T, limit: i64) -> Result<u64, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let assigned = client
+        .execute(
+            "UPDATE outbox_events o
+             SET position = p.position
+             FROM (
+                 SELECT id, nextval('outbox_events_position_seq') AS position
+                 FROM (SELECT id FROM outbox_events WHERE position IS NULL ORDER BY id LIMIT $1) unpositioned
+             ) p
+             WHERE o.id = p.id",
+            &[&limit],
+        )
+        .await?;
+
+    Ok(assigned)
+}
+
+/// The oldest unpublished events that have been given a position.
+pub async fn get_unpublished<T>(client: &T, limit: i64) -> Result<Vec<OutboxEvent>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT id, position, event_id, aggregate_type, aggregate_id, user_ids, event_type, payload, created_at
+             FROM outbox_events
+             WHERE published_at IS NULL AND position IS NOT NULL
+             ORDER BY position
+             LIMIT $1",
+            &[&limit],
+        )
+        .await?;
+
+    rows.iter().map(outbox_event_from_row).collect()
+}
+
+/// Events concerning `user_id` after position `after`, oldest first.
+pub async fn get_user_events_after<T>(
+    client: &T,
+    user_id: Uuid,
+    after: i64,
+    limit: i64,
+) -> Result<Vec<OutboxEvent>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT id, position, event_id, aggregate_type, aggregate_id, user_ids, event_type, payload, created_at
+             FROM outbox_events
+             WHERE user_ids @> ARRAY[$1::UUID] AND position > $2
+             ORDER BY position
+             LIMIT $3",
+            &[&user_id, &after, &limit],
+        )
+        .await?;
+
+    rows.iter().map(outbox_event_from_row).collect()
+}
+
+pub async fn mark_published<T>(client: &T, ids: &[i64]) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "UPDATE outbox_events SET published_at = NOW() WHERE id = ANY($1)",
+            &[&ids],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes events published more than `retention_seconds` ago.
+pub async fn delete_published_before<T>(client: &T, retention_seconds: i64) -> Result<u64, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let deleted = client
+        .execute(
+            "DELETE FROM outbox_events
+             WHERE published_at < NOW() - make_interval(secs => $1)",
+            &[&(retention_seconds as f64)],
+        )
+        .await?;
+
+    Ok(deleted)
+}
@@ -0,0 +1,298 @@
+use crate::db::transactions::Screening;
+use crate::db::{accounts, outbox, transactions};
+use crate::models::merchant::Merchant;
+use crate::models::outbox::AggregateType;
+use crate::models::payment_intent::{
+    ConfirmPaymentIntentRequest, CreatePaymentIntentRequest, PaymentIntent, PaymentIntentStatus,
+};
+use crate::models::transaction::{CreateTransactionRequest, TransactionStatus, TransactionType};
+use crate::utils::error::AppError;
+use deadpool_postgres::Client;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde_json::json;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn payment_intent_from_row(row: &Row) -> PaymentIntent {
+    let metadata: serde_json::Value = row.get("metadata");
+
+    PaymentIntent {
+        id: row.get("id"),
+        merchant_id: row.get("merchant_id"),
+        destination_account_id: row.get("destination_account_id"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        description: row.get("description"),
+        metadata: serde_json::from_value(metadata).unwrap_or_default(),
+        client_secret: row.get("client_secret"),
+        status: PaymentIntentStatus::from(row.get::<_, &str>("status")),
+        customer_user_id: row.get("customer_user_id"),
+        source_account_id: row.get("source_account_id"),
+        transaction_id: row.get("transaction_id"),
+        last_error: row.get("last_error"),
+        cancellation_reason: row.get("cancellation_reason"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn generate_client_secret(payment_intent_id: Uuid) -> String {
+    let mut bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut bytes);
+    format!("pi_{}_secret_{}", payment_intent_id.simple(), hex::encode(bytes))
+}
+
+pub async fn create_payment_intent(
+    client: &mut Client,
+    merchant: &Merchant,
+    data: &CreatePaymentIntentRequest,
+) -> Result<PaymentIntent, AppError> {
+    let tx = client.transaction().await?;
+
+    let payment_intent_id = Uuid::new_v4();
+    let metadata = serde_json::to_value(&data.metadata)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize metadata: {}", e)))?;
+    let row = tx
+        .query_one(
+            "INSERT INTO payment_intents
+             (id, merchant_id, destination_account_id, amount, currency, description, metadata, client_secret)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING *",
+            &[
+                &payment_intent_id,
+                &merchant.id,
+                &merchant.settlement_account_id,
+                &data.amount,
+                &data.currency,
+                &data.description,
+                &metadata,
+                &generate_client_secret(payment_intent_id),
+            ],
+        )
+        .await?;
+    let payment_intent = payment_intent_from_row(&row);
+
+    record_event(&tx, "payment_intent.created", &payment_intent).await?;
+
+    tx.commit().await?;
+
+    Ok(payment_intent)
+}
+
+pub async fn get_payment_intent(client: &Client, payment_intent_id: Uuid) -> Result<PaymentIntent, AppError> {
+    let row = client
+        .query_opt("SELECT * FROM payment_intents WHERE id = $1", &[&payment_intent_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Payment intent not found: {}", payment_intent_id)))?;
+
+    Ok(payment_intent_from_row(&row))
+}
+
+async fn lock_payment_intent<T>(client: &T, payment_intent_id: Uuid) -> Result<PaymentIntent, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT * FROM payment_intents WHERE id = $1 FOR UPDATE",
+            &[&payment_intent_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Payment intent not found: {}", payment_intent_id)))?;
+
+    Ok(payment_intent_from_row(&row))
+}
+
+fn ensure_requires_confirmation(payment_intent: &PaymentIntent, action: &str) -> Result<(), AppError> {
+    if payment_intent.status != PaymentIntentStatus::RequiresConfirmation {
+        return Err(AppError::Conflict(format!(
+            "Payment intent cannot be {} because it has {}",
+            action, payment_intent.status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Pays an intent by transferring its amount from the customer's account to
+/// the merchant's settlement account, screened like any other transfer. The
+/// intent is locked throughout, so it can only be paid once. If the transfer
+/// is refused the intent keeps waiting and records why, so the customer can
+/// try again. One held for review leaves the intent `processing` until the
+/// review is decided.
+pub async fn confirm_payment_intent(
+    client: &mut Client,
+    user_id: Uuid,
+    payment_intent_id: Uuid,
+    data: &ConfirmPaymentIntentRequest,
+    screening: &Screening<'_>,
+) -> Result<PaymentIntent, AppError> {
+    let mut tx = client.transaction().await?;
+
+    let payment_intent = lock_payment_intent(&tx, payment_intent_id).await?;
+    if !payment_intent.verify_client_secret(&data.client_secret) {
+        return Err(AppError::Forbidden("Invalid client secret".to_string()));
+    }
+    ensure_requires_confirmation(&payment_intent, "confirmed")?;
+
+    let request = CreateTransactionRequest {
+        source_account_id: Some(data.source_account_id),
+        destination_account_id: Some(payment_intent.destination_account_id),
+        amount: payment_intent.amount,
+        currency: payment_intent.currency.clone(),
+        transaction_type: TransactionType::Transfer.to_string(),
+        description: payment_intent.description.clone(),
+        authorize_only: false,
+        fx_quote_id: None,
+    };
+    let error = match transactions::create_screened_transaction(&mut tx, user_id, &request, screening).await {
+        Ok(transaction) => {
+            let (status, event_type) = match transaction.status {
+                TransactionStatus::UnderReview => (PaymentIntentStatus::Processing, "payment_intent.processing"),
+                _ => (PaymentIntentStatus::Succeeded, "payment_intent.succeeded"),
+            };
+            let row = tx
+                .query_one(
+                    "UPDATE payment_intents
+                     SET status = $1, customer_user_id = $2, source_account_id = $3,
+                         transaction_id = $4, last_error = NULL, updated_at = NOW()
+                     WHERE id = $5
+                     RETURNING *",
+                    &[
+                        &status.to_string(),
+                        &user_id,
+                        &data.source_account_id,
+                        &transaction.id,
+                        &payment_intent_id,
+                    ],
+                )
+                .await?;
+            let payment_intent = payment_intent_from_row(&row);
+
+            record_event(&tx, event_type, &payment_intent).await?;
+            tx.commit().await?;
+
+            return Ok(payment_intent);
+        }
+        Err(error @ (AppError::Database(_) | AppError::Internal(_))) => return Err(error),
+        Err(error) => error,
+    };
+
+    let row = tx
+        .query_one(
+            "UPDATE payment_intents SET last_error = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+            &[&error.status_and_message().1, &payment_intent_id],
+        )
+        .await?;
+    record_event(&tx, "payment_intent.payment_failed", &payment_intent_from_row(&row)).await?;
+    tx.commit().await?;
+
+    Err(error)
+}
+
+/// Settles an intent whose payment was held for review once the transfer
+/// leaves review. It succeeds if the transfer completed, and otherwise goes
+/// back to waiting for confirmation with the reason in `last_error`. Must run
+/// in the database transaction that moves the transfer on.
+pub async fn settle_reviewed_payment<T>(
+    client: &T,
+    transaction_id: Uuid,
+    transaction_status: &TransactionStatus,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let Some(row) = client
+        .query_opt(
+            "SELECT * FROM payment_intents WHERE transaction_id = $1 AND status = $2 FOR UPDATE",
+            &[&transaction_id, &PaymentIntentStatus::Processing.to_string()],
+        )
+        .await?
+    else {
+        return Ok(());
+    };
+    let payment_intent = payment_intent_from_row(&row);
+
+    let (row, event_type) = if *transaction_status == TransactionStatus::Completed {
+        let row = client
+            .query_one(
+                "UPDATE payment_intents SET status = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+                &[&PaymentIntentStatus::Succeeded.to_string(), &payment_intent.id],
+            )
+            .await?;
+        (row, "payment_intent.succeeded")
+    } else {
+        let row = client
+            .query_one(
+                "UPDATE payment_intents
+                 SET status = $1, transaction_id = NULL, last_error = $2, updated_at = NOW()
+                 WHERE id = $3
+                 RETURNING *",
+                &[
+                    &PaymentIntentStatus::RequiresConfirmation.to_string(),
+                    &format!("Payment was not approved; transaction {} is {}", transaction_id, transaction_status),
+                    &payment_intent.id,
+                ],
+            )
+            .await?;
+        (row, "payment_intent.payment_failed")
+    };
+
+    record_event(client, event_type, &payment_intent_from_row(&row)).await
+}
+
+pub async fn cancel_payment_intent(
+    client: &mut Client,
+    payment_intent_id: Uuid,
+    cancellation_reason: Option<&str>,
+) -> Result<PaymentIntent, AppError> {
+    let tx = client.transaction().await?;
+
+    let payment_intent = lock_payment_intent(&tx, payment_intent_id).await?;
+    ensure_requires_confirmation(&payment_intent, "canceled")?;
+
+    let row = tx
+        .query_one(
+            "UPDATE payment_intents
+             SET status = $1, cancellation_reason = $2, updated_at = NOW()
+             WHERE id = $3
+             RETURNING *",
+            &[
+                &PaymentIntentStatus::Canceled.to_string(),
+                &cancellation_reason,
+                &payment_intent_id,
+            ],
+        )
+        .await?;
+    let payment_intent = payment_intent_from_row(&row);
+
+    record_event(&tx, "payment_intent.canceled", &payment_intent).await?;
+
+    tx.commit().await?;
+
+    Ok(payment_intent)
+}
+
+/// Publishes a change to an intent to the merchant and, once there is one, the
+/// customer.
+async fn record_event<T>(client: &T, event_type: &str, payment_intent: &PaymentIntent) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let account_ids: Vec<Uuid> = [Some(payment_intent.destination_account_id), payment_intent.source_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    let user_ids = accounts::get_owner_ids(client, &account_ids).await?;
+
+    outbox::record(
+        client,
+        AggregateType::PaymentIntent,
+        payment_intent.id,
+        &user_ids,
+        event_type,
+        json!({ "payment_intent": payment_intent }),
+    )
+    .await
+}
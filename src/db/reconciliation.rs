@@ -0,0 +1,240 @@
+use crate::models::hold::HoldStatus;
+use crate::models::reconciliation::{
+    BalanceDiscrepancy, OrphanReason, OrphanedTransaction, ReconciliationReport,
+};
+use crate::models::transaction::TransactionType;
+use crate::utils::error::AppError;
+use deadpool_postgres::Client;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn discrepancy_from_row(row: &Row) -> BalanceDiscrepancy {
+    BalanceDiscrepancy::new(
+        row.get("account_id"),
+        row.get("currency"),
+        (row.get("stored_balance"), row.get("expected_balance")),
+        (row.get("stored_held_balance"), row.get("expected_held_balance")),
+    )
+}
+
+fn orphan_from_row(row: &Row) -> Result<OrphanedTransaction, AppError> {
+    let hold_status = row
+        .get::<_, Option<&str>>("hold_status")
+        .map(HoldStatus::try_from)
+        .transpose()
+        .map_err(AppError::Internal)?;
+
+    Ok(OrphanedTransaction {
+        transaction_id: row.get("transaction_id"),
+        transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
+        source_account_id: row.get("source_account_id"),
+        destination_account_id: row.get("destination_account_id"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        reason: OrphanReason::for_hold(hold_status.as_ref()),
+        hold_status,
+        created_at: row.get("created_at"),
+    })
+}
+
+pub async fn count_accounts<T>(client: &T) -> Result<i64, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client.query_one("SELECT COUNT(*) AS total FROM accounts", &[]).await?;
+
+    Ok(row.get("total"))
+}
+
+/// Recomputes every account's balance from its transactions and its held
+/// balance from its holds, returning the accounts where either differs from
+/// what is stored. A reversed transaction keeps only the part refunded before
+/// it was reversed, since the rest was posted back by the reversal and the
+/// refunds themselves are transactions of their own.
+pub async fn find_balance_discrepancies<T>(client: &T) -> Result<Vec<BalanceDiscrepancy>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "WITH movements AS (
+                 SELECT source_account_id AS account_id,
+                        -CASE WHEN status = 'completed' THEN amount ELSE refunded_amount END AS amount
+                 FROM transactions
+                 WHERE source_account_id IS NOT NULL AND status IN ('completed', 'reversed')
+                 UNION ALL
+                 SELECT destination_account_id,
+                        CASE WHEN status = 'completed' THEN COALESCE(destination_amount, amount)
+                             ELSE refunded_amount END
+                 FROM transactions
+                 WHERE destination_account_id IS NOT NULL AND status IN ('completed', 'reversed')
+             ),
+             expected AS (
+                 SELECT account_id, SUM(amount)::BIGINT AS balance
+                 FROM movements
+                 GROUP BY account_id
+             ),
+             held AS (
+                 SELECT account_id, SUM(amount)::BIGINT AS held_balance
+                 FROM transaction_holds
+                 WHERE status = 'active'
+                 GROUP BY account_id
+             )
+             SELECT a.id AS account_id, a.currency,
+                    a.balance AS stored_balance,
+                    COALESCE(e.balance, 0) AS expected_balance,
+                    a.held_balance AS stored_held_balance,
+                    COALESCE(h.held_balance, 0) AS expected_held_balance
+             FROM accounts a
+             LEFT JOIN expected e ON e.account_id = a.id
+             LEFT JOIN held h ON h.account_id = a.id
+             WHERE a.balance <> COALESCE(e.balance, 0)
+                OR a.held_balance <> COALESCE(h.held_balance, 0)
+             ORDER BY a.id",
+            &[],
+        )
+        .await?;
+
+    Ok(rows.iter().map(discrepancy_from_row).collect())
+}
+
+/// Finds transactions still `pending` more than `max_age_seconds` after they
+/// were created. Authorizations are only counted once their hold has been
+/// released or has outlived its expiry by the same margin, since until then
+/// they are legitimately waiting to be captured.
+pub async fn find_orphaned_transactions<T>(
+    client: &T,
+    max_age_seconds: i64,
+) -> Result<Vec<OrphanedTransaction>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT t.id AS transaction_id, t.transaction_type, t.source_account_id,
+                    t.destination_account_id, t.amount, t.currency, t.created_at,
+                    h.status AS hold_status
+             FROM transactions t
+             LEFT JOIN transaction_holds h ON h.transaction_id = t.id
+             WHERE t.status = 'pending'
+               AND t.created_at < NOW() - make_interval(secs => $1)
+               AND (h.id IS NULL
+                    OR h.status <> 'active'
+                    OR h.expires_at < NOW() - make_interval(secs => $1))
+             ORDER BY t.created_at, t.id",
+            &[&(max_age_seconds as f64)],
+        )
+        .await?;
+
+    rows.iter().map(orphan_from_row).collect()
+}
+
+/// Saves a report and its findings, returning the report's id.
+pub async fn save_report(client: &mut Client, report: &ReconciliationReport) -> Result<Uuid, AppError> {
+    let tx = client.transaction().await?;
+
+    let row = tx
+        .query_one(
+            "INSERT INTO reconciliation_reports
+             (started_at, finished_at, accounts_checked, discrepancy_count, orphaned_transaction_count)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id",
+            &[
+                &report.started_at,
+                &report.finished_at,
+                &report.accounts_checked,
+                &(report.discrepancies.len() as i32),
+                &(report.orphaned_transactions.len() as i32),
+            ],
+        )
+        .await?;
+    let report_id: Uuid = row.get("id");
+
+    for discrepancy in &report.discrepancies {
+        tx.execute(
+            "INSERT INTO reconciliation_discrepancies
+             (report_id, account_id, currency, stored_balance, expected_balance,
+              stored_held_balance, expected_held_balance)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            &[
+                &report_id,
+                &discrepancy.account_id,
+                &discrepancy.currency,
+                &discrepancy.stored_balance,
+                &discrepancy.expected_balance,
+                &discrepancy.stored_held_balance,
+                &discrepancy.expected_held_balance,
+            ],
+        )
+        .await?;
+    }
+
+    for orphan in &report.orphaned_transactions {
+        tx.execute(
+            "INSERT INTO reconciliation_orphaned_transactions
+             (report_id, transaction_id, hold_status, reason)
+             VALUES ($1, $2, $3, $4)",
+            &[
+                &report_id,
+                &orphan.transaction_id,
+                &orphan.hold_status.as_ref().map(ToString::to_string),
+                &orphan.reason.to_string(),
+            ],
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(report_id)
+}
+
+/// Loads a saved report. Orphaned transactions are shown as they were when the
+/// report was made, apart from the transaction details, which never change
+/// while a transaction is pending.
+pub async fn get_report(client: &Client, report_id: Uuid) -> Result<ReconciliationReport, AppError> {
+    let row = client
+        .query_opt(
+            "SELECT * FROM reconciliation_reports WHERE id = $1",
+            &[&report_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Reconciliation report not found: {}", report_id)))?;
+
+    let discrepancies = client
+        .query(
+            "SELECT * FROM reconciliation_discrepancies WHERE report_id = $1 ORDER BY account_id",
+            &[&report_id],
+        )
+        .await?;
+
+    let orphans = client
+        .query(
+            "SELECT o.transaction_id, o.hold_status, o.reason, t.transaction_type,
+                    t.source_account_id, t.destination_account_id, t.amount, t.currency,
+                    t.created_at
+             FROM reconciliation_orphaned_transactions o
+             JOIN transactions t ON t.id = o.transaction_id
+             WHERE o.report_id = $1
+             ORDER BY t.created_at, t.id",
+            &[&report_id],
+        )
+        .await?;
+
+    Ok(ReconciliationReport {
+        id: Some(row.get("id")),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+        accounts_checked: row.get("accounts_checked"),
+        discrepancies: discrepancies.iter().map(discrepancy_from_row).collect(),
+        orphaned_transactions: orphans
+            .iter()
+            .map(|row| {
+                Ok(OrphanedTransaction {
+                    reason: OrphanReason::from(row.get::<_, &str>("reason")),
+                    ..orphan_from_row(row)?
+                })
+            })
+            .collect::<Result<_, AppError>>()?,
+    })
+}
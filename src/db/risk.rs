@@ -0,0 +1,228 @@
+use crate::db::accounts;
+use crate::models::risk::{RiskAssessment, RiskContext, RiskReview, RiskReviewStatus, RiskSignals};
+use crate::models::transaction::CreateTransactionRequest;
+use crate::services::risk_service::RiskEngine;
+use crate::utils::error::AppError;
+use chrono::{Duration, Utc};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Most reviews returned by one listing.
+const REVIEW_PAGE_SIZE: i64 = 100;
+
+fn review_from_row(row: &Row) -> Result<RiskReview, AppError> {
+    let assessment: serde_json::Value = row.get("assessment");
+
+    Ok(RiskReview {
+        id: row.get("id"),
+        transaction_id: row.get("transaction_id"),
+        assessment: serde_json::from_value(assessment)
+            .map_err(|e| AppError::Internal(format!("Invalid stored risk assessment: {}", e)))?,
+        authorize_only: row.get("authorize_only"),
+        status: RiskReviewStatus::from(row.get::<_, &str>("status")),
+        decided_by: row.get("decided_by"),
+        note: row.get("note"),
+        decided_at: row.get("decided_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Screens a transfer to another user's account before it is recorded. Other
+/// transactions, and transfers between the user's own accounts, are not
+/// screened and return `None`. Must run after the request's accounts are
+/// locked, so the history it reads cannot change underneath it.
+pub async fn assess_transfer<T>(
+    client: &T,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    engine: &RiskEngine,
+    signals: &RiskSignals,
+) -> Result<Option<RiskAssessment>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let (Some(source_account_id), Some(destination_account_id)) =
+        (data.source_account_id, data.destination_account_id)
+    else {
+        return Ok(None);
+    };
+
+    let source = accounts::get_account(client, source_account_id).await?;
+    let destination = accounts::get_account(client, destination_account_id).await?;
+    if source.user_id == destination.user_id {
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    let window_start = now - Duration::seconds(engine.policy().velocity_window());
+
+    let row = client
+        .query_one(
+            "SELECT COUNT(*) AS previous_outgoing_count,
+                    COALESCE(ARRAY_AGG(created_at) FILTER (WHERE created_at > $2), '{}') AS recent_outgoing
+             FROM transactions
+             WHERE source_account_id = $1
+               AND transaction_type IN ('withdrawal', 'transfer')
+               AND status IN ('pending', 'under_review', 'completed')",
+            &[&source.id, &window_start],
+        )
+        .await?;
+    let previous_outgoing_count: i64 = row.get("previous_outgoing_count");
+    let recent_outgoing = row.get("recent_outgoing");
+
+    let known_destination = client
+        .query_opt(
+            "SELECT 1 FROM transactions t
+             JOIN accounts s ON s.id = t.source_account_id
+             WHERE s.user_id = $1 AND t.destination_account_id = $2
+               AND t.transaction_type = 'transfer' AND t.status = 'completed'
+             LIMIT 1",
+            &[&user_id, &destination.id],
+        )
+        .await?
+        .is_some();
+
+    // Transfers made without a request, such as scheduled ones, have no
+    // signals to compare against and are passed over
+    let previous_signals = client
+        .query_opt(
+            "SELECT e.event_data->'risk'->'signals' AS signals
+             FROM transaction_events e
+             JOIN transactions t ON t.id = e.transaction_id
+             JOIN accounts s ON s.id = t.source_account_id
+             WHERE s.user_id = $1
+               AND (e.event_data->'risk'->'signals'->>'ip' IS NOT NULL
+                    OR e.event_data->'risk'->'signals'->>'country' IS NOT NULL)
+             ORDER BY e.created_at DESC
+             LIMIT 1",
+            &[&user_id],
+        )
+        .await?
+        .and_then(|row| serde_json::from_value(row.get("signals")).ok());
+
+    let context = RiskContext {
+        amount: data.amount,
+        currency: data.currency.clone(),
+        source_account_created_at: source.created_at,
+        previous_outgoing_count,
+        known_destination,
+        recent_outgoing,
+        signals: signals.clone(),
+        previous_signals,
+        now,
+    };
+
+    Ok(Some(engine.assess(&context)))
+}
+
+pub async fn open_review<T>(
+    client: &T,
+    transaction_id: Uuid,
+    assessment: &RiskAssessment,
+    authorize_only: bool,
+) -> Result<RiskReview, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let assessment = serde_json::to_value(assessment)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize risk assessment: {}", e)))?;
+    let row = client
+        .query_one(
+            "INSERT INTO risk_reviews (transaction_id, assessment, authorize_only)
+             VALUES ($1, $2, $3)
+             RETURNING *",
+            &[&transaction_id, &assessment, &authorize_only],
+        )
+        .await?;
+
+    review_from_row(&row)
+}
+
+pub async fn get_review<T>(client: &T, review_id: Uuid) -> Result<RiskReview, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM risk_reviews WHERE id = $1", &[&review_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Risk review not found: {}", review_id)))?;
+
+    review_from_row(&row)
+}
+
+/// Loads an open review and locks it, so it can only be decided once.
+pub async fn lock_open_review<T>(client: &T, review_id: Uuid) -> Result<RiskReview, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM risk_reviews WHERE id = $1 FOR UPDATE", &[&review_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Risk review not found: {}", review_id)))?;
+    let review = review_from_row(&row)?;
+
+    if review.status != RiskReviewStatus::Open {
+        return Err(AppError::Conflict(format!("Risk review is already {}", review.status)));
+    }
+
+    Ok(review)
+}
+
+pub async fn close_review<T>(
+    client: &T,
+    review_id: Uuid,
+    status: &RiskReviewStatus,
+    decided_by: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<RiskReview, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "UPDATE risk_reviews
+             SET status = $1, decided_by = $2, note = $3, decided_at = NOW()
+             WHERE id = $4
+             RETURNING *",
+            &[&status.to_string(), &decided_by, &note, &review_id],
+        )
+        .await?;
+
+    review_from_row(&row)
+}
+
+/// Closes the open review of a transaction whose hold ran out before anyone
+/// decided it.
+pub async fn expire_review<T>(client: &T, transaction_id: Uuid) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    client
+        .execute(
+            "UPDATE risk_reviews SET status = $1, decided_at = NOW()
+             WHERE transaction_id = $2 AND status = $3",
+            &[
+                &RiskReviewStatus::Expired.to_string(),
+                &transaction_id,
+                &RiskReviewStatus::Open.to_string(),
+            ],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// The oldest reviews with `status`.
+pub async fn get_reviews<T>(client: &T, status: &RiskReviewStatus) -> Result<Vec<RiskReview>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT * FROM risk_reviews WHERE status = $1 ORDER BY created_at LIMIT $2",
+            &[&status.to_string(), &REVIEW_PAGE_SIZE],
+        )
+        .await?;
+
+    rows.iter().map(review_from_row).collect()
+}
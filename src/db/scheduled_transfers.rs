@@ -0,0 +1,408 @@
+use crate::db::transactions::Screening;
+use crate::db::{accounts, transactions};
+use crate::models::scheduled_transfer::{
+    CreateScheduledTransferRequest, RunStatus, Schedule, ScheduleStatus, ScheduledTransfer,
+    ScheduledTransferRun, UpdateScheduledTransferRequest,
+};
+use crate::models::transaction::{CreateTransactionRequest, TransactionType};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Client;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn scheduled_transfer_from_row(row: &Row) -> ScheduledTransfer {
+    ScheduledTransfer {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        source_account_id: row.get("source_account_id"),
+        destination_account_id: row.get("destination_account_id"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        description: row.get("description"),
+        cron_expression: row.get("cron_expression"),
+        interval_seconds: row.get("interval_seconds"),
+        start_at: row.get("start_at"),
+        end_at: row.get("end_at"),
+        max_occurrences: row.get("max_occurrences"),
+        occurrences: row.get("occurrences"),
+        consecutive_failures: row.get("consecutive_failures"),
+        status: ScheduleStatus::from(row.get::<_, &str>("status")),
+        next_run_at: row.get("next_run_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn run_from_row(row: &Row) -> ScheduledTransferRun {
+    ScheduledTransferRun {
+        id: row.get("id"),
+        scheduled_transfer_id: row.get("scheduled_transfer_id"),
+        scheduled_for: row.get("scheduled_for"),
+        status: RunStatus::from(row.get::<_, &str>("status")),
+        transaction_id: row.get("transaction_id"),
+        error: row.get("error"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// What the worker did with one due scheduled transfer.
+#[derive(Debug)]
+pub struct RunOutcome {
+    pub scheduled_transfer: ScheduledTransfer,
+    pub run: ScheduledTransferRun,
+}
+
+pub async fn create_scheduled_transfer(
+    client: &Client,
+    user_id: Uuid,
+    data: &CreateScheduledTransferRequest,
+) -> Result<ScheduledTransfer, AppError> {
+    let source_account = accounts::get_account(client, data.source_account_id).await?;
+    if source_account.user_id != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to transfer from this account".to_string(),
+        ));
+    }
+    if source_account.currency != data.currency {
+        return Err(AppError::BadRequest(format!(
+            "Currency mismatch: transfer is in {}, but source account is in {}",
+            data.currency, source_account.currency
+        )));
+    }
+    accounts::get_account(client, data.destination_account_id).await?;
+
+    let now = Utc::now();
+    let start_at = data.start_at.unwrap_or(now);
+    let schedule = Schedule::new(data.cron_expression.as_deref(), data.interval_seconds)
+        .map_err(AppError::BadRequest)?;
+    let next_run_at = schedule
+        .next_occurrence(start_at, data.end_at, now - Duration::nanoseconds(1))
+        .ok_or_else(|| AppError::BadRequest("Schedule has no occurrences after now".to_string()))?;
+
+    let row = client
+        .query_one(
+            "INSERT INTO scheduled_transfers
+             (user_id, source_account_id, destination_account_id, amount, currency, description,
+              cron_expression, interval_seconds, start_at, end_at, max_occurrences, next_run_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+             RETURNING *",
+            &[
+                &user_id,
+                &data.source_account_id,
+                &data.destination_account_id,
+                &data.amount,
+                &data.currency,
+                &data.description,
+                &data.cron_expression,
+                &data.interval_seconds,
+                &start_at,
+                &data.end_at,
+                &data.max_occurrences,
+                &next_run_at,
+            ],
+        )
+        .await?;
+
+    Ok(scheduled_transfer_from_row(&row))
+}
+
+pub async fn get_scheduled_transfer<T>(client: &T, id: Uuid) -> Result<ScheduledTransfer, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM scheduled_transfers WHERE id = $1", &[&id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Scheduled transfer not found: {}", id)))?;
+
+    Ok(scheduled_transfer_from_row(&row))
+}
+
+pub async fn get_user_scheduled_transfers(
+    client: &Client,
+    user_id: Uuid,
+) -> Result<Vec<ScheduledTransfer>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM scheduled_transfers WHERE user_id = $1 ORDER BY created_at DESC",
+            &[&user_id],
+        )
+        .await?;
+
+    Ok(rows.iter().map(scheduled_transfer_from_row).collect())
+}
+
+/// Loads and locks a user's scheduled transfer for modification.
+async fn lock_user_scheduled_transfer<T>(
+    client: &T,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<ScheduledTransfer, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM scheduled_transfers WHERE id = $1 FOR UPDATE", &[&id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Scheduled transfer not found: {}", id)))?;
+    let scheduled_transfer = scheduled_transfer_from_row(&row);
+
+    if scheduled_transfer.user_id != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this scheduled transfer".to_string(),
+        ));
+    }
+
+    if !matches!(
+        scheduled_transfer.status,
+        ScheduleStatus::Active | ScheduleStatus::Paused
+    ) {
+        return Err(AppError::BadRequest(format!(
+            "Scheduled transfer is {} and can no longer be changed",
+            scheduled_transfer.status
+        )));
+    }
+
+    Ok(scheduled_transfer)
+}
+
+/// Applies changes to a scheduled transfer. Changing when it runs, or resuming
+/// it, moves the next run to the next occurrence from now.
+pub async fn update_scheduled_transfer(
+    client: &mut Client,
+    user_id: Uuid,
+    id: Uuid,
+    data: &UpdateScheduledTransferRequest,
+) -> Result<ScheduledTransfer, AppError> {
+    let tx = client.transaction().await?;
+
+    let current = lock_user_scheduled_transfer(&tx, user_id, id).await?;
+    let mut updated = current.clone();
+
+    if let Some(amount) = data.amount {
+        updated.amount = amount;
+    }
+    if data.description.is_some() {
+        updated.description = data.description.clone();
+    }
+    // A schedule has exactly one of the two, so either replaces the schedule whole
+    if data.cron_expression.is_some() || data.interval_seconds.is_some() {
+        updated.cron_expression = data.cron_expression.clone();
+        updated.interval_seconds = data.interval_seconds;
+    }
+    if data.end_at.is_some() {
+        updated.end_at = data.end_at;
+    }
+    if data.max_occurrences.is_some() {
+        updated.max_occurrences = data.max_occurrences;
+    }
+    if let Some(status) = &data.status {
+        updated.status = status.clone();
+    }
+
+    let resumed = current.status == ScheduleStatus::Paused && updated.status == ScheduleStatus::Active;
+    let timing_changed = updated.cron_expression != current.cron_expression
+        || updated.interval_seconds != current.interval_seconds
+        || updated.end_at != current.end_at
+        || updated.max_occurrences != current.max_occurrences;
+
+    if resumed {
+        updated.consecutive_failures = 0;
+    }
+
+    if updated.status == ScheduleStatus::Paused {
+        updated.next_run_at = None;
+    } else if resumed || timing_changed {
+        updated.next_run_at = updated.next_run_after(Utc::now()).map_err(AppError::BadRequest)?;
+        if updated.next_run_at.is_none() {
+            updated.status = ScheduleStatus::Completed;
+        }
+    }
+
+    let row = tx
+        .query_one(
+            "UPDATE scheduled_transfers
+             SET amount = $1, description = $2, cron_expression = $3, interval_seconds = $4,
+                 end_at = $5, max_occurrences = $6, status = $7, next_run_at = $8,
+                 consecutive_failures = $9, updated_at = NOW()
+             WHERE id = $10
+             RETURNING *",
+            &[
+                &updated.amount,
+                &updated.description,
+                &updated.cron_expression,
+                &updated.interval_seconds,
+                &updated.end_at,
+                &updated.max_occurrences,
+                &updated.status.to_string(),
+                &updated.next_run_at,
+                &updated.consecutive_failures,
+                &id,
+            ],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(scheduled_transfer_from_row(&row))
+}
+
+pub async fn cancel_scheduled_transfer(
+    client: &mut Client,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<ScheduledTransfer, AppError> {
+    let tx = client.transaction().await?;
+
+    lock_user_scheduled_transfer(&tx, user_id, id).await?;
+    let row = tx
+        .query_one(
+            "UPDATE scheduled_transfers
+             SET status = $1, next_run_at = NULL, updated_at = NOW()
+             WHERE id = $2
+             RETURNING *",
+            &[&ScheduleStatus::Cancelled.to_string(), &id],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(scheduled_transfer_from_row(&row))
+}
+
+pub async fn get_runs(client: &Client, scheduled_transfer_id: Uuid) -> Result<Vec<ScheduledTransferRun>, AppError> {
+    let rows = client
+        .query(
+            "SELECT id, scheduled_transfer_id, scheduled_for, status, transaction_id, error, created_at
+             FROM scheduled_transfer_runs
+             WHERE scheduled_transfer_id = $1
+             ORDER BY created_at DESC",
+            &[&scheduled_transfer_id],
+        )
+        .await?;
+
+    Ok(rows.iter().map(run_from_row).collect())
+}
+
+/// Executes the earliest due scheduled transfer, if any, through the regular
+/// transaction path, screened like any other transfer. The transfer, its run
+/// record and the schedule update commit together, so an occurrence is never
+/// executed twice. A transfer held for review still counts as run.
+///
+/// A transfer that fails for lack of funds or a server error is retried after
+/// `retry_delay_seconds` until it has failed `max_failures` times in a row,
+/// when the schedule is paused. Any other rejection pauses it straight away,
+/// since retrying will not help. Every attempt is recorded as a run, so a
+/// schedule that keeps failing never holds up the ones due after it.
+pub async fn run_next_due(
+    client: &mut Client,
+    retry_delay_seconds: i64,
+    max_failures: i32,
+    screening: &Screening<'_>,
+) -> Result<Option<RunOutcome>, AppError> {
+    let mut tx = client.transaction().await?;
+
+    let Some(row) = tx
+        .query_opt(
+            "SELECT * FROM scheduled_transfers
+             WHERE status = 'active' AND next_run_at <= NOW()
+             ORDER BY next_run_at
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED",
+            &[],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let mut scheduled_transfer = scheduled_transfer_from_row(&row);
+    let scheduled_for = scheduled_transfer.next_run_at.unwrap();
+
+    let request = CreateTransactionRequest {
+        source_account_id: Some(scheduled_transfer.source_account_id),
+        destination_account_id: Some(scheduled_transfer.destination_account_id),
+        amount: scheduled_transfer.amount,
+        currency: scheduled_transfer.currency.clone(),
+        transaction_type: TransactionType::Transfer.to_string(),
+        description: scheduled_transfer.description.clone(),
+        authorize_only: false,
+        fx_quote_id: None,
+    };
+    let result =
+        transactions::create_screened_transaction(&mut tx, scheduled_transfer.user_id, &request, screening).await;
+
+    let now = Utc::now();
+    let (run_status, transaction_id, error) = match result {
+        Ok(transaction) => {
+            scheduled_transfer.occurrences += 1;
+            scheduled_transfer.consecutive_failures = 0;
+            // A schedule that can no longer be evaluated is paused rather than run again
+            let next_run_at = scheduled_transfer.next_run_after(now);
+            scheduled_transfer.status = match next_run_at {
+                Ok(Some(_)) => ScheduleStatus::Active,
+                Ok(None) => ScheduleStatus::Completed,
+                Err(_) => ScheduleStatus::Paused,
+            };
+            scheduled_transfer.next_run_at = next_run_at.clone().unwrap_or_default();
+            (RunStatus::Succeeded, Some(transaction.id), next_run_at.err())
+        }
+        Err(error) => {
+            scheduled_transfer.consecutive_failures += 1;
+            let retry = matches!(
+                error,
+                AppError::InsufficientFunds(_) | AppError::Database(_) | AppError::Internal(_)
+            ) && scheduled_transfer.consecutive_failures < max_failures;
+            if retry {
+                scheduled_transfer.next_run_at = Some(retry_at(now, retry_delay_seconds));
+            } else {
+                scheduled_transfer.status = ScheduleStatus::Paused;
+                scheduled_transfer.next_run_at = None;
+            }
+            (RunStatus::Failed, None, Some(error.status_and_message().1))
+        }
+    };
+
+    let run_row = tx
+        .query_one(
+            "INSERT INTO scheduled_transfer_runs (scheduled_transfer_id, scheduled_for, status, transaction_id, error)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id, scheduled_transfer_id, scheduled_for, status, transaction_id, error, created_at",
+            &[
+                &scheduled_transfer.id,
+                &scheduled_for,
+                &run_status.to_string(),
+                &transaction_id,
+                &error,
+            ],
+        )
+        .await?;
+
+    let row = tx
+        .query_one(
+            "UPDATE scheduled_transfers
+             SET occurrences = $1, consecutive_failures = $2, status = $3, next_run_at = $4,
+                 updated_at = NOW()
+             WHERE id = $5
+             RETURNING *",
+            &[
+                &scheduled_transfer.occurrences,
+                &scheduled_transfer.consecutive_failures,
+                &scheduled_transfer.status.to_string(),
+                &scheduled_transfer.next_run_at,
+                &scheduled_transfer.id,
+            ],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(RunOutcome {
+        scheduled_transfer: scheduled_transfer_from_row(&row),
+        run: run_from_row(&run_row),
+    }))
+}
+
+fn retry_at(now: DateTime<Utc>, retry_delay_seconds: i64) -> DateTime<Utc> {
+    now + Duration::seconds(retry_delay_seconds)
+}
@@ -0,0 +1,222 @@
+use crate::models::risk::RiskAssessment;
+use crate::models::watchlist::{
+    ScreenedPerson, ScreeningAction, ScreeningCase, ScreeningCaseStatus, ScreeningSubject, Watchlist, WatchlistMatch,
+};
+use crate::utils::error::AppError;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Most cases returned by one listing.
+const CASE_PAGE_SIZE: i64 = 100;
+
+/// The transfer a hit holds, for cases opened during a transaction.
+pub struct HeldTransfer<'a> {
+    pub transaction_id: Uuid,
+    pub authorize_only: bool,
+    pub risk_assessment: Option<&'a RiskAssessment>,
+}
+
+fn case_from_row(row: &Row) -> Result<ScreeningCase, AppError> {
+    let watchlist_match: serde_json::Value = row.get("watchlist_match");
+    let risk_assessment: Option<serde_json::Value> = row.get("risk_assessment");
+
+    Ok(ScreeningCase {
+        id: row.get("id"),
+        subject: ScreeningSubject::from(row.get::<_, &str>("subject")),
+        user_id: row.get("user_id"),
+        email: row.get("email"),
+        screened_name: row.get("screened_name"),
+        date_of_birth: row.get("date_of_birth"),
+        watchlist_match: serde_json::from_value(watchlist_match)
+            .map_err(|e| AppError::Internal(format!("Invalid stored watchlist match: {}", e)))?,
+        action: ScreeningAction::from(row.get::<_, &str>("action")),
+        transaction_id: row.get("transaction_id"),
+        authorize_only: row.get("authorize_only"),
+        risk_assessment: risk_assessment
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(format!("Invalid stored risk assessment: {}", e)))?,
+        status: ScreeningCaseStatus::from(row.get::<_, &str>("status")),
+        decided_by: row.get("decided_by"),
+        note: row.get("note"),
+        decided_at: row.get("decided_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// The best watchlist entry `person` matches, skipping entries they have
+/// already been cleared against.
+pub async fn find_match<T>(
+    client: &T,
+    watchlist: &Watchlist,
+    threshold: f64,
+    person: &ScreenedPerson,
+) -> Result<Option<WatchlistMatch>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    for watchlist_match in watchlist.screen(&person.name, person.date_of_birth, threshold) {
+        let cleared = client
+            .query_opt(
+                "SELECT 1 FROM screening_cases
+                 WHERE entry_id = $1 AND status = $2
+                   AND (user_id = $3 OR LOWER(email) = LOWER($4))
+                 LIMIT 1",
+                &[
+                    &watchlist_match.entry_id,
+                    &ScreeningCaseStatus::Cleared.to_string(),
+                    &person.user_id,
+                    &person.email,
+                ],
+            )
+            .await?
+            .is_some();
+        if !cleared {
+            return Ok(Some(watchlist_match));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The owner of an account, as screened when they are sent money. Users
+/// without a full name are screened by their username.
+pub async fn get_account_owner<T>(client: &T, account_id: Uuid) -> Result<ScreenedPerson, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT u.id, u.email, COALESCE(u.full_name, u.username) AS name, u.date_of_birth
+             FROM accounts a JOIN users u ON u.id = a.user_id
+             WHERE a.id = $1",
+            &[&account_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Account not found: {}", account_id)))?;
+
+    Ok(ScreenedPerson {
+        user_id: Some(row.get("id")),
+        email: row.get("email"),
+        name: row.get("name"),
+        date_of_birth: row.get("date_of_birth"),
+    })
+}
+
+/// Opens a case for a hit. A hit on a registration blocks it; one on a
+/// transfer holds the transfer until the case is decided.
+pub async fn open_case<T>(
+    client: &T,
+    person: &ScreenedPerson,
+    watchlist_match: &WatchlistMatch,
+    held: Option<&HeldTransfer<'_>>,
+) -> Result<ScreeningCase, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    // Only a registration screens someone who is not a user yet
+    let (subject, action) = match (held, person.user_id) {
+        (Some(_), _) => (ScreeningSubject::Transfer, ScreeningAction::Held),
+        (None, Some(_)) => (ScreeningSubject::Profile, ScreeningAction::Blocked),
+        (None, None) => (ScreeningSubject::Registration, ScreeningAction::Blocked),
+    };
+    let match_value = serde_json::to_value(watchlist_match)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize watchlist match: {}", e)))?;
+    let risk_assessment = held
+        .and_then(|held| held.risk_assessment)
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| AppError::Internal(format!("Failed to serialize risk assessment: {}", e)))?;
+    let row = client
+        .query_one(
+            "INSERT INTO screening_cases
+             (subject, user_id, email, screened_name, date_of_birth, entry_id, watchlist_match, action,
+              transaction_id, authorize_only, risk_assessment)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             RETURNING *",
+            &[
+                &subject.to_string(),
+                &person.user_id,
+                &person.email,
+                &person.name,
+                &person.date_of_birth,
+                &watchlist_match.entry_id,
+                &match_value,
+                &action.to_string(),
+                &held.map(|held| held.transaction_id),
+                &held.is_some_and(|held| held.authorize_only),
+                &risk_assessment,
+            ],
+        )
+        .await?;
+
+    case_from_row(&row)
+}
+
+pub async fn get_case<T>(client: &T, case_id: Uuid) -> Result<ScreeningCase, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM screening_cases WHERE id = $1", &[&case_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Screening case not found: {}", case_id)))?;
+
+    case_from_row(&row)
+}
+
+/// Loads an open case and locks it, so it can only be decided once.
+pub async fn lock_open_case<T>(client: &T, case_id: Uuid) -> Result<ScreeningCase, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM screening_cases WHERE id = $1 FOR UPDATE", &[&case_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Screening case not found: {}", case_id)))?;
+    let case = case_from_row(&row)?;
+
+    if case.status != ScreeningCaseStatus::Open {
+        return Err(AppError::Conflict(format!("Screening case is already {}", case.status)));
+    }
+
+    Ok(case)
+}
+
+pub async fn close_case<T>(
+    client: &T,
+    case_id: Uuid,
+    status: &ScreeningCaseStatus,
+    decided_by: Uuid,
+    note: Option<&str>,
+) -> Result<ScreeningCase, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_one(
+            "UPDATE screening_cases
+             SET status = $1, decided_by = $2, note = $3, decided_at = NOW()
+             WHERE id = $4
+             RETURNING *",
+            &[&status.to_string(), &decided_by, &note, &case_id],
+        )
+        .await?;
+
+    case_from_row(&row)
+}
+
+/// The oldest cases with `status`.
+pub async fn get_cases<T>(client: &T, status: &ScreeningCaseStatus) -> Result<Vec<ScreeningCase>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let rows = client
+        .query(
+            "SELECT * FROM screening_cases WHERE status = $1 ORDER BY created_at LIMIT $2",
+            &[&status.to_string(), &CASE_PAGE_SIZE],
+        )
+        .await?;
+
+    rows.iter().map(case_from_row).collect()
+}
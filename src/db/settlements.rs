@@ -0,0 +1,149 @@
+use crate::db::transactions;
+use crate::models::settlement::{
+    SettlementImport, SettlementLine, SettlementMatchStatus, SettlementRecord, SettlementReport,
+};
+use crate::utils::error::AppError;
+use deadpool_postgres::Client;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn import_from_row(row: &Row) -> SettlementImport {
+    SettlementImport {
+        id: row.get("id"),
+        file_name: row.get("file_name"),
+        format: row.get("format"),
+        imported_by: row.get("imported_by"),
+        line_count: row.get("line_count"),
+        matched_count: row.get("matched_count"),
+        unmatched_count: row.get("unmatched_count"),
+        mismatched_count: row.get("mismatched_count"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn line_from_row(row: &Row) -> SettlementLine {
+    SettlementLine {
+        id: row.get("id"),
+        line_number: row.get("line_number"),
+        reference: row.get("reference"),
+        bank_reference: row.get("bank_reference"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        value_date: row.get("value_date"),
+        status: SettlementMatchStatus::from(row.get::<_, &str>("status")),
+        transaction_id: row.get("transaction_id"),
+        reasons: row.get("reasons"),
+    }
+}
+
+/// Matches every record of a settlement file against the transaction its
+/// reference names and stores the outcome. Matched transactions are marked
+/// settled in the same database transaction, and each is locked while it is
+/// compared, so two imports of the same file cannot both settle it.
+pub async fn import_records(
+    client: &mut Client,
+    file_name: &str,
+    format: &str,
+    imported_by: Option<Uuid>,
+    date_tolerance_days: i64,
+    records: &[SettlementRecord],
+) -> Result<SettlementReport, AppError> {
+    let tx = client.transaction().await?;
+
+    let row = tx
+        .query_one(
+            "INSERT INTO settlement_imports
+             (file_name, format, imported_by, line_count, matched_count, unmatched_count, mismatched_count)
+             VALUES ($1, $2, $3, $4, 0, 0, 0)
+             RETURNING id",
+            &[&file_name, &format, &imported_by, &(records.len() as i32)],
+        )
+        .await?;
+    let import_id: Uuid = row.get("id");
+
+    for record in records {
+        let transaction = match record.transaction_id() {
+            Some(transaction_id) => transactions::try_lock_transaction(&tx, transaction_id).await?,
+            None => None,
+        };
+
+        let (status, reasons) = match &transaction {
+            None => (
+                SettlementMatchStatus::Unmatched,
+                vec!["Reference does not match any transaction".to_string()],
+            ),
+            Some(transaction) => {
+                let differences = record.differences(transaction, date_tolerance_days);
+                if differences.is_empty() {
+                    (SettlementMatchStatus::Matched, differences)
+                } else {
+                    (SettlementMatchStatus::Mismatched, differences)
+                }
+            }
+        };
+
+        let row = tx
+            .query_one(
+                "INSERT INTO settlement_lines
+                 (import_id, line_number, reference, bank_reference, amount, currency, value_date,
+                  status, transaction_id, reasons)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 RETURNING id",
+                &[
+                    &import_id,
+                    &record.line_number,
+                    &record.reference,
+                    &record.bank_reference,
+                    &record.amount,
+                    &record.currency,
+                    &record.value_date,
+                    &status.to_string(),
+                    &transaction.as_ref().map(|transaction| transaction.id),
+                    &reasons,
+                ],
+            )
+            .await?;
+
+        if let (SettlementMatchStatus::Matched, Some(transaction)) = (status, &transaction) {
+            transactions::mark_settled(&tx, transaction, import_id, row.get("id")).await?;
+        }
+    }
+
+    tx.execute(
+        "UPDATE settlement_imports
+         SET matched_count = (SELECT COUNT(*) FROM settlement_lines WHERE import_id = $1 AND status = 'matched'),
+             unmatched_count = (SELECT COUNT(*) FROM settlement_lines WHERE import_id = $1 AND status = 'unmatched'),
+             mismatched_count = (SELECT COUNT(*) FROM settlement_lines WHERE import_id = $1 AND status = 'mismatched')
+         WHERE id = $1",
+        &[&import_id],
+    )
+    .await?;
+
+    let report = get_report(&tx, import_id).await?;
+
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+pub async fn get_report<T>(client: &T, import_id: Uuid) -> Result<SettlementReport, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt("SELECT * FROM settlement_imports WHERE id = $1", &[&import_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Settlement import not found: {}", import_id)))?;
+
+    let lines = client
+        .query(
+            "SELECT * FROM settlement_lines WHERE import_id = $1 ORDER BY line_number",
+            &[&import_id],
+        )
+        .await?;
+
+    Ok(SettlementReport::new(
+        import_from_row(&row),
+        lines.iter().map(line_from_row).collect(),
+    ))
+}
@@ -1,24 +1,891 @@
-use crate::db::accounts;
+use crate::db::screening::{self, HeldTransfer};
+use crate::db::{accounts, fx, holds, ledger, limits, outbox, payment_intents, risk};
+use crate::models::account::Account;
+use crate::models::fx::{convert, FxConversion};
+use crate::models::hold::{HoldStatus, TransactionHold};
+use crate::models::ledger::{LedgerAccount, Posting, SystemAccount};
 use crate::models::transaction::{
-    CreateTransactionRequest, Transaction, TransactionStatus, TransactionType,
+    AccountActivityItem, CreateTransactionRequest, ReverseTransactionRequest, SortDirection,
+    Transaction, TransactionCursor, TransactionEvent, TransactionFilter, TransactionStatus,
+    TransactionType,
 };
+use crate::models::outbox::AggregateType;
+use crate::models::risk::{RiskAssessment, RiskDecision, RiskReview, RiskReviewStatus, RiskSignals};
+use crate::models::transaction_state;
+use crate::models::watchlist::{ScreenedPerson, ScreeningCase, ScreeningCaseStatus, Watchlist, WatchlistMatch};
+use crate::services::risk_service::RiskEngine;
 use crate::utils::error::AppError;
+use chrono::Utc;
 use deadpool_postgres::Client;
 use serde_json::json;
+use tokio_postgres::Row;
 use uuid::Uuid;
 
-pub async fn create_transaction(
-    client: &mut Client,
+fn transaction_from_row(row: &Row) -> Result<Transaction, AppError> {
+    Ok(Transaction {
+        id: row.get("id"),
+        source_account_id: row.get("source_account_id"),
+        destination_account_id: row.get("destination_account_id"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        status: TransactionStatus::try_from(row.get::<_, &str>("status"))?,
+        transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
+        description: row.get("description"),
+        parent_transaction_id: row.get("parent_transaction_id"),
+        refunded_amount: row.get("refunded_amount"),
+        destination_amount: row.get("destination_amount"),
+        destination_currency: row.get("destination_currency"),
+        fx_rate: row
+            .get::<_, Option<&str>>("fx_rate")
+            .map(fx::parse_rate)
+            .transpose()?,
+        fx_spread_bps: row.get("fx_spread_bps"),
+        fx_spread_amount: row.get("fx_spread_amount"),
+        fx_quote_id: row.get("fx_quote_id"),
+        settled_at: row.get("settled_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+/// Creates and settles a transaction. When `client` is already inside a
+/// database transaction this runs in a savepoint, so the caller can record the
+/// outcome atomically with the transfer.
+pub async fn create_transaction<C>(
+    client: &mut C,
     user_id: Uuid,
     data: &CreateTransactionRequest,
-) -> Result<Transaction, AppError> {
+) -> Result<Transaction, AppError>
+where
+    C: deadpool_postgres::GenericClient + Sync + Send,
+{
+    process_transaction(client, user_id, data, None).await
+}
+
+/// What a transfer to another user's account is screened against: the risk
+/// engine, and the watchlist its recipient is checked on.
+pub struct Screening<'a> {
+    pub engine: &'a RiskEngine,
+    pub signals: &'a RiskSignals,
+    pub watchlist: &'a Watchlist,
+    pub match_threshold: f64,
+}
+
+/// Like `create_transaction`, but a transfer to another user's account is
+/// screened first. A declined transfer is recorded as failed and refused; one
+/// held for review, or whose recipient is on the watchlist, keeps its funds
+/// reserved and stays `under_review` until an operator decides it.
+pub async fn create_screened_transaction<C>(
+    client: &mut C,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    screening: &Screening<'_>,
+) -> Result<Transaction, AppError>
+where
+    C: deadpool_postgres::GenericClient + Sync + Send,
+{
+    process_transaction(client, user_id, data, Some(screening)).await
+}
+
+async fn process_transaction<C>(
+    client: &mut C,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    screening: Option<&Screening<'_>>,
+) -> Result<Transaction, AppError>
+where
+    C: deadpool_postgres::GenericClient + Sync + Send,
+{
     // Begin a transaction
     let tx = client.transaction().await?;
 
     // Parse transaction type
     let transaction_type = TransactionType::from(data.transaction_type.as_str());
 
-    // Validate the request based on transaction type
+    // Lock the accounts and validate the request against their current state
+    lock_request_accounts(&tx, data).await?;
+    validate_request(&tx, user_id, data, &transaction_type, true).await?;
+    limits::enforce(&tx, data, &transaction_type).await?;
+    let fx_leg = resolve_fx_leg(&tx, user_id, data, &transaction_type).await?;
+    let outcome = screen_request(&tx, user_id, data, &transaction_type, screening).await?;
+
+    let transaction_id = insert_pending_transaction(&tx, user_id, data, None, fx_leg.as_ref()).await?;
+
+    if let Some(assessment) = outcome.declined() {
+        decline_screened(&tx, user_id, transaction_id, assessment).await?;
+        tx.commit().await?;
+        return Err(declined_error(transaction_id));
+    }
+
+    if let (true, Some(screening)) = (outcome.held(), screening) {
+        if let Some(quote_id) = fx_leg.as_ref().and_then(|leg| leg.quote_id) {
+            fx::mark_quote_used(&tx, quote_id, transaction_id).await?;
+        }
+
+        let source_account_id = data.source_account_id.unwrap();
+        accounts::place_hold(&tx, source_account_id, data.amount).await?;
+        let hold = holds::create_hold(
+            &tx,
+            transaction_id,
+            source_account_id,
+            data.amount,
+            screening.engine.policy().review_hold_ttl,
+        )
+        .await?;
+        hold_screened(&tx, user_id, transaction_id, &outcome, &hold, false).await?;
+
+        tx.commit().await?;
+        return get_transaction_by_id(&*client, transaction_id).await;
+    }
+
+    // Post the balanced ledger entries, which also moves the account balances
+    let postings = match &fx_leg {
+        Some(leg) => build_fx_postings(
+            data.source_account_id.unwrap(),
+            data.destination_account_id.unwrap(),
+            data.amount,
+            &data.currency,
+            leg.conversion.destination_amount,
+            &leg.currency,
+        ),
+        None => build_postings(
+            &transaction_type,
+            data.source_account_id,
+            data.destination_account_id,
+            data.amount,
+            &data.currency,
+        ),
+    };
+    ledger::post_entries(&tx, transaction_id, &postings).await?;
+
+    if let Some(quote_id) = fx_leg.as_ref().and_then(|leg| leg.quote_id) {
+        fx::mark_quote_used(&tx, quote_id, transaction_id).await?;
+    }
+
+    transition_status(
+        &tx,
+        transaction_id,
+        &TransactionStatus::Completed,
+        with_assessment(
+            json!({"user_id": user_id.to_string(), "action": "processed"}),
+            outcome.assessment.as_ref(),
+        ),
+    )
+    .await?;
+
+    // Commit the transaction
+    tx.commit().await?;
+
+    // Get the final transaction record
+    get_transaction_by_id(&*client, transaction_id).await
+}
+
+/// Creates a pending withdrawal or transfer and places a hold on the source
+/// account for its amount. The funds only move once the transaction is captured.
+/// With `screening`, a transfer to another user's account is screened first,
+/// and one held for review can only be captured once it is approved or cleared.
+pub async fn authorize_transaction<C>(
+    client: &mut C,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    hold_ttl_seconds: i64,
+    screening: Option<&Screening<'_>>,
+) -> Result<Transaction, AppError>
+where
+    C: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let tx = client.transaction().await?;
+
+    let transaction_type = TransactionType::from(data.transaction_type.as_str());
+    if transaction_type == TransactionType::Deposit {
+        return Err(AppError::BadRequest(
+            "Only withdrawals and transfers can be authorized".to_string(),
+        ));
+    }
+
+    if data.fx_quote_id.is_some() {
+        return Err(AppError::BadRequest(
+            "Cross-currency transfers cannot be authorized".to_string(),
+        ));
+    }
+
+    lock_request_accounts(&tx, data).await?;
+    validate_request(&tx, user_id, data, &transaction_type, false).await?;
+    limits::enforce(&tx, data, &transaction_type).await?;
+    let outcome = screen_request(&tx, user_id, data, &transaction_type, screening).await?;
+
+    let transaction_id = insert_pending_transaction(&tx, user_id, data, None, None).await?;
+
+    if let Some(assessment) = outcome.declined() {
+        decline_screened(&tx, user_id, transaction_id, assessment).await?;
+        tx.commit().await?;
+        return Err(declined_error(transaction_id));
+    }
+
+    // Reserve the funds on the source account
+    let source_account_id = data.source_account_id.unwrap();
+    accounts::place_hold(&tx, source_account_id, data.amount).await?;
+    let hold = holds::create_hold(&tx, transaction_id, source_account_id, data.amount, hold_ttl_seconds).await?;
+
+    if outcome.held() {
+        hold_screened(&tx, user_id, transaction_id, &outcome, &hold, true).await?;
+    } else {
+        record_event(
+            &tx,
+            transaction_id,
+            Some(&TransactionStatus::Pending),
+            &TransactionStatus::Pending,
+            with_assessment(
+                json!({
+                    "user_id": user_id.to_string(),
+                    "action": "authorized",
+                    "hold_id": hold.id.to_string(),
+                    "expires_at": hold.expires_at.to_rfc3339(),
+                }),
+                outcome.assessment.as_ref(),
+            ),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    get_transaction_by_id(&*client, transaction_id).await
+}
+
+/// Captures an authorized transaction, either in full or for a smaller amount,
+/// releasing the hold and moving the captured funds.
+pub async fn capture_transaction(
+    client: &mut Client,
+    user_id: Uuid,
+    transaction_id: Uuid,
+    amount: Option<i64>,
+) -> Result<Transaction, AppError> {
+    let tx = client.transaction().await?;
+
+    let hold = holds::lock_hold_for_transaction(&tx, transaction_id).await?;
+    let transaction = get_transaction_by_id(&tx, transaction_id).await?;
+    ensure_not_under_review(&transaction)?;
+    let locked = ensure_hold_owner(&tx, user_id, &transaction).await?;
+    ensure_hold_active(&hold.status, hold.expires_at)?;
+
+    // The funds were reserved while the account was active, but only move if it still is
+    for account in &locked {
+        if Some(account.id) == transaction.source_account_id {
+            accounts::ensure_can_debit(account)?;
+        } else {
+            accounts::ensure_can_credit(account)?;
+        }
+    }
+
+    let capture_amount = amount.unwrap_or(hold.amount);
+    if capture_amount <= 0 || capture_amount > hold.amount {
+        return Err(AppError::BadRequest(format!(
+            "Capture amount must be between 1 and the authorized amount of {}",
+            hold.amount
+        )));
+    }
+
+    // Release the hold before debiting so the funds count as available again
+    accounts::release_hold(&tx, hold.account_id, hold.amount).await?;
+    holds::update_hold_status(&tx, hold.id, &HoldStatus::Captured, Some(capture_amount)).await?;
+
+    if capture_amount != transaction.amount {
+        tx.execute(
+            "UPDATE transactions SET amount = $1, updated_at = NOW() WHERE id = $2",
+            &[&capture_amount, &transaction_id],
+        )
+        .await?;
+    }
+
+    let postings = build_postings(
+        &transaction.transaction_type,
+        transaction.source_account_id,
+        transaction.destination_account_id,
+        capture_amount,
+        &transaction.currency,
+    );
+    ledger::post_entries(&tx, transaction_id, &postings).await?;
+
+    transition_status(
+        &tx,
+        transaction_id,
+        &TransactionStatus::Completed,
+        json!({
+            "user_id": user_id.to_string(),
+            "action": "captured",
+            "authorized_amount": hold.amount,
+            "captured_amount": capture_amount,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    get_transaction_by_id(client, transaction_id).await
+}
+
+/// Cancels an authorized transaction and releases its hold.
+pub async fn void_transaction(
+    client: &mut Client,
+    user_id: Uuid,
+    transaction_id: Uuid,
+) -> Result<Transaction, AppError> {
+    let tx = client.transaction().await?;
+
+    let hold = holds::lock_hold_for_transaction(&tx, transaction_id).await?;
+    let transaction = get_transaction_by_id(&tx, transaction_id).await?;
+    ensure_not_under_review(&transaction)?;
+    ensure_hold_owner(&tx, user_id, &transaction).await?;
+    ensure_hold_active(&hold.status, hold.expires_at)?;
+
+    accounts::release_hold(&tx, hold.account_id, hold.amount).await?;
+    holds::update_hold_status(&tx, hold.id, &HoldStatus::Voided, None).await?;
+
+    transition_status(
+        &tx,
+        transaction_id,
+        &TransactionStatus::Cancelled,
+        json!({"user_id": user_id.to_string(), "action": "voided"}),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    get_transaction_by_id(client, transaction_id).await
+}
+
+/// Releases up to `limit` holds that have passed their expiry and cancels the
+/// transactions they belong to. Returns the number of holds expired.
+pub async fn expire_holds(client: &mut Client, limit: i64) -> Result<usize, AppError> {
+    let tx = client.transaction().await?;
+
+    let expired = holds::lock_expired_holds(&tx, limit).await?;
+    for hold in &expired {
+        accounts::release_hold(&tx, hold.account_id, hold.amount).await?;
+        holds::update_hold_status(&tx, hold.id, &HoldStatus::Expired, None).await?;
+
+        transition_status(
+            &tx,
+            hold.transaction_id,
+            &TransactionStatus::Cancelled,
+            json!({"action": "expired", "hold_id": hold.id.to_string()}),
+        )
+        .await?;
+        risk::expire_review(&tx, hold.transaction_id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(expired.len())
+}
+
+/// Lets a transfer held for review go ahead on an operator's behalf. An
+/// immediate transfer is completed from its hold; an authorization goes back to
+/// pending and can then be captured or voided as usual.
+pub async fn approve_review(
+    client: &mut Client,
+    operator_id: Uuid,
+    review_id: Uuid,
+    note: Option<&str>,
+) -> Result<RiskReview, AppError> {
+    let tx = client.transaction().await?;
+
+    let review = risk::lock_open_review(&tx, review_id).await?;
+    let transaction = lock_reviewed_transaction(&tx, review.transaction_id).await?;
+    let hold = holds::lock_hold_for_transaction(&tx, transaction.id).await?;
+    ensure_hold_active(&hold.status, hold.expires_at)?;
+
+    release_reviewed(
+        &tx,
+        &transaction,
+        &hold,
+        review.authorize_only,
+        json!({
+            "operator_id": operator_id.to_string(),
+            "action": "review_approved",
+            "review_id": review.id.to_string(),
+            "note": note,
+        }),
+    )
+    .await?;
+
+    let review = risk::close_review(&tx, review.id, &RiskReviewStatus::Approved, Some(operator_id), note).await?;
+
+    tx.commit().await?;
+
+    Ok(review)
+}
+
+/// Refuses a transfer held for review on an operator's behalf, releasing its
+/// hold and failing the transaction.
+pub async fn reject_review(
+    client: &mut Client,
+    operator_id: Uuid,
+    review_id: Uuid,
+    note: Option<&str>,
+) -> Result<RiskReview, AppError> {
+    let tx = client.transaction().await?;
+
+    let review = risk::lock_open_review(&tx, review_id).await?;
+    let transaction = lock_reviewed_transaction(&tx, review.transaction_id).await?;
+    let hold = holds::lock_hold_for_transaction(&tx, transaction.id).await?;
+
+    fail_reviewed(
+        &tx,
+        &transaction,
+        &hold,
+        json!({
+            "operator_id": operator_id.to_string(),
+            "action": "review_rejected",
+            "review_id": review.id.to_string(),
+            "note": note,
+        }),
+    )
+    .await?;
+
+    let review = risk::close_review(&tx, review.id, &RiskReviewStatus::Rejected, Some(operator_id), note).await?;
+
+    tx.commit().await?;
+
+    Ok(review)
+}
+
+/// Clears a watchlist hit as a false positive. A transfer it still holds goes
+/// ahead as an approved review would, unless the risk engine also wanted it
+/// reviewed, in which case a risk review is opened for it instead.
+pub async fn clear_screening_case(
+    client: &mut Client,
+    operator_id: Uuid,
+    case_id: Uuid,
+    note: Option<&str>,
+) -> Result<ScreeningCase, AppError> {
+    let tx = client.transaction().await?;
+
+    let case = screening::lock_open_case(&tx, case_id).await?;
+    if let Some(transaction) = lock_held_transaction(&tx, &case).await? {
+        let hold = holds::lock_hold_for_transaction(&tx, transaction.id).await?;
+        ensure_hold_active(&hold.status, hold.expires_at)?;
+
+        let mut event_data = json!({
+            "operator_id": operator_id.to_string(),
+            "action": "screening_cleared",
+            "case_id": case.id.to_string(),
+            "note": note,
+        });
+
+        match case.risk_assessment.as_ref().filter(|a| a.decision != RiskDecision::Approve) {
+            Some(assessment) => {
+                let review = risk::open_review(&tx, transaction.id, assessment, case.authorize_only).await?;
+                event_data["review_id"] = json!(review.id.to_string());
+                record_event(
+                    &tx,
+                    transaction.id,
+                    Some(&TransactionStatus::UnderReview),
+                    &TransactionStatus::UnderReview,
+                    event_data,
+                )
+                .await?;
+            }
+            None => release_reviewed(&tx, &transaction, &hold, case.authorize_only, event_data).await?,
+        }
+    }
+
+    let case = screening::close_case(&tx, case.id, &ScreeningCaseStatus::Cleared, operator_id, note).await?;
+
+    tx.commit().await?;
+
+    Ok(case)
+}
+
+/// Confirms a watchlist hit. A transfer it still holds is failed and its funds
+/// released; otherwise only the decision is recorded.
+pub async fn confirm_screening_case(
+    client: &mut Client,
+    operator_id: Uuid,
+    case_id: Uuid,
+    note: Option<&str>,
+) -> Result<ScreeningCase, AppError> {
+    let tx = client.transaction().await?;
+
+    let case = screening::lock_open_case(&tx, case_id).await?;
+    if let Some(transaction) = lock_held_transaction(&tx, &case).await? {
+        let hold = holds::lock_hold_for_transaction(&tx, transaction.id).await?;
+
+        fail_reviewed(
+            &tx,
+            &transaction,
+            &hold,
+            json!({
+                "operator_id": operator_id.to_string(),
+                "action": "screening_confirmed",
+                "case_id": case.id.to_string(),
+                "note": note,
+            }),
+        )
+        .await?;
+    }
+
+    let case = screening::close_case(&tx, case.id, &ScreeningCaseStatus::Confirmed, operator_id, note).await?;
+
+    tx.commit().await?;
+
+    Ok(case)
+}
+
+/// Refunds a completed deposit or transfer, in full or in part. The refund is
+/// a new transaction linked to its parent that moves the money back out of the
+/// account that originally received it.
+pub async fn refund_transaction(
+    client: &mut Client,
+    user_id: Uuid,
+    transaction_id: Uuid,
+    amount: Option<i64>,
+    description: Option<String>,
+) -> Result<Transaction, AppError> {
+    let tx = client.transaction().await?;
+
+    // Lock the parent so concurrent refunds cannot exceed the original amount
+    let parent = lock_transaction(&tx, transaction_id).await?;
+
+    if parent.status != TransactionStatus::Completed {
+        return Err(AppError::BadRequest(format!(
+            "Only completed transactions can be refunded, this one is {}",
+            parent.status
+        )));
+    }
+
+    if !matches!(
+        parent.transaction_type,
+        TransactionType::Deposit | TransactionType::Transfer
+    ) {
+        return Err(AppError::BadRequest(format!(
+            "{} transactions cannot be refunded",
+            parent.transaction_type
+        )));
+    }
+
+    if parent.destination_currency.is_some() {
+        return Err(AppError::BadRequest(
+            "Cross-currency transfers cannot be refunded".to_string(),
+        ));
+    }
+
+    // Only the owner of the account that received the funds may refund them
+    let refunding_account_id = parent.destination_account_id.unwrap();
+    let account_ids: Vec<Uuid> = [parent.source_account_id, parent.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    let locked = accounts::lock_accounts(&tx, &account_ids).await?;
+    let refunding_account = locked.iter().find(|a| a.id == refunding_account_id).unwrap();
+    if refunding_account.user_id != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to refund this transaction".to_string(),
+        ));
+    }
+    accounts::ensure_can_debit(refunding_account)?;
+    if let Some(refunded_account) = locked.iter().find(|a| Some(a.id) == parent.source_account_id) {
+        accounts::ensure_can_credit(refunded_account)?;
+    }
+
+    let refundable = parent.amount - parent.refunded_amount;
+    let refund_amount = amount.unwrap_or(refundable);
+    if refundable == 0 {
+        return Err(AppError::BadRequest(
+            "Transaction has already been fully refunded".to_string(),
+        ));
+    }
+    if refund_amount <= 0 || refund_amount > refundable {
+        return Err(AppError::BadRequest(format!(
+            "Refund amount must be between 1 and the refundable amount of {}",
+            refundable
+        )));
+    }
+
+    let refund_request = CreateTransactionRequest {
+        source_account_id: Some(refunding_account_id),
+        destination_account_id: parent.source_account_id,
+        amount: refund_amount,
+        currency: parent.currency.clone(),
+        transaction_type: TransactionType::Refund.to_string(),
+        description,
+        authorize_only: false,
+        fx_quote_id: None,
+    };
+    let refund_id = insert_pending_transaction(&tx, user_id, &refund_request, Some(parent.id), None).await?;
+
+    let postings = build_postings(
+        &TransactionType::Refund,
+        refund_request.source_account_id,
+        refund_request.destination_account_id,
+        refund_amount,
+        &refund_request.currency,
+    );
+    ledger::post_entries(&tx, refund_id, &postings).await?;
+
+    transition_status(
+        &tx,
+        refund_id,
+        &TransactionStatus::Completed,
+        json!({
+            "user_id": user_id.to_string(),
+            "action": "processed",
+            "parent_transaction_id": parent.id.to_string(),
+        }),
+    )
+    .await?;
+
+    // Track the refunded total on the parent and record the refund in its history
+    let row = tx
+        .query_one(
+            "UPDATE transactions 
+             SET refunded_amount = refunded_amount + $1, updated_at = NOW() 
+             WHERE id = $2 
+             RETURNING refunded_amount",
+            &[&refund_amount, &parent.id],
+        )
+        .await?;
+    let refunded_amount: i64 = row.get("refunded_amount");
+
+    record_event(
+        &tx,
+        parent.id,
+        Some(&parent.status),
+        &parent.status,
+        json!({
+            "user_id": user_id.to_string(),
+            "action": "refunded",
+            "refund_transaction_id": refund_id.to_string(),
+            "amount": refund_amount,
+            "refunded_amount": refunded_amount,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    get_transaction_by_id(client, refund_id).await
+}
+
+/// Unwinds a completed transaction on an operator's behalf. The original
+/// movement, less anything already refunded, is posted in reverse even if that
+/// leaves the credited account negative, and the transaction becomes `reversed`.
+/// Reversing a refund makes its amount refundable on the parent again.
+pub async fn reverse_transaction(
+    client: &mut Client,
+    operator_id: Uuid,
+    transaction_id: Uuid,
+    data: &ReverseTransactionRequest,
+) -> Result<Transaction, AppError> {
+    let tx = client.transaction().await?;
+
+    let transaction = lock_transaction(&tx, transaction_id).await?;
+
+    // The parent's refunded amount must shrink with the refund, or a later
+    // reversal of the parent would leave this money with the merchant
+    let parent = match (&transaction.transaction_type, transaction.parent_transaction_id) {
+        (TransactionType::Refund, Some(parent_id)) => {
+            let parent = lock_transaction(&tx, parent_id).await?;
+            if parent.status != TransactionStatus::Completed {
+                return Err(AppError::Conflict(format!(
+                    "Refund cannot be reversed because its original transaction {} is {}",
+                    parent.id, parent.status
+                )));
+            }
+            Some(parent)
+        }
+        _ => None,
+    };
+
+    // Refunded money has already gone back, so only the remainder is reversed
+    let reversed_amount = transaction.amount - transaction.refunded_amount;
+
+    transition_status(
+        &tx,
+        transaction_id,
+        &TransactionStatus::Reversed,
+        json!({
+            "operator_id": operator_id.to_string(),
+            "action": data.kind.to_string(),
+            "reason_code": data.reason_code,
+            "note": data.note,
+            "reversed_amount": reversed_amount,
+        }),
+    )
+    .await?;
+
+    let account_ids: Vec<Uuid> = [transaction.source_account_id, transaction.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    accounts::lock_accounts(&tx, &account_ids).await?;
+
+    if reversed_amount > 0 {
+        // Cross-currency transfers cannot be refunded, so both legs unwind in full
+        let original = match (transaction.destination_amount, &transaction.destination_currency) {
+            (Some(destination_amount), Some(destination_currency)) => build_fx_postings(
+                transaction.source_account_id.unwrap(),
+                transaction.destination_account_id.unwrap(),
+                reversed_amount,
+                &transaction.currency,
+                destination_amount,
+                destination_currency,
+            ),
+            _ => build_postings(
+                &transaction.transaction_type,
+                transaction.source_account_id,
+                transaction.destination_account_id,
+                reversed_amount,
+                &transaction.currency,
+            ),
+        };
+        let postings: Vec<Posting> = original.iter().map(Posting::reversed).collect();
+        ledger::post_compensating_entries(&tx, transaction_id, &postings).await?;
+    }
+
+    if let Some(parent) = parent {
+        let row = tx
+            .query_one(
+                "UPDATE transactions 
+                 SET refunded_amount = refunded_amount - $1, updated_at = NOW() 
+                 WHERE id = $2 
+                 RETURNING refunded_amount",
+                &[&transaction.amount, &parent.id],
+            )
+            .await?;
+        let refunded_amount: i64 = row.get("refunded_amount");
+
+        record_event(
+            &tx,
+            parent.id,
+            Some(&parent.status),
+            &parent.status,
+            json!({
+                "operator_id": operator_id.to_string(),
+                "action": "refund_reversed",
+                "refund_transaction_id": transaction_id.to_string(),
+                "amount": transaction.amount,
+                "refunded_amount": refunded_amount,
+            }),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    get_transaction_by_id(client, transaction_id).await
+}
+
+pub async fn get_refunds(client: &Client, transaction_id: Uuid) -> Result<Vec<Transaction>, AppError> {
+    let rows = client
+        .query(
+            "SELECT id, source_account_id, destination_account_id, amount, currency, status, 
+                    transaction_type, description, parent_transaction_id, refunded_amount, 
+                    destination_amount, destination_currency, fx_rate::TEXT AS fx_rate, 
+                    fx_spread_bps, fx_spread_amount, fx_quote_id, settled_at, created_at, updated_at 
+             FROM transactions 
+             WHERE parent_transaction_id = $1 AND transaction_type = 'refund'
+             ORDER BY created_at",
+            &[&transaction_id],
+        )
+        .await?;
+
+    rows.iter().map(transaction_from_row).collect()
+}
+
+/// Loads a transaction and locks its row for the rest of the database transaction.
+async fn lock_transaction<T>(client: &T, transaction_id: Uuid) -> Result<Transaction, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    try_lock_transaction(client, transaction_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Transaction not found: {}", transaction_id))
+        })
+}
+
+/// Like `lock_transaction`, but a missing transaction is not an error.
+pub async fn try_lock_transaction<T>(client: &T, transaction_id: Uuid) -> Result<Option<Transaction>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let row = client
+        .query_opt(
+            "SELECT id, source_account_id, destination_account_id, amount, currency, status, 
+                    transaction_type, description, parent_transaction_id, refunded_amount, 
+                    destination_amount, destination_currency, fx_rate::TEXT AS fx_rate, 
+                    fx_spread_bps, fx_spread_amount, fx_quote_id, settled_at, created_at, updated_at 
+             FROM transactions 
+             WHERE id = $1 
+             FOR UPDATE",
+            &[&transaction_id],
+        )
+        .await?;
+
+    row.as_ref().map(transaction_from_row).transpose()
+}
+
+/// Records that the bank confirmed a transaction on a line of a settlement
+/// file. The transaction's status is unchanged, but the settlement appears in
+/// its event history.
+pub async fn mark_settled<T>(
+    tx: &T,
+    transaction: &Transaction,
+    import_id: Uuid,
+    settlement_line_id: Uuid,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    tx.execute(
+        "UPDATE transactions 
+         SET settled_at = NOW(), settlement_line_id = $1, updated_at = NOW() 
+         WHERE id = $2",
+        &[&settlement_line_id, &transaction.id],
+    )
+    .await?;
+
+    record_event(
+        tx,
+        transaction.id,
+        Some(&transaction.status),
+        &transaction.status,
+        json!({
+            "action": "settled",
+            "settlement_import_id": import_id.to_string(),
+            "settlement_line_id": settlement_line_id.to_string(),
+        }),
+    )
+    .await
+}
+
+/// Locks every account named in the request, in a deterministic order, so the
+/// balance checks that follow cannot race with concurrent debits.
+async fn lock_request_accounts<T>(client: &T, data: &CreateTransactionRequest) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let account_ids: Vec<Uuid> = [data.source_account_id, data.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    accounts::lock_accounts(client, &account_ids).await?;
+
+    Ok(())
+}
+
+async fn validate_request<T>(
+    tx: &T,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    transaction_type: &TransactionType,
+    allow_fx: bool,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
     match transaction_type {
         TransactionType::Deposit => {
             if data.destination_account_id.is_none() {
@@ -29,12 +896,13 @@ pub async fn create_transaction(
 
             // Verify the destination account belongs to the user
             let dest_account_id = data.destination_account_id.unwrap();
-            let dest_account = accounts::get_account(&tx, dest_account_id).await?;
+            let dest_account = accounts::get_account(tx, dest_account_id).await?;
             if dest_account.user_id != user_id {
                 return Err(AppError::Forbidden(
                     "You do not have permission to deposit to this account".to_string(),
                 ));
             }
+            accounts::ensure_can_credit(&dest_account)?;
 
             // Ensure currency matches the account
             if dest_account.currency != data.currency {
@@ -53,12 +921,13 @@ pub async fn create_transaction(
 
             // Verify the source account belongs to the user
             let source_account_id = data.source_account_id.unwrap();
-            let source_account = accounts::get_account(&tx, source_account_id).await?;
+            let source_account = accounts::get_account(tx, source_account_id).await?;
             if source_account.user_id != user_id {
                 return Err(AppError::Forbidden(
                     "You do not have permission to withdraw from this account".to_string(),
                 ));
             }
+            accounts::ensure_can_debit(&source_account)?;
 
             // Ensure currency matches the account
             if source_account.currency != data.currency {
@@ -69,10 +938,10 @@ pub async fn create_transaction(
             }
 
             // Check if sufficient funds
-            if source_account.balance < data.amount {
-                return Err(AppError::BadRequest(format!(
-                    "Insufficient funds: balance is {}, but withdrawal amount is {}",
-                    source_account.balance, data.amount
+            if source_account.available_balance() < data.amount {
+                return Err(AppError::InsufficientFunds(format!(
+                    "available balance is {}, but withdrawal amount is {}",
+                    source_account.available_balance(), data.amount
                 )));
             }
         }
@@ -87,14 +956,16 @@ pub async fn create_transaction(
             let source_account_id = data.source_account_id.unwrap();
             let dest_account_id = data.destination_account_id.unwrap();
 
-            let source_account = accounts::get_account(&tx, source_account_id).await?;
+            let source_account = accounts::get_account(tx, source_account_id).await?;
             if source_account.user_id != user_id {
                 return Err(AppError::Forbidden(
                     "You do not have permission to transfer from this account".to_string(),
                 ));
             }
 
-            let dest_account = accounts::get_account(&tx, dest_account_id).await?;
+            let dest_account = accounts::get_account(tx, dest_account_id).await?;
+            accounts::ensure_can_debit(&source_account)?;
+            accounts::ensure_can_credit(&dest_account)?;
 
             // Ensure currency matches the accounts
             if source_account.currency != data.currency {
@@ -104,174 +975,654 @@ pub async fn create_transaction(
                 )));
             }
 
-            if dest_account.currency != data.currency {
+            // A different destination currency makes this a cross-currency transfer
+            if dest_account.currency != data.currency && !allow_fx {
                 return Err(AppError::BadRequest(format!(
                     "Currency mismatch: transaction is in {}, but destination account is in {}",
                     data.currency, dest_account.currency
                 )));
             }
-
-            if source_account.balance < data.amount {
+
+            if source_account.available_balance() < data.amount {
+                return Err(AppError::InsufficientFunds(format!(
+                    "available balance is {}, but transfer amount is {}",
+                    source_account.available_balance(), data.amount
+                )));
+            }
+        }
+        TransactionType::Refund => {
+            return Err(AppError::BadRequest(
+                "Refunds must be created through the refunds endpoint".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn insert_pending_transaction<T>(
+    tx: &T,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    parent_transaction_id: Option<Uuid>,
+    fx_leg: Option<&FxLeg>,
+) -> Result<Uuid, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    // Create the transaction record
+    let row = tx
+        .query_one(
+            "INSERT INTO transactions 
+             (source_account_id, destination_account_id, amount, currency, status, transaction_type, 
+              description, parent_transaction_id, destination_amount, destination_currency, fx_rate, 
+              fx_spread_bps, fx_spread_amount, fx_quote_id) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11::TEXT::NUMERIC, $12, $13, $14) 
+             RETURNING id",
+            &[
+                &data.source_account_id,
+                &data.destination_account_id,
+                &data.amount,
+                &data.currency,
+                &TransactionStatus::Pending.to_string(),
+                &data.transaction_type,
+                &data.description,
+                &parent_transaction_id,
+                &fx_leg.map(|leg| leg.conversion.destination_amount),
+                &fx_leg.map(|leg| leg.currency.as_str()),
+                &fx_leg.map(|leg| leg.conversion.rate.to_string()),
+                &fx_leg.map(|leg| leg.conversion.spread_bps),
+                &fx_leg.map(|leg| leg.conversion.spread_amount),
+                &fx_leg.and_then(|leg| leg.quote_id),
+            ],
+        )
+        .await?;
+
+    let transaction_id: Uuid = row.get("id");
+
+    // Add transaction event
+    record_event(
+        tx,
+        transaction_id,
+        None,
+        &TransactionStatus::Pending,
+        json!({"user_id": user_id.to_string(), "action": "created"}),
+    )
+    .await?;
+
+    Ok(transaction_id)
+}
+
+/// Destination side of a cross-currency transfer.
+struct FxLeg {
+    currency: String,
+    conversion: FxConversion,
+    quote_id: Option<Uuid>,
+}
+
+/// Works out the destination leg of a transfer between accounts in different
+/// currencies, from the quote named in the request or else the rate currently
+/// in force. Returns `None` when no conversion is needed.
+async fn resolve_fx_leg<T>(
+    tx: &T,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    transaction_type: &TransactionType,
+) -> Result<Option<FxLeg>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let destination_currency = match (transaction_type, data.destination_account_id) {
+        (TransactionType::Transfer, Some(account_id)) => {
+            Some(accounts::get_account(tx, account_id).await?.currency)
+        }
+        _ => None,
+    };
+
+    let Some(destination_currency) = destination_currency.filter(|currency| *currency != data.currency) else {
+        if data.fx_quote_id.is_some() {
+            return Err(AppError::BadRequest(
+                "FX quotes can only be used for cross-currency transfers".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let conversion = match data.fx_quote_id {
+        Some(quote_id) => {
+            let quote = fx::lock_quote(tx, user_id, quote_id).await?;
+            if quote.transaction_id.is_some() {
+                return Err(AppError::BadRequest("FX quote has already been used".to_string()));
+            }
+            if quote.expires_at <= Utc::now() {
+                return Err(AppError::BadRequest("FX quote has expired".to_string()));
+            }
+            if quote.source_currency != data.currency
+                || quote.destination_currency != destination_currency
+                || quote.source_amount != data.amount
+            {
                 return Err(AppError::BadRequest(format!(
-                    "Insufficient funds: balance is {}, but transfer amount is {}",
-                    source_account.balance, data.amount
+                    "FX quote is for {} {} to {}, which does not match this transfer",
+                    quote.source_amount, quote.source_currency, quote.destination_currency
                 )));
             }
+
+            FxConversion {
+                rate: quote.rate,
+                spread_bps: quote.spread_bps,
+                destination_amount: quote.destination_amount,
+                spread_amount: quote.spread_amount,
+            }
+        }
+        None => {
+            let rate = fx::find_current_rate(tx, &data.currency, &destination_currency).await?;
+            convert(
+                data.amount,
+                &data.currency,
+                &destination_currency,
+                rate.rate_from(&data.currency),
+                rate.spread_bps,
+            )
+            .map_err(AppError::BadRequest)?
+        }
+    };
+
+    Ok(Some(FxLeg {
+        currency: destination_currency,
+        conversion,
+        quote_id: data.fx_quote_id,
+    }))
+}
+
+/// Builds the balanced ledger postings that move `amount` for a transaction.
+fn build_postings(
+    transaction_type: &TransactionType,
+    source_account_id: Option<Uuid>,
+    destination_account_id: Option<Uuid>,
+    amount: i64,
+    currency: &str,
+) -> Vec<Posting> {
+    match transaction_type {
+        TransactionType::Deposit => vec![
+            Posting::debit(LedgerAccount::System(SystemAccount::DepositClearing), amount, currency),
+            Posting::credit(LedgerAccount::Customer(destination_account_id.unwrap()), amount, currency),
+        ],
+        TransactionType::Withdrawal => vec![
+            Posting::debit(LedgerAccount::Customer(source_account_id.unwrap()), amount, currency),
+            Posting::credit(LedgerAccount::System(SystemAccount::WithdrawalClearing), amount, currency),
+        ],
+        TransactionType::Transfer => vec![
+            Posting::debit(LedgerAccount::Customer(source_account_id.unwrap()), amount, currency),
+            Posting::credit(LedgerAccount::Customer(destination_account_id.unwrap()), amount, currency),
+        ],
+        // Refunds of deposits send the money back out through deposit clearing
+        TransactionType::Refund => {
+            let credited = match destination_account_id {
+                Some(account_id) => LedgerAccount::Customer(account_id),
+                None => LedgerAccount::System(SystemAccount::DepositClearing),
+            };
+            vec![
+                Posting::debit(LedgerAccount::Customer(source_account_id.unwrap()), amount, currency),
+                Posting::credit(credited, amount, currency),
+            ]
         }
     }
+}
 
-    // Create the transaction record
+/// Builds the postings for a cross-currency transfer. Each leg balances in its
+/// own currency against FX clearing, which is left holding the spread.
+fn build_fx_postings(
+    source_account_id: Uuid,
+    destination_account_id: Uuid,
+    amount: i64,
+    currency: &str,
+    destination_amount: i64,
+    destination_currency: &str,
+) -> Vec<Posting> {
+    let clearing = LedgerAccount::System(SystemAccount::FxClearing);
+    vec![
+        Posting::debit(LedgerAccount::Customer(source_account_id), amount, currency),
+        Posting::credit(clearing, amount, currency),
+        Posting::debit(clearing, destination_amount, destination_currency),
+        Posting::credit(LedgerAccount::Customer(destination_account_id), destination_amount, destination_currency),
+    ]
+}
+
+/// Moves a transaction to `new_status` through the transaction state machine.
+/// The current status is read under a row lock, the transition is validated,
+/// and a `transaction_events` row recording the previous status is always
+/// written. Every status change must go through here.
+pub async fn transition_status<T>(
+    tx: &T,
+    transaction_id: Uuid,
+    new_status: &TransactionStatus,
+    event_data: serde_json::Value,
+) -> Result<TransactionStatus, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
     let row = tx
-        .query_one(
-            "INSERT INTO transactions 
-             (source_account_id, destination_account_id, amount, currency, status, transaction_type, description) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7) 
-             RETURNING id, source_account_id, destination_account_id, amount, currency, status, 
-                     transaction_type, description, created_at, updated_at",
-            &[
-                &data.source_account_id,
-                &data.destination_account_id,
-                &data.amount,
-                &data.currency,
-                &"pending",
-                &data.transaction_type,
-                &data.description,
-            ],
+        .query_opt(
+            "SELECT status FROM transactions WHERE id = $1 FOR UPDATE",
+            &[&transaction_id],
         )
-        .await?;
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Transaction not found: {}", transaction_id))
+        })?;
+    let previous_status = TransactionStatus::try_from(row.get::<_, &str>("status"))?;
 
-    let transaction_id: Uuid = row.get("id");
+    transaction_state::validate_transition(&previous_status, new_status)?;
 
-    // Add transaction event
+    tx.execute(
+        "UPDATE transactions SET status = $1, updated_at = NOW() WHERE id = $2",
+        &[&new_status.to_string(), &transaction_id],
+    )
+    .await?;
+
+    record_event(tx, transaction_id, Some(&previous_status), new_status, event_data).await?;
+
+    // A payment intent paid by a transfer held for review settles with it
+    if previous_status == TransactionStatus::UnderReview && *new_status != TransactionStatus::Pending {
+        payment_intents::settle_reviewed_payment(tx, transaction_id, new_status).await?;
+    }
+
+    Ok(previous_status)
+}
+
+/// Appends an entry to a transaction's event history and publishes it through
+/// the outbox with a snapshot of the transaction.
+async fn record_event<T>(
+    tx: &T,
+    transaction_id: Uuid,
+    previous_status: Option<&TransactionStatus>,
+    new_status: &TransactionStatus,
+    event_data: serde_json::Value,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
     tx.execute(
         "INSERT INTO transaction_events (transaction_id, previous_status, new_status, event_data) 
-         VALUES ($1, NULL, $2, $3)",
+         VALUES ($1, $2, $3, $4)",
         &[
             &transaction_id,
-            &"pending",
-            &json!({"user_id": user_id.to_string(), "action": "created"}),
+            &previous_status.map(|status| status.to_string()),
+            &new_status.to_string(),
+            &event_data,
         ],
     )
     .await?;
 
-    // Process the transaction
-    match transaction_type {
-        TransactionType::Deposit => {
-            let dest_account_id = data.destination_account_id.unwrap();
-            accounts::update_balance(&tx, dest_account_id, data.amount).await?;
+    let event_type = match previous_status {
+        Some(_) => format!("transaction.{}", new_status),
+        None => "transaction.created".to_string(),
+    };
+    let transaction = get_transaction_by_id(tx, transaction_id).await?;
+    let account_ids: Vec<Uuid> = [transaction.source_account_id, transaction.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    let user_ids = accounts::get_owner_ids(tx, &account_ids).await?;
+    let payload = json!({
+        "transaction": transaction,
+        "previous_status": previous_status.map(|status| status.to_string()),
+        "event_data": event_data,
+    });
+    outbox::record(
+        tx,
+        AggregateType::Transaction,
+        transaction_id,
+        &user_ids,
+        &event_type,
+        payload,
+    )
+    .await?;
 
-            // Update transaction status to completed
-            tx.execute(
-                "UPDATE transactions SET status = $1, updated_at = NOW() WHERE id = $2",
-                &[&"completed", &transaction_id],
-            )
-            .await?;
+    Ok(())
+}
 
-            // Add transaction event
-            tx.execute(
-                "INSERT INTO transaction_events (transaction_id, previous_status, new_status, event_data) 
-                 VALUES ($1, $2, $3, $4)",
-                &[
-                    &transaction_id,
-                    &"pending",
-                    &"completed",
-                    &json!({"user_id": user_id.to_string(), "action": "processed"}),
-                ],
-            )
-            .await?;
-        }
-        TransactionType::Withdrawal => {
-            let source_account_id = data.source_account_id.unwrap();
-            // Subtract the amount (negative value)
-            accounts::update_balance(&tx, source_account_id, -data.amount).await?;
+/// Only the owner of the source account may capture or void its authorization.
+async fn ensure_hold_owner<T>(tx: &T, user_id: Uuid, transaction: &Transaction) -> Result<Vec<Account>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let source_account_id = transaction.source_account_id.ok_or_else(|| {
+        AppError::BadRequest("Transaction has no source account to release".to_string())
+    })?;
 
-            // Update transaction status to completed
-            tx.execute(
-                "UPDATE transactions SET status = $1, updated_at = NOW() WHERE id = $2",
-                &[&"completed", &transaction_id],
-            )
-            .await?;
+    let account_ids: Vec<Uuid> = [transaction.source_account_id, transaction.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    let locked = accounts::lock_accounts(tx, &account_ids).await?;
 
-            // Add transaction event
-            tx.execute(
-                "INSERT INTO transaction_events (transaction_id, previous_status, new_status, event_data) 
-                 VALUES ($1, $2, $3, $4)",
-                &[
-                    &transaction_id,
-                    &"pending",
-                    &"completed",
-                    &json!({"user_id": user_id.to_string(), "action": "processed"}),
-                ],
-            )
-            .await?;
-        }
-        TransactionType::Transfer => {
-            let source_account_id = data.source_account_id.unwrap();
-            let dest_account_id = data.destination_account_id.unwrap();
+    let source_account = locked.iter().find(|a| a.id == source_account_id).unwrap();
+    if source_account.user_id != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to modify this authorization".to_string(),
+        ));
+    }
 
-            // Subtract from source account
-            accounts::update_balance(&tx, source_account_id, -data.amount).await?;
-            // Add to destination account
-            accounts::update_balance(&tx, dest_account_id, data.amount).await?;
+    Ok(locked)
+}
 
-            // Update transaction status to completed
-            tx.execute(
-                "UPDATE transactions SET status = $1, updated_at = NOW() WHERE id = $2",
-                &[&"completed", &transaction_id],
-            )
-            .await?;
+/// The recipient of a screened transfer and the watchlist entry they matched.
+struct WatchlistHit {
+    recipient: ScreenedPerson,
+    watchlist_match: WatchlistMatch,
+}
 
-            // Add transaction event
-            tx.execute(
-                "INSERT INTO transaction_events (transaction_id, previous_status, new_status, event_data) 
-                 VALUES ($1, $2, $3, $4)",
-                &[
-                    &transaction_id,
-                    &"pending",
-                    &"completed",
-                    &json!({"user_id": user_id.to_string(), "action": "processed"}),
-                ],
-            )
-            .await?;
-        }
+/// What screening made of a transfer.
+#[derive(Default)]
+struct ScreeningOutcome {
+    assessment: Option<RiskAssessment>,
+    watchlist_hit: Option<WatchlistHit>,
+}
+
+impl ScreeningOutcome {
+    /// A watchlist hit holds a transfer whatever the risk engine decided, so
+    /// compliance sees it rather than it being declined outright.
+    fn declined(&self) -> Option<&RiskAssessment> {
+        self.assessment
+            .as_ref()
+            .filter(|assessment| self.watchlist_hit.is_none() && assessment.decision == RiskDecision::Decline)
     }
 
-    // Commit the transaction
-    tx.commit().await?;
+    fn held(&self) -> bool {
+        self.watchlist_hit.is_some()
+            || self
+                .assessment
+                .as_ref()
+                .is_some_and(|assessment| assessment.decision == RiskDecision::Review)
+    }
+}
 
-    // Get the final transaction record
-    let row = client
-        .query_one(
-            "SELECT id, source_account_id, destination_account_id, amount, currency, status, 
-                    transaction_type, description, created_at, updated_at 
-             FROM transactions 
-             WHERE id = $1",
-            &[&transaction_id],
-        )
-        .await?;
+/// Runs the risk engine over a transfer, and checks its recipient against the
+/// watchlist, when screening was asked for.
+async fn screen_request<T>(
+    tx: &T,
+    user_id: Uuid,
+    data: &CreateTransactionRequest,
+    transaction_type: &TransactionType,
+    screening: Option<&Screening<'_>>,
+) -> Result<ScreeningOutcome, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let (TransactionType::Transfer, Some(screening)) = (transaction_type, screening) else {
+        return Ok(ScreeningOutcome::default());
+    };
+    let Some(assessment) = risk::assess_transfer(tx, user_id, data, screening.engine, screening.signals).await?
+    else {
+        return Ok(ScreeningOutcome::default());
+    };
 
-    Ok(Transaction {
-        id: row.get("id"),
-        source_account_id: row.get("source_account_id"),
-        destination_account_id: row.get("destination_account_id"),
-        amount: row.get("amount"),
-        currency: row.get("currency"),
-        status: TransactionStatus::from(row.get::<_, &str>("status")),
-        transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
-        description: row.get("description"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
+    let recipient = screening::get_account_owner(tx, data.destination_account_id.unwrap()).await?;
+    let watchlist_hit = screening::find_match(tx, screening.watchlist, screening.match_threshold, &recipient)
+        .await?
+        .map(|watchlist_match| WatchlistHit { recipient, watchlist_match });
+
+    Ok(ScreeningOutcome {
+        assessment: Some(assessment),
+        watchlist_hit,
     })
 }
 
-pub async fn get_transaction_by_id(
-    client: &Client,
+/// Adds the risk engine's decision and the rules it hit to an event.
+fn with_assessment(mut event_data: serde_json::Value, assessment: Option<&RiskAssessment>) -> serde_json::Value {
+    if let Some(assessment) = assessment {
+        event_data["risk"] = json!(assessment);
+    }
+    event_data
+}
+
+async fn decline_screened<T>(
+    tx: &T,
+    user_id: Uuid,
     transaction_id: Uuid,
-) -> Result<Transaction, AppError> {
+    assessment: &RiskAssessment,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    transition_status(
+        tx,
+        transaction_id,
+        &TransactionStatus::Failed,
+        json!({"user_id": user_id.to_string(), "action": "risk_declined", "risk": assessment}),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn declined_error(transaction_id: Uuid) -> AppError {
+    AppError::Forbidden(format!("Transaction {} was declined by risk checks", transaction_id))
+}
+
+/// Moves a transaction whose funds are already held into review and opens a
+/// review for it.
+async fn hold_for_review<T>(
+    tx: &T,
+    user_id: Uuid,
+    transaction_id: Uuid,
+    assessment: &RiskAssessment,
+    hold: &TransactionHold,
+    authorize_only: bool,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let review = risk::open_review(tx, transaction_id, assessment, authorize_only).await?;
+
+    transition_status(
+        tx,
+        transaction_id,
+        &TransactionStatus::UnderReview,
+        json!({
+            "user_id": user_id.to_string(),
+            "action": "risk_review",
+            "review_id": review.id.to_string(),
+            "hold_id": hold.id.to_string(),
+            "expires_at": hold.expires_at.to_rfc3339(),
+            "risk": assessment,
+        }),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Moves a transaction whose funds are already held into review, opening a
+/// screening case for a watchlist hit or a risk review otherwise.
+async fn hold_screened<T>(
+    tx: &T,
+    user_id: Uuid,
+    transaction_id: Uuid,
+    outcome: &ScreeningOutcome,
+    hold: &TransactionHold,
+    authorize_only: bool,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let Some(hit) = &outcome.watchlist_hit else {
+        let assessment = outcome.assessment.as_ref().unwrap();
+        return hold_for_review(tx, user_id, transaction_id, assessment, hold, authorize_only).await;
+    };
+
+    let held = HeldTransfer {
+        transaction_id,
+        authorize_only,
+        risk_assessment: outcome.assessment.as_ref(),
+    };
+    let case = screening::open_case(tx, &hit.recipient, &hit.watchlist_match, Some(&held)).await?;
+
+    transition_status(
+        tx,
+        transaction_id,
+        &TransactionStatus::UnderReview,
+        with_assessment(
+            json!({
+                "user_id": user_id.to_string(),
+                "action": "watchlist_hold",
+                "case_id": case.id.to_string(),
+                "hold_id": hold.id.to_string(),
+                "expires_at": hold.expires_at.to_rfc3339(),
+                "watchlist": hit.watchlist_match,
+            }),
+            outcome.assessment.as_ref(),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Lets a transaction held in review go ahead. An immediate transfer is
+/// completed from its hold; an authorization goes back to pending.
+async fn release_reviewed<T>(
+    tx: &T,
+    transaction: &Transaction,
+    hold: &TransactionHold,
+    authorize_only: bool,
+    event_data: serde_json::Value,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    if authorize_only {
+        transition_status(tx, transaction.id, &TransactionStatus::Pending, event_data).await?;
+        return Ok(());
+    }
+
+    let account_ids: Vec<Uuid> = [transaction.source_account_id, transaction.destination_account_id]
+        .into_iter()
+        .flatten()
+        .collect();
+    for account in accounts::lock_accounts(tx, &account_ids).await? {
+        if Some(account.id) == transaction.source_account_id {
+            accounts::ensure_can_debit(&account)?;
+        } else {
+            accounts::ensure_can_credit(&account)?;
+        }
+    }
+
+    accounts::release_hold(tx, hold.account_id, hold.amount).await?;
+    holds::update_hold_status(tx, hold.id, &HoldStatus::Captured, Some(hold.amount)).await?;
+
+    let postings = match (transaction.destination_amount, &transaction.destination_currency) {
+        (Some(destination_amount), Some(destination_currency)) => build_fx_postings(
+            transaction.source_account_id.unwrap(),
+            transaction.destination_account_id.unwrap(),
+            transaction.amount,
+            &transaction.currency,
+            destination_amount,
+            destination_currency,
+        ),
+        _ => build_postings(
+            &transaction.transaction_type,
+            transaction.source_account_id,
+            transaction.destination_account_id,
+            transaction.amount,
+            &transaction.currency,
+        ),
+    };
+    ledger::post_entries(tx, transaction.id, &postings).await?;
+
+    transition_status(tx, transaction.id, &TransactionStatus::Completed, event_data).await?;
+
+    Ok(())
+}
+
+/// Fails a transaction held in review, releasing its hold if it is still active.
+async fn fail_reviewed<T>(
+    tx: &T,
+    transaction: &Transaction,
+    hold: &TransactionHold,
+    event_data: serde_json::Value,
+) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    if hold.status == HoldStatus::Active {
+        accounts::release_hold(tx, hold.account_id, hold.amount).await?;
+        holds::update_hold_status(tx, hold.id, &HoldStatus::Voided, None).await?;
+    }
+
+    transition_status(tx, transaction.id, &TransactionStatus::Failed, event_data).await?;
+
+    Ok(())
+}
+
+/// The transfer a screening case still holds. Transfers whose hold expired
+/// before the case was decided have already been cancelled.
+async fn lock_held_transaction<T>(tx: &T, case: &ScreeningCase) -> Result<Option<Transaction>, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let Some(transaction_id) = case.transaction_id else {
+        return Ok(None);
+    };
+    let transaction = lock_transaction(tx, transaction_id).await?;
+
+    Ok((transaction.status == TransactionStatus::UnderReview).then_some(transaction))
+}
+
+async fn lock_reviewed_transaction<T>(tx: &T, transaction_id: Uuid) -> Result<Transaction, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let transaction = lock_transaction(tx, transaction_id).await?;
+    if transaction.status != TransactionStatus::UnderReview {
+        return Err(AppError::Conflict(format!(
+            "Transaction is {}, not under review",
+            transaction.status
+        )));
+    }
+
+    Ok(transaction)
+}
+
+/// Held funds cannot be captured or released by their owner while an
+/// operator is reviewing the transfer.
+fn ensure_not_under_review(transaction: &Transaction) -> Result<(), AppError> {
+    if transaction.status == TransactionStatus::UnderReview {
+        return Err(AppError::Conflict("Transaction is under review".to_string()));
+    }
+
+    Ok(())
+}
+
+fn ensure_hold_active(status: &HoldStatus, expires_at: chrono::DateTime<Utc>) -> Result<(), AppError> {
+    if *status != HoldStatus::Active {
+        return Err(AppError::BadRequest(format!(
+            "Authorization is already {}",
+            status
+        )));
+    }
+
+    if expires_at <= Utc::now() {
+        return Err(AppError::BadRequest("Authorization has expired".to_string()));
+    }
+
+    Ok(())
+}
+
+pub async fn get_transaction_by_id<T>(
+    client: &T,
+    transaction_id: Uuid,
+) -> Result<Transaction, AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
     let row = client
         .query_opt(
-            "SELECT id, source_account_id, destination_account_id, amount, 
-                    currency, status, transaction_type, description, created_at, updated_at 
+            "SELECT id, source_account_id, destination_account_id, amount, currency, status, 
+                    transaction_type, description, parent_transaction_id, refunded_amount, 
+                    destination_amount, destination_currency, fx_rate::TEXT AS fx_rate, 
+                    fx_spread_bps, fx_spread_amount, fx_quote_id, settled_at, created_at, updated_at 
              FROM transactions 
              WHERE id = $1",
             &[&transaction_id],
@@ -281,75 +1632,254 @@ pub async fn get_transaction_by_id(
             AppError::NotFound(format!("Transaction not found: {}", transaction_id))
         })?;
 
-    Ok(Transaction {
-        id: row.get("id"),
-        source_account_id: row.get("source_account_id"),
-        destination_account_id: row.get("destination_account_id"),
-        amount: row.get("amount"),
-        currency: row.get("currency"),
-        status: TransactionStatus::from(row.get::<_, &str>("status")),
-        transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
-        description: row.get("description"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    transaction_from_row(&row)
 }
 
+/// One page of the transactions into or out of the user's accounts that match
+/// `filter`, ordered by `(created_at, id)` and starting after `cursor`. Also
+/// returns the cursor for the following page when there is one.
 pub async fn get_user_transactions(
     client: &Client,
     user_id: Uuid,
+    filter: &TransactionFilter,
+    cursor: Option<&TransactionCursor>,
+    sort: SortDirection,
+    page_size: usize,
+) -> Result<(Vec<Transaction>, Option<TransactionCursor>), AppError> {
+    let mut query = String::from(
+        "SELECT t.id, t.source_account_id, t.destination_account_id, t.amount,
+                t.currency, t.status, t.transaction_type, t.description,
+                t.parent_transaction_id, t.refunded_amount, t.destination_amount,
+                t.destination_currency, t.fx_rate::TEXT AS fx_rate, t.fx_spread_bps,
+                t.fx_spread_amount, t.fx_quote_id, t.settled_at, t.created_at, t.updated_at
+         FROM transactions t
+         WHERE (t.source_account_id IN (SELECT id FROM accounts WHERE user_id = $1)
+                OR t.destination_account_id IN (SELECT id FROM accounts WHERE user_id = $1))",
+    );
+    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&user_id];
+    let mut param_count = 2;
+
+    if let Some(account_id) = &filter.account_id {
+        query.push_str(&format!(
+            " AND (t.source_account_id = ${0} OR t.destination_account_id = ${0})",
+            param_count
+        ));
+        params.push(account_id);
+        param_count += 1;
+    }
+
+    let status = filter.status.as_ref().map(|status| status.to_string());
+    if let Some(status) = &status {
+        query.push_str(&format!(" AND t.status = ${}", param_count));
+        params.push(status);
+        param_count += 1;
+    }
+
+    let transaction_type = filter.transaction_type.as_ref().map(|transaction_type| transaction_type.to_string());
+    if let Some(transaction_type) = &transaction_type {
+        query.push_str(&format!(" AND t.transaction_type = ${}", param_count));
+        params.push(transaction_type);
+        param_count += 1;
+    }
+
+    if let Some(currency) = &filter.currency {
+        query.push_str(&format!(" AND t.currency = ${}", param_count));
+        params.push(currency);
+        param_count += 1;
+    }
+
+    if let Some(min_amount) = &filter.min_amount {
+        query.push_str(&format!(" AND t.amount >= ${}", param_count));
+        params.push(min_amount);
+        param_count += 1;
+    }
+
+    if let Some(max_amount) = &filter.max_amount {
+        query.push_str(&format!(" AND t.amount <= ${}", param_count));
+        params.push(max_amount);
+        param_count += 1;
+    }
+
+    if let Some(created_after) = &filter.created_after {
+        query.push_str(&format!(" AND t.created_at >= ${}", param_count));
+        params.push(created_after);
+        param_count += 1;
+    }
+
+    if let Some(created_before) = &filter.created_before {
+        query.push_str(&format!(" AND t.created_at < ${}", param_count));
+        params.push(created_before);
+        param_count += 1;
+    }
+
+    let description = filter
+        .description
+        .as_deref()
+        .map(|description| format!("%{}%", escape_like(description)));
+    if let Some(description) = &description {
+        query.push_str(&format!(" AND t.description ILIKE ${}", param_count));
+        params.push(description);
+        param_count += 1;
+    }
+
+    let (comparison, direction) = match sort {
+        SortDirection::Asc => (">", "ASC"),
+        SortDirection::Desc => ("<", "DESC"),
+    };
+
+    if let Some(cursor) = cursor {
+        query.push_str(&format!(
+            " AND (t.created_at, t.id) {} (${}, ${})",
+            comparison,
+            param_count,
+            param_count + 1
+        ));
+        params.push(&cursor.created_at);
+        params.push(&cursor.id);
+        param_count += 2;
+    }
+
+    // Fetch one extra row to learn whether there is another page
+    let limit = page_size as i64 + 1;
+    query.push_str(&format!(
+        " ORDER BY t.created_at {0}, t.id {0} LIMIT ${1}",
+        direction, param_count
+    ));
+    params.push(&limit);
+
+    let rows = client.query(&query, &params[..]).await?;
+
+    let mut transactions = rows
+        .iter()
+        .map(transaction_from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let next_cursor = if transactions.len() > page_size {
+        transactions.truncate(page_size);
+        transactions.last().map(|transaction| TransactionCursor {
+            created_at: transaction.created_at,
+            id: transaction.id,
+        })
+    } else {
+        None
+    };
+
+    Ok((transactions, next_cursor))
+}
+
+/// Escapes the wildcards in a `LIKE` pattern so they match literally.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn transaction_event_from_row(row: &Row) -> TransactionEvent {
+    TransactionEvent {
+        id: row.get("id"),
+        sequence_number: row.get("sequence_number"),
+        transaction_id: row.get("transaction_id"),
+        previous_status: row.get("previous_status"),
+        new_status: row.get("new_status"),
+        event_data: row.get("event_data"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Events for a transaction after the one numbered `after`, oldest first.
+pub async fn get_transaction_events_after(
+    client: &Client,
+    transaction_id: Uuid,
+    after: i64,
+) -> Result<Vec<TransactionEvent>, AppError> {
+    let rows = client
+        .query(
+            "SELECT id, sequence_number, transaction_id, previous_status, new_status, event_data, created_at
+             FROM transaction_events
+             WHERE transaction_id = $1 AND sequence_number > $2
+             ORDER BY sequence_number",
+            &[&transaction_id, &after],
+        )
+        .await?;
+
+    Ok(rows.iter().map(transaction_event_from_row).collect())
+}
+
+/// One page of a transaction's events, oldest first.
+pub async fn get_transaction_history(
+    client: &Client,
+    transaction_id: Uuid,
     page: usize,
     page_size: usize,
-) -> Result<(Vec<Transaction>, usize), AppError> {
-    // Get the total count
-    let total_row = client
+) -> Result<(Vec<TransactionEvent>, usize), AppError> {
+    let total: i64 = client
         .query_one(
-            "SELECT COUNT(*) as total
-             FROM transactions t
-             JOIN accounts a 
-                ON t.source_account_id = a.id OR t.destination_account_id = a.id
-             WHERE a.user_id = $1",
-            &[&user_id],
+            "SELECT COUNT(*) AS total FROM transaction_events WHERE transaction_id = $1",
+            &[&transaction_id],
+        )
+        .await?
+        .get("total");
+
+    let offset = (page.max(1) - 1) * page_size;
+    let rows = client
+        .query(
+            "SELECT id, sequence_number, transaction_id, previous_status, new_status, event_data, created_at
+             FROM transaction_events
+             WHERE transaction_id = $1
+             ORDER BY sequence_number
+             LIMIT $2 OFFSET $3",
+            &[&transaction_id, &(page_size as i64), &(offset as i64)],
         )
         .await?;
 
-    let total: i64 = total_row.get("total");
-    
-    // Skip for pagination
-    let offset = (page - 1) * page_size;
+    Ok((rows.iter().map(transaction_event_from_row).collect(), total as usize))
+}
+
+/// One page of the events of every transaction into or out of an account,
+/// newest first.
+pub async fn get_account_activity(
+    client: &Client,
+    account_id: Uuid,
+    page: usize,
+    page_size: usize,
+) -> Result<(Vec<AccountActivityItem>, usize), AppError> {
+    let total: i64 = client
+        .query_one(
+            "SELECT COUNT(*) AS total
+             FROM transaction_events e
+             JOIN transactions t ON t.id = e.transaction_id
+             WHERE t.source_account_id = $1 OR t.destination_account_id = $1",
+            &[&account_id],
+        )
+        .await?
+        .get("total");
 
-    // Get the transactions
+    let offset = (page.max(1) - 1) * page_size;
     let rows = client
         .query(
-            "SELECT t.id, t.source_account_id, t.destination_account_id, t.amount, 
-                    t.currency, t.status, t.transaction_type, t.description, t.created_at, t.updated_at 
-             FROM transactions t
-             JOIN accounts a 
-                ON t.source_account_id = a.id OR t.destination_account_id = a.id
-             WHERE a.user_id = $1
-             ORDER BY t.created_at DESC
+            "SELECT e.id, e.sequence_number, e.transaction_id, e.previous_status, e.new_status,
+                    e.event_data, e.created_at, t.transaction_type, t.amount, t.currency
+             FROM transaction_events e
+             JOIN transactions t ON t.id = e.transaction_id
+             WHERE t.source_account_id = $1 OR t.destination_account_id = $1
+             ORDER BY e.sequence_number DESC
              LIMIT $2 OFFSET $3",
-            &[&user_id, &(page_size as i64), &(offset as i64)],
+            &[&account_id, &(page_size as i64), &(offset as i64)],
         )
         .await?;
 
-    let transactions = rows
+    let activity = rows
         .iter()
-        .map(|row| Transaction {
-            id: row.get("id"),
-            source_account_id: row.get("source_account_id"),
-            destination_account_id: row.get("destination_account_id"),
+        .map(|row| AccountActivityItem {
+            event: transaction_event_from_row(row),
+            transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
             amount: row.get("amount"),
             currency: row.get("currency"),
-            status: TransactionStatus::from(row.get::<_, &str>("status")),
-            transaction_type: TransactionType::from(row.get::<_, &str>("transaction_type")),
-            description: row.get("description"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
         })
         .collect();
 
-    Ok((transactions, total as usize))
+    Ok((activity, total as usize))
 }
 
 pub async fn can_user_access_transaction(
@@ -1,11 +1,26 @@
-use crate::models::user::{CreateUserRequest, User, UpdateUserRequest};
+use crate::models::user::{CreateUserRequest, User, UpdateUserRequest, UserRole};
 use crate::utils::error::AppError;
 use argon2::{password_hash::SaltString, Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use chrono::Utc;
 use deadpool_postgres::Client;
 use rand::rngs::OsRng;
+use tokio_postgres::Row;
 use uuid::Uuid;
 
+fn user_from_row(row: &Row) -> User {
+    User {
+        id: row.get("id"),
+        email: row.get("email"),
+        username: row.get("username"),
+        password_hash: row.get("password_hash"),
+        full_name: row.get("full_name"),
+        date_of_birth: row.get("date_of_birth"),
+        role: UserRole::from(row.get::<_, &str>("role")),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
 pub async fn create_user(
     client: &Client,
     user_data: &CreateUserRequest,
@@ -21,14 +36,15 @@ pub async fn create_user(
     // Insert the new user into the database
     let row = client
         .query_one(
-            "INSERT INTO users (email, username, password_hash, full_name) 
-             VALUES ($1, $2, $3, $4) 
-             RETURNING id, email, username, password_hash, full_name, created_at, updated_at",
+            "INSERT INTO users (email, username, password_hash, full_name, date_of_birth) 
+             VALUES ($1, $2, $3, $4, $5) 
+             RETURNING id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at",
             &[
                 &user_data.email,
                 &user_data.username,
                 &password_hash,
                 &user_data.full_name,
+                &user_data.date_of_birth,
             ],
         )
         .await
@@ -46,21 +62,13 @@ pub async fn create_user(
             }
         })?;
 
-    Ok(User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    Ok(user_from_row(&row))
 }
 
 pub async fn get_user_by_id(client: &Client, user_id: Uuid) -> Result<User, AppError> {
     let row = client
         .query_opt(
-            "SELECT id, email, username, password_hash, full_name, created_at, updated_at 
+            "SELECT id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at 
              FROM users 
              WHERE id = $1",
             &[&user_id],
@@ -68,22 +76,14 @@ pub async fn get_user_by_id(client: &Client, user_id: Uuid) -> Result<User, AppE
         .await?
         .ok_or_else(|| AppError::NotFound(format!("User not found with ID: {}", user_id)))?;
 
-    Ok(User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    Ok(user_from_row(&row))
 }
 
 #[allow(dead_code)]
 pub async fn get_user_by_email(client: &Client, email: &str) -> Result<User, AppError> {
     let row = client
         .query_opt(
-            "SELECT id, email, username, password_hash, full_name, created_at, updated_at 
+            "SELECT id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at 
              FROM users 
              WHERE email = $1",
             &[&email],
@@ -91,22 +91,14 @@ pub async fn get_user_by_email(client: &Client, email: &str) -> Result<User, App
         .await?
         .ok_or_else(|| AppError::NotFound(format!("User not found with email: {}", email)))?;
 
-    Ok(User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    Ok(user_from_row(&row))
 }
 
 #[allow(dead_code)]
 pub async fn get_user_by_username(client: &Client, username: &str) -> Result<User, AppError> {
     let row = client
         .query_opt(
-            "SELECT id, email, username, password_hash, full_name, created_at, updated_at 
+            "SELECT id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at 
              FROM users 
              WHERE username = $1",
             &[&username],
@@ -114,15 +106,7 @@ pub async fn get_user_by_username(client: &Client, username: &str) -> Result<Use
         .await?
         .ok_or_else(|| AppError::NotFound(format!("User not found with username: {}", username)))?;
 
-    Ok(User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    Ok(user_from_row(&row))
 }
 
 pub async fn update_user(
@@ -153,7 +137,7 @@ pub async fn update_user(
         param_count += 1;
     }
 
-    query.push_str(&format!(" WHERE id = ${} RETURNING id, email, username, password_hash, full_name, created_at, updated_at", param_count));
+    query.push_str(&format!(" WHERE id = ${} RETURNING id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at", param_count));
     params.push(&user_id);
 
     let row = client
@@ -161,15 +145,7 @@ pub async fn update_user(
         .await?
         .ok_or_else(|| AppError::NotFound(format!("User not found with ID: {}", user_id)))?;
 
-    Ok(User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    Ok(user_from_row(&row))
 }
 
 pub async fn authenticate_user(
@@ -180,7 +156,7 @@ pub async fn authenticate_user(
     // Try to get user by username or email
     let row = client
         .query_opt(
-            "SELECT id, email, username, password_hash, full_name, created_at, updated_at 
+            "SELECT id, email, username, password_hash, full_name, date_of_birth, role, created_at, updated_at 
              FROM users 
              WHERE username = $1 OR email = $1",
             &[&username_or_email],
@@ -193,15 +169,7 @@ pub async fn authenticate_user(
             ))
         })?;
 
-    let user = User {
-        id: row.get("id"),
-        email: row.get("email"),
-        username: row.get("username"),
-        password_hash: row.get("password_hash"),
-        full_name: row.get("full_name"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    };
+    let user = user_from_row(&row);
 
     // Verify password
     let parsed_hash =
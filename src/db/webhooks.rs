@@ -0,0 +1,321 @@
+use crate::models::webhook::{
+    parse_event_types, CreateWebhookEndpointRequest, RetryPolicy, UpdateWebhookEndpointRequest,
+    WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryStatus, WebhookEndpoint, WebhookEvent,
+};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use deadpool_postgres::Client;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+fn endpoint_from_row(row: &Row) -> WebhookEndpoint {
+    let event_types: Vec<String> = row.get("event_types");
+
+    WebhookEndpoint {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        url: row.get("url"),
+        description: row.get("description"),
+        secret: row.get("secret"),
+        event_types: parse_event_types(&event_types),
+        active: row.get("active"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn delivery_from_row(row: &Row) -> WebhookDelivery {
+    WebhookDelivery {
+        id: row.get("id"),
+        endpoint_id: row.get("endpoint_id"),
+        event_id: row.get("event_id"),
+        event_type: row.get("event_type"),
+        payload: row.get("payload"),
+        status: WebhookDeliveryStatus::from(row.get::<_, &str>("status")),
+        attempts: row.get("attempts"),
+        next_attempt_at: row.get("next_attempt_at"),
+        last_response_status: row.get("last_response_status"),
+        last_error: row.get("last_error"),
+        delivered_at: row.get("delivered_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn attempt_from_row(row: &Row) -> WebhookDeliveryAttempt {
+    WebhookDeliveryAttempt {
+        id: row.get("id"),
+        delivery_id: row.get("delivery_id"),
+        attempt: row.get("attempt"),
+        response_status: row.get("response_status"),
+        error: row.get("error"),
+        duration_ms: row.get("duration_ms"),
+        attempted_at: row.get("attempted_at"),
+    }
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+pub async fn create_endpoint(
+    client: &Client,
+    user_id: Uuid,
+    data: &CreateWebhookEndpointRequest,
+) -> Result<WebhookEndpoint, AppError> {
+    let row = client
+        .query_one(
+            "INSERT INTO webhook_endpoints (user_id, url, description, secret, event_types)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            &[
+                &user_id,
+                &data.url,
+                &data.description,
+                &generate_secret(),
+                &data.event_types,
+            ],
+        )
+        .await?;
+
+    Ok(endpoint_from_row(&row))
+}
+
+pub async fn get_endpoint(client: &Client, endpoint_id: Uuid) -> Result<WebhookEndpoint, AppError> {
+    let row = client
+        .query_opt("SELECT * FROM webhook_endpoints WHERE id = $1", &[&endpoint_id])
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Webhook endpoint not found: {}", endpoint_id)))?;
+
+    Ok(endpoint_from_row(&row))
+}
+
+pub async fn get_user_endpoints(client: &Client, user_id: Uuid) -> Result<Vec<WebhookEndpoint>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM webhook_endpoints WHERE user_id = $1 ORDER BY created_at DESC",
+            &[&user_id],
+        )
+        .await?;
+
+    Ok(rows.iter().map(endpoint_from_row).collect())
+}
+
+pub async fn update_endpoint(
+    client: &Client,
+    endpoint_id: Uuid,
+    data: &UpdateWebhookEndpointRequest,
+) -> Result<WebhookEndpoint, AppError> {
+    let row = client
+        .query_opt(
+            "UPDATE webhook_endpoints
+             SET url = COALESCE($1, url),
+                 description = COALESCE($2, description),
+                 event_types = COALESCE($3, event_types),
+                 active = COALESCE($4, active),
+                 updated_at = NOW()
+             WHERE id = $5
+             RETURNING *",
+            &[
+                &data.url,
+                &data.description,
+                &data.event_types,
+                &data.active,
+                &endpoint_id,
+            ],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Webhook endpoint not found: {}", endpoint_id)))?;
+
+    Ok(endpoint_from_row(&row))
+}
+
+/// Deletes an endpoint along with its delivery history.
+pub async fn delete_endpoint(client: &Client, endpoint_id: Uuid) -> Result<(), AppError> {
+    client
+        .execute("DELETE FROM webhook_endpoints WHERE id = $1", &[&endpoint_id])
+        .await?;
+
+    Ok(())
+}
+
+/// Queues an event for every active endpoint of `user_ids` subscribed to it.
+/// An event already queued for an endpoint is not queued again, so the same
+/// event can safely be passed in more than once.
+pub async fn enqueue_event<T>(client: &T, event: &WebhookEvent, user_ids: &[Uuid]) -> Result<(), AppError>
+where
+    T: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let payload = serde_json::to_value(event)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize webhook event: {}", e)))?;
+
+    client
+        .execute(
+            "INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, payload)
+             SELECT id, $1, $2::TEXT, $3
+             FROM webhook_endpoints
+             WHERE active AND user_id = ANY($4) AND $2::TEXT = ANY(event_types)
+             ON CONFLICT (endpoint_id, event_id) DO NOTHING",
+            &[&event.id, &event.event_type.to_string(), &payload, &user_ids],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Claims up to `limit` due deliveries along with the endpoint each goes to.
+/// Claimed deliveries are pushed back by `lease_seconds`, so if the worker
+/// dies mid-request they are retried rather than lost.
+pub async fn claim_due_deliveries(
+    client: &Client,
+    limit: i64,
+    lease_seconds: i64,
+) -> Result<Vec<(WebhookDelivery, WebhookEndpoint)>, AppError> {
+    let rows = client
+        .query(
+            "WITH due AS (
+                 SELECT id FROM webhook_deliveries
+                 WHERE status = 'pending' AND next_attempt_at <= NOW()
+                 ORDER BY next_attempt_at
+                 LIMIT $1
+                 FOR UPDATE SKIP LOCKED
+             )
+             UPDATE webhook_deliveries d
+             SET next_attempt_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+             FROM due
+             WHERE d.id = due.id
+             RETURNING d.*",
+            &[&limit, &(lease_seconds as f64)],
+        )
+        .await?;
+
+    let mut claimed = Vec::with_capacity(rows.len());
+    for row in rows {
+        let delivery = delivery_from_row(&row);
+        let endpoint = get_endpoint(client, delivery.endpoint_id).await?;
+        claimed.push((delivery, endpoint));
+    }
+
+    Ok(claimed)
+}
+
+/// Records the outcome of one attempt and decides what happens next: done on
+/// success, otherwise a retry after the policy's backoff until the attempts
+/// run out. A failed manual redelivery leaves a finished delivery as it was.
+pub async fn record_attempt(
+    client: &mut Client,
+    delivery_id: Uuid,
+    response_status: Option<i32>,
+    error: Option<&str>,
+    duration_ms: i32,
+    policy: &RetryPolicy,
+) -> Result<WebhookDeliveryAttempt, AppError> {
+    let tx = client.transaction().await?;
+
+    let row = tx
+        .query_one(
+            "SELECT attempts, status FROM webhook_deliveries WHERE id = $1 FOR UPDATE",
+            &[&delivery_id],
+        )
+        .await?;
+    let attempt = row.get::<_, i32>("attempts") + 1;
+    let current_status = WebhookDeliveryStatus::from(row.get::<_, &str>("status"));
+    let succeeded = error.is_none();
+
+    let row = tx
+        .query_one(
+            "INSERT INTO webhook_delivery_attempts (delivery_id, attempt, response_status, error, duration_ms)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+            &[&delivery_id, &attempt, &response_status, &error, &duration_ms],
+        )
+        .await?;
+
+    let status = match current_status {
+        _ if succeeded => WebhookDeliveryStatus::Succeeded,
+        WebhookDeliveryStatus::Pending if attempt >= policy.max_attempts => WebhookDeliveryStatus::Failed,
+        status => status,
+    };
+    let next_attempt_at = Utc::now() + policy.delay_after(attempt);
+
+    tx.execute(
+        "UPDATE webhook_deliveries
+         SET attempts = $1, status = $2, last_response_status = $3, last_error = $4,
+             next_attempt_at = $5,
+             delivered_at = CASE WHEN $6 THEN COALESCE(delivered_at, NOW()) ELSE delivered_at END,
+             updated_at = NOW()
+         WHERE id = $7",
+        &[
+            &attempt,
+            &status.to_string(),
+            &response_status,
+            &error,
+            &next_attempt_at,
+            &succeeded,
+            &delivery_id,
+        ],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(attempt_from_row(&row))
+}
+
+pub async fn get_delivery(client: &Client, delivery_id: Uuid) -> Result<WebhookDelivery, AppError> {
+    let row = client
+        .query_opt(
+            "SELECT * FROM webhook_deliveries WHERE id = $1",
+            &[&delivery_id],
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Webhook delivery not found: {}", delivery_id)))?;
+
+    Ok(delivery_from_row(&row))
+}
+
+pub async fn get_endpoint_deliveries(
+    client: &Client,
+    endpoint_id: Uuid,
+    page: usize,
+    page_size: usize,
+) -> Result<(Vec<WebhookDelivery>, usize), AppError> {
+    let total: i64 = client
+        .query_one(
+            "SELECT COUNT(*) AS total FROM webhook_deliveries WHERE endpoint_id = $1",
+            &[&endpoint_id],
+        )
+        .await?
+        .get("total");
+
+    let offset = (page.max(1) - 1) * page_size;
+    let rows = client
+        .query(
+            "SELECT * FROM webhook_deliveries
+             WHERE endpoint_id = $1
+             ORDER BY created_at DESC
+             LIMIT $2 OFFSET $3",
+            &[&endpoint_id, &(page_size as i64), &(offset as i64)],
+        )
+        .await?;
+
+    Ok((rows.iter().map(delivery_from_row).collect(), total as usize))
+}
+
+pub async fn get_delivery_attempts(
+    client: &Client,
+    delivery_id: Uuid,
+) -> Result<Vec<WebhookDeliveryAttempt>, AppError> {
+    let rows = client
+        .query(
+            "SELECT * FROM webhook_delivery_attempts WHERE delivery_id = $1 ORDER BY attempt",
+            &[&delivery_id],
+        )
+        .await?;
+
+    Ok(rows.iter().map(attempt_from_row).collect())
+}
@@ -1,14 +1,29 @@
 use axum::{
     Json,
-    extract::{Extension, Path, State},
+    body::Body,
+    extract::{Extension, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
 };
+use chrono::Utc;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::config::Config;
-use crate::db::{Database, accounts};
+use crate::db::{Database, accounts, ledger, limits, transactions};
+use crate::handlers::transactions::{FormatParams, PaginationParams, transaction_response};
 use crate::middleware::auth::CurrentUser;
-use crate::models::account::{AccountListResponse, AccountResponse, CreateAccountRequest};
+use crate::models::account::{
+    Account, AccountEventListResponse, AccountListResponse, AccountResponse, CloseAccountRequest,
+    CreateAccountRequest, CurrencyTotal, MoveFundsRequest,
+};
+use crate::models::ledger::{AccountLedgerResponse, LedgerEntryResponse};
+use crate::models::limit::{AccountLimitsResponse, LimitScope, LimitWindows};
+use crate::models::statement::StatementParams;
+use crate::models::transaction::{
+    AccountActivityResponse, CreateTransactionRequest, TransactionResponse, TransactionType,
+};
+use crate::services::statement_service;
 use crate::utils::error::AppError;
 
 pub async fn create_account(
@@ -20,18 +35,10 @@ pub async fn create_account(
     // Validate the payload
     payload.validate()?;
 
-    let client = db.pool.get().await?;
-    let account = accounts::create_account(&client, current_user.user_id, &payload).await?;
+    let mut client = db.pool.get().await?;
+    let account = accounts::create_account(&mut client, current_user.user_id, &payload).await?;
 
-    Ok(Json(AccountResponse {
-        id: account.id,
-        user_id: account.user_id,
-        balance: account.balance,
-        currency: account.currency,
-        status: account.status.to_string(),
-        created_at: account.created_at,
-        updated_at: account.updated_at,
-    }))
+    Ok(Json(account_response(account)))
 }
 
 pub async fn get_account(
@@ -39,6 +46,7 @@ pub async fn get_account(
     Extension(db): Extension<Database>,
     State(_config): State<Config>,
     Path(account_id): Path<Uuid>,
+    Query(format): Query<FormatParams>,
 ) -> Result<Json<AccountResponse>, AppError> {
     let client = db.pool.get().await?;
     let account = accounts::get_account(&client, account_id).await?;
@@ -50,40 +58,301 @@ pub async fn get_account(
         ));
     }
 
-    Ok(Json(AccountResponse {
-        id: account.id,
-        user_id: account.user_id,
-        balance: account.balance,
-        currency: account.currency,
-        status: account.status.to_string(),
-        created_at: account.created_at,
-        updated_at: account.updated_at,
-    }))
+    let mut response = account_response(account);
+    if format.formatted {
+        response = response.with_formatted_amounts();
+    }
+
+    Ok(Json(response))
 }
 
 pub async fn list_accounts(
     current_user: CurrentUser,
     Extension(db): Extension<Database>,
     State(_config): State<Config>,
+    Query(format): Query<FormatParams>,
 ) -> Result<Json<AccountListResponse>, AppError> {
     let client = db.pool.get().await?;
     let accounts = accounts::get_user_accounts(&client, current_user.user_id).await?;
 
+    let totals = CurrencyTotal::from_accounts(&accounts)
+        .into_iter()
+        .map(|total| {
+            if format.formatted {
+                total.with_formatted_amounts()
+            } else {
+                total
+            }
+        })
+        .collect();
+
     let account_responses = accounts
         .into_iter()
-        .map(|account| AccountResponse {
-            id: account.id,
-            user_id: account.user_id,
-            balance: account.balance,
-            currency: account.currency,
-            status: account.status.to_string(),
-            created_at: account.created_at,
-            updated_at: account.updated_at,
+        .map(account_response)
+        .map(|response| {
+            if format.formatted {
+                response.with_formatted_amounts()
+            } else {
+                response
+            }
         })
         .collect();
 
     Ok(Json(AccountListResponse {
         accounts: account_responses,
+        totals,
+    }))
+}
+
+/// Moves money between two of the user's own accounts. The move is an
+/// ordinary transfer, so it completes straight away and shows up in both
+/// accounts' history. It is not screened, since screening only covers
+/// transfers to another user's account.
+pub async fn move_funds(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<MoveFundsRequest>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let source = accounts::get_account(&client, payload.source_account_id).await?;
+    let destination = accounts::get_account(&client, payload.destination_account_id).await?;
+
+    // Ensure both accounts belong to the current user
+    if source.user_id != current_user.user_id || destination.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    if source.currency != destination.currency {
+        return Err(AppError::BadRequest(format!(
+            "Cannot move funds from a {} account to a {} account",
+            source.currency, destination.currency
+        )));
+    }
+
+    let transaction = transactions::create_transaction(
+        &mut client,
+        current_user.user_id,
+        &CreateTransactionRequest {
+            source_account_id: Some(source.id),
+            destination_account_id: Some(destination.id),
+            amount: payload.amount,
+            currency: source.currency,
+            transaction_type: TransactionType::Transfer.to_string(),
+            description: payload.description,
+            authorize_only: false,
+            fx_quote_id: None,
+        },
+    )
+    .await?;
+
+    Ok(Json(transaction_response(transaction)))
+}
+
+/// Closes one of the user's accounts, sweeping any remaining balance to the
+/// account named in the request.
+pub async fn close_account(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+    Json(payload): Json<CloseAccountRequest>,
+) -> Result<Json<AccountResponse>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let account = accounts::close_account(&mut client, current_user.user_id, account_id, &payload).await?;
+
+    Ok(Json(account_response(account)))
+}
+
+pub async fn get_account_events(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountEventListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let account = accounts::get_account(&client, account_id).await?;
+
+    // Ensure the account belongs to the current user
+    if account.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    let events = accounts::get_account_events(&client, account_id).await?;
+
+    Ok(Json(AccountEventListResponse { events }))
+}
+
+/// The limits on money leaving the account, with what is left of each.
+pub async fn get_account_limits(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountLimitsResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let account = accounts::get_account(&client, account_id).await?;
+
+    // Ensure the account belongs to the current user
+    if account.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    let windows = LimitWindows::at(Utc::now());
+    let account_limits = limits::get_account_limits(&client, account.id).await?;
+    let account_usage = limits::get_usage(&client, LimitScope::Account, &account, &windows).await?;
+    let user_limits = limits::get_limits(&client, LimitScope::User, Some(account.user_id)).await?.limits;
+    let user_usage = limits::get_usage(&client, LimitScope::User, &account, &windows).await?;
+
+    Ok(Json(AccountLimitsResponse {
+        account_id: account.id,
+        currency: account.currency,
+        account: account_usage.remaining(&account_limits, &windows),
+        user: user_usage.remaining(&user_limits, &windows),
+    }))
+}
+
+pub async fn get_account_ledger(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<AccountLedgerResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let account = accounts::get_account(&client, account_id).await?;
+
+    // Ensure the account belongs to the current user
+    if account.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    let entries = ledger::get_account_entries(&client, account_id).await?;
+    let ledger_balance = ledger::get_ledger_balance(&client, account_id).await?;
+
+    let entry_responses = entries
+        .into_iter()
+        .map(|entry| LedgerEntryResponse {
+            id: entry.id,
+            transaction_id: entry.transaction_id,
+            account_id: entry.account_id,
+            system_account: entry.system_account,
+            entry_type: entry.entry_type.to_string(),
+            amount: entry.amount,
+            currency: entry.currency,
+            created_at: entry.created_at,
+        })
+        .collect();
+
+    Ok(Json(AccountLedgerResponse {
+        account_id: account.id,
+        balance: account.balance,
+        ledger_balance,
+        balanced: account.balance == ledger_balance,
+        entries: entry_responses,
     }))
 }
 
+pub(crate) fn account_response(account: Account) -> AccountResponse {
+    AccountResponse {
+        id: account.id,
+        user_id: account.user_id,
+        balance: account.balance,
+        available_balance: account.available_balance(),
+        currency: account.currency,
+        label: account.label,
+        purpose: account.purpose.to_string(),
+        status: account.status.to_string(),
+        merchant_id: account.merchant_id,
+        formatted_balance: None,
+        formatted_available_balance: None,
+        created_at: account.created_at,
+        updated_at: account.updated_at,
+    }
+}
+
+pub async fn get_account_activity(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<AccountActivityResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let account = accounts::get_account(&client, account_id).await?;
+
+    // Ensure the account belongs to the current user
+    if account.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    let (activity, total) =
+        transactions::get_account_activity(&client, account_id, params.page, params.page_size).await?;
+
+    Ok(Json(AccountActivityResponse {
+        account_id,
+        activity,
+        total,
+        page: params.page,
+        page_size: params.page_size,
+    }))
+}
+
+/// Downloads a statement for the period. The body is streamed as it is
+/// generated, so long periods are never held in memory.
+pub async fn get_account_statement(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+    Query(params): Query<StatementParams>,
+) -> Result<Response, AppError> {
+    let client = db.pool.get().await?;
+    let account = accounts::get_account(&client, account_id).await?;
+
+    // Ensure the account belongs to the current user
+    if account.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this account".to_string(),
+        ));
+    }
+
+    let from = params.from.unwrap_or(account.created_at);
+    let to = params.to.unwrap_or_else(Utc::now);
+    if from >= to {
+        return Err(AppError::BadRequest("from must be before to".to_string()));
+    }
+
+    let filename = format!(
+        "statement-{}-{}-{}.{}",
+        account.id,
+        from.format("%Y%m%d"),
+        to.format("%Y%m%d"),
+        params.format.extension()
+    );
+    let body = Body::from_stream(statement_service::stream(db, account, from, to, params.format));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, params.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,441 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{accounts, fx, limits, reconciliation, risk, screening, settlements, transactions, users, Database};
+use crate::handlers::accounts::account_response;
+use crate::handlers::transactions::transaction_response;
+use crate::middleware::auth::AdminUser;
+use crate::models::account::{AccountResponse, AccountStatus, ChangeAccountStatusRequest};
+use crate::models::fx::{parse_rates_csv, CreateFxRateRequest, FxRate, FxRateListResponse};
+use crate::models::limit::{LimitScope, SpendingLimitSet, SpendingLimits};
+use crate::models::reconciliation::{ReconciliationParams, ReconciliationReport};
+use crate::models::risk::{
+    DecideRiskReviewRequest, RiskReview, RiskReviewFilter, RiskReviewListResponse, RiskReviewResponse,
+    RiskReviewStatus,
+};
+use crate::models::settlement::{ImportSettlementRequest, SettlementReport};
+use crate::models::transaction::{ReverseTransactionRequest, TransactionResponse};
+use crate::models::watchlist::{
+    DecideScreeningCaseRequest, ScreeningCase, ScreeningCaseFilter, ScreeningCaseListResponse, ScreeningCaseResponse,
+    ScreeningCaseStatus, Watchlist, WatchlistStatusResponse,
+};
+use crate::services::screening_service::WatchlistHandle;
+use crate::services::{reconciliation_service, settlement_service};
+use crate::utils::error::AppError;
+
+pub async fn reverse_transaction(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+    Json(payload): Json<ReverseTransactionRequest>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let transaction =
+        transactions::reverse_transaction(&mut client, operator.user_id, transaction_id, &payload).await?;
+
+    Ok(Json(transaction_response(transaction)))
+}
+
+/// Stops money leaving an account until the suspension is lifted. The account
+/// can still receive money.
+pub async fn suspend_account(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+    Json(payload): Json<ChangeAccountStatusRequest>,
+) -> Result<Json<AccountResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let account = accounts::change_status(
+        &mut client,
+        operator.user_id,
+        account_id,
+        AccountStatus::Suspended,
+        &payload.reason,
+    )
+    .await?;
+
+    Ok(Json(account_response(account)))
+}
+
+pub async fn unsuspend_account(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(account_id): Path<Uuid>,
+    Json(payload): Json<ChangeAccountStatusRequest>,
+) -> Result<Json<AccountResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let account = accounts::change_status(
+        &mut client,
+        operator.user_id,
+        account_id,
+        AccountStatus::Active,
+        &payload.reason,
+    )
+    .await?;
+
+    Ok(Json(account_response(account)))
+}
+
+pub async fn get_default_limits(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+) -> Result<Json<SpendingLimitSet>, AppError> {
+    let client = db.pool.get().await?;
+    let limit_set = limits::get_limits(&client, LimitScope::Default, None).await?;
+
+    Ok(Json(limit_set))
+}
+
+/// Replaces the limits for accounts without their own. Unset limits do not
+/// apply.
+pub async fn set_default_limits(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<SpendingLimits>,
+) -> Result<Json<SpendingLimitSet>, AppError> {
+    payload.validate()?;
+
+    let client = db.pool.get().await?;
+    let limit_set = limits::set_limits(&client, LimitScope::Default, None, &payload, operator.user_id).await?;
+
+    Ok(Json(limit_set))
+}
+
+pub async fn get_subject_limits(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path((scope, subject_id)): Path<(String, Uuid)>,
+) -> Result<Json<SpendingLimitSet>, AppError> {
+    let client = db.pool.get().await?;
+    let scope = limit_subject(&client, &scope, subject_id).await?;
+    let limit_set = limits::get_limits(&client, scope, Some(subject_id)).await?;
+
+    Ok(Json(limit_set))
+}
+
+/// Replaces an account's or a user's limits. An account's unset limits fall
+/// back to the default; a user's do not apply.
+pub async fn set_subject_limits(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path((scope, subject_id)): Path<(String, Uuid)>,
+    Json(payload): Json<SpendingLimits>,
+) -> Result<Json<SpendingLimitSet>, AppError> {
+    payload.validate()?;
+
+    let client = db.pool.get().await?;
+    let scope = limit_subject(&client, &scope, subject_id).await?;
+    let limit_set = limits::set_limits(&client, scope, Some(subject_id), &payload, operator.user_id).await?;
+
+    Ok(Json(limit_set))
+}
+
+/// Transfers held by the risk engine, oldest first. Lists open reviews unless
+/// another status is asked for.
+pub async fn list_risk_reviews(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Query(filter): Query<RiskReviewFilter>,
+) -> Result<Json<RiskReviewListResponse>, AppError> {
+    let status = RiskReviewStatus::from(filter.status.as_deref().unwrap_or("open"));
+
+    let client = db.pool.get().await?;
+    let mut reviews = Vec::new();
+    for review in risk::get_reviews(&client, &status).await? {
+        reviews.push(risk_review_response(&client, review).await?);
+    }
+
+    Ok(Json(RiskReviewListResponse { reviews }))
+}
+
+pub async fn get_risk_review(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(review_id): Path<Uuid>,
+) -> Result<Json<RiskReviewResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let review = risk::get_review(&client, review_id).await?;
+
+    Ok(Json(risk_review_response(&client, review).await?))
+}
+
+pub async fn approve_risk_review(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(review_id): Path<Uuid>,
+    Json(payload): Json<DecideRiskReviewRequest>,
+) -> Result<Json<RiskReviewResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let review =
+        transactions::approve_review(&mut client, operator.user_id, review_id, payload.note.as_deref()).await?;
+
+    Ok(Json(risk_review_response(&client, review).await?))
+}
+
+pub async fn reject_risk_review(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(review_id): Path<Uuid>,
+    Json(payload): Json<DecideRiskReviewRequest>,
+) -> Result<Json<RiskReviewResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let review =
+        transactions::reject_review(&mut client, operator.user_id, review_id, payload.note.as_deref()).await?;
+
+    Ok(Json(risk_review_response(&client, review).await?))
+}
+
+async fn risk_review_response(
+    client: &deadpool_postgres::Client,
+    review: RiskReview,
+) -> Result<RiskReviewResponse, AppError> {
+    let transaction = transactions::get_transaction_by_id(client, review.transaction_id).await?;
+
+    Ok(RiskReviewResponse {
+        review,
+        transaction: transaction_response(transaction),
+    })
+}
+
+/// Watchlist hits on registrations and transfers, oldest first. Lists open
+/// cases unless another status is asked for.
+pub async fn list_screening_cases(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Query(filter): Query<ScreeningCaseFilter>,
+) -> Result<Json<ScreeningCaseListResponse>, AppError> {
+    let status = ScreeningCaseStatus::from(filter.status.as_deref().unwrap_or("open"));
+
+    let client = db.pool.get().await?;
+    let mut cases = Vec::new();
+    for case in screening::get_cases(&client, &status).await? {
+        cases.push(screening_case_response(&client, case).await?);
+    }
+
+    Ok(Json(ScreeningCaseListResponse { cases }))
+}
+
+pub async fn get_screening_case(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(case_id): Path<Uuid>,
+) -> Result<Json<ScreeningCaseResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let case = screening::get_case(&client, case_id).await?;
+
+    Ok(Json(screening_case_response(&client, case).await?))
+}
+
+pub async fn clear_screening_case(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(case_id): Path<Uuid>,
+    Json(payload): Json<DecideScreeningCaseRequest>,
+) -> Result<Json<ScreeningCaseResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let case =
+        transactions::clear_screening_case(&mut client, operator.user_id, case_id, payload.note.as_deref()).await?;
+
+    Ok(Json(screening_case_response(&client, case).await?))
+}
+
+pub async fn confirm_screening_case(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(case_id): Path<Uuid>,
+    Json(payload): Json<DecideScreeningCaseRequest>,
+) -> Result<Json<ScreeningCaseResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let case =
+        transactions::confirm_screening_case(&mut client, operator.user_id, case_id, payload.note.as_deref()).await?;
+
+    Ok(Json(screening_case_response(&client, case).await?))
+}
+
+async fn screening_case_response(
+    client: &deadpool_postgres::Client,
+    case: ScreeningCase,
+) -> Result<ScreeningCaseResponse, AppError> {
+    let transaction = match case.transaction_id {
+        Some(transaction_id) => Some(transaction_response(
+            transactions::get_transaction_by_id(client, transaction_id).await?,
+        )),
+        None => None,
+    };
+
+    Ok(ScreeningCaseResponse { case, transaction })
+}
+
+pub async fn get_watchlist(
+    _operator: AdminUser,
+    Extension(watchlist): Extension<WatchlistHandle>,
+    State(_config): State<Config>,
+) -> Result<Json<WatchlistStatusResponse>, AppError> {
+    Ok(Json(watchlist_status(&watchlist.current())))
+}
+
+/// Reads the watchlist file again without restarting. A file that fails to
+/// load is reported and the list already in use is kept.
+pub async fn reload_watchlist(
+    _operator: AdminUser,
+    Extension(watchlist): Extension<WatchlistHandle>,
+    State(_config): State<Config>,
+) -> Result<Json<WatchlistStatusResponse>, AppError> {
+    let watchlist = watchlist.reload()?;
+
+    Ok(Json(watchlist_status(&watchlist)))
+}
+
+fn watchlist_status(watchlist: &Watchlist) -> WatchlistStatusResponse {
+    WatchlistStatusResponse {
+        source: watchlist.source.clone(),
+        entries: watchlist.len(),
+        loaded_at: watchlist.loaded_at,
+    }
+}
+
+/// Resolves `accounts` or `users` from the path, checking the subject exists.
+async fn limit_subject(
+    client: &deadpool_postgres::Client,
+    scope: &str,
+    subject_id: Uuid,
+) -> Result<LimitScope, AppError> {
+    match scope {
+        "accounts" => {
+            accounts::get_account(client, subject_id).await?;
+            Ok(LimitScope::Account)
+        }
+        "users" => {
+            users::get_user_by_id(client, subject_id).await?;
+            Ok(LimitScope::User)
+        }
+        _ => Err(AppError::NotFound(format!("Unknown limit scope: {}", scope))),
+    }
+}
+
+pub async fn create_fx_rate(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<CreateFxRateRequest>,
+) -> Result<Json<FxRate>, AppError> {
+    payload.validate()?;
+
+    let client = db.pool.get().await?;
+    let rate = fx::create_rate(&client, &payload).await?;
+
+    Ok(Json(rate))
+}
+
+/// Loads rates from a CSV request body. Every row is validated before any is
+/// stored, and either all rows are loaded or none are.
+pub async fn import_fx_rates(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    body: String,
+) -> Result<Json<FxRateListResponse>, AppError> {
+    let requests = parse_rates_csv(&body).map_err(AppError::BadRequest)?;
+    for request in &requests {
+        request.validate()?;
+    }
+
+    let mut client = db.pool.get().await?;
+    let rates = fx::create_rates(&mut client, &requests).await?;
+
+    Ok(Json(FxRateListResponse { rates }))
+}
+
+/// Checks account balances against their transaction history and reports
+/// orphaned pending transactions, saving the report with `?persist=true`.
+pub async fn reconcile(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(config): State<Config>,
+    Query(params): Query<ReconciliationParams>,
+) -> Result<Json<ReconciliationReport>, AppError> {
+    let report =
+        reconciliation_service::reconcile(&db, config.reconciliation_pending_age, params.persist).await?;
+
+    Ok(Json(report))
+}
+
+pub async fn get_reconciliation_report(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(report_id): Path<Uuid>,
+) -> Result<Json<ReconciliationReport>, AppError> {
+    let client = db.pool.get().await?;
+    let report = reconciliation::get_report(&client, report_id).await?;
+
+    Ok(Json(report))
+}
+
+/// Imports a bank settlement file, settling the transactions its lines match
+/// and reporting the lines that do not.
+pub async fn import_settlement(
+    AdminUser(operator): AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<ImportSettlementRequest>,
+) -> Result<Json<SettlementReport>, AppError> {
+    payload.validate()?;
+
+    let report = settlement_service::import(
+        &db,
+        &payload.file_name,
+        &payload.mapping,
+        &payload.content,
+        Some(operator.user_id),
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
+pub async fn get_settlement_import(
+    _operator: AdminUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(import_id): Path<Uuid>,
+) -> Result<Json<SettlementReport>, AppError> {
+    let client = db.pool.get().await?;
+    let report = settlements::get_report(&client, import_id).await?;
+
+    Ok(Json(report))
+}
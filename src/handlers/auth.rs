@@ -7,19 +7,39 @@ use validator::Validate;
 use crate::config::Config;
 use crate::db::{users, Database};
 use crate::models::user::{CreateUserRequest, LoginRequest, LoginResponse, UserResponse};
+use crate::models::watchlist::ScreenedPerson;
+use crate::services::screening_service::{self, WatchlistHandle};
 use crate::utils::error::AppError;
 use crate::utils::jwt::create_token;
 
 pub async fn register(
     Extension(db): Extension<Database>,
+    Extension(watchlist): Extension<WatchlistHandle>,
     State(config): State<Config>,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
     // Validate the payload
     payload.validate()?;
 
-    // Create user in the database
     let client = db.pool.get().await?;
+
+    // Refuse names on the watchlist, leaving a case for compliance to review.
+    // Without a full name the username is screened, as it is on transfers.
+    let person = ScreenedPerson {
+        user_id: None,
+        email: payload.email.clone(),
+        name: payload.full_name.clone().unwrap_or_else(|| payload.username.clone()),
+        date_of_birth: payload.date_of_birth,
+    };
+    screening_service::screen_registration(
+        &client,
+        &watchlist.current(),
+        config.watchlist_match_threshold,
+        &person,
+    )
+    .await?;
+
+    // Create user in the database
     let user = users::create_user(&client, &payload).await?;
 
     // Return the user without sensitive information
@@ -28,6 +48,7 @@ pub async fn register(
         email: user.email,
         username: user.username,
         full_name: user.full_name,
+        date_of_birth: user.date_of_birth,
         created_at: user.created_at,
     }))
 }
@@ -42,7 +63,7 @@ pub async fn login(
     let user = users::authenticate_user(&client, &payload.username_or_email, &payload.password).await?;
 
     // Generate a JWT token
-    let token = create_token(user.id, &user.username, &user.email, &config)?;
+    let token = create_token(user.id, &user.username, &user.email, &user.role, &config)?;
 
     // Return the token and user
     Ok(Json(LoginResponse { token, user }))
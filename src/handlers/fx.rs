@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{fx, Database};
+use crate::middleware::auth::CurrentUser;
+use crate::models::fx::{CreateFxQuoteRequest, FxQuote, FxRateListResponse};
+use crate::utils::error::AppError;
+
+pub async fn list_rates(
+    _current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+) -> Result<Json<FxRateListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let rates = fx::get_current_rates(&client).await?;
+
+    Ok(Json(FxRateListResponse { rates }))
+}
+
+pub async fn create_quote(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(config): State<Config>,
+    Json(payload): Json<CreateFxQuoteRequest>,
+) -> Result<Json<FxQuote>, AppError> {
+    payload.validate()?;
+
+    let client = db.pool.get().await?;
+    let quote = fx::create_quote(
+        &client,
+        current_user.user_id,
+        &payload.source_currency,
+        &payload.destination_currency,
+        payload.amount,
+        config.fx_quote_ttl,
+    )
+    .await?;
+
+    Ok(Json(quote))
+}
@@ -0,0 +1,57 @@
+use axum::{
+    Json,
+    extract::{Extension, Path, State},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{Database, merchants};
+use crate::middleware::auth::CurrentUser;
+use crate::models::merchant::{CreateMerchantRequest, Merchant, MerchantListResponse};
+use crate::utils::error::AppError;
+
+pub async fn create_merchant(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<CreateMerchantRequest>,
+) -> Result<Json<Merchant>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let merchant = merchants::create_merchant(&mut client, current_user.user_id, &payload).await?;
+
+    Ok(Json(merchant))
+}
+
+pub async fn list_merchants(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+) -> Result<Json<MerchantListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let merchants = merchants::get_user_merchants(&client, current_user.user_id).await?;
+
+    Ok(Json(MerchantListResponse { merchants }))
+}
+
+pub async fn get_merchant(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(merchant_id): Path<Uuid>,
+) -> Result<Json<Merchant>, AppError> {
+    let client = db.pool.get().await?;
+    let merchant = merchants::get_merchant(&client, merchant_id).await?;
+
+    // Ensure the merchant belongs to the current user
+    if merchant.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this merchant".to_string(),
+        ));
+    }
+
+    Ok(Json(merchant))
+}
@@ -1,4 +1,10 @@
 pub mod auth;
 pub mod users;
 pub mod accounts;
-pub mod transactions; 
\ No newline at end of file
+pub mod transactions; 
+pub mod admin;
+pub mod fx;pub mod scheduled_transfers;
+pub mod stream;
+pub mod webhooks;
+pub mod merchants;
+pub mod payment_intents;
@@ -0,0 +1,135 @@
+use axum::{
+    Json,
+    extract::{Extension, Path, Query, State},
+};
+use deadpool_postgres::Client;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{Database, merchants, payment_intents};
+use crate::middleware::auth::CurrentUser;
+use crate::models::merchant::Merchant;
+use crate::models::payment_intent::{
+    CancelPaymentIntentRequest, ConfirmPaymentIntentRequest, CreatePaymentIntentRequest, CreatedPaymentIntent,
+    PaymentIntent, PaymentIntentParams,
+};
+use crate::models::risk::RiskSignals;
+use crate::services::screening_service::TransferScreener;
+use crate::utils::error::AppError;
+
+pub async fn create_payment_intent(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<CreatePaymentIntentRequest>,
+) -> Result<Json<CreatedPaymentIntent>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let merchant = get_owned_merchant(&client, &current_user, payload.merchant_id).await?;
+    if payload.currency != merchant.currency {
+        return Err(AppError::BadRequest(format!(
+            "Payment intent currency {} does not match merchant currency {}",
+            payload.currency, merchant.currency
+        )));
+    }
+
+    let payment_intent = payment_intents::create_payment_intent(&mut client, &merchant, &payload).await?;
+    let client_secret = payment_intent.client_secret.clone();
+
+    Ok(Json(CreatedPaymentIntent {
+        payment_intent,
+        client_secret,
+    }))
+}
+
+/// The merchant can always look an intent up; anyone else needs its client
+/// secret.
+pub async fn get_payment_intent(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(payment_intent_id): Path<Uuid>,
+    Query(params): Query<PaymentIntentParams>,
+) -> Result<Json<PaymentIntent>, AppError> {
+    let client = db.pool.get().await?;
+    let payment_intent = payment_intents::get_payment_intent(&client, payment_intent_id).await?;
+
+    let has_secret = params
+        .client_secret
+        .as_deref()
+        .is_some_and(|client_secret| payment_intent.verify_client_secret(client_secret));
+    if !has_secret {
+        get_owned_merchant(&client, &current_user, payment_intent.merchant_id).await?;
+    }
+
+    Ok(Json(payment_intent))
+}
+
+pub async fn confirm_payment_intent(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    Extension(screener): Extension<TransferScreener>,
+    State(_config): State<Config>,
+    signals: RiskSignals,
+    Path(payment_intent_id): Path<Uuid>,
+    Json(payload): Json<ConfirmPaymentIntentRequest>,
+) -> Result<Json<PaymentIntent>, AppError> {
+    let watchlist = screener.watchlist();
+    let screening = screener.screening(&watchlist, &signals);
+
+    let mut client = db.pool.get().await?;
+    let payment_intent = payment_intents::confirm_payment_intent(
+        &mut client,
+        current_user.user_id,
+        payment_intent_id,
+        &payload,
+        &screening,
+    )
+    .await?;
+
+    Ok(Json(payment_intent))
+}
+
+pub async fn cancel_payment_intent(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(payment_intent_id): Path<Uuid>,
+    Json(payload): Json<CancelPaymentIntentRequest>,
+) -> Result<Json<PaymentIntent>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let payment_intent = payment_intents::get_payment_intent(&client, payment_intent_id).await?;
+    get_owned_merchant(&client, &current_user, payment_intent.merchant_id).await?;
+
+    let payment_intent = payment_intents::cancel_payment_intent(
+        &mut client,
+        payment_intent_id,
+        payload.cancellation_reason.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(payment_intent))
+}
+
+async fn get_owned_merchant(
+    client: &Client,
+    current_user: &CurrentUser,
+    merchant_id: Uuid,
+) -> Result<Merchant, AppError> {
+    let merchant = merchants::get_merchant(client, merchant_id).await?;
+
+    // Ensure the merchant belongs to the current user
+    if merchant.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this merchant".to_string(),
+        ));
+    }
+
+    Ok(merchant)
+}
@@ -0,0 +1,124 @@
+use axum::{
+    Json,
+    extract::{Extension, Path, State},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{Database, scheduled_transfers};
+use crate::middleware::auth::CurrentUser;
+use crate::models::scheduled_transfer::{
+    CreateScheduledTransferRequest, ScheduledTransfer, ScheduledTransferListResponse,
+    ScheduledTransferRunListResponse, UpdateScheduledTransferRequest,
+};
+use crate::utils::error::AppError;
+
+pub async fn create_scheduled_transfer(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Json(payload): Json<CreateScheduledTransferRequest>,
+) -> Result<Json<ScheduledTransfer>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let client = db.pool.get().await?;
+    let scheduled_transfer =
+        scheduled_transfers::create_scheduled_transfer(&client, current_user.user_id, &payload).await?;
+
+    Ok(Json(scheduled_transfer))
+}
+
+pub async fn list_scheduled_transfers(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+) -> Result<Json<ScheduledTransferListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let scheduled_transfers =
+        scheduled_transfers::get_user_scheduled_transfers(&client, current_user.user_id).await?;
+
+    Ok(Json(ScheduledTransferListResponse { scheduled_transfers }))
+}
+
+pub async fn get_scheduled_transfer(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(scheduled_transfer_id): Path<Uuid>,
+) -> Result<Json<ScheduledTransfer>, AppError> {
+    let client = db.pool.get().await?;
+    let scheduled_transfer =
+        scheduled_transfers::get_scheduled_transfer(&client, scheduled_transfer_id).await?;
+
+    // Ensure the scheduled transfer belongs to the current user
+    if scheduled_transfer.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this scheduled transfer".to_string(),
+        ));
+    }
+
+    Ok(Json(scheduled_transfer))
+}
+
+pub async fn update_scheduled_transfer(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(scheduled_transfer_id): Path<Uuid>,
+    Json(payload): Json<UpdateScheduledTransferRequest>,
+) -> Result<Json<ScheduledTransfer>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let scheduled_transfer = scheduled_transfers::update_scheduled_transfer(
+        &mut client,
+        current_user.user_id,
+        scheduled_transfer_id,
+        &payload,
+    )
+    .await?;
+
+    Ok(Json(scheduled_transfer))
+}
+
+pub async fn cancel_scheduled_transfer(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(scheduled_transfer_id): Path<Uuid>,
+) -> Result<Json<ScheduledTransfer>, AppError> {
+    let mut client = db.pool.get().await?;
+    let scheduled_transfer = scheduled_transfers::cancel_scheduled_transfer(
+        &mut client,
+        current_user.user_id,
+        scheduled_transfer_id,
+    )
+    .await?;
+
+    Ok(Json(scheduled_transfer))
+}
+
+pub async fn list_scheduled_transfer_runs(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(scheduled_transfer_id): Path<Uuid>,
+) -> Result<Json<ScheduledTransferRunListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let scheduled_transfer =
+        scheduled_transfers::get_scheduled_transfer(&client, scheduled_transfer_id).await?;
+
+    // Ensure the scheduled transfer belongs to the current user
+    if scheduled_transfer.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this scheduled transfer".to_string(),
+        ));
+    }
+
+    let runs = scheduled_transfers::get_runs(&client, scheduled_transfer_id).await?;
+
+    Ok(Json(ScheduledTransferRunListResponse { runs }))
+}
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Extension, Query, State, WebSocketUpgrade},
+    response::Response,
+};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::middleware::auth::CurrentUser;
+use crate::models::outbox::OutboxEvent;
+use crate::models::stream::{StreamParams, StreamSubscription};
+use crate::services::event_stream;
+use crate::utils::error::AppError;
+
+pub async fn stream(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    Extension(events): Extension<broadcast::Sender<OutboxEvent>>,
+    State(_config): State<Config>,
+    Query(params): Query<StreamParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let account_ids = params
+        .account_ids
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|id| !id.trim().is_empty())
+        .map(|id| {
+            Uuid::parse_str(id.trim()).map_err(|_| AppError::BadRequest(format!("Invalid account ID: {}", id)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Refuse the connection outright rather than after upgrading
+    event_stream::verify_account_ownership(&db, current_user.user_id, &account_ids).await?;
+
+    let subscription = StreamSubscription::new(current_user.user_id, account_ids);
+    Ok(ws.on_upgrade(move |socket| event_stream::run(socket, db, events, subscription, params.after)))
+}
@@ -1,26 +1,121 @@
 use axum::{
     extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use serde::Deserialize;
+use futures_util::Stream;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use std::convert::Infallible;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::config::Config;
-use crate::db::{transactions, Database};
+use crate::db::{accounts, idempotency, transactions, Database};
 use crate::middleware::auth::CurrentUser;
+use crate::models::idempotency::{IdempotencyOutcome, StoredResponse};
+use crate::models::outbox::OutboxEvent;
+use crate::models::hold::CaptureTransactionRequest;
+use crate::models::risk::RiskSignals;
 use crate::models::transaction::{
-    CreateTransactionRequest, TransactionListResponse, TransactionResponse,
+    CreateRefundRequest, CreateTransactionRequest, RefundListResponse, SortDirection, Transaction,
+    TransactionCursor, TransactionFilter, TransactionHistoryResponse, TransactionListResponse,
+    TransactionResponse, TransactionStatus, TransactionType,
 };
+use crate::services::event_stream;
+use crate::services::screening_service::TransferScreener;
 use crate::utils::error::AppError;
 
+/// Largest page any listing returns, whatever the client asks for.
+pub const MAX_PAGE_SIZE: usize = 100;
+
 #[derive(Debug, Deserialize)]
 pub struct PaginationParams {
-    #[serde(default = "default_page")]
+    #[serde(default = "default_page", deserialize_with = "deserialize_page")]
     pub page: usize,
     
-    #[serde(default = "default_page_size")]
+    #[serde(default = "default_page_size", deserialize_with = "deserialize_page_size")]
+    pub page_size: usize,
+}
+
+/// Filters, sort order and cursor for listing transactions.
+#[derive(Debug, Deserialize)]
+pub struct TransactionListParams {
+    /// `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+
+    #[serde(default = "default_page_size", deserialize_with = "deserialize_page_size")]
     pub page_size: usize,
+
+    #[serde(default)]
+    pub sort: SortDirection,
+
+    pub account_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub transaction_type: Option<String>,
+    pub currency: Option<String>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub description: Option<String>,
+}
+
+impl TransactionListParams {
+    pub fn filter(&self) -> Result<TransactionFilter, AppError> {
+        let status = self
+            .status
+            .as_deref()
+            .map(TransactionStatus::try_from)
+            .transpose()
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+        let transaction_type = match self.transaction_type.as_deref() {
+            // `TransactionType::from` falls back to transfer for unknown names
+            Some(name) => match TransactionType::from(name) {
+                transaction_type if transaction_type.to_string() == name.to_lowercase() => Some(transaction_type),
+                _ => {
+                    return Err(AppError::BadRequest(format!(
+                        "Unknown transaction type: {}",
+                        name
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        if let (Some(min_amount), Some(max_amount)) = (self.min_amount, self.max_amount)
+            && min_amount > max_amount
+        {
+            return Err(AppError::BadRequest(
+                "min_amount cannot be greater than max_amount".to_string(),
+            ));
+        }
+
+        Ok(TransactionFilter {
+            account_id: self.account_id,
+            status,
+            transaction_type,
+            currency: self.currency.as_ref().map(|currency| currency.to_uppercase()),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            created_after: self.created_after,
+            created_before: self.created_before,
+            description: self.description.clone().filter(|description| !description.is_empty()),
+        })
+    }
+}
+
+/// Opt-in rendering of decimal amounts alongside the integer minor units.
+#[derive(Debug, Deserialize)]
+pub struct FormatParams {
+    #[serde(default)]
+    pub formatted: bool,
 }
 
 fn default_page() -> usize {
@@ -31,32 +126,128 @@ fn default_page_size() -> usize {
     10
 }
 
+fn deserialize_page<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(usize::deserialize(deserializer)?.max(1))
+}
+
+fn deserialize_page_size<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(usize::deserialize(deserializer)?.clamp(1, MAX_PAGE_SIZE))
+}
+
 pub async fn create_transaction(
     current_user: CurrentUser,
     Extension(db): Extension<Database>,
-    State(_config): State<Config>,
+    Extension(screener): Extension<TransferScreener>,
+    State(config): State<Config>,
+    signals: RiskSignals,
+    headers: HeaderMap,
     Json(payload): Json<CreateTransactionRequest>,
-) -> Result<Json<TransactionResponse>, AppError> {
+) -> Result<Response, AppError> {
     // Manually validate the payload
     if let Err(e) = payload.validate() {
         return Err(AppError::Validation(e));
     }
 
+    let idempotency_key = match headers.get("idempotency-key") {
+        Some(value) => {
+            let key = value
+                .to_str()
+                .map_err(|_| AppError::BadRequest("Invalid Idempotency-Key header".to_string()))?;
+            if key.is_empty() || key.len() > 255 {
+                return Err(AppError::BadRequest(
+                    "Idempotency-Key must be between 1 and 255 characters".to_string(),
+                ));
+            }
+            Some(key.to_string())
+        }
+        None => None,
+    };
+
     let mut client = db.pool.get().await?;
-    let transaction = transactions::create_transaction(&mut client, current_user.user_id, &payload).await?;
+    let Some(key) = idempotency_key else {
+        let response =
+            execute_transaction(&mut client, &config, &screener, current_user.user_id, &payload, &signals).await?;
+        return Ok(Json(response).into_response());
+    };
 
-    Ok(Json(TransactionResponse {
-        id: transaction.id,
-        source_account_id: transaction.source_account_id,
-        destination_account_id: transaction.destination_account_id,
-        amount: transaction.amount,
-        currency: transaction.currency,
-        status: transaction.status.to_string(),
-        transaction_type: transaction.transaction_type.to_string(),
-        description: transaction.description,
-        created_at: transaction.created_at,
-        updated_at: transaction.updated_at,
-    }))
+    // Claim the key, or replay the response stored for an earlier identical
+    // request. The claim and the stored response commit with the transaction
+    // itself, so a request that never finishes leaves nothing behind.
+    let request_body = serde_json::to_value(&payload)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize request: {}", e)))?;
+    let mut tx = client.transaction().await?;
+    let outcome = idempotency::begin(
+        &tx,
+        current_user.user_id,
+        &key,
+        "/api/transactions",
+        &request_body,
+        config.idempotency_key_ttl,
+    )
+    .await?;
+
+    if let IdempotencyOutcome::Replay(stored) = outcome {
+        let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+        return Ok((status, [("idempotent-replayed", "true")], Json(stored.body)).into_response());
+    }
+
+    let result = execute_transaction(&mut tx, &config, &screener, current_user.user_id, &payload, &signals).await;
+
+    // Remember the outcome, except for server errors which the client may retry
+    let stored = match &result {
+        Ok(response) => StoredResponse {
+            status: StatusCode::OK.as_u16(),
+            body: serde_json::to_value(response)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize response: {}", e)))?,
+        },
+        Err(error) => StoredResponse {
+            status: error.status_and_message().0.as_u16(),
+            body: error.body(),
+        },
+    };
+
+    // Dropping the transaction rolls the claim back
+    if !StatusCode::from_u16(stored.status).is_ok_and(|status| status.is_server_error()) {
+        idempotency::complete(&tx, current_user.user_id, &key, &stored).await?;
+        tx.commit().await?;
+    }
+
+    result.map(|response| Json(response).into_response())
+}
+
+async fn execute_transaction<C>(
+    client: &mut C,
+    config: &Config,
+    screener: &TransferScreener,
+    user_id: Uuid,
+    payload: &CreateTransactionRequest,
+    signals: &RiskSignals,
+) -> Result<TransactionResponse, AppError>
+where
+    C: deadpool_postgres::GenericClient + Sync + Send,
+{
+    let watchlist = screener.watchlist();
+    let screening = screener.screening(&watchlist, signals);
+    let transaction = if payload.authorize_only {
+        transactions::authorize_transaction(
+            client,
+            user_id,
+            payload,
+            config.hold_expiration,
+            Some(&screening),
+        )
+        .await?
+    } else {
+        transactions::create_screened_transaction(client, user_id, payload, &screening).await?
+    };
+
+    Ok(transaction_response(transaction))
 }
 
 pub async fn get_transaction(
@@ -64,12 +255,13 @@ pub async fn get_transaction(
     Extension(db): Extension<Database>,
     State(_config): State<Config>,
     Path(transaction_id): Path<Uuid>,
+    Query(format): Query<FormatParams>,
 ) -> Result<Json<TransactionResponse>, AppError> {
-    let mut client = db.pool.get().await?;
+    let client = db.pool.get().await?;
     
     // Check if the user has access to the transaction
     let has_access = transactions::can_user_access_transaction(
-        &mut client, 
+        &client, 
         current_user.user_id, 
         transaction_id
     ).await?;
@@ -80,57 +272,253 @@ pub async fn get_transaction(
         ));
     }
     
-    let transaction = transactions::get_transaction_by_id(&mut client, transaction_id).await?;
+    let transaction = transactions::get_transaction_by_id(&client, transaction_id).await?;
+    let mut response = transaction_response(transaction);
+    if format.formatted {
+        response = response.with_formatted_amounts();
+    }
 
-    Ok(Json(TransactionResponse {
-        id: transaction.id,
-        source_account_id: transaction.source_account_id,
-        destination_account_id: transaction.destination_account_id,
-        amount: transaction.amount,
-        currency: transaction.currency,
-        status: transaction.status.to_string(),
-        transaction_type: transaction.transaction_type.to_string(),
-        description: transaction.description,
-        created_at: transaction.created_at,
-        updated_at: transaction.updated_at,
+    Ok(Json(response))
+}
+
+pub async fn get_transaction_history(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<TransactionHistoryResponse>, AppError> {
+    let client = db.pool.get().await?;
+
+    // Check if the user has access to the transaction
+    let has_access = transactions::can_user_access_transaction(
+        &client,
+        current_user.user_id,
+        transaction_id
+    ).await?;
+
+    if !has_access {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this transaction".to_string(),
+        ));
+    }
+
+    let (events, total) = transactions::get_transaction_history(
+        &client,
+        transaction_id,
+        params.page,
+        params.page_size,
+    ).await?;
+
+    Ok(Json(TransactionHistoryResponse {
+        transaction_id,
+        events,
+        total,
+        page: params.page,
+        page_size: params.page_size,
     }))
 }
 
+/// Streams the transaction's status changes as server-sent events, resuming
+/// after the `Last-Event-ID` header when a client reconnects.
+pub async fn transaction_events(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    Extension(events): Extension<broadcast::Sender<OutboxEvent>>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let client = db.pool.get().await?;
+
+    // Check if the user has access to the transaction
+    let has_access = transactions::can_user_access_transaction(
+        &client,
+        current_user.user_id,
+        transaction_id
+    ).await?;
+
+    if !has_access {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this transaction".to_string(),
+        ));
+    }
+
+    let after = match headers.get("last-event-id") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .ok_or_else(|| AppError::BadRequest("Invalid Last-Event-ID header".to_string()))?,
+        None => 0,
+    };
+
+    let stream = event_stream::transaction_events(db, events, transaction_id, after);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 pub async fn list_transactions(
     current_user: CurrentUser,
     Extension(db): Extension<Database>,
     State(_config): State<Config>,
-    Query(params): Query<PaginationParams>,
+    Query(params): Query<TransactionListParams>,
+    Query(format): Query<FormatParams>,
 ) -> Result<Json<TransactionListResponse>, AppError> {
-    let mut client = db.pool.get().await?;
-    
-    let (transactions, total) = transactions::get_user_transactions(
-        &mut client, 
+    let filter = params.filter()?;
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(TransactionCursor::decode)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+
+    let client = db.pool.get().await?;
+
+    // Ensure a filtered account belongs to the current user
+    if let Some(account_id) = filter.account_id {
+        let account = accounts::get_account(&client, account_id).await?;
+        if account.user_id != current_user.user_id {
+            return Err(AppError::Forbidden(
+                "You do not have permission to access this account".to_string(),
+            ));
+        }
+    }
+
+    let (transactions, next_cursor) = transactions::get_user_transactions(
+        &client,
         current_user.user_id,
-        params.page,
+        &filter,
+        cursor.as_ref(),
+        params.sort,
         params.page_size,
     ).await?;
 
     let transaction_responses = transactions
         .into_iter()
-        .map(|transaction| TransactionResponse {
-            id: transaction.id,
-            source_account_id: transaction.source_account_id,
-            destination_account_id: transaction.destination_account_id,
-            amount: transaction.amount,
-            currency: transaction.currency,
-            status: transaction.status.to_string(),
-            transaction_type: transaction.transaction_type.to_string(),
-            description: transaction.description,
-            created_at: transaction.created_at,
-            updated_at: transaction.updated_at,
+        .map(transaction_response)
+        .map(|response| {
+            if format.formatted {
+                response.with_formatted_amounts()
+            } else {
+                response
+            }
         })
         .collect();
 
     Ok(Json(TransactionListResponse {
         transactions: transaction_responses,
-        total,
-        page: params.page,
+        next_cursor: next_cursor.map(|cursor| cursor.encode()),
         page_size: params.page_size,
     }))
-} 
\ No newline at end of file
+} 
+
+pub async fn capture_transaction(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+    payload: Option<Json<CaptureTransactionRequest>>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    let Json(payload) = payload.unwrap_or_default();
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let transaction = transactions::capture_transaction(
+        &mut client,
+        current_user.user_id,
+        transaction_id,
+        payload.amount,
+    )
+    .await?;
+
+    Ok(Json(transaction_response(transaction)))
+}
+
+pub async fn void_transaction(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    let mut client = db.pool.get().await?;
+    let transaction =
+        transactions::void_transaction(&mut client, current_user.user_id, transaction_id).await?;
+
+    Ok(Json(transaction_response(transaction)))
+}
+
+pub async fn create_refund(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+    Json(payload): Json<CreateRefundRequest>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    payload.validate()?;
+
+    let mut client = db.pool.get().await?;
+    let refund = transactions::refund_transaction(
+        &mut client,
+        current_user.user_id,
+        transaction_id,
+        payload.amount,
+        payload.description,
+    )
+    .await?;
+
+    Ok(Json(transaction_response(refund)))
+}
+
+pub async fn list_refunds(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(transaction_id): Path<Uuid>,
+) -> Result<Json<RefundListResponse>, AppError> {
+    let client = db.pool.get().await?;
+
+    // Check if the user has access to the transaction
+    let has_access =
+        transactions::can_user_access_transaction(&client, current_user.user_id, transaction_id).await?;
+
+    if !has_access {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this transaction".to_string(),
+        ));
+    }
+
+    let transaction = transactions::get_transaction_by_id(&client, transaction_id).await?;
+    let refunds = transactions::get_refunds(&client, transaction_id).await?;
+
+    Ok(Json(RefundListResponse {
+        refunds: refunds.into_iter().map(transaction_response).collect(),
+        refunded_amount: transaction.refunded_amount,
+        refundable_amount: transaction.amount - transaction.refunded_amount,
+    }))
+}
+
+pub(crate) fn transaction_response(transaction: Transaction) -> TransactionResponse {
+    TransactionResponse {
+        id: transaction.id,
+        source_account_id: transaction.source_account_id,
+        destination_account_id: transaction.destination_account_id,
+        amount: transaction.amount,
+        currency: transaction.currency,
+        status: transaction.status.to_string(),
+        transaction_type: transaction.transaction_type.to_string(),
+        description: transaction.description,
+        parent_transaction_id: transaction.parent_transaction_id,
+        refunded_amount: transaction.refunded_amount,
+        destination_amount: transaction.destination_amount,
+        destination_currency: transaction.destination_currency,
+        fx_rate: transaction.fx_rate,
+        fx_spread_bps: transaction.fx_spread_bps,
+        fx_spread_amount: transaction.fx_spread_amount,
+        fx_quote_id: transaction.fx_quote_id,
+        settled_at: transaction.settled_at,
+        formatted_amount: None,
+        formatted_destination_amount: None,
+        created_at: transaction.created_at,
+        updated_at: transaction.updated_at,
+    }
+}
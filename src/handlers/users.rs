@@ -8,6 +8,8 @@ use crate::config::Config;
 use crate::db::{users, Database};
 use crate::middleware::auth::CurrentUser;
 use crate::models::user::{UpdateUserRequest, UserResponse};
+use crate::models::watchlist::ScreenedPerson;
+use crate::services::screening_service::{self, WatchlistHandle};
 use crate::utils::error::AppError;
 
 pub async fn get_profile(
@@ -23,6 +25,7 @@ pub async fn get_profile(
         email: user.email,
         username: user.username,
         full_name: user.full_name,
+        date_of_birth: user.date_of_birth,
         created_at: user.created_at,
     }))
 }
@@ -30,13 +33,39 @@ pub async fn get_profile(
 pub async fn update_profile(
     current_user: CurrentUser,
     Extension(db): Extension<Database>,
-    State(_config): State<Config>,
+    Extension(watchlist): Extension<WatchlistHandle>,
+    State(config): State<Config>,
     Json(payload): Json<UpdateUserRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
     // Validate the payload
     payload.validate()?;
 
     let client = db.pool.get().await?;
+
+    // A change to the name the user is screened under, their full name or
+    // else their username, is screened like a registration
+    let user = users::get_user_by_id(&client, current_user.user_id).await?;
+    let screened_name = |full_name: Option<&String>, username: &String| full_name.unwrap_or(username).clone();
+    let name = screened_name(
+        payload.full_name.as_ref().or(user.full_name.as_ref()),
+        payload.username.as_ref().unwrap_or(&user.username),
+    );
+    if name != screened_name(user.full_name.as_ref(), &user.username) {
+        let person = ScreenedPerson {
+            user_id: Some(user.id),
+            email: payload.email.clone().unwrap_or(user.email),
+            name,
+            date_of_birth: user.date_of_birth,
+        };
+        screening_service::screen_name_change(
+            &client,
+            &watchlist.current(),
+            config.watchlist_match_threshold,
+            &person,
+        )
+        .await?;
+    }
+
     let user = users::update_user(&client, current_user.user_id, &payload).await?;
 
     Ok(Json(UserResponse {
@@ -44,6 +73,7 @@ pub async fn update_profile(
         email: user.email,
         username: user.username,
         full_name: user.full_name,
+        date_of_birth: user.date_of_birth,
         created_at: user.created_at,
     }))
 } 
\ No newline at end of file
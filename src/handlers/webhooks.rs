@@ -0,0 +1,176 @@
+use axum::{
+    Json,
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+};
+use deadpool_postgres::Client;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::{Database, webhooks};
+use crate::handlers::transactions::PaginationParams;
+use crate::middleware::auth::CurrentUser;
+use crate::models::webhook::{
+    check_endpoint_url, CreateWebhookEndpointRequest, CreatedWebhookEndpoint, RetryPolicy,
+    UpdateWebhookEndpointRequest, WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryDetailResponse,
+    WebhookDeliveryListResponse, WebhookEndpoint, WebhookEndpointListResponse,
+};
+use crate::services::webhook_service::WebhookSender;
+use crate::utils::error::AppError;
+
+pub async fn create_endpoint(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(config): State<Config>,
+    Json(payload): Json<CreateWebhookEndpointRequest>,
+) -> Result<Json<CreatedWebhookEndpoint>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+    check_endpoint_url(&payload.url, &config.webhook_allowed_hosts).map_err(AppError::BadRequest)?;
+
+    let client = db.pool.get().await?;
+    let endpoint = webhooks::create_endpoint(&client, current_user.user_id, &payload).await?;
+    let secret = endpoint.secret.clone();
+
+    Ok(Json(CreatedWebhookEndpoint { endpoint, secret }))
+}
+
+pub async fn list_endpoints(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+) -> Result<Json<WebhookEndpointListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let endpoints = webhooks::get_user_endpoints(&client, current_user.user_id).await?;
+
+    Ok(Json(WebhookEndpointListResponse { endpoints }))
+}
+
+pub async fn get_endpoint(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(endpoint_id): Path<Uuid>,
+) -> Result<Json<WebhookEndpoint>, AppError> {
+    let client = db.pool.get().await?;
+    let endpoint = get_owned_endpoint(&client, &current_user, endpoint_id).await?;
+
+    Ok(Json(endpoint))
+}
+
+pub async fn update_endpoint(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(config): State<Config>,
+    Path(endpoint_id): Path<Uuid>,
+    Json(payload): Json<UpdateWebhookEndpointRequest>,
+) -> Result<Json<WebhookEndpoint>, AppError> {
+    // Validate the payload
+    payload.validate()?;
+    if let Some(url) = &payload.url {
+        check_endpoint_url(url, &config.webhook_allowed_hosts).map_err(AppError::BadRequest)?;
+    }
+
+    let client = db.pool.get().await?;
+    get_owned_endpoint(&client, &current_user, endpoint_id).await?;
+    let endpoint = webhooks::update_endpoint(&client, endpoint_id, &payload).await?;
+
+    Ok(Json(endpoint))
+}
+
+pub async fn delete_endpoint(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(endpoint_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let client = db.pool.get().await?;
+    get_owned_endpoint(&client, &current_user, endpoint_id).await?;
+    webhooks::delete_endpoint(&client, endpoint_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_deliveries(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(endpoint_id): Path<Uuid>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<WebhookDeliveryListResponse>, AppError> {
+    let client = db.pool.get().await?;
+    get_owned_endpoint(&client, &current_user, endpoint_id).await?;
+
+    let (deliveries, total) =
+        webhooks::get_endpoint_deliveries(&client, endpoint_id, params.page, params.page_size).await?;
+
+    Ok(Json(WebhookDeliveryListResponse {
+        deliveries,
+        total,
+        page: params.page,
+        page_size: params.page_size,
+    }))
+}
+
+pub async fn get_delivery(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    State(_config): State<Config>,
+    Path(delivery_id): Path<Uuid>,
+) -> Result<Json<WebhookDeliveryDetailResponse>, AppError> {
+    let client = db.pool.get().await?;
+    let (delivery, _) = get_owned_delivery(&client, &current_user, delivery_id).await?;
+    let attempt_log = webhooks::get_delivery_attempts(&client, delivery_id).await?;
+
+    Ok(Json(WebhookDeliveryDetailResponse { delivery, attempt_log }))
+}
+
+/// Sends a delivery again straight away, whatever its status, and returns the
+/// attempt. A pending delivery that still fails stays on its retry schedule.
+pub async fn redeliver(
+    current_user: CurrentUser,
+    Extension(db): Extension<Database>,
+    Extension(sender): Extension<WebhookSender>,
+    State(config): State<Config>,
+    Path(delivery_id): Path<Uuid>,
+) -> Result<Json<WebhookDeliveryAttempt>, AppError> {
+    let mut client = db.pool.get().await?;
+    let (delivery, endpoint) = get_owned_delivery(&client, &current_user, delivery_id).await?;
+
+    let policy = RetryPolicy {
+        max_attempts: config.webhook_max_attempts,
+        base_delay_seconds: config.webhook_retry_base_delay,
+    };
+    let attempt = sender.deliver(&mut client, &endpoint, &delivery, &policy).await?;
+
+    Ok(Json(attempt))
+}
+
+async fn get_owned_endpoint(
+    client: &Client,
+    current_user: &CurrentUser,
+    endpoint_id: Uuid,
+) -> Result<WebhookEndpoint, AppError> {
+    let endpoint = webhooks::get_endpoint(client, endpoint_id).await?;
+
+    // Ensure the endpoint belongs to the current user
+    if endpoint.user_id != current_user.user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to access this webhook endpoint".to_string(),
+        ));
+    }
+
+    Ok(endpoint)
+}
+
+async fn get_owned_delivery(
+    client: &Client,
+    current_user: &CurrentUser,
+    delivery_id: Uuid,
+) -> Result<(WebhookDelivery, WebhookEndpoint), AppError> {
+    let delivery = webhooks::get_delivery(client, delivery_id).await?;
+    let endpoint = get_owned_endpoint(client, current_user, delivery.endpoint_id).await?;
+
+    Ok((delivery, endpoint))
+}
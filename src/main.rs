@@ -1,4 +1,5 @@
 mod api;
+mod cli;
 mod config;
 mod db;
 mod handlers;
@@ -7,17 +8,24 @@ mod models;
 mod services;
 mod tests;
 mod utils;
+mod workers;
 
 use std::net::SocketAddr;
 
 use axum::{extract::Extension, http::Method};
 use config::Config;
 use db::Database;
+use models::outbox::EventSinkKind;
+use models::webhook::RetryPolicy;
+use services::event_relay;
+use services::screening_service::{TransferScreener, WatchlistHandle};
+use services::webhook_service::WebhookSender;
+use tokio::sync::broadcast;
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() {
@@ -25,17 +33,71 @@ async fn main() {
     let config = Config::from_env();
     let config_clone = config.clone();
 
-    // Initialize logging
+    // Any arguments name a one-off command to run instead of the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Initialize logging, on stderr for commands and whenever events are
+    // written to stdout, so that output stays clean
+    let log_writer = if args.is_empty() && !config.outbox_sinks.contains(&EventSinkKind::Stdout) {
+        BoxMakeWriter::new(std::io::stdout)
+    } else {
+        BoxMakeWriter::new(std::io::stderr)
+    };
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
             std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
         ))
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(log_writer))
         .init();
 
     // Initialize database connection
     let db = Database::new(&config);
 
+    if let Some((command, options)) = args.split_first() {
+        std::process::exit(cli::run(command, options, &db, &config).await);
+    }
+
+    // Registrations and transfers are screened against this list, reloadable at runtime
+    let watchlist = WatchlistHandle::load(config.watchlist_path.clone())
+        .unwrap_or_else(|e| panic!("WATCHLIST_PATH is invalid: {}", e));
+    if watchlist.current().is_empty() {
+        tracing::warn!("The watchlist is empty, so screening will not flag anyone");
+    }
+
+    // Transfers are screened by one risk engine built from the policy, whether
+    // a request or a schedule makes them
+    let screener = TransferScreener::new(&config.risk_policy, watchlist.clone(), config.watchlist_match_threshold);
+    let webhook_sender = WebhookSender::new(&config.webhook_allowed_hosts);
+
+    // Domain events are relayed from the outbox to these sinks
+    let (events, _) = broadcast::channel(event_relay::BROADCAST_CAPACITY);
+    let sinks = event_relay::build_sinks(&config.outbox_sinks, &db, &events);
+
+    // Start background workers
+    tokio::spawn(workers::outbox_relay::run(
+        db.clone(),
+        config.outbox_relay_interval,
+        config.outbox_retention,
+        sinks,
+    ));
+    tokio::spawn(workers::hold_expiry::run(db.clone(), config.hold_expiry_interval));
+    tokio::spawn(workers::scheduled_transfers::run(
+        db.clone(),
+        config.scheduled_transfer_interval,
+        config.scheduled_transfer_retry_delay,
+        config.scheduled_transfer_max_failures,
+        screener.clone(),
+    ));
+    tokio::spawn(workers::webhook_delivery::run(
+        db.clone(),
+        config.webhook_delivery_interval,
+        RetryPolicy {
+            max_attempts: config.webhook_max_attempts,
+            base_delay_seconds: config.webhook_retry_base_delay,
+        },
+        webhook_sender.clone(),
+    ));
+
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
@@ -47,12 +109,18 @@ async fn main() {
         .with_state(config_clone)
         .layer(TraceLayer::new_for_http())
         .layer(cors)
-        .layer(Extension(db));
+        .layer(Extension(db))
+        .layer(Extension(events))
+        .layer(Extension(screener))
+        .layer(Extension(webhook_sender))
+        .layer(Extension(watchlist));
 
     // Run the server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Starting server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
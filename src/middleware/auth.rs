@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::models::user::UserRole;
 use crate::utils::error::AppError;
 use crate::utils::jwt::{verify_token, Claims};
 use axum::{
@@ -14,8 +15,13 @@ pub struct CurrentUser {
     pub user_id: Uuid,
     pub username: String,
     pub email: String,
+    pub role: UserRole,
 }
 
+/// An authenticated user with the admin role, for operator-only endpoints.
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub CurrentUser);
+
 impl<S> FromRequestParts<S> for CurrentUser
 where
     Config: FromRef<S>,
@@ -43,12 +49,33 @@ where
 
         Ok(CurrentUser {
             user_id,
+            role: UserRole::from(token_data.claims.role.as_str()),
             username: token_data.claims.username,
             email: token_data.claims.email,
         })
     }
 }
 
+impl<S> FromRequestParts<S> for AdminUser
+where
+    Config: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let current_user = CurrentUser::from_request_parts(parts, state).await?;
+
+        if current_user.role != UserRole::Admin {
+            return Err(AppError::Forbidden(
+                "This operation requires an administrator".to_string(),
+            ));
+        }
+
+        Ok(AdminUser(current_user))
+    }
+}
+
 #[allow(dead_code)]
 pub fn get_current_user(token_data: &TokenData<Claims>) -> Result<CurrentUser, AppError> {
     let user_id = Uuid::parse_str(&token_data.claims.sub)
@@ -58,5 +85,6 @@ pub fn get_current_user(token_data: &TokenData<Claims>) -> Result<CurrentUser, A
         user_id,
         username: token_data.claims.username.clone(),
         email: token_data.claims.email.clone(),
+        role: UserRole::from(token_data.claims.role.as_str()),
     })
 } 
\ No newline at end of file
@@ -0,0 +1,44 @@
+use crate::config::Config;
+use crate::models::risk::RiskSignals;
+use crate::utils::error::AppError;
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use std::net::SocketAddr;
+
+impl<S> FromRequestParts<S> for RiskSignals
+where
+    Config: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Config::from_ref(state);
+        let header = |name: &Option<String>| {
+            name.as_ref()
+                .and_then(|name| parts.headers.get(name.as_str()))
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        // Each proxy appends the address it was reached from, so the client
+        // controls every entry left of those our own proxies added
+        let ip = match header(&config.risk_policy.ip_header) {
+            Some(forwarded) => {
+                let entries: Vec<&str> = forwarded.split(',').map(str::trim).filter(|ip| !ip.is_empty()).collect();
+                let from_right = config.risk_policy.trusted_proxies.max(1).min(entries.len());
+                entries.get(entries.len() - from_right).map(|ip| ip.to_string())
+            }
+            None => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string()),
+        };
+        let country = header(&config.risk_policy.country_header).map(str::to_uppercase);
+
+        Ok(RiskSignals { ip, country })
+    }
+}
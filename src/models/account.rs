@@ -3,17 +3,32 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::utils::currency::{format_amount, validate_currency_code};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Account {
     pub id: Uuid,
     pub user_id: Uuid,
     pub balance: i64,
+    pub held_balance: i64,
     pub currency: String,
+    /// Tells a user's accounts in the same currency apart.
+    pub label: String,
+    pub purpose: AccountPurpose,
     pub status: AccountStatus,
+    /// Set on a merchant's settlement account.
+    pub merchant_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Account {
+    /// Balance that can still be spent, i.e. excluding funds held by pending authorizations.
+    pub fn available_balance(&self) -> i64 {
+        self.balance - self.held_balance
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum AccountStatus {
@@ -32,21 +47,141 @@ impl std::fmt::Display for AccountStatus {
     }
 }
 
-impl From<&str> for AccountStatus {
+impl TryFrom<&str> for AccountStatus {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "active" => Ok(AccountStatus::Active),
+            "suspended" => Ok(AccountStatus::Suspended),
+            "closed" => Ok(AccountStatus::Closed),
+            _ => Err(format!("Unknown account status '{}'", s)),
+        }
+    }
+}
+
+impl AccountStatus {
+    /// Statuses an account may move to from this one. Suspension can be
+    /// lifted, but a closed account stays closed.
+    pub fn allowed_transitions(&self) -> &'static [AccountStatus] {
+        match self {
+            AccountStatus::Active => &[AccountStatus::Suspended, AccountStatus::Closed],
+            AccountStatus::Suspended => &[AccountStatus::Active],
+            AccountStatus::Closed => &[],
+        }
+    }
+
+    /// Suspended accounts still receive money, but nothing can leave them.
+    pub fn can_debit(&self) -> bool {
+        *self == AccountStatus::Active
+    }
+
+    pub fn can_credit(&self) -> bool {
+        *self != AccountStatus::Closed
+    }
+}
+
+/// What a user keeps an account for. It is only a hint for displaying the
+/// account and does not restrict how it can be used.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountPurpose {
+    #[default]
+    General,
+    Spending,
+    Savings,
+    Bills,
+}
+
+impl std::fmt::Display for AccountPurpose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountPurpose::General => write!(f, "general"),
+            AccountPurpose::Spending => write!(f, "spending"),
+            AccountPurpose::Savings => write!(f, "savings"),
+            AccountPurpose::Bills => write!(f, "bills"),
+        }
+    }
+}
+
+impl From<&str> for AccountPurpose {
     fn from(s: &str) -> Self {
         match s.to_lowercase().as_str() {
-            "active" => AccountStatus::Active,
-            "suspended" => AccountStatus::Suspended,
-            "closed" => AccountStatus::Closed,
-            _ => AccountStatus::Active,
+            "spending" => AccountPurpose::Spending,
+            "savings" => AccountPurpose::Savings,
+            "bills" => AccountPurpose::Bills,
+            _ => AccountPurpose::General,
         }
     }
 }
 
+/// Label given to an account opened without one.
+pub const DEFAULT_ACCOUNT_LABEL: &str = "Main";
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateAccountRequest {
-    #[validate(length(equal = 3, message = "Currency code must be 3 characters"))]
+    #[validate(custom = "validate_currency_code")]
     pub currency: String,
+
+    /// Defaults to "Main". Must be unique among the user's accounts in the
+    /// currency, ignoring case.
+    #[validate(length(min = 1, max = 50, message = "Label must be between 1 and 50 characters"))]
+    pub label: Option<String>,
+
+    #[serde(default)]
+    pub purpose: AccountPurpose,
+}
+
+impl CreateAccountRequest {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(DEFAULT_ACCOUNT_LABEL)
+    }
+}
+
+/// A change of an account's status.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountEvent {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub previous_status: AccountStatus,
+    pub new_status: AccountStatus,
+    pub reason: Option<String>,
+    pub performed_by: Option<Uuid>,
+    pub sweep_transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountEventListResponse {
+    pub events: Vec<AccountEvent>,
+}
+
+/// Suspends an account, or lifts a suspension.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ChangeAccountStatusRequest {
+    #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CloseAccountRequest {
+    /// Where any remaining balance goes. Required unless the balance is zero.
+    pub sweep_account_id: Option<Uuid>,
+
+    #[validate(length(max = 500, message = "Reason must be at most 500 characters"))]
+    pub reason: Option<String>,
+}
+
+/// Moves money between two of the user's own accounts in the same currency.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct MoveFundsRequest {
+    pub source_account_id: Uuid,
+    pub destination_account_id: Uuid,
+
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: i64,
+
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,13 +189,73 @@ pub struct AccountResponse {
     pub id: Uuid,
     pub user_id: Uuid,
     pub balance: i64,
+    pub available_balance: i64,
     pub currency: String,
+    pub label: String,
+    pub purpose: String,
     pub status: String,
+    pub merchant_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_balance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_available_balance: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl AccountResponse {
+    /// Adds the balances rendered as decimals in the account currency's major unit.
+    pub fn with_formatted_amounts(mut self) -> Self {
+        self.formatted_balance = format_amount(self.balance, &self.currency);
+        self.formatted_available_balance = format_amount(self.available_balance, &self.currency);
+        self
+    }
+}
+
+/// What a user holds in one currency across all their accounts.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub balance: i64,
+    pub available_balance: i64,
+    pub account_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_balance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_available_balance: Option<String>,
+}
+
+impl CurrencyTotal {
+    /// Totals the accounts per currency, in currency order.
+    pub fn from_accounts(accounts: &[Account]) -> Vec<CurrencyTotal> {
+        let mut totals: std::collections::BTreeMap<&str, CurrencyTotal> = std::collections::BTreeMap::new();
+        for account in accounts {
+            let total = totals.entry(&account.currency).or_insert_with(|| CurrencyTotal {
+                currency: account.currency.clone(),
+                balance: 0,
+                available_balance: 0,
+                account_count: 0,
+                formatted_balance: None,
+                formatted_available_balance: None,
+            });
+            total.balance += account.balance;
+            total.available_balance += account.available_balance();
+            total.account_count += 1;
+        }
+
+        totals.into_values().collect()
+    }
+
+    pub fn with_formatted_amounts(mut self) -> Self {
+        self.formatted_balance = format_amount(self.balance, &self.currency);
+        self.formatted_available_balance = format_amount(self.available_balance, &self.currency);
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountListResponse {
     pub accounts: Vec<AccountResponse>,
+    /// Consolidated balances per currency.
+    pub totals: Vec<CurrencyTotal>,
 } 
\ No newline at end of file
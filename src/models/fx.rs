@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::utils::currency::{self, validate_currency_code};
+
+/// Places of precision kept when a rate is stored or inverted.
+pub const RATE_SCALE: u32 = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FxRate {
+    pub id: Uuid,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: Decimal,
+    pub spread_bps: i32,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FxRate {
+    /// Mid rate for converting out of `source_currency`, inverting the stored
+    /// rate when the pair is held the other way round.
+    pub fn rate_from(&self, source_currency: &str) -> Decimal {
+        if self.base_currency == source_currency {
+            self.rate
+        } else {
+            (Decimal::ONE / self.rate).round_dp(RATE_SCALE)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq)]
+#[validate(schema(function = "validate_rate_request"))]
+pub struct CreateFxRateRequest {
+    #[validate(custom = "validate_currency_code")]
+    pub base_currency: String,
+
+    #[validate(custom = "validate_currency_code")]
+    pub quote_currency: String,
+
+    #[validate(custom = "validate_rate")]
+    pub rate: Decimal,
+
+    /// Margin taken on conversions, in basis points of the converted amount.
+    #[serde(default)]
+    #[validate(range(min = 0, max = 9999, message = "Spread must be between 0 and 9999 basis points"))]
+    pub spread_bps: i32,
+
+    /// Defaults to now.
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+fn validate_rate(rate: &Decimal) -> Result<(), ValidationError> {
+    if *rate <= Decimal::ZERO {
+        let mut error = ValidationError::new("rate");
+        error.message = Some("Rate must be greater than zero".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn validate_rate_request(request: &CreateFxRateRequest) -> Result<(), ValidationError> {
+    if request.base_currency == request.quote_currency {
+        let mut error = ValidationError::new("currency_pair");
+        error.message = Some("Base and quote currencies must differ".into());
+        return Err(error);
+    }
+    if let (Some(valid_from), Some(valid_to)) = (request.valid_from, request.valid_to)
+        && valid_to <= valid_from
+    {
+        let mut error = ValidationError::new("validity");
+        error.message = Some("valid_to must be after valid_from".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FxRateListResponse {
+    pub rates: Vec<FxRate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateFxQuoteRequest {
+    #[validate(custom = "validate_currency_code")]
+    pub source_currency: String,
+
+    #[validate(custom = "validate_currency_code")]
+    pub destination_currency: String,
+
+    /// Amount to convert, in the source currency.
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: i64,
+}
+
+/// A rate locked for one conversion until `expires_at`. Passing its id as
+/// `fx_quote_id` on a transfer executes the transfer at exactly these amounts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FxQuote {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub fx_rate_id: Uuid,
+    pub source_currency: String,
+    pub destination_currency: String,
+    pub source_amount: i64,
+    pub destination_amount: i64,
+    pub rate: Decimal,
+    pub spread_bps: i32,
+    pub spread_amount: i64,
+    pub transaction_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Result of converting an amount at a mid rate less a spread.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxConversion {
+    /// Rate actually applied, i.e. the mid rate net of the spread.
+    pub rate: Decimal,
+    pub spread_bps: i32,
+    pub destination_amount: i64,
+    /// Destination units kept as spread compared to converting at the mid rate.
+    pub spread_amount: i64,
+}
+
+/// Converts `amount` from `source_currency` into `destination_currency` at
+/// `mid_rate`, taking `spread_bps` off the rate. Rates are per major unit while
+/// amounts are in each currency's minor units, and the destination amount is
+/// rounded down.
+pub fn convert(
+    amount: i64,
+    source_currency: &str,
+    destination_currency: &str,
+    mid_rate: Decimal,
+    spread_bps: i32,
+) -> Result<FxConversion, String> {
+    let source = currency::find(source_currency)
+        .ok_or_else(|| format!("Unsupported currency {}", source_currency))?;
+    let destination = currency::find(destination_currency)
+        .ok_or_else(|| format!("Unsupported currency {}", destination_currency))?;
+
+    if amount <= 0 {
+        return Err("Amount must be greater than zero".to_string());
+    }
+    if mid_rate <= Decimal::ZERO {
+        return Err("Rate must be greater than zero".to_string());
+    }
+    if !(0..10_000).contains(&spread_bps) {
+        return Err(format!("Invalid spread of {} basis points", spread_bps));
+    }
+
+    let applied_rate = (mid_rate * Decimal::from(10_000 - spread_bps) / Decimal::from(10_000))
+        .round_dp(RATE_SCALE);
+
+    // Shift between the two currencies' minor units, e.g. 100 for JPY to USD
+    let unit_shift = if destination.minor_units >= source.minor_units {
+        Decimal::from(10_i64.pow(destination.minor_units - source.minor_units))
+    } else {
+        Decimal::new(1, source.minor_units - destination.minor_units)
+    };
+
+    let to_minor_units = |rate: Decimal| {
+        Decimal::from(amount)
+            .checked_mul(rate)
+            .and_then(|converted| converted.checked_mul(unit_shift))
+            .and_then(|converted| converted.floor().to_i64())
+            .ok_or_else(|| "Converted amount is too large".to_string())
+    };
+    let destination_amount = to_minor_units(applied_rate)?;
+    let mid_amount = to_minor_units(mid_rate)?;
+
+    if destination_amount <= 0 {
+        return Err("Amount is too small to convert".to_string());
+    }
+
+    Ok(FxConversion {
+        rate: applied_rate,
+        spread_bps,
+        destination_amount,
+        spread_amount: mid_amount - destination_amount,
+    })
+}
+
+/// Parses rates from CSV with a header row. `base_currency`, `quote_currency`
+/// and `rate` are required; `spread_bps`, `valid_from` and `valid_to` may be
+/// omitted or left empty. Blank lines and lines starting with `#` are skipped.
+pub fn parse_rates_csv(input: &str) -> Result<Vec<CreateFxRateRequest>, String> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (_, header) = lines.next().ok_or_else(|| "CSV is empty".to_string())?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let position = |name: &str| columns.iter().position(|column| *column == name);
+
+    let base_index = position("base_currency").ok_or("Missing base_currency column")?;
+    let quote_index = position("quote_currency").ok_or("Missing quote_currency column")?;
+    let rate_index = position("rate").ok_or("Missing rate column")?;
+    let spread_index = position("spread_bps");
+    let valid_from_index = position("valid_from");
+    let valid_to_index = position("valid_to");
+
+    let mut rates = Vec::new();
+    for (line_number, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != columns.len() {
+            return Err(format!(
+                "line {}: expected {} fields, found {}",
+                line_number,
+                columns.len(),
+                fields.len()
+            ));
+        }
+
+        let optional = |index: Option<usize>| index.map(|i| fields[i]).filter(|value| !value.is_empty());
+        let parse_time = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| format!("line {}: invalid timestamp '{}'", line_number, value))
+        };
+
+        rates.push(CreateFxRateRequest {
+            base_currency: fields[base_index].to_uppercase(),
+            quote_currency: fields[quote_index].to_uppercase(),
+            rate: fields[rate_index]
+                .parse()
+                .map_err(|_| format!("line {}: invalid rate '{}'", line_number, fields[rate_index]))?,
+            spread_bps: optional(spread_index)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid spread_bps '{}'", line_number, value))
+                })
+                .transpose()?
+                .unwrap_or(0),
+            valid_from: optional(valid_from_index).map(parse_time).transpose()?,
+            valid_to: optional(valid_to_index).map(parse_time).transpose()?,
+        });
+    }
+
+    if rates.is_empty() {
+        return Err("CSV contains no rates".to_string());
+    }
+
+    Ok(rates)
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransactionHold {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    pub amount: i64,
+    pub captured_amount: Option<i64>,
+    pub status: HoldStatus,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HoldStatus {
+    Active,
+    Captured,
+    Voided,
+    Expired,
+}
+
+impl std::fmt::Display for HoldStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HoldStatus::Active => write!(f, "active"),
+            HoldStatus::Captured => write!(f, "captured"),
+            HoldStatus::Voided => write!(f, "voided"),
+            HoldStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+impl TryFrom<&str> for HoldStatus {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "active" => Ok(HoldStatus::Active),
+            "captured" => Ok(HoldStatus::Captured),
+            "voided" => Ok(HoldStatus::Voided),
+            "expired" => Ok(HoldStatus::Expired),
+            _ => Err(format!("Unknown hold status '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, Default)]
+pub struct CaptureTransactionRequest {
+    /// Amount to capture; defaults to the full authorized amount.
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: Option<i64>,
+}
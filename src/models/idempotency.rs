@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Response recorded for the first request made with an idempotency key.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+/// Result of claiming an idempotency key before processing a request.
+#[derive(Debug, Clone)]
+pub enum IdempotencyOutcome {
+    /// The key is new and the request should be processed.
+    Started,
+    /// The key was already used for an identical request.
+    Replay(StoredResponse),
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LedgerEntry {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub system_account: Option<String>,
+    pub entry_type: EntryType,
+    pub amount: i64,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    Debit,
+    Credit,
+}
+
+impl std::fmt::Display for EntryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntryType::Debit => write!(f, "debit"),
+            EntryType::Credit => write!(f, "credit"),
+        }
+    }
+}
+
+impl TryFrom<&str> for EntryType {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "debit" => Ok(EntryType::Debit),
+            "credit" => Ok(EntryType::Credit),
+            _ => Err(format!("Unknown entry type '{}'", s)),
+        }
+    }
+}
+
+/// Internal accounts that stand in for money entering or leaving the system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum SystemAccount {
+    DepositClearing,
+    WithdrawalClearing,
+    /// Both legs of cross-currency transfers, including the spread kept.
+    FxClearing,
+}
+
+impl SystemAccount {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemAccount::DepositClearing => "deposit_clearing",
+            SystemAccount::WithdrawalClearing => "withdrawal_clearing",
+            SystemAccount::FxClearing => "fx_clearing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LedgerAccount {
+    Customer(Uuid),
+    System(SystemAccount),
+}
+
+/// A single line to be posted to the ledger as part of a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub entry_type: EntryType,
+    pub amount: i64,
+    pub currency: String,
+}
+
+impl Posting {
+    pub fn debit(account: LedgerAccount, amount: i64, currency: &str) -> Self {
+        Self {
+            account,
+            entry_type: EntryType::Debit,
+            amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    pub fn credit(account: LedgerAccount, amount: i64, currency: &str) -> Self {
+        Self {
+            account,
+            entry_type: EntryType::Credit,
+            amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    /// The opposite posting, used to unwind an earlier movement.
+    pub fn reversed(&self) -> Self {
+        Self {
+            account: self.account,
+            entry_type: match self.entry_type {
+                EntryType::Debit => EntryType::Credit,
+                EntryType::Credit => EntryType::Debit,
+            },
+            amount: self.amount,
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// Effect of this posting on a customer account balance. Customer accounts
+    /// are liabilities, so credits increase the balance and debits decrease it.
+    pub fn balance_delta(&self) -> i64 {
+        match self.entry_type {
+            EntryType::Credit => self.amount,
+            EntryType::Debit => -self.amount,
+        }
+    }
+}
+
+/// Checks that a set of postings is non-empty, has positive amounts and that
+/// debits equal credits in every currency.
+pub fn ensure_balanced(postings: &[Posting]) -> Result<(), String> {
+    if postings.is_empty() {
+        return Err("A ledger posting needs at least one entry".to_string());
+    }
+
+    let mut totals: HashMap<&str, i64> = HashMap::new();
+    for posting in postings {
+        if posting.amount <= 0 {
+            return Err(format!(
+                "Ledger entry amounts must be positive, got {}",
+                posting.amount
+            ));
+        }
+        *totals.entry(posting.currency.as_str()).or_insert(0) += posting.balance_delta();
+    }
+
+    match totals.iter().find(|(_, total)| **total != 0) {
+        Some((currency, total)) => Err(format!(
+            "Unbalanced ledger posting in {}: {} exceed {} by {}",
+            currency,
+            if *total > 0 { "credits" } else { "debits" },
+            if *total > 0 { "debits" } else { "credits" },
+            total.abs()
+        )),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LedgerEntryResponse {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub system_account: Option<String>,
+    pub entry_type: String,
+    pub amount: i64,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountLedgerResponse {
+    pub account_id: Uuid,
+    pub balance: i64,
+    pub ledger_balance: i64,
+    pub balanced: bool,
+    pub entries: Vec<LedgerEntryResponse>,
+}
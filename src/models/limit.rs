@@ -0,0 +1,273 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// What a set of limits applies to. Account limits count one account's
+/// outgoing money; user limits count everything a user sends in a currency
+/// across all their accounts. The default applies to every account without a
+/// limit of its own.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitScope {
+    Default,
+    Account,
+    User,
+}
+
+impl std::fmt::Display for LimitScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitScope::Default => write!(f, "default"),
+            LimitScope::Account => write!(f, "account"),
+            LimitScope::User => write!(f, "user"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    MaxTransactionAmount,
+    DailyAmount,
+    MonthlyAmount,
+    HourlyCount,
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitKind::MaxTransactionAmount => write!(f, "max_transaction_amount"),
+            LimitKind::DailyAmount => write!(f, "daily_amount"),
+            LimitKind::MonthlyAmount => write!(f, "monthly_amount"),
+            LimitKind::HourlyCount => write!(f, "hourly_count"),
+        }
+    }
+}
+
+/// Limits on outgoing money. Amounts are in minor units of the currency being
+/// sent, and an unset limit does not apply.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Validate)]
+pub struct SpendingLimits {
+    #[validate(range(min = 1, message = "Max transaction amount must be greater than zero"))]
+    pub max_transaction_amount: Option<i64>,
+
+    #[validate(range(min = 1, message = "Daily amount must be greater than zero"))]
+    pub daily_amount: Option<i64>,
+
+    #[validate(range(min = 1, message = "Monthly amount must be greater than zero"))]
+    pub monthly_amount: Option<i64>,
+
+    /// Most outgoing transactions in any rolling hour.
+    #[validate(range(min = 1, message = "Hourly count must be greater than zero"))]
+    pub hourly_count: Option<i64>,
+}
+
+impl SpendingLimits {
+    /// These limits, with any that are unset taken from `fallback`.
+    pub fn or(self, fallback: SpendingLimits) -> SpendingLimits {
+        SpendingLimits {
+            max_transaction_amount: self.max_transaction_amount.or(fallback.max_transaction_amount),
+            daily_amount: self.daily_amount.or(fallback.daily_amount),
+            monthly_amount: self.monthly_amount.or(fallback.monthly_amount),
+            hourly_count: self.hourly_count.or(fallback.hourly_count),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == SpendingLimits::default()
+    }
+}
+
+/// A stored set of limits.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpendingLimitSet {
+    pub scope: LimitScope,
+    /// The account or user, unset for the default.
+    pub subject_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub limits: SpendingLimits,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The periods limits are counted over. Days and months are calendar periods
+/// in UTC; the hourly count is a rolling hour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitWindows {
+    pub hour_start: DateTime<Utc>,
+    pub day_start: DateTime<Utc>,
+    pub next_day: DateTime<Utc>,
+    pub month_start: DateTime<Utc>,
+    pub next_month: DateTime<Utc>,
+}
+
+impl LimitWindows {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        let midnight = |date: NaiveDate| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap());
+        let today = now.date_naive();
+        let month_start = today.with_day(1).unwrap();
+        let next_month = if month_start.month() == 12 {
+            NaiveDate::from_ymd_opt(month_start.year() + 1, 1, 1).unwrap()
+        } else {
+            NaiveDate::from_ymd_opt(month_start.year(), month_start.month() + 1, 1).unwrap()
+        };
+
+        LimitWindows {
+            hour_start: now - Duration::hours(1),
+            day_start: midnight(today),
+            next_day: midnight(today.succ_opt().unwrap()),
+            month_start: midnight(month_start),
+            next_month: midnight(next_month),
+        }
+    }
+
+    /// Start of the longest window, so usage can be counted in one pass.
+    pub fn earliest(&self) -> DateTime<Utc> {
+        self.month_start.min(self.hour_start)
+    }
+}
+
+/// Outgoing money already counted against a set of limits.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LimitUsage {
+    pub daily_amount: i64,
+    pub monthly_amount: i64,
+    pub hourly_count: i64,
+    /// The oldest transaction in the rolling hour, which is the next to stop
+    /// counting.
+    pub oldest_in_hour: Option<DateTime<Utc>>,
+}
+
+/// How much of one limit is left.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RemainingLimit {
+    pub limit: LimitKind,
+    pub limit_value: i64,
+    pub used: i64,
+    pub remaining: i64,
+    /// When the usage counted against the limit next goes down. Unset for the
+    /// single transaction limit, which has no usage.
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+impl LimitUsage {
+    /// Every configured limit with what has been used of it.
+    pub fn remaining(&self, limits: &SpendingLimits, windows: &LimitWindows) -> Vec<RemainingLimit> {
+        let hourly_reset = self.oldest_in_hour.map(|oldest| oldest + Duration::hours(1));
+        let counted = [
+            (LimitKind::MaxTransactionAmount, limits.max_transaction_amount, 0, None),
+            (LimitKind::DailyAmount, limits.daily_amount, self.daily_amount, Some(windows.next_day)),
+            (LimitKind::MonthlyAmount, limits.monthly_amount, self.monthly_amount, Some(windows.next_month)),
+            (LimitKind::HourlyCount, limits.hourly_count, self.hourly_count, hourly_reset),
+        ];
+
+        counted
+            .into_iter()
+            .filter_map(|(limit, limit_value, used, resets_at)| {
+                limit_value.map(|limit_value| RemainingLimit {
+                    limit,
+                    limit_value,
+                    used,
+                    remaining: (limit_value - used).max(0),
+                    resets_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Checks one more outgoing transaction of `amount` against the limits,
+    /// returning the first limit it would break.
+    pub fn check(
+        &self,
+        limits: &SpendingLimits,
+        windows: &LimitWindows,
+        amount: i64,
+    ) -> Result<(), (RemainingLimit, i64)> {
+        for remaining in self.remaining(limits, windows) {
+            let requested = match remaining.limit {
+                LimitKind::HourlyCount => 1,
+                _ => amount,
+            };
+            let exceeded = match remaining.limit {
+                LimitKind::MaxTransactionAmount => requested > remaining.limit_value,
+                _ => remaining.used + requested > remaining.limit_value,
+            };
+            if exceeded {
+                return Err((remaining, requested));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Details of a transaction refused for breaking a limit.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LimitExceeded {
+    pub scope: LimitScope,
+    pub limit: LimitKind,
+    pub currency: String,
+    pub limit_value: i64,
+    pub used: i64,
+    pub requested: i64,
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+impl LimitExceeded {
+    pub fn new(scope: LimitScope, currency: &str, (remaining, requested): (RemainingLimit, i64)) -> Self {
+        LimitExceeded {
+            scope,
+            limit: remaining.limit,
+            currency: currency.to_string(),
+            limit_value: remaining.limit_value,
+            used: remaining.used,
+            requested,
+            resets_at: remaining.resets_at,
+        }
+    }
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            LimitKind::MaxTransactionAmount => write!(
+                f,
+                "{} {} exceeds the {} limit of {} {} per transaction",
+                self.requested, self.currency, self.scope, self.limit_value, self.currency
+            )?,
+            LimitKind::HourlyCount => write!(
+                f,
+                "{} hourly limit of {} transactions reached",
+                self.scope, self.limit_value
+            )?,
+            LimitKind::DailyAmount | LimitKind::MonthlyAmount => write!(
+                f,
+                "{} {} would exceed the {} {} limit of {} {}, of which {} is used",
+                self.requested,
+                self.currency,
+                self.scope,
+                if self.limit == LimitKind::DailyAmount { "daily" } else { "monthly" },
+                self.limit_value,
+                self.currency,
+                self.used
+            )?,
+        }
+
+        match self.resets_at {
+            Some(resets_at) => write!(f, "; resets at {}", resets_at.to_rfc3339()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Limits that apply to an account and what is left of them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountLimitsResponse {
+    pub account_id: Uuid,
+    pub currency: String,
+    /// The account's own limits, or the default where it has none.
+    pub account: Vec<RemainingLimit>,
+    /// The owner's limits across all their accounts in the currency.
+    pub user: Vec<RemainingLimit>,
+}
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::utils::currency::validate_currency_code;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Merchant {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// The account payments to the merchant are made into.
+    pub settlement_account_id: Uuid,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateMerchantRequest {
+    #[validate(length(min = 1, max = 255, message = "Name must be between 1 and 255 characters"))]
+    pub name: String,
+
+    /// Currency of the settlement account, and so of every payment.
+    #[validate(custom = "validate_currency_code")]
+    pub currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MerchantListResponse {
+    pub merchants: Vec<Merchant>,
+}
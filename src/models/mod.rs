@@ -1,3 +1,20 @@
 pub mod user;
 pub mod account;
-pub mod transaction; 
\ No newline at end of file
+pub mod transaction;
+pub mod ledger;
+pub mod idempotency;
+pub mod hold;
+pub mod transaction_state;
+pub mod fx;
+pub mod scheduled_transfer;
+pub mod webhook;
+pub mod outbox;
+pub mod stream;
+pub mod statement;
+pub mod reconciliation;
+pub mod settlement;
+pub mod merchant;
+pub mod payment_intent;
+pub mod limit;
+pub mod risk;
+pub mod watchlist;
\ No newline at end of file
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// A domain event recorded in the outbox alongside the change it describes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboxEvent {
+    /// Increases with every event, and for any one aggregate in the order the
+    /// changes were made.
+    pub id: i64,
+    /// Where the event falls in the order the relay publishes events, which
+    /// a stream client resumes from. Unlike the id, no event is ever given a
+    /// lower position than one already published.
+    pub position: i64,
+    pub event_id: Uuid,
+    pub aggregate_type: AggregateType,
+    pub aggregate_id: Uuid,
+    /// Owners of the accounts involved, who may see the event.
+    pub user_ids: Vec<Uuid>,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The kind of entity an event is about.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateType {
+    Transaction,
+    Account,
+    #[serde(rename = "payment_intent")]
+    PaymentIntent,
+}
+
+impl std::fmt::Display for AggregateType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregateType::Transaction => write!(f, "transaction"),
+            AggregateType::Account => write!(f, "account"),
+            AggregateType::PaymentIntent => write!(f, "payment_intent"),
+        }
+    }
+}
+
+impl TryFrom<&str> for AggregateType {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "transaction" => Ok(AggregateType::Transaction),
+            "account" => Ok(AggregateType::Account),
+            "payment_intent" => Ok(AggregateType::PaymentIntent),
+            _ => Err(format!("Unknown aggregate type '{}'", s)),
+        }
+    }
+}
+
+/// Where the relay publishes outbox events.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSinkKind {
+    /// An in-process broadcast channel for subscribers inside the server.
+    Broadcast,
+    /// Queues deliveries for subscribed webhook endpoints.
+    Webhooks,
+    /// Writes each event to standard output as a line of JSON.
+    Stdout,
+}
+
+impl FromStr for EventSinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "broadcast" => Ok(EventSinkKind::Broadcast),
+            "webhooks" => Ok(EventSinkKind::Webhooks),
+            "stdout" => Ok(EventSinkKind::Stdout),
+            other => Err(format!("Unknown event sink '{}'", other)),
+        }
+    }
+}
+
+/// Parses a comma-separated list of sinks, e.g. `broadcast,webhooks`.
+pub fn parse_event_sinks(value: &str) -> Result<Vec<EventSinkKind>, String> {
+    let mut sinks = Vec::new();
+    for sink in value.split(',').filter(|sink| !sink.trim().is_empty()) {
+        let sink = sink.parse()?;
+        if !sinks.contains(&sink) {
+            sinks.push(sink);
+        }
+    }
+
+    Ok(sinks)
+}
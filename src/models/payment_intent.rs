@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Most metadata keys an intent can carry, and their longest keys and values.
+const MAX_METADATA_KEYS: usize = 20;
+const MAX_METADATA_KEY_LENGTH: usize = 40;
+const MAX_METADATA_VALUE_LENGTH: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaymentIntent {
+    pub id: Uuid,
+    pub merchant_id: Uuid,
+    /// The merchant's settlement account.
+    pub destination_account_id: Uuid,
+    pub amount: i64,
+    pub currency: String,
+    pub description: Option<String>,
+    pub metadata: HashMap<String, String>,
+    /// Only revealed to the merchant when the intent is created, to be handed
+    /// to the customer who pays it.
+    #[serde(skip_serializing)]
+    pub client_secret: String,
+    pub status: PaymentIntentStatus,
+    /// Set once a customer has confirmed the intent.
+    pub customer_user_id: Option<Uuid>,
+    pub source_account_id: Option<Uuid>,
+    /// The transfer that paid the intent.
+    pub transaction_id: Option<Uuid>,
+    /// Why the last confirmation failed, cleared once one succeeds.
+    pub last_error: Option<String>,
+    pub cancellation_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PaymentIntent {
+    /// Compares digests rather than the secrets themselves, so the comparison
+    /// takes the same time however much of the secret a guess gets right.
+    pub fn verify_client_secret(&self, client_secret: &str) -> bool {
+        Sha256::digest(self.client_secret.as_bytes()) == Sha256::digest(client_secret.as_bytes())
+    }
+}
+
+/// Intents start out waiting for a customer, who confirms them by paying. A
+/// failed payment leaves the intent waiting, so it can be confirmed again.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentIntentStatus {
+    RequiresConfirmation,
+    /// Paid by a transfer that screening held for review.
+    Processing,
+    Succeeded,
+    Canceled,
+}
+
+impl std::fmt::Display for PaymentIntentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentIntentStatus::RequiresConfirmation => write!(f, "requires_confirmation"),
+            PaymentIntentStatus::Processing => write!(f, "processing"),
+            PaymentIntentStatus::Succeeded => write!(f, "succeeded"),
+            PaymentIntentStatus::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+impl From<&str> for PaymentIntentStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "processing" => PaymentIntentStatus::Processing,
+            "succeeded" => PaymentIntentStatus::Succeeded,
+            "canceled" => PaymentIntentStatus::Canceled,
+            _ => PaymentIntentStatus::RequiresConfirmation,
+        }
+    }
+}
+
+/// Returned when an intent is created, the only time its client secret is
+/// shown.
+#[derive(Debug, Serialize)]
+pub struct CreatedPaymentIntent {
+    #[serde(flatten)]
+    pub payment_intent: PaymentIntent,
+    pub client_secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreatePaymentIntentRequest {
+    pub merchant_id: Uuid,
+
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: i64,
+
+    /// Must be the merchant's currency.
+    pub currency: String,
+
+    pub description: Option<String>,
+
+    #[serde(default)]
+    #[validate(custom = "validate_metadata")]
+    pub metadata: HashMap<String, String>,
+}
+
+fn validate_metadata(metadata: &HashMap<String, String>) -> Result<(), ValidationError> {
+    let message = if metadata.len() > MAX_METADATA_KEYS {
+        format!("Metadata can have at most {} keys", MAX_METADATA_KEYS)
+    } else if let Some(key) = metadata
+        .keys()
+        .find(|key| key.is_empty() || key.chars().count() > MAX_METADATA_KEY_LENGTH)
+    {
+        format!(
+            "Metadata key '{}' must be between 1 and {} characters",
+            key, MAX_METADATA_KEY_LENGTH
+        )
+    } else if let Some(key) = metadata
+        .iter()
+        .find(|(_, value)| value.chars().count() > MAX_METADATA_VALUE_LENGTH)
+        .map(|(key, _)| key)
+    {
+        format!(
+            "Metadata value for '{}' must be at most {} characters",
+            key, MAX_METADATA_VALUE_LENGTH
+        )
+    } else {
+        return Ok(());
+    };
+
+    let mut error = ValidationError::new("metadata");
+    error.message = Some(message.into());
+    Err(error)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmPaymentIntentRequest {
+    pub client_secret: String,
+    /// The customer's account to pay from, in the intent's currency.
+    pub source_account_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CancelPaymentIntentRequest {
+    #[validate(length(max = 255, message = "Cancellation reason must be at most 255 characters"))]
+    pub cancellation_reason: Option<String>,
+}
+
+/// Lets a customer who is not the merchant look an intent up.
+#[derive(Debug, Deserialize)]
+pub struct PaymentIntentParams {
+    pub client_secret: Option<String>,
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::hold::HoldStatus;
+use crate::models::transaction::TransactionType;
+
+/// An account whose stored balances disagree with its history. The expected
+/// balance is the net of its completed transactions, plus whatever part of a
+/// reversed transaction was refunded before the reversal; the expected held
+/// balance is the total of its active holds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BalanceDiscrepancy {
+    pub account_id: Uuid,
+    pub currency: String,
+    pub stored_balance: i64,
+    pub expected_balance: i64,
+    /// Stored minus expected, so positive when the account holds too much.
+    pub balance_difference: i64,
+    pub stored_held_balance: i64,
+    pub expected_held_balance: i64,
+    pub held_balance_difference: i64,
+}
+
+impl BalanceDiscrepancy {
+    pub fn new(
+        account_id: Uuid,
+        currency: String,
+        (stored_balance, expected_balance): (i64, i64),
+        (stored_held_balance, expected_held_balance): (i64, i64),
+    ) -> Self {
+        BalanceDiscrepancy {
+            account_id,
+            currency,
+            stored_balance,
+            expected_balance,
+            balance_difference: stored_balance - expected_balance,
+            stored_held_balance,
+            expected_held_balance,
+            held_balance_difference: stored_held_balance - expected_held_balance,
+        }
+    }
+}
+
+/// Why a pending transaction is considered abandoned.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// Never authorized, so it should have completed or failed immediately.
+    NoHold,
+    /// Its hold was captured, voided or expired but the transaction never moved on.
+    HoldReleased,
+    /// Its hold is still active long after it expired.
+    HoldExpired,
+}
+
+impl std::fmt::Display for OrphanReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrphanReason::NoHold => write!(f, "no_hold"),
+            OrphanReason::HoldReleased => write!(f, "hold_released"),
+            OrphanReason::HoldExpired => write!(f, "hold_expired"),
+        }
+    }
+}
+
+impl OrphanReason {
+    pub fn for_hold(hold_status: Option<&HoldStatus>) -> Self {
+        match hold_status {
+            None => OrphanReason::NoHold,
+            Some(HoldStatus::Active) => OrphanReason::HoldExpired,
+            Some(_) => OrphanReason::HoldReleased,
+        }
+    }
+}
+
+impl From<&str> for OrphanReason {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "hold_released" => OrphanReason::HoldReleased,
+            "hold_expired" => OrphanReason::HoldExpired,
+            _ => OrphanReason::NoHold,
+        }
+    }
+}
+
+/// A transaction left `pending` for longer than any flow should take.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanedTransaction {
+    pub transaction_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub source_account_id: Option<Uuid>,
+    pub destination_account_id: Option<Uuid>,
+    pub amount: i64,
+    pub currency: String,
+    pub hold_status: Option<HoldStatus>,
+    pub reason: OrphanReason,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReconciliationReport {
+    /// Only set once the report has been saved.
+    pub id: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub accounts_checked: i64,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+    pub orphaned_transactions: Vec<OrphanedTransaction>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty() && self.orphaned_transactions.is_empty()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ReconciliationParams {
+    /// Save the report so it can be fetched again later.
+    #[serde(default)]
+    pub persist: bool,
+}
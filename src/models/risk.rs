@@ -0,0 +1,267 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::transaction::TransactionResponse;
+
+/// The risk rules and the scores at which transfers are held or declined,
+/// loaded from the JSON file named by `RISK_RULES_PATH`. Without a file there
+/// are no rules and every transfer is approved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskPolicy {
+    /// Total score at which a transfer is held for review.
+    #[serde(default = "default_review_score")]
+    pub review_score: u32,
+    /// Total score at which a transfer is declined outright.
+    #[serde(default = "default_decline_score")]
+    pub decline_score: u32,
+    /// How long the funds of a transfer held for review stay reserved, in
+    /// seconds. The transfer is cancelled if nobody reviews it in time.
+    #[serde(default = "default_review_hold_ttl")]
+    pub review_hold_ttl: i64,
+    /// Header carrying the client's address when the server sits behind a
+    /// proxy, such as `x-forwarded-for`. Without one the peer address is used.
+    #[serde(default)]
+    pub ip_header: Option<String>,
+    /// How many proxies of ours append to `ip_header`. The client's address is
+    /// the entry this many places from the right; anything further left was
+    /// sent by the client and is ignored.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: usize,
+    /// Header carrying the client's country as set by a proxy, such as
+    /// `cf-ipcountry`. Without one country rules never match.
+    #[serde(default)]
+    pub country_header: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<RiskRuleConfig>,
+}
+
+fn default_review_score() -> u32 {
+    50
+}
+
+fn default_decline_score() -> u32 {
+    100
+}
+
+fn default_review_hold_ttl() -> i64 {
+    604800
+}
+
+fn default_trusted_proxies() -> usize {
+    1
+}
+
+impl Default for RiskPolicy {
+    fn default() -> Self {
+        RiskPolicy {
+            review_score: default_review_score(),
+            decline_score: default_decline_score(),
+            review_hold_ttl: default_review_hold_ttl(),
+            ip_header: None,
+            trusted_proxies: default_trusted_proxies(),
+            country_header: None,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl RiskPolicy {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let policy: RiskPolicy =
+            serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+
+        if policy.review_score > policy.decline_score {
+            return Err("review_score must not be greater than decline_score".to_string());
+        }
+        if policy.review_hold_ttl <= 0 {
+            return Err("review_hold_ttl must be greater than zero".to_string());
+        }
+
+        Ok(policy)
+    }
+
+    /// The decision for a transfer with a total score of `score`.
+    pub fn decide(&self, score: u32) -> RiskDecision {
+        if score >= self.decline_score {
+            RiskDecision::Decline
+        } else if score >= self.review_score {
+            RiskDecision::Review
+        } else {
+            RiskDecision::Approve
+        }
+    }
+
+    /// The longest window any velocity rule counts over, in seconds.
+    pub fn velocity_window(&self) -> i64 {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                RiskRuleConfig::Velocity { window_seconds, .. } => Some(*window_seconds),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// One rule in the rules file, tagged by `type`. Each adds `score` to a
+/// transfer it matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RiskRuleConfig {
+    /// Transfers of at least `min_amount`, in minor units of `currency` or of
+    /// any currency when it is unset.
+    AmountThreshold {
+        min_amount: i64,
+        #[serde(default)]
+        currency: Option<String>,
+        score: u32,
+    },
+    /// Transfers to an account the sender has never paid before.
+    NewDestination { score: u32 },
+    /// More than `max_count` withdrawals and transfers from the source account
+    /// within `window_seconds`, counting this one.
+    Velocity { window_seconds: i64, max_count: i64, score: u32 },
+    /// Transfers made from a different country than the sender's last
+    /// screened transfer.
+    CountryMismatch { score: u32 },
+    /// Transfers made from a different address than the sender's last
+    /// screened transfer.
+    IpMismatch { score: u32 },
+    /// The first withdrawal or transfer from an account opened less than
+    /// `max_age_seconds` ago.
+    NewAccount { max_age_seconds: i64, score: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskDecision {
+    Approve,
+    Review,
+    Decline,
+}
+
+impl std::fmt::Display for RiskDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskDecision::Approve => write!(f, "approve"),
+            RiskDecision::Review => write!(f, "review"),
+            RiskDecision::Decline => write!(f, "decline"),
+        }
+    }
+}
+
+/// Where a request came from, as far as the server can tell.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RiskSignals {
+    pub ip: Option<String>,
+    pub country: Option<String>,
+}
+
+/// What the rules know about a transfer being screened.
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    pub amount: i64,
+    pub currency: String,
+    pub source_account_created_at: DateTime<Utc>,
+    /// Withdrawals and transfers already made from the source account.
+    pub previous_outgoing_count: i64,
+    /// Whether the sender has completed a transfer to the destination before.
+    pub known_destination: bool,
+    /// When the source account's recent withdrawals and transfers were made,
+    /// within the longest velocity window.
+    pub recent_outgoing: Vec<DateTime<Utc>>,
+    pub signals: RiskSignals,
+    /// Signals of the sender's last screened transfer.
+    pub previous_signals: Option<RiskSignals>,
+    pub now: DateTime<Utc>,
+}
+
+/// A rule that matched a transfer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RuleHit {
+    pub rule: String,
+    pub score: u32,
+    pub detail: String,
+}
+
+/// The outcome of screening a transfer, stored with its events.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RiskAssessment {
+    pub decision: RiskDecision,
+    pub score: u32,
+    pub hits: Vec<RuleHit>,
+    pub signals: RiskSignals,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskReviewStatus {
+    Open,
+    Approved,
+    Rejected,
+    Expired,
+}
+
+impl std::fmt::Display for RiskReviewStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskReviewStatus::Open => write!(f, "open"),
+            RiskReviewStatus::Approved => write!(f, "approved"),
+            RiskReviewStatus::Rejected => write!(f, "rejected"),
+            RiskReviewStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+impl From<&str> for RiskReviewStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "approved" => RiskReviewStatus::Approved,
+            "rejected" => RiskReviewStatus::Rejected,
+            "expired" => RiskReviewStatus::Expired,
+            _ => RiskReviewStatus::Open,
+        }
+    }
+}
+
+/// A transfer held for review. Approving an immediate transfer completes it;
+/// approving an authorization returns it to pending so it can be captured.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RiskReview {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub assessment: RiskAssessment,
+    pub authorize_only: bool,
+    pub status: RiskReviewStatus,
+    pub decided_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DecideRiskReviewRequest {
+    #[validate(length(max = 500, message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RiskReviewFilter {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RiskReviewResponse {
+    #[serde(flatten)]
+    pub review: RiskReview,
+    pub transaction: TransactionResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RiskReviewListResponse {
+    pub reviews: Vec<RiskReviewResponse>,
+}
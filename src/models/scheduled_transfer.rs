@@ -0,0 +1,267 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::utils::cron::CronSchedule;
+use crate::utils::currency::validate_currency_code;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledTransfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub source_account_id: Uuid,
+    pub destination_account_id: Uuid,
+    pub amount: i64,
+    pub currency: String,
+    pub description: Option<String>,
+    pub cron_expression: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub start_at: DateTime<Utc>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub max_occurrences: Option<i32>,
+    pub occurrences: i32,
+    pub consecutive_failures: i32,
+    pub status: ScheduleStatus,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScheduledTransfer {
+    pub fn schedule(&self) -> Result<Schedule, String> {
+        Schedule::new(self.cron_expression.as_deref(), self.interval_seconds)
+    }
+
+    /// When the transfer should next run after `after`, or `None` once it has
+    /// passed its end date or made its maximum number of transfers.
+    pub fn next_run_after(&self, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+        if self
+            .max_occurrences
+            .is_some_and(|max_occurrences| self.occurrences >= max_occurrences)
+        {
+            return Ok(None);
+        }
+
+        Ok(self.schedule()?.next_occurrence(self.start_at, self.end_at, after))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    Active,
+    /// Stopped after repeated failures or by the user; resuming picks up at the
+    /// next occurrence.
+    Paused,
+    Completed,
+    Cancelled,
+}
+
+impl std::fmt::Display for ScheduleStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleStatus::Active => write!(f, "active"),
+            ScheduleStatus::Paused => write!(f, "paused"),
+            ScheduleStatus::Completed => write!(f, "completed"),
+            ScheduleStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl From<&str> for ScheduleStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "paused" => ScheduleStatus::Paused,
+            "completed" => ScheduleStatus::Completed,
+            "cancelled" => ScheduleStatus::Cancelled,
+            _ => ScheduleStatus::Active,
+        }
+    }
+}
+
+/// When a scheduled transfer repeats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    Cron(CronSchedule),
+    /// Every interval, counted from the start date.
+    Interval(Duration),
+}
+
+impl Schedule {
+    pub fn new(cron_expression: Option<&str>, interval_seconds: Option<i64>) -> Result<Self, String> {
+        match (cron_expression, interval_seconds) {
+            (Some(expression), None) => Ok(Schedule::Cron(expression.parse()?)),
+            (None, Some(seconds)) if seconds > 0 => Ok(Schedule::Interval(Duration::seconds(seconds))),
+            (None, Some(_)) => Err("Interval must be positive".to_string()),
+            _ => Err("Exactly one of cron_expression or interval_seconds is required".to_string()),
+        }
+    }
+
+    /// The first occurrence strictly after `after` that falls between
+    /// `start_at` (inclusive) and `end_at` (inclusive).
+    pub fn next_occurrence(
+        &self,
+        start_at: DateTime<Utc>,
+        end_at: Option<DateTime<Utc>>,
+        after: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let next = match self {
+            Schedule::Cron(cron) => cron.next_after(after.max(start_at - Duration::nanoseconds(1)))?,
+            Schedule::Interval(interval) => {
+                if after < start_at {
+                    start_at
+                } else {
+                    let seconds = interval.num_seconds();
+                    let elapsed = (after - start_at).num_seconds();
+                    start_at + Duration::seconds(seconds * (elapsed / seconds + 1))
+                }
+            }
+        };
+
+        match end_at {
+            Some(end_at) if next > end_at => None,
+            _ => Some(next),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_request"))]
+pub struct CreateScheduledTransferRequest {
+    pub source_account_id: Uuid,
+    pub destination_account_id: Uuid,
+
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: i64,
+
+    #[validate(custom = "validate_currency_code")]
+    pub currency: String,
+
+    pub description: Option<String>,
+
+    /// Five-field cron expression evaluated in UTC, e.g. `0 9 1 * *`.
+    pub cron_expression: Option<String>,
+
+    #[validate(range(min = 60, message = "Interval must be at least 60 seconds"))]
+    pub interval_seconds: Option<i64>,
+
+    /// Defaults to now.
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 1, message = "Maximum occurrences must be at least 1"))]
+    pub max_occurrences: Option<i32>,
+}
+
+fn validate_create_request(request: &CreateScheduledTransferRequest) -> Result<(), ValidationError> {
+    validate_schedule(request.cron_expression.as_deref(), request.interval_seconds)?;
+
+    if let (Some(start_at), Some(end_at)) = (request.start_at, request.end_at)
+        && end_at <= start_at
+    {
+        return Err(schedule_error("end_at must be after start_at".to_string()));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_request"))]
+pub struct UpdateScheduledTransferRequest {
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: Option<i64>,
+
+    pub description: Option<String>,
+
+    /// Replaces the current schedule; give at most one of the two. A schedule
+    /// is either cron or interval based, so giving one clears the other.
+    pub cron_expression: Option<String>,
+
+    /// Replaces the current schedule and clears any cron expression.
+    #[validate(range(min = 60, message = "Interval must be at least 60 seconds"))]
+    pub interval_seconds: Option<i64>,
+
+    pub end_at: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 1, message = "Maximum occurrences must be at least 1"))]
+    pub max_occurrences: Option<i32>,
+
+    /// `paused` to pause the schedule or `active` to resume it.
+    pub status: Option<ScheduleStatus>,
+}
+
+fn validate_update_request(request: &UpdateScheduledTransferRequest) -> Result<(), ValidationError> {
+    if request.cron_expression.is_some() || request.interval_seconds.is_some() {
+        validate_schedule(request.cron_expression.as_deref(), request.interval_seconds)?;
+    }
+
+    if let Some(status) = &request.status
+        && !matches!(status, ScheduleStatus::Active | ScheduleStatus::Paused)
+    {
+        return Err(schedule_error(
+            "Status can only be changed to active or paused".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_schedule(cron_expression: Option<&str>, interval_seconds: Option<i64>) -> Result<(), ValidationError> {
+    Schedule::new(cron_expression, interval_seconds)
+        .map(|_| ())
+        .map_err(schedule_error)
+}
+
+fn schedule_error(message: String) -> ValidationError {
+    let mut error = ValidationError::new("schedule");
+    error.message = Some(message.into());
+    error
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduledTransferListResponse {
+    pub scheduled_transfers: Vec<ScheduledTransfer>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+impl std::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunStatus::Succeeded => write!(f, "succeeded"),
+            RunStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl From<&str> for RunStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "succeeded" => RunStatus::Succeeded,
+            _ => RunStatus::Failed,
+        }
+    }
+}
+
+/// One attempt to execute a scheduled transfer.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledTransferRun {
+    pub id: Uuid,
+    pub scheduled_transfer_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub status: RunStatus,
+    pub transaction_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduledTransferRunListResponse {
+    pub runs: Vec<ScheduledTransferRun>,
+}
@@ -0,0 +1,420 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::transaction::{Transaction, TransactionStatus, TransactionType};
+use crate::utils::currency;
+
+/// How the records of a settlement file are laid out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum SettlementFileFormat {
+    Csv {
+        #[serde(default = "default_delimiter")]
+        delimiter: char,
+        /// Whether the first record names the columns.
+        #[serde(default = "default_has_header")]
+        has_header: bool,
+    },
+    FixedWidth,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+impl std::fmt::Display for SettlementFileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettlementFileFormat::Csv { .. } => write!(f, "csv"),
+            SettlementFileFormat::FixedWidth => write!(f, "fixed_width"),
+        }
+    }
+}
+
+/// Whether amounts in the file are written in the currency's major unit
+/// (`10.50`) or its minor unit (`1050`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AmountUnit {
+    #[default]
+    Major,
+    Minor,
+}
+
+/// Where a field is found on each line: a CSV column by header name or
+/// zero-based position, or a span of characters in a fixed-width record.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Column {
+    Position(usize),
+    Name(String),
+    Span { start: usize, length: usize },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementColumns {
+    /// Our transaction id, as given to the bank with the payment.
+    pub reference: Column,
+    pub amount: Column,
+    pub currency: Column,
+    /// The day the bank settled the payment.
+    pub date: Column,
+    /// The bank's own reference, kept for the report.
+    #[serde(default)]
+    pub bank_reference: Option<Column>,
+}
+
+/// Describes a bank's settlement file so its lines can be read.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementMapping {
+    #[serde(flatten)]
+    pub format: SettlementFileFormat,
+    pub columns: SettlementColumns,
+    /// Lines to skip at the top of the file, before any header.
+    #[serde(default)]
+    pub skip_lines: usize,
+    /// When set, only lines starting with this are read, which skips the
+    /// header and trailer records of fixed-width files.
+    #[serde(default)]
+    pub record_prefix: Option<String>,
+    /// A chrono format string for the date column.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default)]
+    pub amount_unit: AmountUnit,
+    /// How many days the settlement date may be from the day the transaction
+    /// was made.
+    #[serde(default)]
+    pub date_tolerance_days: i64,
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+/// A column resolved against the file being read.
+enum Field {
+    Index(usize),
+    Span(usize, usize),
+}
+
+impl SettlementMapping {
+    /// Reads every record in a settlement file. Any line that cannot be read
+    /// fails the whole file, so a file is either imported in full or not at all.
+    pub fn parse(&self, content: &str) -> Result<Vec<SettlementRecord>, String> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+            .skip(self.skip_lines)
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let header = match self.format {
+            SettlementFileFormat::Csv { delimiter, has_header: true } => {
+                let (_, line) = lines.next().ok_or_else(|| "File is empty".to_string())?;
+                Some(split_csv_line(line, delimiter))
+            }
+            _ => None,
+        };
+
+        let columns = &self.columns;
+        let reference = self.resolve(&columns.reference, "reference", header.as_deref())?;
+        let amount = self.resolve(&columns.amount, "amount", header.as_deref())?;
+        let currency = self.resolve(&columns.currency, "currency", header.as_deref())?;
+        let date = self.resolve(&columns.date, "date", header.as_deref())?;
+        let bank_reference = columns
+            .bank_reference
+            .as_ref()
+            .map(|column| self.resolve(column, "bank_reference", header.as_deref()))
+            .transpose()?;
+
+        let mut records = Vec::new();
+        for (line_number, line) in lines {
+            if let Some(prefix) = &self.record_prefix
+                && !line.starts_with(prefix.as_str())
+            {
+                continue;
+            }
+
+            let fields = match self.format {
+                SettlementFileFormat::Csv { delimiter, .. } => split_csv_line(line, delimiter),
+                SettlementFileFormat::FixedWidth => Vec::new(),
+            };
+            let value = |field: &Field, name: &str| -> Result<String, String> {
+                let value = match *field {
+                    Field::Index(index) => fields.get(index).cloned(),
+                    Field::Span(start, length) if line.chars().count() > start => {
+                        Some(line.chars().skip(start).take(length).collect())
+                    }
+                    Field::Span(..) => None,
+                };
+                value
+                    .map(|value| value.trim().to_string())
+                    .ok_or_else(|| format!("line {}: missing {}", line_number, name))
+            };
+
+            let record_reference = value(&reference, "reference")?;
+            if record_reference.is_empty() {
+                return Err(format!("line {}: reference is empty", line_number));
+            }
+
+            let record_currency = value(&currency, "currency")?.to_uppercase();
+            if currency::find(&record_currency).is_none() {
+                return Err(format!("line {}: unknown currency '{}'", line_number, record_currency));
+            }
+
+            // Debits are often signed, but the reference already says which
+            // transaction the money belongs to
+            let raw_amount = value(&amount, "amount")?;
+            let unsigned = raw_amount.trim_start_matches(['-', '+']);
+            let record_amount = match self.amount_unit {
+                AmountUnit::Major => currency::parse_amount(unsigned, &record_currency),
+                AmountUnit::Minor => unsigned.parse().ok(),
+            }
+            .filter(|amount| *amount > 0)
+            .ok_or_else(|| format!("line {}: invalid amount '{}'", line_number, raw_amount))?;
+
+            let raw_date = value(&date, "date")?;
+            let value_date = NaiveDate::parse_from_str(&raw_date, &self.date_format)
+                .map_err(|_| format!("line {}: invalid date '{}'", line_number, raw_date))?;
+
+            records.push(SettlementRecord {
+                line_number: line_number as i32,
+                reference: record_reference,
+                bank_reference: bank_reference
+                    .as_ref()
+                    .map(|field| value(field, "bank_reference"))
+                    .transpose()?
+                    .filter(|value| !value.is_empty()),
+                amount: record_amount,
+                currency: record_currency,
+                value_date,
+            });
+        }
+
+        if records.is_empty() {
+            return Err("File contains no settlement lines".to_string());
+        }
+
+        Ok(records)
+    }
+
+    fn resolve(&self, column: &Column, name: &str, header: Option<&[String]>) -> Result<Field, String> {
+        match (&self.format, column) {
+            (SettlementFileFormat::Csv { .. }, Column::Position(index)) => Ok(Field::Index(*index)),
+            (SettlementFileFormat::Csv { .. }, Column::Name(column_name)) => header
+                .ok_or_else(|| format!("The {} column is named but the file has no header", name))?
+                .iter()
+                .position(|heading| heading.trim() == column_name)
+                .map(Field::Index)
+                .ok_or_else(|| format!("Missing {} column '{}'", name, column_name)),
+            (SettlementFileFormat::FixedWidth, Column::Span { start, length }) if *length > 0 => {
+                Ok(Field::Span(*start, *length))
+            }
+            (SettlementFileFormat::Csv { .. }, _) => Err(format!(
+                "The {} column must be a column name or position in a CSV file",
+                name
+            )),
+            (SettlementFileFormat::FixedWidth, _) => Err(format!(
+                "The {} column must be a start and a non-zero length in a fixed-width file",
+                name
+            )),
+        }
+    }
+}
+
+/// Splits a CSV line on `delimiter`, honouring double-quoted fields.
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+/// One line of a settlement file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettlementRecord {
+    pub line_number: i32,
+    pub reference: String,
+    pub bank_reference: Option<String>,
+    /// Always positive, in minor units.
+    pub amount: i64,
+    pub currency: String,
+    pub value_date: NaiveDate,
+}
+
+impl SettlementRecord {
+    /// The transaction the line refers to, if its reference is one of ours.
+    pub fn transaction_id(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.reference).ok()
+    }
+
+    /// Every way the line disagrees with the transaction it refers to. No
+    /// differences means the line settles the transaction.
+    pub fn differences(&self, transaction: &Transaction, date_tolerance_days: i64) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        if !matches!(
+            transaction.transaction_type,
+            TransactionType::Deposit | TransactionType::Withdrawal
+        ) {
+            differences.push(format!(
+                "Only deposits and withdrawals settle with the bank, not {}s",
+                transaction.transaction_type
+            ));
+        }
+        if transaction.status != TransactionStatus::Completed {
+            differences.push(format!("Transaction is {}", transaction.status));
+        }
+        if let Some(settled_at) = transaction.settled_at {
+            differences.push(format!("Transaction was already settled at {}", settled_at.to_rfc3339()));
+        }
+        if self.amount != transaction.amount {
+            differences.push(format!(
+                "Amount {} does not match the transaction amount {}",
+                self.amount, transaction.amount
+            ));
+        }
+        if self.currency != transaction.currency {
+            differences.push(format!(
+                "Currency {} does not match the transaction currency {}",
+                self.currency, transaction.currency
+            ));
+        }
+
+        let transaction_date = transaction.created_at.date_naive();
+        if (self.value_date - transaction_date).num_days().abs() > date_tolerance_days {
+            differences.push(format!(
+                "Settlement date {} is more than {} days from the transaction date {}",
+                self.value_date, date_tolerance_days, transaction_date
+            ));
+        }
+
+        differences
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SettlementMatchStatus {
+    /// Agrees with its transaction, which is now settled.
+    Matched,
+    /// Its reference is not one of our transactions.
+    Unmatched,
+    /// Refers to a transaction but disagrees with it.
+    Mismatched,
+}
+
+impl std::fmt::Display for SettlementMatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettlementMatchStatus::Matched => write!(f, "matched"),
+            SettlementMatchStatus::Unmatched => write!(f, "unmatched"),
+            SettlementMatchStatus::Mismatched => write!(f, "mismatched"),
+        }
+    }
+}
+
+impl From<&str> for SettlementMatchStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "matched" => SettlementMatchStatus::Matched,
+            "mismatched" => SettlementMatchStatus::Mismatched,
+            _ => SettlementMatchStatus::Unmatched,
+        }
+    }
+}
+
+/// A stored settlement line and the outcome of matching it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementLine {
+    pub id: Uuid,
+    pub line_number: i32,
+    pub reference: String,
+    pub bank_reference: Option<String>,
+    pub amount: i64,
+    pub currency: String,
+    pub value_date: NaiveDate,
+    pub status: SettlementMatchStatus,
+    pub transaction_id: Option<Uuid>,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementImport {
+    pub id: Uuid,
+    pub file_name: String,
+    pub format: String,
+    /// The operator who uploaded the file; empty for command-line imports.
+    pub imported_by: Option<Uuid>,
+    pub line_count: i32,
+    pub matched_count: i32,
+    pub unmatched_count: i32,
+    pub mismatched_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettlementReport {
+    #[serde(flatten)]
+    pub import: SettlementImport,
+    pub matched: Vec<SettlementLine>,
+    pub unmatched: Vec<SettlementLine>,
+    pub mismatched: Vec<SettlementLine>,
+}
+
+impl SettlementReport {
+    pub fn new(import: SettlementImport, lines: Vec<SettlementLine>) -> Self {
+        let mut report = SettlementReport {
+            import,
+            matched: Vec::new(),
+            unmatched: Vec::new(),
+            mismatched: Vec::new(),
+        };
+        for line in lines {
+            match line.status {
+                SettlementMatchStatus::Matched => report.matched.push(line),
+                SettlementMatchStatus::Unmatched => report.unmatched.push(line),
+                SettlementMatchStatus::Mismatched => report.mismatched.push(line),
+            }
+        }
+        report
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.unmatched.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ImportSettlementRequest {
+    #[validate(length(min = 1, max = 255, message = "File name must be between 1 and 255 characters"))]
+    pub file_name: String,
+
+    pub mapping: SettlementMapping,
+
+    /// The file's contents as text.
+    #[validate(length(min = 1, message = "Content must not be empty"))]
+    pub content: String,
+}
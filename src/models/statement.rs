@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::ledger::EntryType;
+use crate::models::transaction::TransactionType;
+use crate::utils::currency::format_amount;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Txt,
+}
+
+impl StatementFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StatementFormat::Csv => "text/csv; charset=utf-8",
+            StatementFormat::Jsonl => "application/x-ndjson",
+            StatementFormat::Txt => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StatementFormat::Csv => "csv",
+            StatementFormat::Jsonl => "jsonl",
+            StatementFormat::Txt => "txt",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementParams {
+    /// Start of the period, inclusive. Defaults to the account's creation.
+    pub from: Option<DateTime<Utc>>,
+    /// End of the period, exclusive. Defaults to now.
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub format: StatementFormat,
+}
+
+/// One ledger movement on the account, as listed on a statement.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatementEntry {
+    pub entry_id: Uuid,
+    pub transaction_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub description: Option<String>,
+    pub entry_type: EntryType,
+    /// Signed effect on the balance in minor units: credits are positive.
+    pub amount: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A line of a statement, in the order they are written.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StatementLine {
+    OpeningBalance {
+        account_id: Uuid,
+        currency: String,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        balance: i64,
+    },
+    Entry {
+        #[serde(flatten)]
+        entry: StatementEntry,
+        /// Balance after this entry.
+        balance: i64,
+    },
+    ClosingBalance {
+        to: DateTime<Utc>,
+        total_credits: i64,
+        total_debits: i64,
+        balance: i64,
+    },
+}
+
+const CSV_HEADER: &str = "date,transaction_id,type,description,amount,balance\n";
+
+impl StatementLine {
+    /// Renders the line in `format`, including the header that precedes the
+    /// opening balance in CSV and plain text.
+    pub fn render(&self, format: StatementFormat, currency: &str) -> String {
+        match format {
+            StatementFormat::Csv => self.render_csv(currency),
+            StatementFormat::Jsonl => {
+                let mut line = serde_json::to_string(self).unwrap_or_default();
+                line.push('\n');
+                line
+            }
+            StatementFormat::Txt => self.render_txt(currency),
+        }
+    }
+
+    fn render_csv(&self, currency: &str) -> String {
+        let amount = |amount| amount_string(amount, currency);
+        match self {
+            StatementLine::OpeningBalance { from, balance, .. } => format!(
+                "{}{},,opening_balance,,,{}\n",
+                CSV_HEADER,
+                from.to_rfc3339(),
+                amount(*balance)
+            ),
+            StatementLine::Entry { entry, balance } => format!(
+                "{},{},{},{},{},{}\n",
+                entry.created_at.to_rfc3339(),
+                entry.transaction_id,
+                entry.transaction_type,
+                csv_field(entry.description.as_deref().unwrap_or_default()),
+                amount(entry.amount),
+                amount(*balance)
+            ),
+            StatementLine::ClosingBalance { to, balance, .. } => {
+                format!("{},,closing_balance,,,{}\n", to.to_rfc3339(), amount(*balance))
+            }
+        }
+    }
+
+    fn render_txt(&self, currency: &str) -> String {
+        let amount = |amount| amount_string(amount, currency);
+        match self {
+            StatementLine::OpeningBalance {
+                account_id,
+                currency,
+                from,
+                to,
+                balance,
+            } => format!(
+                "Statement for account {} ({})\nPeriod: {} to {}\n\n{:<20}  {:<10}  {:<30}  {:>14}  {:>14}\n{:<20}  {:<10}  {:<30}  {:>14}  {:>14}\n",
+                account_id,
+                currency,
+                from.to_rfc3339(),
+                to.to_rfc3339(),
+                "Date",
+                "Type",
+                "Description",
+                "Amount",
+                "Balance",
+                "",
+                "",
+                "Opening balance",
+                "",
+                amount(*balance)
+            ),
+            StatementLine::Entry { entry, balance } => format!(
+                "{:<20}  {:<10}  {:<30}  {:>14}  {:>14}\n",
+                entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+                entry.transaction_type.to_string(),
+                truncate(entry.description.as_deref().unwrap_or_default(), 30),
+                amount(entry.amount),
+                amount(*balance)
+            ),
+            StatementLine::ClosingBalance {
+                total_credits,
+                total_debits,
+                balance,
+                ..
+            } => format!(
+                "{:<20}  {:<10}  {:<30}  {:>14}  {:>14}\n\nTotal credits: {}\nTotal debits: {}\n",
+                "",
+                "",
+                "Closing balance",
+                "",
+                amount(*balance),
+                amount(*total_credits),
+                amount(*total_debits)
+            ),
+        }
+    }
+}
+
+/// The amount in the currency's major unit, or minor units for a currency the
+/// registry does not know.
+fn amount_string(amount: i64, currency: &str) -> String {
+    format_amount(amount, currency).unwrap_or_else(|| amount.to_string())
+}
+
+/// Quotes a CSV field if it needs it. Fields starting with a formula
+/// character are prefixed so spreadsheets show them as text.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Fits a free-text field into a fixed-width column on one line.
+fn truncate(value: &str, width: usize) -> String {
+    let value = value.replace(['\n', '\r', '\t'], " ");
+    if value.chars().count() <= width {
+        return value;
+    }
+
+    let mut truncated: String = value.chars().take(width - 1).collect();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::outbox::{AggregateType, OutboxEvent};
+
+/// Messages a client sends over the event stream.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ClientMessage {
+    /// Adds accounts to the subscription.
+    Subscribe { account_ids: Vec<Uuid> },
+    /// Removes accounts from the subscription; removing them all goes back to
+    /// every account the user owns.
+    Unsubscribe { account_ids: Vec<Uuid> },
+}
+
+/// Messages the server sends over the event stream.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    /// An event the client can see. Its `position` is where to resume from.
+    Event(OutboxEvent),
+    /// The accounts now subscribed to, empty meaning all of them.
+    Subscribed { account_ids: Vec<Uuid> },
+    Error { message: String },
+}
+
+/// Which events one stream connection receives: everything concerning the
+/// user, narrowed to the given accounts when there are any.
+#[derive(Debug, Clone)]
+pub struct StreamSubscription {
+    pub user_id: Uuid,
+    pub account_ids: HashSet<Uuid>,
+}
+
+impl StreamSubscription {
+    pub fn new(user_id: Uuid, account_ids: impl IntoIterator<Item = Uuid>) -> Self {
+        Self {
+            user_id,
+            account_ids: account_ids.into_iter().collect(),
+        }
+    }
+
+    pub fn matches(&self, event: &OutboxEvent) -> bool {
+        if !event.user_ids.contains(&self.user_id) {
+            return false;
+        }
+        if self.account_ids.is_empty() {
+            return true;
+        }
+
+        match event.aggregate_type {
+            AggregateType::Account => self.account_ids.contains(&event.aggregate_id),
+            AggregateType::Transaction => ["source_account_id", "destination_account_id"]
+                .iter()
+                .filter_map(|field| event.payload["transaction"][field].as_str())
+                .filter_map(|id| Uuid::parse_str(id).ok())
+                .any(|id| self.account_ids.contains(&id)),
+            AggregateType::PaymentIntent => ["source_account_id", "destination_account_id"]
+                .iter()
+                .filter_map(|field| event.payload["payment_intent"][field].as_str())
+                .filter_map(|id| Uuid::parse_str(id).ok())
+                .any(|id| self.account_ids.contains(&id)),
+        }
+    }
+
+    /// Subscribed accounts in a stable order, for reporting back to the client.
+    pub fn sorted_account_ids(&self) -> Vec<Uuid> {
+        let mut account_ids: Vec<Uuid> = self.account_ids.iter().copied().collect();
+        account_ids.sort();
+        account_ids
+    }
+}
+
+/// Query parameters accepted when opening the stream.
+#[derive(Debug, Deserialize)]
+pub struct StreamParams {
+    /// Replays events after this event position before going live, for
+    /// resuming after a reconnect.
+    pub after: Option<i64>,
+    /// Comma-separated account ids to subscribe to from the start.
+    pub account_ids: Option<String>,
+}
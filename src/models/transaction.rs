@@ -1,8 +1,13 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::models::transaction_state::StateError;
+use crate::utils::currency::{format_amount, validate_currency_code};
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Transaction {
     pub id: Uuid,
@@ -13,6 +18,17 @@ pub struct Transaction {
     pub status: TransactionStatus,
     pub transaction_type: TransactionType,
     pub description: Option<String>,
+    pub parent_transaction_id: Option<Uuid>,
+    pub refunded_amount: i64,
+    /// Amount credited to the destination of a cross-currency transfer.
+    pub destination_amount: Option<i64>,
+    pub destination_currency: Option<String>,
+    pub fx_rate: Option<Decimal>,
+    pub fx_spread_bps: Option<i32>,
+    pub fx_spread_amount: Option<i64>,
+    pub fx_quote_id: Option<Uuid>,
+    /// When the bank confirmed a deposit or withdrawal in a settlement file.
+    pub settled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -24,6 +40,10 @@ pub enum TransactionStatus {
     Completed,
     Failed,
     Cancelled,
+    Reversed,
+    /// Held by the risk engine until an operator approves or rejects it.
+    #[serde(rename = "under_review")]
+    UnderReview,
 }
 
 impl std::fmt::Display for TransactionStatus {
@@ -33,18 +53,24 @@ impl std::fmt::Display for TransactionStatus {
             TransactionStatus::Completed => write!(f, "completed"),
             TransactionStatus::Failed => write!(f, "failed"),
             TransactionStatus::Cancelled => write!(f, "cancelled"),
+            TransactionStatus::Reversed => write!(f, "reversed"),
+            TransactionStatus::UnderReview => write!(f, "under_review"),
         }
     }
 }
 
-impl From<&str> for TransactionStatus {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for TransactionStatus {
+    type Error = StateError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s.to_lowercase().as_str() {
-            "pending" => TransactionStatus::Pending,
-            "completed" => TransactionStatus::Completed,
-            "failed" => TransactionStatus::Failed,
-            "cancelled" => TransactionStatus::Cancelled,
-            _ => TransactionStatus::Pending,
+            "pending" => Ok(TransactionStatus::Pending),
+            "completed" => Ok(TransactionStatus::Completed),
+            "failed" => Ok(TransactionStatus::Failed),
+            "cancelled" => Ok(TransactionStatus::Cancelled),
+            "reversed" => Ok(TransactionStatus::Reversed),
+            "under_review" => Ok(TransactionStatus::UnderReview),
+            _ => Err(StateError::UnknownStatus(s.to_string())),
         }
     }
 }
@@ -55,6 +81,7 @@ pub enum TransactionType {
     Deposit,
     Withdrawal,
     Transfer,
+    Refund,
 }
 
 impl std::fmt::Display for TransactionType {
@@ -63,6 +90,7 @@ impl std::fmt::Display for TransactionType {
             TransactionType::Deposit => write!(f, "deposit"),
             TransactionType::Withdrawal => write!(f, "withdrawal"),
             TransactionType::Transfer => write!(f, "transfer"),
+            TransactionType::Refund => write!(f, "refund"),
         }
     }
 }
@@ -73,6 +101,7 @@ impl From<&str> for TransactionType {
             "deposit" => TransactionType::Deposit,
             "withdrawal" => TransactionType::Withdrawal,
             "transfer" => TransactionType::Transfer,
+            "refund" => TransactionType::Refund,
             _ => TransactionType::Transfer,
         }
     }
@@ -86,11 +115,21 @@ pub struct CreateTransactionRequest {
     #[validate(range(min = 1, message = "Amount must be greater than zero"))]
     pub amount: i64,
     
-    #[validate(length(equal = 3, message = "Currency code must be 3 characters"))]
+    #[validate(custom = "validate_currency_code")]
     pub currency: String,
     
     pub transaction_type: String,
     pub description: Option<String>,
+
+    /// Only place a hold on the source account; the transaction stays pending
+    /// until it is captured or voided.
+    #[serde(default)]
+    pub authorize_only: bool,
+
+    /// Quote to execute a cross-currency transfer at; without one the rate
+    /// in force when the transfer is made is used.
+    #[serde(default)]
+    pub fx_quote_id: Option<Uuid>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -103,24 +142,184 @@ pub struct TransactionResponse {
     pub status: String,
     pub transaction_type: String,
     pub description: Option<String>,
+    pub parent_transaction_id: Option<Uuid>,
+    pub refunded_amount: i64,
+    /// Amount credited to the destination of a cross-currency transfer.
+    pub destination_amount: Option<i64>,
+    pub destination_currency: Option<String>,
+    pub fx_rate: Option<Decimal>,
+    pub fx_spread_bps: Option<i32>,
+    pub fx_spread_amount: Option<i64>,
+    pub fx_quote_id: Option<Uuid>,
+    pub settled_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_destination_amount: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl TransactionResponse {
+    /// Adds the amounts rendered as decimals in their currency's major unit.
+    pub fn with_formatted_amounts(mut self) -> Self {
+        self.formatted_amount = format_amount(self.amount, &self.currency);
+        self.formatted_destination_amount = self
+            .destination_amount
+            .zip(self.destination_currency.as_deref())
+            .and_then(|(amount, currency)| format_amount(amount, currency));
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionListResponse {
     pub transactions: Vec<TransactionResponse>,
-    pub total: usize,
-    pub page: usize,
+    /// Pass as `cursor` to get the next page; absent on the last page.
+    pub next_cursor: Option<String>,
     pub page_size: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// The `(created_at, id)` of the last transaction on a page, which the next
+/// page starts after. Clients only ever see it encoded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransactionCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl TransactionCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}.{}", self.created_at.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(token: &str) -> Result<Self, String> {
+        let invalid = || "Invalid cursor".to_string();
+        let decoded = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (micros, id) = decoded.split_once('.').ok_or_else(invalid)?;
+
+        Ok(TransactionCursor {
+            created_at: micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(invalid)?,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Narrows a transaction listing; every filter given must match.
+#[derive(Debug, Default, Clone)]
+pub struct TransactionFilter {
+    /// Transactions into or out of this account.
+    pub account_id: Option<Uuid>,
+    pub status: Option<TransactionStatus>,
+    pub transaction_type: Option<TransactionType>,
+    pub currency: Option<String>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the description.
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateRefundRequest {
+    /// Amount to refund; defaults to everything not yet refunded.
+    #[validate(range(min = 1, message = "Amount must be greater than zero"))]
+    pub amount: Option<i64>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundListResponse {
+    pub refunds: Vec<TransactionResponse>,
+    pub refunded_amount: i64,
+    pub refundable_amount: i64,
+}
+
+/// Why an operator is unwinding a completed transaction.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReversalKind {
+    Reversal,
+    Chargeback,
+}
+
+impl std::fmt::Display for ReversalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReversalKind::Reversal => write!(f, "reversal"),
+            ReversalKind::Chargeback => write!(f, "chargeback"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReverseTransactionRequest {
+    #[serde(default = "default_reversal_kind")]
+    pub kind: ReversalKind,
+
+    #[validate(length(min = 1, max = 50, message = "Reason code must be between 1 and 50 characters"))]
+    pub reason_code: String,
+
+    pub note: Option<String>,
+}
+
+fn default_reversal_kind() -> ReversalKind {
+    ReversalKind::Reversal
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionEvent {
     pub id: Uuid,
+    /// Orders the events of one transaction; used as the SSE event id.
+    pub sequence_number: i64,
     pub transaction_id: Uuid,
     pub previous_status: Option<String>,
     pub new_status: String,
     pub event_data: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
-} 
\ No newline at end of file
+}
+
+/// A transaction's status timeline, oldest event first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionHistoryResponse {
+    pub transaction_id: Uuid,
+    pub events: Vec<TransactionEvent>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// An event in an account's activity feed, with enough of its transaction to
+/// describe it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountActivityItem {
+    #[serde(flatten)]
+    pub event: TransactionEvent,
+    pub transaction_type: TransactionType,
+    pub amount: i64,
+    pub currency: String,
+}
+
+/// Events across all of an account's transactions, newest first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountActivityResponse {
+    pub account_id: Uuid,
+    pub activity: Vec<AccountActivityItem>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
\ No newline at end of file
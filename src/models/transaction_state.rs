@@ -0,0 +1,47 @@
+use crate::models::transaction::TransactionStatus;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum StateError {
+    #[error("Unknown transaction status: {0}")]
+    UnknownStatus(String),
+
+    #[error("Transaction cannot move from {from} to {to}")]
+    InvalidTransition {
+        from: TransactionStatus,
+        to: TransactionStatus,
+    },
+}
+
+/// Statuses a transaction may move to from `from`. Statuses without any
+/// outgoing transitions are terminal.
+pub fn allowed_transitions(from: &TransactionStatus) -> &'static [TransactionStatus] {
+    match from {
+        TransactionStatus::Pending => &[
+            TransactionStatus::Completed,
+            TransactionStatus::Failed,
+            TransactionStatus::Cancelled,
+            TransactionStatus::UnderReview,
+        ],
+        // Approved authorizations go back to pending until they are captured
+        TransactionStatus::UnderReview => &[
+            TransactionStatus::Pending,
+            TransactionStatus::Completed,
+            TransactionStatus::Failed,
+            TransactionStatus::Cancelled,
+        ],
+        TransactionStatus::Completed => &[TransactionStatus::Reversed],
+        TransactionStatus::Failed | TransactionStatus::Cancelled | TransactionStatus::Reversed => &[],
+    }
+}
+
+pub fn validate_transition(from: &TransactionStatus, to: &TransactionStatus) -> Result<(), StateError> {
+    if allowed_transitions(from).contains(to) {
+        Ok(())
+    } else {
+        Err(StateError::InvalidTransition {
+            from: from.clone(),
+            to: to.clone(),
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
@@ -11,10 +11,37 @@ pub struct User {
     #[serde(skip_serializing)]
     pub password_hash: String,
     pub full_name: Option<String>,
+    pub date_of_birth: Option<NaiveDate>,
+    pub role: UserRole,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    User,
+    Admin,
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserRole::User => write!(f, "user"),
+            UserRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl From<&str> for UserRole {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "admin" => UserRole::Admin,
+            _ => UserRole::User,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateUserRequest {
     #[validate(email(message = "Invalid email format"))]
@@ -27,6 +54,8 @@ pub struct CreateUserRequest {
     pub password: String,
     
     pub full_name: Option<String>,
+
+    pub date_of_birth: Option<NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -47,6 +76,7 @@ pub struct UserResponse {
     pub email: String,
     pub username: String,
     pub full_name: Option<String>,
+    pub date_of_birth: Option<NaiveDate>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -0,0 +1,419 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::risk::RiskAssessment;
+use crate::models::transaction::TransactionResponse;
+
+/// A person on the watchlist.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchlistEntry {
+    /// Identifies the entry across reloads. Defaults to the normalized name.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub date_of_birth: Option<NaiveDate>,
+    /// The list the entry comes from, such as a sanctions programme.
+    #[serde(default)]
+    pub list: Option<String>,
+}
+
+/// A watchlist entry a name matched.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchlistMatch {
+    pub entry_id: String,
+    pub entry_name: String,
+    /// The entry's name or alias that matched.
+    pub matched_name: String,
+    /// Similarity between 0 and 1.
+    pub score: f64,
+    pub date_of_birth: Option<NaiveDate>,
+    pub list: Option<String>,
+}
+
+/// The loaded watchlist, with every name normalized for matching.
+#[derive(Debug, Clone, Default)]
+pub struct Watchlist {
+    entries: Vec<(WatchlistEntry, Vec<(String, String)>)>,
+    pub source: Option<String>,
+    pub loaded_at: Option<DateTime<Utc>>,
+}
+
+impl Watchlist {
+    pub fn new(entries: Vec<WatchlistEntry>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|mut entry| {
+                if entry.id.is_empty() {
+                    entry.id = normalize_name(&entry.name);
+                }
+                let names = std::iter::once(&entry.name)
+                    .chain(&entry.aliases)
+                    .map(|name| (name.clone(), normalize_name(name)))
+                    .filter(|(_, normalized)| !normalized.is_empty())
+                    .collect();
+                (entry, names)
+            })
+            .collect();
+
+        Watchlist {
+            entries,
+            source: None,
+            loaded_at: None,
+        }
+    }
+
+    /// Loads a watchlist from a `.json` file holding an array of entries, or a
+    /// CSV file with `id`, `name`, `aliases`, `date_of_birth` and `list`
+    /// columns, where aliases are separated by `;`. Only `name` is required.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let entries = if path.to_lowercase().ends_with(".json") {
+            serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?
+        } else {
+            parse_watchlist_csv(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?
+        };
+
+        let mut watchlist = Watchlist::new(entries);
+        watchlist.source = Some(path.to_string());
+        watchlist.loaded_at = Some(Utc::now());
+
+        Ok(watchlist)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries with a name or alias at least `threshold` similar to `name`,
+    /// best first. An entry whose date of birth is known and differs from
+    /// `date_of_birth` never matches.
+    pub fn screen(&self, name: &str, date_of_birth: Option<NaiveDate>, threshold: f64) -> Vec<WatchlistMatch> {
+        let normalized = normalize_name(name);
+        if normalized.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<WatchlistMatch> = self
+            .entries
+            .iter()
+            .filter(|(entry, _)| match (entry.date_of_birth, date_of_birth) {
+                (Some(listed), Some(screened)) => listed == screened,
+                _ => true,
+            })
+            .filter_map(|(entry, names)| {
+                names
+                    .iter()
+                    .map(|(original, candidate)| (original, jaro_winkler(&normalized, candidate)))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .filter(|(_, score)| *score >= threshold)
+                    .map(|(matched_name, score)| WatchlistMatch {
+                        entry_id: entry.id.clone(),
+                        entry_name: entry.name.clone(),
+                        matched_name: matched_name.clone(),
+                        score: (score * 1000.0).round() / 1000.0,
+                        date_of_birth: entry.date_of_birth,
+                        list: entry.list.clone(),
+                    })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        matches
+    }
+}
+
+/// Folds a name to lowercase ASCII-ish words in sorted order, so accents,
+/// punctuation and word order do not affect matching.
+pub fn normalize_name(name: &str) -> String {
+    let folded: String = name
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+    words.sort_unstable();
+
+    words.join(" ")
+}
+
+/// Jaro-Winkler similarity between two strings, from 0 for nothing in common
+/// to 1 for identical.
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+    for (i, ca) in a.iter().enumerate() {
+        let end = (i + window + 1).min(b.len());
+        for j in i.saturating_sub(window)..end {
+            if !b_matched[j] && b[j] == *ca {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_order = a.iter().zip(&a_matched).filter(|(_, matched)| **matched);
+    let b_order = b.iter().zip(&b_matched).filter(|(_, matched)| **matched);
+    let transpositions = a_order.zip(b_order).filter(|((x, _), (y, _))| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Splits a CSV line on commas outside double quotes, where `""` inside quotes
+/// is a literal quote.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+
+    fields
+}
+
+pub fn parse_watchlist_csv(input: &str) -> Result<Vec<WatchlistEntry>, String> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (_, header) = lines.next().ok_or_else(|| "CSV is empty".to_string())?;
+    let columns = split_csv_line(header);
+    let position = |name: &str| columns.iter().position(|column| column == name);
+
+    let name_index = position("name").ok_or("Missing name column")?;
+    let id_index = position("id");
+    let aliases_index = position("aliases");
+    let date_of_birth_index = position("date_of_birth");
+    let list_index = position("list");
+
+    let mut entries = Vec::new();
+    for (line_number, line) in lines {
+        let fields = split_csv_line(line);
+        if fields.len() != columns.len() {
+            return Err(format!(
+                "line {}: expected {} fields, found {}",
+                line_number,
+                columns.len(),
+                fields.len()
+            ));
+        }
+
+        let optional = |index: Option<usize>| index.map(|i| fields[i].as_str()).filter(|value| !value.is_empty());
+        let name = fields[name_index].clone();
+        if name.is_empty() {
+            return Err(format!("line {}: name is required", line_number));
+        }
+
+        entries.push(WatchlistEntry {
+            id: optional(id_index).unwrap_or_default().to_string(),
+            name,
+            aliases: optional(aliases_index)
+                .map(|aliases| {
+                    aliases
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|alias| !alias.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            date_of_birth: optional(date_of_birth_index)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid date of birth '{}'", line_number, value))
+                })
+                .transpose()?,
+            list: optional(list_index).map(str::to_string),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// What was being screened when a case was opened.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningSubject {
+    Registration,
+    /// A user changing the name they are screened under.
+    Profile,
+    Transfer,
+}
+
+impl std::fmt::Display for ScreeningSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreeningSubject::Registration => write!(f, "registration"),
+            ScreeningSubject::Profile => write!(f, "profile"),
+            ScreeningSubject::Transfer => write!(f, "transfer"),
+        }
+    }
+}
+
+impl From<&str> for ScreeningSubject {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "profile" => ScreeningSubject::Profile,
+            "transfer" => ScreeningSubject::Transfer,
+            _ => ScreeningSubject::Registration,
+        }
+    }
+}
+
+/// What a hit did: registrations are blocked, transfers held.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningAction {
+    Blocked,
+    Held,
+}
+
+impl std::fmt::Display for ScreeningAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreeningAction::Blocked => write!(f, "blocked"),
+            ScreeningAction::Held => write!(f, "held"),
+        }
+    }
+}
+
+impl From<&str> for ScreeningAction {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "held" => ScreeningAction::Held,
+            _ => ScreeningAction::Blocked,
+        }
+    }
+}
+
+/// A case is cleared when the hit was a false positive and confirmed when the
+/// person is the one listed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningCaseStatus {
+    Open,
+    Cleared,
+    Confirmed,
+}
+
+impl std::fmt::Display for ScreeningCaseStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreeningCaseStatus::Open => write!(f, "open"),
+            ScreeningCaseStatus::Cleared => write!(f, "cleared"),
+            ScreeningCaseStatus::Confirmed => write!(f, "confirmed"),
+        }
+    }
+}
+
+impl From<&str> for ScreeningCaseStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "cleared" => ScreeningCaseStatus::Cleared,
+            "confirmed" => ScreeningCaseStatus::Confirmed,
+            _ => ScreeningCaseStatus::Open,
+        }
+    }
+}
+
+/// A watchlist hit waiting for, or given, a compliance decision.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScreeningCase {
+    pub id: Uuid,
+    pub subject: ScreeningSubject,
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub screened_name: String,
+    pub date_of_birth: Option<NaiveDate>,
+    pub watchlist_match: WatchlistMatch,
+    pub action: ScreeningAction,
+    pub transaction_id: Option<Uuid>,
+    pub authorize_only: bool,
+    pub risk_assessment: Option<RiskAssessment>,
+    pub status: ScreeningCaseStatus,
+    pub decided_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The person a case is opened about.
+#[derive(Debug, Clone)]
+pub struct ScreenedPerson {
+    pub user_id: Option<Uuid>,
+    pub email: String,
+    pub name: String,
+    pub date_of_birth: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DecideScreeningCaseRequest {
+    #[validate(length(max = 500, message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningCaseFilter {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScreeningCaseResponse {
+    #[serde(flatten)]
+    pub case: ScreeningCase,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScreeningCaseListResponse {
+    pub cases: Vec<ScreeningCaseResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchlistStatusResponse {
+    pub source: Option<String>,
+    pub entries: usize,
+    pub loaded_at: Option<DateTime<Utc>>,
+}
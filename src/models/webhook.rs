@@ -0,0 +1,340 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use url::{Host, Url};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Longest wait between two delivery attempts, however many have failed.
+const MAX_RETRY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Events an endpoint can subscribe to, named as in the outbox.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum WebhookEventType {
+    #[serde(rename = "transaction.completed")]
+    TransactionCompleted,
+    #[serde(rename = "transaction.failed")]
+    TransactionFailed,
+    #[serde(rename = "transaction.cancelled")]
+    TransactionCancelled,
+    #[serde(rename = "transaction.reversed")]
+    TransactionReversed,
+    #[serde(rename = "transaction.under_review")]
+    TransactionUnderReview,
+    #[serde(rename = "account.suspended")]
+    AccountSuspended,
+    #[serde(rename = "account.unsuspended")]
+    AccountUnsuspended,
+    #[serde(rename = "account.closed")]
+    AccountClosed,
+    #[serde(rename = "payment_intent.succeeded")]
+    PaymentIntentSucceeded,
+    #[serde(rename = "payment_intent.payment_failed")]
+    PaymentIntentPaymentFailed,
+    #[serde(rename = "payment_intent.canceled")]
+    PaymentIntentCanceled,
+}
+
+impl std::fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookEventType::TransactionCompleted => write!(f, "transaction.completed"),
+            WebhookEventType::TransactionFailed => write!(f, "transaction.failed"),
+            WebhookEventType::TransactionCancelled => write!(f, "transaction.cancelled"),
+            WebhookEventType::TransactionReversed => write!(f, "transaction.reversed"),
+            WebhookEventType::TransactionUnderReview => write!(f, "transaction.under_review"),
+            WebhookEventType::AccountSuspended => write!(f, "account.suspended"),
+            WebhookEventType::AccountUnsuspended => write!(f, "account.unsuspended"),
+            WebhookEventType::AccountClosed => write!(f, "account.closed"),
+            WebhookEventType::PaymentIntentSucceeded => write!(f, "payment_intent.succeeded"),
+            WebhookEventType::PaymentIntentPaymentFailed => write!(f, "payment_intent.payment_failed"),
+            WebhookEventType::PaymentIntentCanceled => write!(f, "payment_intent.canceled"),
+        }
+    }
+}
+
+impl TryFrom<&str> for WebhookEventType {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "transaction.completed" => Ok(WebhookEventType::TransactionCompleted),
+            "transaction.failed" => Ok(WebhookEventType::TransactionFailed),
+            "transaction.cancelled" => Ok(WebhookEventType::TransactionCancelled),
+            "transaction.reversed" => Ok(WebhookEventType::TransactionReversed),
+            "transaction.under_review" => Ok(WebhookEventType::TransactionUnderReview),
+            "account.suspended" => Ok(WebhookEventType::AccountSuspended),
+            "account.unsuspended" => Ok(WebhookEventType::AccountUnsuspended),
+            "account.closed" => Ok(WebhookEventType::AccountClosed),
+            "payment_intent.succeeded" => Ok(WebhookEventType::PaymentIntentSucceeded),
+            "payment_intent.payment_failed" => Ok(WebhookEventType::PaymentIntentPaymentFailed),
+            "payment_intent.canceled" => Ok(WebhookEventType::PaymentIntentCanceled),
+            _ => Err(format!("Unknown webhook event type '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    /// Only revealed once, when the endpoint is created.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Returned when an endpoint is created, the only time its signing secret is
+/// shown.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateWebhookEndpointRequest {
+    #[validate(custom = "validate_webhook_url")]
+    pub url: String,
+
+    pub description: Option<String>,
+
+    #[validate(custom = "validate_event_types")]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateWebhookEndpointRequest {
+    #[validate(custom = "validate_webhook_url")]
+    pub url: Option<String>,
+
+    pub description: Option<String>,
+
+    #[validate(custom = "validate_event_types")]
+    pub event_types: Option<Vec<String>>,
+
+    /// Inactive endpoints keep their history but receive no new events.
+    pub active: Option<bool>,
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), ValidationError> {
+    if (url.starts_with("https://") || url.starts_with("http://")) && validator::validate_url(url) {
+        return Ok(());
+    }
+
+    let mut error = ValidationError::new("url");
+    error.message = Some("URL must be an absolute http or https URL".into());
+    Err(error)
+}
+
+/// Checks that an endpoint URL uses https and does not name a loopback,
+/// private or link-local host, unless the host is in `allowed_hosts`. Names
+/// are checked again when deliveries resolve them.
+pub fn check_endpoint_url(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|_| "URL must be an absolute http or https URL".to_string())?;
+    let host = url
+        .host()
+        .ok_or_else(|| "URL must be an absolute http or https URL".to_string())?;
+
+    if is_allowed_host(&host.to_string(), allowed_hosts) {
+        return Ok(());
+    }
+    if url.scheme() != "https" {
+        return Err("Webhook URLs must use https".to_string());
+    }
+
+    let public = match &host {
+        Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.');
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Host::Ipv4(ip) => is_public_address(IpAddr::V4(*ip)),
+        Host::Ipv6(ip) => is_public_address(IpAddr::V6(*ip)),
+    };
+    if !public {
+        return Err(format!(
+            "Webhook URLs cannot point at loopback, private or link-local hosts such as {}",
+            host
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether `host` is exempt from the endpoint URL rules, ignoring case and the
+/// brackets around IPv6 addresses.
+pub fn is_allowed_host(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// False for addresses that reach this network rather than the internet:
+/// loopback, private, link-local, shared, unspecified, broadcast, multicast
+/// and documentation ranges.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let shared = first == 100 && (64..128).contains(&second);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || shared
+                || first == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_address(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+fn validate_event_types(event_types: &[String]) -> Result<(), ValidationError> {
+    let message = if event_types.is_empty() {
+        "At least one event type is required".to_string()
+    } else if let Some(Err(message)) = event_types
+        .iter()
+        .map(|event_type| WebhookEventType::try_from(event_type.as_str()))
+        .find(Result::is_err)
+    {
+        message
+    } else {
+        return Ok(());
+    };
+
+    let mut error = ValidationError::new("event_types");
+    error.message = Some(message.into());
+    Err(error)
+}
+
+/// Parses event types that have already passed request validation.
+pub fn parse_event_types(event_types: &[String]) -> Vec<WebhookEventType> {
+    event_types
+        .iter()
+        .filter_map(|event_type| WebhookEventType::try_from(event_type.as_str()).ok())
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookEndpointListResponse {
+    pub endpoints: Vec<WebhookEndpoint>,
+}
+
+/// The JSON body posted to an endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first attempt or for a retry.
+    Pending,
+    Succeeded,
+    /// Gave up after the maximum number of attempts.
+    Failed,
+}
+
+impl std::fmt::Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookDeliveryStatus::Pending => write!(f, "pending"),
+            WebhookDeliveryStatus::Succeeded => write!(f, "succeeded"),
+            WebhookDeliveryStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl From<&str> for WebhookDeliveryStatus {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "succeeded" => WebhookDeliveryStatus::Succeeded,
+            "failed" => WebhookDeliveryStatus::Failed,
+            _ => WebhookDeliveryStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One HTTP request made for a delivery.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDeliveryAttempt {
+    pub id: Uuid,
+    pub delivery_id: Uuid,
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveryListResponse {
+    pub deliveries: Vec<WebhookDelivery>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveryDetailResponse {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempt_log: Vec<WebhookDeliveryAttempt>,
+}
+
+/// How failed deliveries are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub base_delay_seconds: i64,
+}
+
+impl RetryPolicy {
+    /// The wait before the next attempt once `attempts` have failed, doubling
+    /// each time up to a day.
+    pub fn delay_after(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 30) as u32;
+        let seconds = self
+            .base_delay_seconds
+            .saturating_mul(1i64 << exponent)
+            .min(MAX_RETRY_DELAY_SECONDS);
+        Duration::seconds(seconds)
+    }
+}
@@ -6,13 +6,15 @@ use uuid::Uuid;
 
 #[allow(dead_code)]
 pub async fn create_account(
-    client: &Client,
+    client: &mut Client,
     user_id: Uuid,
     data: &CreateAccountRequest,
 ) -> Result<Account, AppError> {
     // Normalize currency code to uppercase
     let normalized_request = CreateAccountRequest {
         currency: data.currency.to_uppercase(),
+        label: data.label.clone(),
+        purpose: data.purpose,
     };
     
     // Create the account in the database
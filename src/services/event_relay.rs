@@ -0,0 +1,178 @@
+use crate::db::{outbox, webhooks, Database};
+use crate::models::outbox::{AggregateType, EventSinkKind, OutboxEvent};
+use crate::models::webhook::{WebhookEvent, WebhookEventType};
+use crate::utils::error::AppError;
+use async_trait::async_trait;
+use deadpool_postgres::Client;
+use std::collections::HashSet;
+use std::io::Write;
+use tokio::sync::broadcast;
+
+/// Events buffered for in-process subscribers; one that falls further behind
+/// than this misses events.
+pub const BROADCAST_CAPACITY: usize = 1024;
+
+/// A destination for outbox events. Delivery is at least once, so a sink may
+/// see the same event again after a failure or restart and should tolerate it.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError>;
+}
+
+/// Publishes to an in-process broadcast channel.
+pub struct BroadcastSink {
+    sender: broadcast::Sender<OutboxEvent>,
+}
+
+impl BroadcastSink {
+    pub fn new(sender: broadcast::Sender<OutboxEvent>) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl EventSink for BroadcastSink {
+    fn name(&self) -> &'static str {
+        "broadcast"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError> {
+        // Sending only fails when nobody is subscribed, which is fine
+        let _ = self.sender.send(event.clone());
+        Ok(())
+    }
+}
+
+/// Queues webhook deliveries for events that endpoints can subscribe to.
+pub struct WebhookSink {
+    db: Database,
+}
+
+impl WebhookSink {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError> {
+        let Ok(event_type) = WebhookEventType::try_from(event.event_type.as_str()) else {
+            return Ok(());
+        };
+
+        // Endpoints receive the object the event is about
+        let data = match event.aggregate_type {
+            AggregateType::Transaction => &event.payload["transaction"],
+            AggregateType::Account => &event.payload["account"],
+            AggregateType::PaymentIntent => &event.payload["payment_intent"],
+        };
+        let webhook_event = WebhookEvent {
+            id: event.event_id,
+            event_type,
+            created_at: event.created_at,
+            data: data.clone(),
+        };
+
+        let client = self.db.pool.get().await?;
+        webhooks::enqueue_event(&client, &webhook_event, &event.user_ids).await
+    }
+}
+
+/// Writes each event to standard output as one line of JSON.
+pub struct StdoutSink;
+
+#[async_trait]
+impl EventSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError> {
+        let line = serde_json::to_string(event)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize event: {}", e)))?;
+
+        writeln!(std::io::stdout().lock(), "{}", line)
+            .map_err(|e| AppError::Internal(format!("Failed to write event to stdout: {}", e)))
+    }
+}
+
+pub fn build_sinks(
+    kinds: &[EventSinkKind],
+    db: &Database,
+    broadcast: &broadcast::Sender<OutboxEvent>,
+) -> Vec<Box<dyn EventSink>> {
+    kinds
+        .iter()
+        .map(|kind| -> Box<dyn EventSink> {
+            match kind {
+                EventSinkKind::Broadcast => Box::new(BroadcastSink::new(broadcast.clone())),
+                EventSinkKind::Webhooks => Box::new(WebhookSink::new(db.clone())),
+                EventSinkKind::Stdout => Box::new(StdoutSink),
+            }
+        })
+        .collect()
+}
+
+/// Publishes the oldest unpublished events to every sink and marks them
+/// published, returning how many were. Newly committed events are first given
+/// their positions in a transaction of their own, so a position a client has
+/// seen is never rolled back. An event is only marked once every sink has
+/// taken it. When one fails, later events for the same aggregate are held back
+/// until it succeeds, so each aggregate's events are published in order.
+/// Returns 0 without doing anything if another relay is running.
+pub async fn relay_batch(client: &mut Client, sinks: &[Box<dyn EventSink>], limit: i64) -> Result<usize, AppError> {
+    let tx = client.transaction().await?;
+    if !outbox::try_lock_relay(&tx).await? {
+        return Ok(0);
+    }
+    outbox::assign_positions(&tx, limit).await?;
+    tx.commit().await?;
+
+    let tx = client.transaction().await?;
+    if !outbox::try_lock_relay(&tx).await? {
+        return Ok(0);
+    }
+
+    let events = outbox::get_unpublished(&tx, limit).await?;
+    let mut blocked = HashSet::new();
+    let mut published = Vec::with_capacity(events.len());
+
+    for event in &events {
+        let aggregate = (event.aggregate_type, event.aggregate_id);
+        if blocked.contains(&aggregate) {
+            continue;
+        }
+
+        let mut failed = false;
+        for sink in sinks {
+            if let Err(e) = sink.publish(event).await {
+                tracing::warn!(
+                    "Failed to publish event {} to the {} sink: {}",
+                    event.id,
+                    sink.name(),
+                    e
+                );
+                failed = true;
+                break;
+            }
+        }
+
+        if failed {
+            blocked.insert(aggregate);
+        } else {
+            published.push(event.id);
+        }
+    }
+
+    outbox::mark_published(&tx, &published).await?;
+    tx.commit().await?;
+
+    Ok(published.len())
+}
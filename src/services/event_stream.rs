@@ -0,0 +1,248 @@
+use axum::extract::ws::{Message, WebSocket};
+use axum::response::sse::Event;
+use futures_util::Stream;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::db::{accounts, outbox, transactions, Database};
+use crate::models::outbox::{AggregateType, OutboxEvent};
+use crate::models::stream::{ClientMessage, ServerMessage, StreamSubscription};
+use crate::utils::error::AppError;
+
+/// Events read from the outbox at a time while replaying.
+const REPLAY_BATCH_SIZE: i64 = 500;
+
+/// Server-sent events buffered for a client that is slow to read them.
+const SSE_BUFFER: usize = 64;
+
+/// Checks that every account belongs to `user_id`.
+pub async fn verify_account_ownership(db: &Database, user_id: Uuid, account_ids: &[Uuid]) -> Result<(), AppError> {
+    let client = db.pool.get().await?;
+    for account_id in account_ids {
+        let account = accounts::get_account(&client, *account_id).await?;
+        if account.user_id != user_id {
+            return Err(AppError::Forbidden(
+                "You do not have permission to access this account".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// One open event stream.
+struct Session {
+    socket: WebSocket,
+    db: Database,
+    subscription: StreamSubscription,
+    /// Highest event position sent so far, where a replay after falling
+    /// behind resumes from.
+    position: i64,
+    /// Positions of events sent during a replay, which may also still arrive
+    /// live.
+    replayed: HashSet<i64>,
+}
+
+/// Serves an upgraded stream until the client disconnects: replays events
+/// after `after` if given, then forwards live events from the relay.
+pub async fn run(
+    socket: WebSocket,
+    db: Database,
+    events: broadcast::Sender<OutboxEvent>,
+    subscription: StreamSubscription,
+    after: Option<i64>,
+) {
+    // Subscribe before replaying so nothing published in between is missed
+    let mut receiver = events.subscribe();
+    let mut session = Session {
+        socket,
+        db,
+        subscription,
+        position: after.unwrap_or(0),
+        replayed: HashSet::new(),
+    };
+
+    if let Err(e) = session.serve(&mut receiver, after.is_some()).await {
+        tracing::debug!("Event stream for user {} ended: {}", session.subscription.user_id, e);
+    }
+}
+
+impl Session {
+    async fn serve(&mut self, receiver: &mut broadcast::Receiver<OutboxEvent>, replay: bool) -> Result<(), AppError> {
+        self.send_subscribed().await?;
+        if replay {
+            self.replay().await?;
+        }
+
+        loop {
+            tokio::select! {
+                message = self.socket.recv() => match message {
+                    Some(Ok(Message::Text(text))) => self.handle_client_message(text.as_str()).await?,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                    // Pings are answered automatically
+                    Some(Ok(_)) => {}
+                },
+                event = receiver.recv() => match event {
+                    Ok(event) => {
+                        if !self.replayed.remove(&event.position) {
+                            self.send_event(event).await?;
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(
+                            "Event stream for user {} fell behind by {} events, replaying",
+                            self.subscription.user_id,
+                            missed
+                        );
+                        self.replay().await?;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Sends the user's events after the current position from the outbox.
+    async fn replay(&mut self) -> Result<(), AppError> {
+        self.replayed.clear();
+
+        loop {
+            let events = {
+                let client = self.db.pool.get().await?;
+                outbox::get_user_events_after(&client, self.subscription.user_id, self.position, REPLAY_BATCH_SIZE)
+                    .await?
+            };
+            let done = (events.len() as i64) < REPLAY_BATCH_SIZE;
+
+            for event in events {
+                self.position = self.position.max(event.position);
+                self.replayed.insert(event.position);
+                self.send_event(event).await?;
+            }
+
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn handle_client_message(&mut self, text: &str) -> Result<(), AppError> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                return self
+                    .send(&ServerMessage::Error {
+                        message: format!("Invalid message: {}", e),
+                    })
+                    .await;
+            }
+        };
+
+        match message {
+            ClientMessage::Subscribe { account_ids } => {
+                if let Err(e) =
+                    verify_account_ownership(&self.db, self.subscription.user_id, &account_ids).await
+                {
+                    return self.send(&ServerMessage::Error { message: e.to_string() }).await;
+                }
+                self.subscription.account_ids.extend(account_ids);
+            }
+            ClientMessage::Unsubscribe { account_ids } => {
+                for account_id in &account_ids {
+                    self.subscription.account_ids.remove(account_id);
+                }
+            }
+        }
+
+        self.send_subscribed().await
+    }
+
+    async fn send_event(&mut self, event: OutboxEvent) -> Result<(), AppError> {
+        self.position = self.position.max(event.position);
+        if !self.subscription.matches(&event) {
+            return Ok(());
+        }
+
+        self.send(&ServerMessage::Event(event)).await
+    }
+
+    async fn send_subscribed(&mut self) -> Result<(), AppError> {
+        let account_ids = self.subscription.sorted_account_ids();
+        self.send(&ServerMessage::Subscribed { account_ids }).await
+    }
+
+    async fn send(&mut self, message: &ServerMessage) -> Result<(), AppError> {
+        let text = serde_json::to_string(message)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize stream message: {}", e)))?;
+
+        self.socket
+            .send(Message::Text(text.into()))
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to send stream message: {}", e)))
+    }
+}
+
+/// Streams a transaction's `transaction_events` as server-sent events: those
+/// after `after` first, then new ones as the relay publishes them. The outbox
+/// events only signal that something changed; the rows themselves are always
+/// read from the database, so a slow client never misses any.
+pub fn transaction_events(
+    db: Database,
+    events: broadcast::Sender<OutboxEvent>,
+    transaction_id: Uuid,
+    after: i64,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let (sender, receiver) = mpsc::channel(SSE_BUFFER);
+    let mut updates = events.subscribe();
+
+    tokio::spawn(async move {
+        let mut position = after;
+
+        loop {
+            let pending = match db.pool.get().await {
+                Ok(client) => transactions::get_transaction_events_after(&client, transaction_id, position).await,
+                Err(e) => Err(e.into()),
+            };
+            let pending = match pending {
+                Ok(pending) => pending,
+                Err(e) => {
+                    tracing::error!("Failed to load events for transaction {}: {}", transaction_id, e);
+                    return;
+                }
+            };
+
+            for event in pending {
+                position = event.sequence_number;
+                let Ok(event) = Event::default().id(position.to_string()).json_data(&event) else {
+                    continue;
+                };
+                if sender.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            // Wait until the transaction changes or the client goes away
+            loop {
+                tokio::select! {
+                    update = updates.recv() => match update {
+                        Ok(update)
+                            if update.aggregate_type == AggregateType::Transaction
+                                && update.aggregate_id == transaction_id => break,
+                        Ok(_) => {}
+                        // Missed updates may have been for this transaction
+                        Err(RecvError::Lagged(_)) => break,
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = sender.closed() => return,
+                }
+            }
+        }
+    });
+
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (event, receiver))
+    })
+}
@@ -1,2 +1,10 @@
 pub mod account_service;
-pub mod transaction_service; 
\ No newline at end of file
+pub mod event_relay;
+pub mod event_stream;
+pub mod reconciliation_service;
+pub mod risk_service;
+pub mod screening_service;
+pub mod settlement_service;
+pub mod statement_service;
+pub mod transaction_service;
+pub mod webhook_service;
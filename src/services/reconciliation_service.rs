@@ -0,0 +1,65 @@
+use chrono::Utc;
+use tokio_postgres::IsolationLevel;
+
+use crate::db::{reconciliation, Database};
+use crate::models::reconciliation::ReconciliationReport;
+use crate::utils::error::AppError;
+
+/// Checks every account's balances against its history and looks for pending
+/// transactions older than `pending_age_seconds`. The checks read a single
+/// snapshot, so transfers made while they run cannot show up as discrepancies.
+/// With `persist` the report is saved and comes back with its id.
+pub async fn reconcile(
+    db: &Database,
+    pending_age_seconds: i64,
+    persist: bool,
+) -> Result<ReconciliationReport, AppError> {
+    let started_at = Utc::now();
+
+    let mut client = db.pool.get().await?;
+    let tx = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await?;
+
+    let accounts_checked = reconciliation::count_accounts(&tx).await?;
+    let discrepancies = reconciliation::find_balance_discrepancies(&tx).await?;
+    let orphaned_transactions = reconciliation::find_orphaned_transactions(&tx, pending_age_seconds).await?;
+    tx.commit().await?;
+
+    let mut report = ReconciliationReport {
+        id: None,
+        started_at,
+        finished_at: Utc::now(),
+        accounts_checked,
+        discrepancies,
+        orphaned_transactions,
+    };
+
+    for discrepancy in &report.discrepancies {
+        tracing::warn!(
+            "Account {} balance is {} but its transactions add up to {} (held {} against {})",
+            discrepancy.account_id,
+            discrepancy.stored_balance,
+            discrepancy.expected_balance,
+            discrepancy.stored_held_balance,
+            discrepancy.expected_held_balance,
+        );
+    }
+    for orphan in &report.orphaned_transactions {
+        tracing::warn!(
+            "Transaction {} has been pending since {} ({})",
+            orphan.transaction_id,
+            orphan.created_at,
+            orphan.reason,
+        );
+    }
+
+    if persist {
+        report.id = Some(reconciliation::save_report(&mut client, &report).await?);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,222 @@
+use chrono::Duration;
+
+use crate::models::risk::{RiskAssessment, RiskContext, RiskPolicy, RiskRuleConfig, RuleHit};
+
+/// A check run against every screened transfer. Rules are independent; the
+/// engine adds up the scores of those that match.
+pub trait RiskRule: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit>;
+}
+
+fn hit(rule: &dyn RiskRule, score: u32, detail: String) -> RuleHit {
+    RuleHit {
+        rule: rule.name().to_string(),
+        score,
+        detail,
+    }
+}
+
+pub struct AmountThreshold {
+    pub min_amount: i64,
+    pub currency: Option<String>,
+    pub score: u32,
+}
+
+impl RiskRule for AmountThreshold {
+    fn name(&self) -> &'static str {
+        "amount_threshold"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        if self.currency.as_ref().is_some_and(|currency| *currency != context.currency) {
+            return None;
+        }
+
+        (context.amount >= self.min_amount).then(|| {
+            hit(
+                self,
+                self.score,
+                format!("{} {} is at least {}", context.amount, context.currency, self.min_amount),
+            )
+        })
+    }
+}
+
+pub struct NewDestination {
+    pub score: u32,
+}
+
+impl RiskRule for NewDestination {
+    fn name(&self) -> &'static str {
+        "new_destination"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        (!context.known_destination)
+            .then(|| hit(self, self.score, "First transfer to this account".to_string()))
+    }
+}
+
+pub struct Velocity {
+    pub window_seconds: i64,
+    pub max_count: i64,
+    pub score: u32,
+}
+
+impl RiskRule for Velocity {
+    fn name(&self) -> &'static str {
+        "velocity"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        let window_start = context.now - Duration::seconds(self.window_seconds);
+        let count = context
+            .recent_outgoing
+            .iter()
+            .filter(|created_at| **created_at > window_start)
+            .count() as i64
+            + 1;
+
+        (count > self.max_count).then(|| {
+            hit(
+                self,
+                self.score,
+                format!(
+                    "{} outgoing transactions in {} seconds, more than {}",
+                    count, self.window_seconds, self.max_count
+                ),
+            )
+        })
+    }
+}
+
+pub struct CountryMismatch {
+    pub score: u32,
+}
+
+impl RiskRule for CountryMismatch {
+    fn name(&self) -> &'static str {
+        "country_mismatch"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        let current = context.signals.country.as_ref()?;
+        let previous = context.previous_signals.as_ref()?.country.as_ref()?;
+
+        (!current.eq_ignore_ascii_case(previous)).then(|| {
+            hit(
+                self,
+                self.score,
+                format!("Sent from {}, previously from {}", current, previous),
+            )
+        })
+    }
+}
+
+pub struct IpMismatch {
+    pub score: u32,
+}
+
+impl RiskRule for IpMismatch {
+    fn name(&self) -> &'static str {
+        "ip_mismatch"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        let current = context.signals.ip.as_ref()?;
+        let previous = context.previous_signals.as_ref()?.ip.as_ref()?;
+
+        (current != previous).then(|| {
+            hit(
+                self,
+                self.score,
+                format!("Sent from {}, previously from {}", current, previous),
+            )
+        })
+    }
+}
+
+pub struct NewAccount {
+    pub max_age_seconds: i64,
+    pub score: u32,
+}
+
+impl RiskRule for NewAccount {
+    fn name(&self) -> &'static str {
+        "new_account"
+    }
+
+    fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+        let age = context.now - context.source_account_created_at;
+
+        (context.previous_outgoing_count == 0 && age < Duration::seconds(self.max_age_seconds)).then(|| {
+            hit(
+                self,
+                self.score,
+                format!("First outgoing transaction from an account opened {} seconds ago", age.num_seconds()),
+            )
+        })
+    }
+}
+
+/// Scores transfers against a set of rules and decides what happens to them.
+pub struct RiskEngine {
+    policy: RiskPolicy,
+    rules: Vec<Box<dyn RiskRule>>,
+}
+
+impl RiskEngine {
+    /// An engine running the rules configured in `policy`.
+    pub fn from_policy(policy: &RiskPolicy) -> Self {
+        let rules = policy
+            .rules
+            .iter()
+            .map(|rule| -> Box<dyn RiskRule> {
+                match rule.clone() {
+                    RiskRuleConfig::AmountThreshold { min_amount, currency, score } => {
+                        Box::new(AmountThreshold { min_amount, currency, score })
+                    }
+                    RiskRuleConfig::NewDestination { score } => Box::new(NewDestination { score }),
+                    RiskRuleConfig::Velocity { window_seconds, max_count, score } => {
+                        Box::new(Velocity { window_seconds, max_count, score })
+                    }
+                    RiskRuleConfig::CountryMismatch { score } => Box::new(CountryMismatch { score }),
+                    RiskRuleConfig::IpMismatch { score } => Box::new(IpMismatch { score }),
+                    RiskRuleConfig::NewAccount { max_age_seconds, score } => {
+                        Box::new(NewAccount { max_age_seconds, score })
+                    }
+                }
+            })
+            .collect();
+
+        RiskEngine {
+            policy: policy.clone(),
+            rules,
+        }
+    }
+
+    /// Adds a rule that is not configured through the rules file.
+    #[allow(dead_code)]
+    pub fn with_rule(mut self, rule: Box<dyn RiskRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn policy(&self) -> &RiskPolicy {
+        &self.policy
+    }
+
+    pub fn assess(&self, context: &RiskContext) -> RiskAssessment {
+        let hits: Vec<RuleHit> = self.rules.iter().filter_map(|rule| rule.evaluate(context)).collect();
+        let score = hits.iter().map(|hit| hit.score).sum();
+
+        RiskAssessment {
+            decision: self.policy.decide(score),
+            score,
+            hits,
+            signals: context.signals.clone(),
+        }
+    }
+}
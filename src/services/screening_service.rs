@@ -0,0 +1,125 @@
+use std::sync::{Arc, RwLock};
+
+use crate::db::screening;
+use crate::db::transactions::Screening;
+use crate::models::risk::{RiskPolicy, RiskSignals};
+use crate::models::watchlist::{ScreenedPerson, Watchlist};
+use crate::services::risk_service::RiskEngine;
+use crate::utils::error::AppError;
+
+/// The watchlist in use, shared by every request and swapped whole when the
+/// file is reloaded. Requests already screening keep the list they started with.
+#[derive(Clone)]
+pub struct WatchlistHandle {
+    path: Option<String>,
+    current: Arc<RwLock<Arc<Watchlist>>>,
+}
+
+impl WatchlistHandle {
+    /// Loads the watchlist at `path`, or an empty one that matches nothing.
+    pub fn load(path: Option<String>) -> Result<Self, String> {
+        let watchlist = match &path {
+            Some(path) => Watchlist::load(path)?,
+            None => Watchlist::default(),
+        };
+
+        Ok(WatchlistHandle {
+            path,
+            current: Arc::new(RwLock::new(Arc::new(watchlist))),
+        })
+    }
+
+    pub fn current(&self) -> Arc<Watchlist> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Reads the file again. If it cannot be loaded the list in use is kept.
+    pub fn reload(&self) -> Result<Arc<Watchlist>, AppError> {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("No watchlist file is configured".to_string()))?;
+        let watchlist = Arc::new(Watchlist::load(path).map_err(AppError::BadRequest)?);
+
+        *self.current.write().unwrap() = watchlist.clone();
+        tracing::info!("Reloaded {} watchlist entries from {}", watchlist.len(), path);
+
+        Ok(watchlist)
+    }
+}
+
+/// What transfers are screened with, shared by every request and worker: the
+/// risk engine, built once from the policy, and the watchlist in use.
+#[derive(Clone)]
+pub struct TransferScreener {
+    engine: Arc<RiskEngine>,
+    watchlist: WatchlistHandle,
+    match_threshold: f64,
+}
+
+impl TransferScreener {
+    pub fn new(policy: &RiskPolicy, watchlist: WatchlistHandle, match_threshold: f64) -> Self {
+        TransferScreener {
+            engine: Arc::new(RiskEngine::from_policy(policy)),
+            watchlist,
+            match_threshold,
+        }
+    }
+
+    /// The watchlist to screen against, kept for the whole transfer even if
+    /// the file is reloaded meanwhile.
+    pub fn watchlist(&self) -> Arc<Watchlist> {
+        self.watchlist.current()
+    }
+
+    pub fn screening<'a>(&'a self, watchlist: &'a Watchlist, signals: &'a RiskSignals) -> Screening<'a> {
+        Screening {
+            engine: &self.engine,
+            signals,
+            watchlist,
+            match_threshold: self.match_threshold,
+        }
+    }
+}
+
+/// Refuses a registration whose name matches the watchlist, opening a case
+/// for compliance to review. Names cleared against an entry before are not
+/// flagged for it again.
+pub async fn screen_registration(
+    client: &deadpool_postgres::Client,
+    watchlist: &Watchlist,
+    threshold: f64,
+    person: &ScreenedPerson,
+) -> Result<(), AppError> {
+    refuse_match(client, watchlist, threshold, person, "Registration").await
+}
+
+/// Refuses a profile change that would screen the user under a name matching
+/// the watchlist, in the same way as a registration.
+pub async fn screen_name_change(
+    client: &deadpool_postgres::Client,
+    watchlist: &Watchlist,
+    threshold: f64,
+    person: &ScreenedPerson,
+) -> Result<(), AppError> {
+    refuse_match(client, watchlist, threshold, person, "Profile change").await
+}
+
+async fn refuse_match(
+    client: &deadpool_postgres::Client,
+    watchlist: &Watchlist,
+    threshold: f64,
+    person: &ScreenedPerson,
+    refused: &str,
+) -> Result<(), AppError> {
+    let Some(watchlist_match) = screening::find_match(client, watchlist, threshold, person).await? else {
+        return Ok(());
+    };
+
+    screening::open_case(client, person, &watchlist_match, None).await?;
+
+    Err(AppError::Forbidden(format!(
+        "{} could not be completed and has been referred for review",
+        refused
+    )))
+}
@@ -0,0 +1,39 @@
+use uuid::Uuid;
+
+use crate::db::{settlements, Database};
+use crate::models::settlement::{SettlementMapping, SettlementReport};
+use crate::utils::error::AppError;
+
+/// Reads a bank settlement file with `mapping`, matches each line against our
+/// transactions and settles those that agree. Nothing is stored if any line
+/// cannot be read.
+pub async fn import(
+    db: &Database,
+    file_name: &str,
+    mapping: &SettlementMapping,
+    content: &str,
+    imported_by: Option<Uuid>,
+) -> Result<SettlementReport, AppError> {
+    let records = mapping.parse(content).map_err(AppError::BadRequest)?;
+
+    let mut client = db.pool.get().await?;
+    let report = settlements::import_records(
+        &mut client,
+        file_name,
+        &mapping.format.to_string(),
+        imported_by,
+        mapping.date_tolerance_days,
+        &records,
+    )
+    .await?;
+
+    tracing::info!(
+        "Imported settlement file {}: {} matched, {} unmatched, {} mismatched",
+        file_name,
+        report.import.matched_count,
+        report.import.unmatched_count,
+        report.import.mismatched_count,
+    );
+
+    Ok(report)
+}
@@ -0,0 +1,123 @@
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use tokio::sync::mpsc;
+use tokio_postgres::IsolationLevel;
+
+use crate::db::{ledger, Database};
+use crate::models::account::Account;
+use crate::models::statement::{StatementFormat, StatementLine};
+use crate::utils::error::AppError;
+
+/// Ledger entries read from the database at a time.
+const BATCH_SIZE: i32 = 500;
+
+/// Rendered chunks buffered ahead of a client that is slow to read them.
+const CHUNK_BUFFER: usize = 16;
+
+/// Streams a statement for `[from, to)`: the opening balance, every ledger
+/// entry with the running balance, and the closing balance. Everything is read
+/// from one snapshot, and the statement is cut short with an error rather than
+/// finished if the closing balance does not match the ledger.
+pub fn stream(
+    db: Database,
+    account: Account,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    format: StatementFormat,
+) -> impl Stream<Item = Result<Bytes, AppError>> {
+    let (sender, receiver) = mpsc::channel(CHUNK_BUFFER);
+
+    tokio::spawn(async move {
+        if let Err(e) = write_statement(&db, &account, from, to, format, &sender).await {
+            tracing::error!("Failed to write statement for account {}: {}", account.id, e);
+            let _ = sender.send(Err(e)).await;
+        }
+    });
+
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    })
+}
+
+async fn write_statement(
+    db: &Database,
+    account: &Account,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    format: StatementFormat,
+    sender: &mpsc::Sender<Result<Bytes, AppError>>,
+) -> Result<(), AppError> {
+    let mut client = db.pool.get().await?;
+    let tx = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await?;
+
+    let opening_balance = ledger::get_balance_before(&tx, account.id, from).await?;
+    let opening = StatementLine::OpeningBalance {
+        account_id: account.id,
+        currency: account.currency.clone(),
+        from,
+        to,
+        balance: opening_balance,
+    };
+    if !send(sender, opening.render(format, &account.currency)).await {
+        return Ok(());
+    }
+
+    let portal = ledger::bind_statement_entries(&tx, account.id, from, to).await?;
+    let mut balance = opening_balance;
+    let mut total_credits = 0;
+    let mut total_debits = 0;
+
+    loop {
+        let entries = ledger::fetch_statement_entries(&tx, &portal, BATCH_SIZE).await?;
+        let done = entries.len() < BATCH_SIZE as usize;
+
+        let mut chunk = String::new();
+        for entry in entries {
+            balance += entry.amount;
+            if entry.amount > 0 {
+                total_credits += entry.amount;
+            } else {
+                total_debits -= entry.amount;
+            }
+            chunk.push_str(&StatementLine::Entry { entry, balance }.render(format, &account.currency));
+        }
+
+        if !chunk.is_empty() && !send(sender, chunk).await {
+            return Ok(());
+        }
+        if done {
+            break;
+        }
+    }
+
+    // Check the running total against the ledger itself
+    let closing_balance = ledger::get_balance_before(&tx, account.id, to).await?;
+    if closing_balance != balance {
+        return Err(AppError::Internal(format!(
+            "Statement does not reconcile: opening balance {} plus movements gives {}, but the ledger closes at {}",
+            opening_balance, balance, closing_balance
+        )));
+    }
+
+    let closing = StatementLine::ClosingBalance {
+        to,
+        total_credits,
+        total_debits,
+        balance,
+    };
+    send(sender, closing.render(format, &account.currency)).await;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Sends a chunk, returning false once the client has gone away.
+async fn send(sender: &mpsc::Sender<Result<Bytes, AppError>>, chunk: String) -> bool {
+    sender.send(Ok(Bytes::from(chunk))).await.is_ok()
+}
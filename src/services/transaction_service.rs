@@ -1,5 +1,7 @@
 use crate::db::transactions;
-use crate::models::transaction::{CreateTransactionRequest, Transaction};
+use crate::models::transaction::{
+    CreateTransactionRequest, SortDirection, Transaction, TransactionCursor, TransactionFilter,
+};
 use crate::utils::error::AppError;
 use deadpool_postgres::Client;
 use uuid::Uuid;
@@ -18,6 +20,8 @@ pub async fn create_transaction(
         currency: data.currency.to_uppercase(),
         transaction_type: data.transaction_type.to_lowercase(),
         description: data.description.clone(),
+        authorize_only: data.authorize_only,
+        fx_quote_id: data.fx_quote_id,
     };
 
     // Process the transaction in the database
@@ -47,8 +51,9 @@ pub async fn get_transaction(
 pub async fn list_user_transactions(
     client: &mut Client,
     user_id: Uuid,
-    page: usize,
+    filter: &TransactionFilter,
+    cursor: Option<&TransactionCursor>,
     page_size: usize,
-) -> Result<(Vec<Transaction>, usize), AppError> {
-    transactions::get_user_transactions(client, user_id, page, page_size).await
+) -> Result<(Vec<Transaction>, Option<TransactionCursor>), AppError> {
+    transactions::get_user_transactions(client, user_id, filter, cursor, SortDirection::Desc, page_size).await
 } 
\ No newline at end of file
@@ -0,0 +1,170 @@
+use crate::db::webhooks;
+use crate::models::webhook::{
+    check_endpoint_url, is_allowed_host, is_public_address, RetryPolicy, WebhookDelivery, WebhookDeliveryAttempt,
+    WebhookEndpoint,
+};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use deadpool_postgres::Client;
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const SIGNATURE_HEADER: &str = "Webhook-Signature";
+pub const EVENT_ID_HEADER: &str = "Webhook-Id";
+pub const EVENT_TYPE_HEADER: &str = "Webhook-Event";
+pub const TIMESTAMP_HEADER: &str = "Webhook-Timestamp";
+
+/// How long an endpoint has to respond before the attempt counts as failed.
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends deliveries over one shared HTTP client, holding every endpoint to
+/// the URL rules of `check_endpoint_url`. Redirects are not followed, and
+/// endpoint names that resolve only to non-public addresses are refused,
+/// unless the host is in `allowed_hosts`.
+#[derive(Clone)]
+pub struct WebhookSender {
+    http: reqwest::Client,
+    allowed_hosts: Arc<[String]>,
+}
+
+impl WebhookSender {
+    pub fn new(allowed_hosts: &[String]) -> Self {
+        let allowed_hosts: Arc<[String]> = allowed_hosts.into();
+        let http = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .user_agent(concat!("payments-backend-webhooks/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver {
+                allowed_hosts: allowed_hosts.clone(),
+            }))
+            .build()
+            .expect("Unable to build webhook HTTP client");
+
+        Self { http, allowed_hosts }
+    }
+
+    /// Sends a delivery once. Endpoints registered before the URL rules
+    /// applied fail without being sent.
+    pub async fn send(&self, endpoint: &WebhookEndpoint, delivery: &WebhookDelivery) -> SendResult {
+        match check_endpoint_url(&endpoint.url, &self.allowed_hosts) {
+            Ok(()) => send(&self.http, endpoint, delivery).await,
+            Err(error) => SendResult {
+                response_status: None,
+                error: Some(error),
+                duration_ms: 0,
+            },
+        }
+    }
+
+    /// Sends a delivery once and records the attempt.
+    pub async fn deliver(
+        &self,
+        client: &mut Client,
+        endpoint: &WebhookEndpoint,
+        delivery: &WebhookDelivery,
+        policy: &RetryPolicy,
+    ) -> Result<WebhookDeliveryAttempt, AppError> {
+        let result = self.send(endpoint, delivery).await;
+
+        webhooks::record_attempt(
+            client,
+            delivery.id,
+            result.response_status,
+            result.error.as_deref(),
+            result.duration_ms,
+            policy,
+        )
+        .await
+    }
+}
+
+/// Resolves endpoint names to their public addresses only, so a name pointing
+/// at an internal service cannot be used to reach it.
+struct PublicResolver {
+    allowed_hosts: Arc<[String]>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed = is_allowed_host(name.as_str(), &self.allowed_hosts);
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|address| allowed || is_public_address(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `"{timestamp}.{body}"` under the endpoint's
+/// secret. Receivers recompute it to check the payload came from us, and
+/// reject stale timestamps to stop replays.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// The value of the signature header, e.g. `t=1767225600,v1=5257a8...`.
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, sign(secret, timestamp, body))
+}
+
+/// What happened when a delivery was sent once.
+#[derive(Debug)]
+pub struct SendResult {
+    pub response_status: Option<i32>,
+    /// Set unless the endpoint answered with a 2xx status.
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+/// Posts a delivery's payload to its endpoint, signed with a fresh timestamp.
+pub async fn send(http: &reqwest::Client, endpoint: &WebhookEndpoint, delivery: &WebhookDelivery) -> SendResult {
+    let body = delivery.payload.to_string().into_bytes();
+    let timestamp = Utc::now().timestamp();
+    let started = Instant::now();
+
+    let result = http
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_ID_HEADER, delivery.event_id.to_string())
+        .header(EVENT_TYPE_HEADER, &delivery.event_type)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, signature_header(&endpoint.secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await;
+    let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            SendResult {
+                response_status: Some(status.as_u16() as i32),
+                error: (!status.is_success()).then(|| format!("Endpoint responded with {}", status)),
+                duration_ms,
+            }
+        }
+        Err(e) => SendResult {
+            response_status: None,
+            error: Some(if e.is_timeout() {
+                format!("Endpoint did not respond within {} seconds", DELIVERY_TIMEOUT.as_secs())
+            } else {
+                format!("Request failed: {}", e)
+            }),
+            duration_ms,
+        },
+    }
+}
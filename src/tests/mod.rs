@@ -1,29 +1,223 @@
 #[cfg(test)]
-mod auth_tests {
+pub fn test_config() -> crate::config::Config {
+    crate::config::Config {
+        database_url: std::env::var("DATABASE_URL").unwrap_or_else(|_| "dummy".to_string()),
+        jwt_secret: "test_secret_key".to_string(),
+        jwt_expiration: 3600, // 1 hour
+        port: 3000,
+        idempotency_key_ttl: 86400,
+        hold_expiration: 604800,
+        hold_expiry_interval: 60,
+        fx_quote_ttl: 30,
+        scheduled_transfer_interval: 60,
+        scheduled_transfer_retry_delay: 3600,
+        scheduled_transfer_max_failures: 3,
+        webhook_delivery_interval: 5,
+        webhook_max_attempts: 8,
+        webhook_retry_base_delay: 30,
+        webhook_allowed_hosts: Vec::new(),
+        outbox_sinks: vec![
+            crate::models::outbox::EventSinkKind::Broadcast,
+            crate::models::outbox::EventSinkKind::Webhooks,
+        ],
+        outbox_relay_interval: 1,
+        outbox_retention: 604800,
+        reconciliation_pending_age: 3600,
+        risk_policy: crate::models::risk::RiskPolicy::default(),
+        watchlist_path: None,
+        watchlist_match_threshold: 0.92,
+    }
+}
+
+/// Setup shared by the tests that run against a database.
+#[cfg(test)]
+mod fixtures {
+    use super::test_config;
+    use crate::db::transactions::Screening;
+    use crate::db::{accounts, transactions, users, Database};
+    use crate::models::account::CreateAccountRequest;
+    use crate::models::risk::{RiskPolicy, RiskSignals};
+    use crate::models::transaction::CreateTransactionRequest;
     use crate::models::user::CreateUserRequest;
+    use crate::models::watchlist::Watchlist;
+    use crate::services::risk_service::RiskEngine;
+    use uuid::Uuid;
+
+    pub(super) fn test_database() -> Database {
+        assert!(std::env::var("DATABASE_URL").is_ok(), "DATABASE_URL must be set");
+        Database::new(&test_config())
+    }
+
+    pub(super) async fn create_test_user(db: &Database) -> Uuid {
+        let client = db.pool.get().await.unwrap();
+        let suffix = Uuid::new_v4().simple().to_string();
+        users::create_user(
+            &client,
+            &CreateUserRequest {
+                email: format!("{}@example.com", suffix),
+                username: format!("user_{}", suffix),
+                password: "password123".to_string(),
+                full_name: None,
+                date_of_birth: None,
+            },
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    /// Opens the user's main account in `currency` and deposits `amount` into it.
+    pub(super) async fn create_funded_account(db: &Database, user_id: Uuid, currency: &str, amount: i64) -> Uuid {
+        let mut client = db.pool.get().await.unwrap();
+        let account = accounts::create_account(
+            &mut client,
+            user_id,
+            &CreateAccountRequest {
+                currency: currency.to_string(),
+                label: None,
+                purpose: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut deposit = deposit(account.id, amount);
+        deposit.currency = currency.to_string();
+        transactions::create_transaction(&mut client, user_id, &deposit).await.unwrap();
+
+        account.id
+    }
+
+    /// A USD transaction of `amount` between the given accounts.
+    pub(super) fn request(
+        source: Option<Uuid>,
+        destination: Option<Uuid>,
+        amount: i64,
+        transaction_type: &str,
+    ) -> CreateTransactionRequest {
+        CreateTransactionRequest {
+            source_account_id: source,
+            destination_account_id: destination,
+            amount,
+            currency: "USD".to_string(),
+            transaction_type: transaction_type.to_string(),
+            description: None,
+            authorize_only: false,
+            fx_quote_id: None,
+        }
+    }
+
+    pub(super) fn deposit(account_id: Uuid, amount: i64) -> CreateTransactionRequest {
+        request(None, Some(account_id), amount, "deposit")
+    }
+
+    pub(super) fn withdrawal(account_id: Uuid, amount: i64) -> CreateTransactionRequest {
+        request(Some(account_id), None, amount, "withdrawal")
+    }
+
+    pub(super) fn transfer(source: Uuid, destination: Uuid, amount: i64) -> CreateTransactionRequest {
+        request(Some(source), Some(destination), amount, "transfer")
+    }
+
+    /// Two users with a funded USD account each.
+    pub(super) struct Parties {
+        pub(super) payer_id: Uuid,
+        pub(super) payer: Uuid,
+        pub(super) payee_id: Uuid,
+        pub(super) payee: Uuid,
+    }
+
+    pub(super) async fn parties(db: &Database, payer_balance: i64, payee_balance: i64) -> Parties {
+        let payer_id = create_test_user(db).await;
+        let payee_id = create_test_user(db).await;
+
+        Parties {
+            payer_id,
+            payer: create_funded_account(db, payer_id, "USD", payer_balance).await,
+            payee_id,
+            payee: create_funded_account(db, payee_id, "USD", payee_balance).await,
+        }
+    }
+
+    /// Gives a user the name they are screened by.
+    pub(super) async fn set_full_name(client: &deadpool_postgres::Client, user_id: Uuid, full_name: &str) {
+        client
+            .execute("UPDATE users SET full_name = $1 WHERE id = $2", &[&full_name, &user_id])
+            .await
+            .unwrap();
+    }
+
+    /// Owns what a `Screening` borrows.
+    pub(super) struct Screener {
+        pub(super) engine: RiskEngine,
+        pub(super) signals: RiskSignals,
+        pub(super) watchlist: Watchlist,
+    }
+
+    impl Screener {
+        pub(super) fn new(policy: &RiskPolicy, watchlist: Watchlist) -> Self {
+            Screener {
+                engine: RiskEngine::from_policy(policy),
+                signals: RiskSignals::default(),
+                watchlist,
+            }
+        }
+
+        /// Approves every transfer and flags nobody.
+        pub(super) fn lenient() -> Self {
+            Self::new(&RiskPolicy::default(), Watchlist::default())
+        }
+
+        pub(super) fn screening(&self) -> Screening<'_> {
+            Screening {
+                engine: &self.engine,
+                signals: &self.signals,
+                watchlist: &self.watchlist,
+                match_threshold: 0.92,
+            }
+        }
+    }
+
+    /// What was recorded when a transaction was held for review, such as the
+    /// `review_id` or `case_id` that decides it.
+    pub(super) async fn held_event_data(client: &deadpool_postgres::Client, transaction_id: Uuid) -> serde_json::Value {
+        let (events, _) = transactions::get_transaction_history(client, transaction_id, 1, 10).await.unwrap();
+        events
+            .into_iter()
+            .find(|event| event.new_status == "under_review")
+            .and_then(|event| event.event_data)
+            .expect("the transaction should have been held")
+    }
+
+    /// An account's balance and held balance.
+    pub(super) async fn balances(client: &deadpool_postgres::Client, account_id: Uuid) -> (i64, i64) {
+        let account = accounts::get_account(client, account_id).await.unwrap();
+        (account.balance, account.held_balance)
+    }
+}
+
+#[cfg(test)]
+mod auth_tests {
+    use crate::models::user::{CreateUserRequest, UserRole};
     use crate::utils::jwt::{create_token, verify_token};
-    use crate::config::Config;
+    use super::test_config;
     use uuid::Uuid;
 
     #[test]
     fn test_jwt_token_creation_and_verification() {
-        let config = Config {
-            database_url: "dummy".to_string(),
-            jwt_secret: "test_secret_key".to_string(),
-            jwt_expiration: 3600, // 1 hour
-            port: 3000,
-        };
+        let config = test_config();
 
         let user_id = Uuid::new_v4();
         let username = "testuser";
         let email = "test@example.com";
 
-        let token = create_token(user_id, username, email, &config).unwrap();
+        let token = create_token(user_id, username, email, &UserRole::Admin, &config).unwrap();
         let token_data = verify_token(&token, &config).unwrap();
 
         assert_eq!(token_data.claims.sub, user_id.to_string());
         assert_eq!(token_data.claims.username, username);
         assert_eq!(token_data.claims.email, email);
+        assert_eq!(UserRole::from(token_data.claims.role.as_str()), UserRole::Admin);
     }
 
     #[test]
@@ -36,6 +230,7 @@ mod auth_tests {
             username: "validuser".to_string(),
             password: "password123".to_string(),
             full_name: Some("Valid User".to_string()),
+            date_of_birth: None,
         };
         assert!(valid_request.validate().is_ok());
 
@@ -45,6 +240,7 @@ mod auth_tests {
             username: "validuser".to_string(),
             password: "password123".to_string(),
             full_name: Some("Valid User".to_string()),
+            date_of_birth: None,
         };
         assert!(invalid_email.validate().is_err());
 
@@ -54,6 +250,7 @@ mod auth_tests {
             username: "ab".to_string(), // Too short
             password: "password123".to_string(),
             full_name: Some("Valid User".to_string()),
+            date_of_birth: None,
         };
         assert!(short_username.validate().is_err());
 
@@ -63,6 +260,7 @@ mod auth_tests {
             username: "validuser".to_string(),
             password: "short".to_string(), // Too short
             full_name: Some("Valid User".to_string()),
+            date_of_birth: None,
         };
         assert!(short_password.validate().is_err());
     }
@@ -70,9 +268,19 @@ mod auth_tests {
 
 #[cfg(test)]
 mod transaction_tests {
-    use crate::models::transaction::{TransactionType, TransactionStatus};
+    use crate::handlers::transactions::{PaginationParams, TransactionListParams, MAX_PAGE_SIZE};
+    use crate::models::transaction::{TransactionCursor, TransactionType, TransactionStatus};
+    use crate::models::transaction_state::{allowed_transitions, validate_transition, StateError};
+    use axum::extract::Query;
+    use chrono::{TimeZone, Utc};
     use rust_decimal::Decimal;
     use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn query<T: serde::de::DeserializeOwned>(query: &str) -> T {
+        let uri = format!("http://localhost/?{}", query).parse().unwrap();
+        Query::<T>::try_from_uri(&uri).unwrap().0
+    }
 
     #[test]
     fn test_transaction_type_conversion() {
@@ -82,22 +290,109 @@ mod transaction_tests {
         assert_eq!(TransactionType::from("DEPOSIT"), TransactionType::Deposit);
         assert_eq!(TransactionType::from("WITHDRAWAL"), TransactionType::Withdrawal);
         assert_eq!(TransactionType::from("TRANSFER"), TransactionType::Transfer);
+        assert_eq!(TransactionType::from("refund"), TransactionType::Refund);
         // Default for unknown types
         assert_eq!(TransactionType::from("unknown"), TransactionType::Transfer);
     }
 
     #[test]
     fn test_transaction_status_conversion() {
-        assert_eq!(TransactionStatus::from("pending"), TransactionStatus::Pending);
-        assert_eq!(TransactionStatus::from("completed"), TransactionStatus::Completed);
-        assert_eq!(TransactionStatus::from("failed"), TransactionStatus::Failed);
-        assert_eq!(TransactionStatus::from("cancelled"), TransactionStatus::Cancelled);
-        assert_eq!(TransactionStatus::from("PENDING"), TransactionStatus::Pending);
-        assert_eq!(TransactionStatus::from("COMPLETED"), TransactionStatus::Completed);
-        assert_eq!(TransactionStatus::from("FAILED"), TransactionStatus::Failed);
-        assert_eq!(TransactionStatus::from("CANCELLED"), TransactionStatus::Cancelled);
-        // Default for unknown statuses
-        assert_eq!(TransactionStatus::from("unknown"), TransactionStatus::Pending);
+        assert_eq!(TransactionStatus::try_from("pending"), Ok(TransactionStatus::Pending));
+        assert_eq!(TransactionStatus::try_from("completed"), Ok(TransactionStatus::Completed));
+        assert_eq!(TransactionStatus::try_from("failed"), Ok(TransactionStatus::Failed));
+        assert_eq!(TransactionStatus::try_from("cancelled"), Ok(TransactionStatus::Cancelled));
+        assert_eq!(TransactionStatus::try_from("PENDING"), Ok(TransactionStatus::Pending));
+        assert_eq!(TransactionStatus::try_from("COMPLETED"), Ok(TransactionStatus::Completed));
+        assert_eq!(TransactionStatus::try_from("FAILED"), Ok(TransactionStatus::Failed));
+        assert_eq!(TransactionStatus::try_from("CANCELLED"), Ok(TransactionStatus::Cancelled));
+        assert_eq!(TransactionStatus::try_from("reversed"), Ok(TransactionStatus::Reversed));
+        assert_eq!(TransactionStatus::try_from("under_review"), Ok(TransactionStatus::UnderReview));
+        assert_eq!(TransactionStatus::UnderReview.to_string(), "under_review");
+        // Unknown statuses are rejected rather than defaulted
+        assert_eq!(
+            TransactionStatus::try_from("unknown"),
+            Err(StateError::UnknownStatus("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_transaction_status_transitions() {
+        assert!(validate_transition(&TransactionStatus::Pending, &TransactionStatus::Completed).is_ok());
+        assert!(validate_transition(&TransactionStatus::Pending, &TransactionStatus::Failed).is_ok());
+        assert!(validate_transition(&TransactionStatus::Pending, &TransactionStatus::Cancelled).is_ok());
+        assert!(validate_transition(&TransactionStatus::Completed, &TransactionStatus::Reversed).is_ok());
+        // Only completed transactions can be reversed, and only once
+        assert!(validate_transition(&TransactionStatus::Pending, &TransactionStatus::Reversed).is_err());
+        assert!(validate_transition(&TransactionStatus::Reversed, &TransactionStatus::Reversed).is_err());
+        // A settled transaction cannot be re-opened
+        assert_eq!(
+            validate_transition(&TransactionStatus::Completed, &TransactionStatus::Pending),
+            Err(StateError::InvalidTransition {
+                from: TransactionStatus::Completed,
+                to: TransactionStatus::Pending,
+            })
+        );
+        assert!(validate_transition(&TransactionStatus::Failed, &TransactionStatus::Completed).is_err());
+        // Reviewed transfers are completed, approved authorizations return to pending
+        assert!(validate_transition(&TransactionStatus::Pending, &TransactionStatus::UnderReview).is_ok());
+        assert!(validate_transition(&TransactionStatus::UnderReview, &TransactionStatus::Completed).is_ok());
+        assert!(validate_transition(&TransactionStatus::UnderReview, &TransactionStatus::Pending).is_ok());
+        assert!(validate_transition(&TransactionStatus::UnderReview, &TransactionStatus::Failed).is_ok());
+        assert!(validate_transition(&TransactionStatus::UnderReview, &TransactionStatus::Reversed).is_err());
+    }
+
+    #[test]
+    fn test_terminal_statuses() {
+        assert!(allowed_transitions(&TransactionStatus::Failed).is_empty());
+        assert!(allowed_transitions(&TransactionStatus::Cancelled).is_empty());
+        assert!(allowed_transitions(&TransactionStatus::Reversed).is_empty());
+        assert!(!allowed_transitions(&TransactionStatus::Pending).is_empty());
+    }
+
+    #[test]
+    fn test_transaction_cursor_round_trip() {
+        let cursor = TransactionCursor {
+            created_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, 30, 0).unwrap() + chrono::Duration::microseconds(123_456),
+            id: Uuid::new_v4(),
+        };
+        let token = cursor.encode();
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(TransactionCursor::decode(&token), Ok(cursor));
+
+        assert!(TransactionCursor::decode("not a cursor").is_err());
+        assert!(TransactionCursor::decode("MTIzNA").is_err());
+    }
+
+    #[test]
+    fn test_page_size_is_capped() {
+        let params: PaginationParams = query("page=0&page_size=5000");
+        assert_eq!(params.page, 1);
+        assert_eq!(params.page_size, MAX_PAGE_SIZE);
+
+        let params: PaginationParams = query("");
+        assert_eq!((params.page, params.page_size), (1, 10));
+
+        let params: TransactionListParams = query("page_size=0");
+        assert_eq!(params.page_size, 1);
+    }
+
+    #[test]
+    fn test_transaction_list_filters() {
+        let params: TransactionListParams =
+            query("status=completed&transaction_type=Deposit&currency=usd&min_amount=5&description=rent");
+        let filter = params.filter().unwrap();
+        assert_eq!(filter.status, Some(TransactionStatus::Completed));
+        assert_eq!(filter.transaction_type, Some(TransactionType::Deposit));
+        assert_eq!(filter.currency.as_deref(), Some("USD"));
+        assert_eq!(filter.min_amount, Some(5));
+        assert_eq!(filter.description.as_deref(), Some("rent"));
+
+        let params: TransactionListParams = query("transaction_type=gift");
+        assert!(params.filter().is_err());
+        let params: TransactionListParams = query("status=settled");
+        assert!(params.filter().is_err());
+        let params: TransactionListParams = query("min_amount=10&max_amount=5");
+        assert!(params.filter().is_err());
     }
 
     #[test]
@@ -116,4 +411,3814 @@ mod transaction_tests {
         assert!(balance > withdrawal);
         assert!(withdrawal < deposit);
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod idempotency_tests {
+    use super::fixtures::{balances, create_funded_account, create_test_user, test_database, withdrawal};
+    use crate::db::{idempotency, transactions};
+    use crate::models::idempotency::{IdempotencyOutcome, StoredResponse};
+    use crate::utils::error::AppError;
+    use serde_json::json;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    const PATH: &str = "/api/transactions";
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_idempotency_key_replay_conflict_and_expiry() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 1_000).await;
+        let key = Uuid::new_v4().to_string();
+        let request = withdrawal(account_id, 100);
+        let body = serde_json::to_value(&request).unwrap();
+
+        // A claim rolled back with its transaction leaves the key free
+        let mut client = db.pool.get().await.unwrap();
+        let tx = client.transaction().await.unwrap();
+        let outcome = idempotency::begin(&tx, user_id, &key, PATH, &body, 60).await.unwrap();
+        assert!(matches!(outcome, IdempotencyOutcome::Started));
+        tx.rollback().await.unwrap();
+
+        // A retry made while the first request runs waits for it, then replays
+        let mut tx = client.transaction().await.unwrap();
+        let outcome = idempotency::begin(&tx, user_id, &key, PATH, &body, 60).await.unwrap();
+        assert!(matches!(outcome, IdempotencyOutcome::Started));
+        let retry = {
+            let (db, key, body) = (db.clone(), key.clone(), body.clone());
+            tokio::spawn(async move {
+                let client = db.pool.get().await.unwrap();
+                idempotency::begin(&client, user_id, &key, PATH, &body, 60).await
+            })
+        };
+        let transaction = transactions::create_transaction(&mut tx, user_id, &request).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!retry.is_finished());
+        let response = StoredResponse {
+            status: 200,
+            body: json!({ "id": transaction.id }),
+        };
+        idempotency::complete(&tx, user_id, &key, &response).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let Ok(IdempotencyOutcome::Replay(replayed)) = retry.await.unwrap() else {
+            panic!("the retry should replay the stored response");
+        };
+        assert_eq!(replayed.status, 200);
+        assert_eq!(replayed.body, response.body);
+        assert_eq!(balances(&client, account_id).await, (900, 0));
+
+        // The same key cannot be reused for a different request
+        let other = serde_json::to_value(withdrawal(account_id, 200)).unwrap();
+        let error = idempotency::begin(&client, user_id, &key, PATH, &other, 60).await;
+        assert!(matches!(error, Err(AppError::Conflict(ref message)) if message.contains("different request")));
+
+        // Until it expires
+        client
+            .execute(
+                "UPDATE idempotency_keys SET expires_at = NOW() - INTERVAL '1 second'
+                 WHERE user_id = $1 AND idempotency_key = $2",
+                &[&user_id, &key],
+            )
+            .await
+            .unwrap();
+        let outcome = idempotency::begin(&client, user_id, &key, PATH, &other, 60).await.unwrap();
+        assert!(matches!(outcome, IdempotencyOutcome::Started));
+    }
+}
+
+#[cfg(test)]
+mod hold_tests {
+    use super::fixtures::{balances, parties, test_database, transfer, Parties};
+    use crate::db::{holds, ledger, transactions};
+    use crate::models::hold::HoldStatus;
+    use crate::models::transaction::TransactionStatus;
+    use crate::utils::error::AppError;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_hold_status_parsing() {
+        for status in [HoldStatus::Active, HoldStatus::Captured, HoldStatus::Voided, HoldStatus::Expired] {
+            assert_eq!(HoldStatus::try_from(status.to_string().as_str()), Ok(status));
+        }
+        assert!(HoldStatus::try_from("released").is_err());
+        assert!(HoldStatus::try_from("").is_err());
+    }
+
+    async fn hold_status(client: &deadpool_postgres::Client, transaction_id: Uuid) -> (HoldStatus, Option<i64>) {
+        let hold = holds::lock_hold_for_transaction(client, transaction_id).await.unwrap();
+        (hold.status, hold.captured_amount)
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_capture_void_and_expire_holds() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee, .. } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+
+        // A partial capture moves only what was captured and rewrites the amount
+        let authorization = transactions::authorize_transaction(&mut client, payer_id, &transfer(payer, payee, 400), 3600, None)
+            .await
+            .unwrap();
+        assert_eq!(authorization.status, TransactionStatus::Pending);
+        assert_eq!(balances(&client, payer).await, (1_000, 400));
+        let error = transactions::capture_transaction(&mut client, payer_id, authorization.id, Some(500)).await;
+        assert!(matches!(error, Err(AppError::BadRequest(_))));
+
+        let captured = transactions::capture_transaction(&mut client, payer_id, authorization.id, Some(250))
+            .await
+            .unwrap();
+        assert_eq!((captured.status, captured.amount), (TransactionStatus::Completed, 250));
+        assert_eq!(balances(&client, payer).await, (750, 0));
+        assert_eq!(balances(&client, payee).await, (350, 0));
+        let posted: Vec<i64> = ledger::get_account_entries(&client, payer)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.transaction_id == authorization.id)
+            .map(|entry| entry.amount)
+            .collect();
+        assert_eq!(posted, vec![250]);
+        assert_eq!(hold_status(&client, authorization.id).await, (HoldStatus::Captured, Some(250)));
+        let error = transactions::capture_transaction(&mut client, payer_id, authorization.id, None).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("already captured")));
+
+        // Voiding releases the reserved funds without moving them
+        let authorization = transactions::authorize_transaction(&mut client, payer_id, &transfer(payer, payee, 300), 3600, None)
+            .await
+            .unwrap();
+        assert_eq!(balances(&client, payer).await, (750, 300));
+        let voided = transactions::void_transaction(&mut client, payer_id, authorization.id).await.unwrap();
+        assert_eq!(voided.status, TransactionStatus::Cancelled);
+        assert_eq!(balances(&client, payer).await, (750, 0));
+        assert_eq!(hold_status(&client, authorization.id).await, (HoldStatus::Voided, None));
+
+        // An expired hold can no longer be captured, and is released by the sweep
+        let authorization = transactions::authorize_transaction(&mut client, payer_id, &transfer(payer, payee, 200), 3600, None)
+            .await
+            .unwrap();
+        client
+            .execute(
+                "UPDATE transaction_holds SET expires_at = NOW() - INTERVAL '1 second' WHERE transaction_id = $1",
+                &[&authorization.id],
+            )
+            .await
+            .unwrap();
+        let error = transactions::capture_transaction(&mut client, payer_id, authorization.id, None).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("expired")));
+
+        assert!(transactions::expire_holds(&mut client, 1_000).await.unwrap() >= 1);
+        let expired = transactions::get_transaction_by_id(&client, authorization.id).await.unwrap();
+        assert_eq!(expired.status, TransactionStatus::Cancelled);
+        assert_eq!(balances(&client, payer).await, (750, 0));
+        assert_eq!(balances(&client, payee).await, (350, 0));
+        assert_eq!(hold_status(&client, authorization.id).await, (HoldStatus::Expired, None));
+    }
+}
+
+#[cfg(test)]
+mod refund_tests {
+    use super::fixtures::{balances, parties, test_database, transfer, withdrawal, Parties};
+    use crate::db::transactions;
+    use crate::models::transaction::{TransactionStatus, TransactionType};
+    use crate::utils::error::AppError;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_partial_refunds_never_exceed_the_original() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 600))
+            .await
+            .unwrap();
+
+        let refund = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(200), None)
+            .await
+            .unwrap();
+        assert!(matches!(refund.transaction_type, TransactionType::Refund));
+        assert_eq!((refund.amount, refund.parent_transaction_id), (200, Some(payment.id)));
+        assert_eq!((refund.source_account_id, refund.destination_account_id), (Some(payee), Some(payer)));
+        assert_eq!(balances(&client, payer).await, (600, 0));
+        assert_eq!(balances(&client, payee).await, (500, 0));
+
+        let parent = transactions::get_transaction_by_id(&client, payment.id).await.unwrap();
+        assert_eq!((parent.status, parent.refunded_amount), (TransactionStatus::Completed, 200));
+
+        // Partials may add up to the original amount, but no further
+        transactions::refund_transaction(&mut client, payee_id, payment.id, Some(300), None)
+            .await
+            .unwrap();
+        let error = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(200), None).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("refundable amount of 100")));
+        assert_eq!(balances(&client, payee).await, (200, 0));
+
+        // Without an amount the rest is refunded
+        let rest = transactions::refund_transaction(&mut client, payee_id, payment.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(rest.amount, 100);
+        let error = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(1), None).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("fully refunded")));
+
+        let refunds = transactions::get_refunds(&client, payment.id).await.unwrap();
+        assert_eq!(refunds.iter().map(|refund| refund.amount).sum::<i64>(), 600);
+        assert_eq!(balances(&client, payer).await, (1_000, 0));
+        assert_eq!(balances(&client, payee).await, (100, 0));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_refunds_need_a_completed_transaction_and_available_funds() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+
+        // An authorization has not moved any money yet
+        let authorization = transactions::authorize_transaction(&mut client, payer_id, &transfer(payer, payee, 300), 3600, None)
+            .await
+            .unwrap();
+        let error = transactions::refund_transaction(&mut client, payee_id, authorization.id, None, None).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("Only completed")));
+
+        // Funds the payee has reserved elsewhere cannot be refunded
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 500))
+            .await
+            .unwrap();
+        transactions::authorize_transaction(&mut client, payee_id, &withdrawal(payee, 550), 3600, None)
+            .await
+            .unwrap();
+        assert_eq!(balances(&client, payee).await, (600, 550));
+        let error = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(100), None).await;
+        assert!(matches!(error, Err(AppError::InsufficientFunds(_))));
+
+        let parent = transactions::get_transaction_by_id(&client, payment.id).await.unwrap();
+        assert_eq!(parent.refunded_amount, 0);
+        assert!(transactions::get_refunds(&client, payment.id).await.unwrap().is_empty());
+        assert_eq!(balances(&client, payer).await, (500, 300));
+    }
+}
+
+#[cfg(test)]
+mod reversal_tests {
+    use super::fixtures::{balances, parties, test_database, transfer, withdrawal, Parties};
+    use crate::db::{ledger, transactions};
+    use crate::models::ledger::EntryType;
+    use crate::models::transaction::{ReversalKind, ReverseTransactionRequest, TransactionStatus};
+    use crate::utils::error::AppError;
+    use uuid::Uuid;
+
+    async fn entries(client: &deadpool_postgres::Client, account_id: Uuid, transaction_id: Uuid) -> Vec<(EntryType, i64)> {
+        ledger::get_account_entries(client, account_id)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.transaction_id == transaction_id)
+            .map(|entry| (entry.entry_type, entry.amount))
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_reversal_restores_balances_once() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 400))
+            .await
+            .unwrap();
+        transactions::create_transaction(&mut client, payee_id, &withdrawal(payee, 450))
+            .await
+            .unwrap();
+
+        // The payee has spent the money, so the chargeback leaves them overdrawn
+        let chargeback = ReverseTransactionRequest {
+            kind: ReversalKind::Chargeback,
+            reason_code: "fraud".to_string(),
+            note: Some("Card reported stolen".to_string()),
+        };
+        let reversed = transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &chargeback)
+            .await
+            .unwrap();
+        assert_eq!(reversed.status, TransactionStatus::Reversed);
+        assert_eq!(balances(&client, payer).await, (1_000, 0));
+        assert_eq!(balances(&client, payee).await, (-350, 0));
+
+        // The original entries stay, offset by compensating ones
+        assert_eq!(
+            entries(&client, payer, payment.id).await,
+            vec![(EntryType::Debit, 400), (EntryType::Credit, 400)]
+        );
+        assert_eq!(
+            entries(&client, payee, payment.id).await,
+            vec![(EntryType::Credit, 400), (EntryType::Debit, 400)]
+        );
+        assert_eq!(ledger::get_ledger_balance(&client, payee).await.unwrap(), -350);
+
+        let error = transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &chargeback).await;
+        assert!(matches!(error, Err(AppError::InvalidState(_))));
+        assert_eq!(balances(&client, payer).await, (1_000, 0));
+        assert_eq!(entries(&client, payer, payment.id).await.len(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_reversed_refund_is_refundable_and_reversed_with_its_parent() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 400))
+            .await
+            .unwrap();
+        let refund = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(150), None)
+            .await
+            .unwrap();
+        let chargeback = ReverseTransactionRequest {
+            kind: ReversalKind::Chargeback,
+            reason_code: "duplicate".to_string(),
+            note: None,
+        };
+
+        // The refunded money goes back to the payee and becomes refundable again
+        transactions::reverse_transaction(&mut client, Uuid::new_v4(), refund.id, &chargeback)
+            .await
+            .unwrap();
+        assert_eq!(balances(&client, payer).await, (600, 0));
+        assert_eq!(balances(&client, payee).await, (500, 0));
+        let parent = transactions::get_transaction_by_id(&client, payment.id).await.unwrap();
+        assert_eq!(parent.refunded_amount, 0);
+
+        // Reversing the parent now unwinds the whole payment
+        let reversed = transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &chargeback)
+            .await
+            .unwrap();
+        assert_eq!(reversed.status, TransactionStatus::Reversed);
+        assert_eq!(balances(&client, payer).await, (1_000, 0));
+        assert_eq!(balances(&client, payee).await, (100, 0));
+
+        // Once the parent is reversed its remaining refunds stay as they are
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 100))
+            .await
+            .unwrap();
+        let refund = transactions::refund_transaction(&mut client, payee_id, payment.id, Some(50), None)
+            .await
+            .unwrap();
+        transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &chargeback)
+            .await
+            .unwrap();
+        let error = transactions::reverse_transaction(&mut client, Uuid::new_v4(), refund.id, &chargeback).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+        assert_eq!(balances(&client, payer).await, (1_000, 0));
+        assert_eq!(balances(&client, payee).await, (100, 0));
+    }
+}
+
+#[cfg(test)]
+mod ledger_tests {
+    use crate::models::ledger::{ensure_balanced, EntryType, LedgerAccount, Posting, SystemAccount};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_balanced_postings() {
+        let account_id = Uuid::new_v4();
+
+        let deposit = vec![
+            Posting::debit(LedgerAccount::System(SystemAccount::DepositClearing), 1000, "USD"),
+            Posting::credit(LedgerAccount::Customer(account_id), 1000, "USD"),
+        ];
+        assert!(ensure_balanced(&deposit).is_ok());
+        assert_eq!(deposit[1].balance_delta(), 1000);
+        assert_eq!(deposit[0].balance_delta(), -1000);
+    }
+
+    #[test]
+    fn test_unbalanced_postings_are_rejected() {
+        let source = Uuid::new_v4();
+        let destination = Uuid::new_v4();
+
+        // Amounts differ
+        let unbalanced = vec![
+            Posting::debit(LedgerAccount::Customer(source), 1000, "USD"),
+            Posting::credit(LedgerAccount::Customer(destination), 900, "USD"),
+        ];
+        assert_eq!(
+            ensure_balanced(&unbalanced),
+            Err("Unbalanced ledger posting in USD: debits exceed credits by 100".to_string())
+        );
+        let unbalanced = vec![
+            Posting::debit(LedgerAccount::Customer(source), 900, "USD"),
+            Posting::credit(LedgerAccount::Customer(destination), 1000, "USD"),
+        ];
+        assert_eq!(
+            ensure_balanced(&unbalanced),
+            Err("Unbalanced ledger posting in USD: credits exceed debits by 100".to_string())
+        );
+
+        // Balanced in total but not per currency
+        let mixed_currency = vec![
+            Posting::debit(LedgerAccount::Customer(source), 1000, "USD"),
+            Posting::credit(LedgerAccount::Customer(destination), 1000, "EUR"),
+        ];
+        assert!(ensure_balanced(&mixed_currency).is_err());
+
+        // Empty and non-positive postings
+        assert!(ensure_balanced(&[]).is_err());
+        let zero = vec![
+            Posting::debit(LedgerAccount::Customer(source), 0, "USD"),
+            Posting::credit(LedgerAccount::Customer(destination), 0, "USD"),
+        ];
+        assert!(ensure_balanced(&zero).is_err());
+    }
+
+    #[test]
+    fn test_entry_type_parsing() {
+        assert_eq!(EntryType::try_from("debit"), Ok(EntryType::Debit));
+        assert_eq!(EntryType::try_from("CREDIT"), Ok(EntryType::Credit));
+        assert_eq!(EntryType::try_from(EntryType::Credit.to_string().as_str()), Ok(EntryType::Credit));
+        // A corrupt row is an error rather than a debit
+        assert!(EntryType::try_from("refund").is_err());
+    }
+}
+
+#[cfg(test)]
+mod fx_tests {
+    use super::fixtures::{balances, create_funded_account, create_test_user, test_database, transfer};
+    use crate::db::{fx, transactions};
+    use crate::models::fx::{convert, parse_rates_csv, CreateFxRateRequest, FxRate};
+    use crate::utils::error::AppError;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    #[test]
+    fn test_convert_applies_spread() {
+        let rate = Decimal::from_str("0.9").unwrap();
+
+        let conversion = convert(10_000, "USD", "EUR", rate, 0).unwrap();
+        assert_eq!(conversion.destination_amount, 9_000);
+        assert_eq!(conversion.spread_amount, 0);
+
+        // 50bps off the mid rate is kept as spread
+        let conversion = convert(10_000, "USD", "EUR", rate, 50).unwrap();
+        assert_eq!(conversion.rate, Decimal::from_str("0.8955").unwrap());
+        assert_eq!(conversion.destination_amount, 8_955);
+        assert_eq!(conversion.spread_amount, 45);
+    }
+
+    #[test]
+    fn test_convert_rounds_down_and_rejects_tiny_amounts() {
+        let rate = Decimal::from_str("1.2345").unwrap();
+        assert_eq!(convert(101, "USD", "EUR", rate, 0).unwrap().destination_amount, 124);
+
+        let rate = Decimal::from_str("0.001").unwrap();
+        assert!(convert(999, "USD", "EUR", rate, 0).is_err());
+        assert!(convert(0, "USD", "EUR", Decimal::ONE, 0).is_err());
+        assert!(convert(100, "USD", "EUR", Decimal::ONE, 10_000).is_err());
+    }
+
+    #[test]
+    fn test_convert_between_minor_units() {
+        // 1 USD = 150 JPY, so 12.34 USD (1234) is 1851 JPY
+        let rate = Decimal::from_str("150").unwrap();
+        assert_eq!(convert(1234, "USD", "JPY", rate, 0).unwrap().destination_amount, 1851);
+
+        // 1000 JPY at 1/150 is 6.66 USD (666)
+        let rate = Decimal::from_str("0.0066666667").unwrap();
+        assert_eq!(convert(1000, "JPY", "USD", rate, 0).unwrap().destination_amount, 666);
+
+        // 1 KWD = 3.25 USD, so 1.000 KWD (1000) is 3.25 USD (325)
+        let rate = Decimal::from_str("3.25").unwrap();
+        assert_eq!(convert(1000, "KWD", "USD", rate, 0).unwrap().destination_amount, 325);
+
+        assert!(convert(1000, "USD", "XYZ", Decimal::ONE, 0).is_err());
+    }
+
+    #[test]
+    fn test_inverse_rate() {
+        let rate = FxRate {
+            id: Uuid::new_v4(),
+            base_currency: "USD".to_string(),
+            quote_currency: "EUR".to_string(),
+            rate: Decimal::from_str("0.8").unwrap(),
+            spread_bps: 0,
+            valid_from: Utc::now(),
+            valid_to: None,
+            created_at: Utc::now(),
+        };
+
+        assert_eq!(rate.rate_from("USD"), Decimal::from_str("0.8").unwrap());
+        assert_eq!(rate.rate_from("EUR"), Decimal::from_str("1.25").unwrap());
+    }
+
+    #[test]
+    fn test_parse_rates_csv() {
+        let csv = "# daily rates\n\
+                   base_currency,quote_currency,rate,spread_bps,valid_from\n\
+                   usd,EUR,0.9215,25,2026-01-01T00:00:00Z\n\
+                   \n\
+                   GBP,USD,1.27,,\n";
+        let rates = parse_rates_csv(csv).unwrap();
+
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].base_currency, "USD");
+        assert_eq!(rates[0].rate, Decimal::from_str("0.9215").unwrap());
+        assert_eq!(rates[0].spread_bps, 25);
+        assert!(rates[0].valid_from.is_some());
+        assert_eq!(rates[1].spread_bps, 0);
+        assert_eq!(rates[1].valid_from, None);
+    }
+
+    #[test]
+    fn test_parse_rates_csv_errors() {
+        assert!(parse_rates_csv("").is_err());
+        assert!(parse_rates_csv("base_currency,quote_currency\nUSD,EUR\n").is_err());
+        assert!(parse_rates_csv("base_currency,quote_currency,rate\n").is_err());
+
+        let error = parse_rates_csv("base_currency,quote_currency,rate\nUSD,EUR,0.9\nUSD,GBP,abc\n").unwrap_err();
+        assert!(error.starts_with("line 3:"), "{}", error);
+
+        let error = parse_rates_csv("base_currency,quote_currency,rate\nUSD,EUR\n").unwrap_err();
+        assert!(error.starts_with("line 2:"), "{}", error);
+    }
+
+    #[test]
+    fn test_rate_request_validation() {
+        let request = |base: &str, quote: &str, rate: &str| CreateFxRateRequest {
+            base_currency: base.to_string(),
+            quote_currency: quote.to_string(),
+            rate: Decimal::from_str(rate).unwrap(),
+            spread_bps: 0,
+            valid_from: None,
+            valid_to: None,
+        };
+
+        assert!(request("USD", "EUR", "0.9").validate().is_ok());
+        assert!(request("USD", "usd", "0.9").validate().is_err());
+        assert!(request("USD", "EUR", "0").validate().is_err());
+        assert!(request("USD", "EUR", "-1").validate().is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_cross_currency_transfer_with_quote() {
+        let db = test_database();
+        let payer_id = create_test_user(&db).await;
+        let payee_id = create_test_user(&db).await;
+        let payer = create_funded_account(&db, payer_id, "USD", 10_000).await;
+        let payee = create_funded_account(&db, payee_id, "EUR", 100).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let rate = CreateFxRateRequest {
+            base_currency: "USD".to_string(),
+            quote_currency: "EUR".to_string(),
+            rate: Decimal::from_str("0.9").unwrap(),
+            spread_bps: 100,
+            valid_from: None,
+            valid_to: None,
+        };
+        fx::create_rate(&client, &rate).await.unwrap();
+        let quote = fx::create_quote(&client, payer_id, "USD", "EUR", 1_000, 60).await.unwrap();
+        assert_eq!((quote.destination_amount, quote.spread_amount), (891, 9));
+
+        // The transfer executes at exactly the quoted amounts
+        let mut request = transfer(payer, payee, 1_000);
+        request.fx_quote_id = Some(quote.id);
+        let converted = transactions::create_transaction(&mut client, payer_id, &request).await.unwrap();
+        assert_eq!(converted.destination_amount, Some(891));
+        assert_eq!(converted.destination_currency.as_deref(), Some("EUR"));
+        assert_eq!(converted.fx_quote_id, Some(quote.id));
+        assert_eq!(balances(&client, payer).await, (9_000, 0));
+        assert_eq!(balances(&client, payee).await, (991, 0));
+
+        // Each currency balances through the clearing account
+        let legs: Vec<(String, i64, String)> = client
+            .query(
+                "SELECT entry_type, amount, currency FROM ledger_entries
+                 WHERE transaction_id = $1 AND system_account = 'fx_clearing'
+                 ORDER BY currency",
+                &[&converted.id],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get("entry_type"), row.get("amount"), row.get("currency")))
+            .collect();
+        assert_eq!(
+            legs,
+            vec![
+                ("debit".to_string(), 891, "EUR".to_string()),
+                ("credit".to_string(), 1_000, "USD".to_string()),
+            ]
+        );
+
+        // A quote is good for one transfer, and only until it expires
+        let error = transactions::create_transaction(&mut client, payer_id, &request).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("already been used")));
+
+        let quote = fx::create_quote(&client, payer_id, "USD", "EUR", 1_000, 60).await.unwrap();
+        client
+            .execute("UPDATE fx_quotes SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1", &[&quote.id])
+            .await
+            .unwrap();
+        request.fx_quote_id = Some(quote.id);
+        let error = transactions::create_transaction(&mut client, payer_id, &request).await;
+        assert!(matches!(error, Err(AppError::BadRequest(ref message)) if message.contains("expired")));
+        assert_eq!(balances(&client, payer).await, (9_000, 0));
+        assert_eq!(balances(&client, payee).await, (991, 0));
+    }
+}
+
+#[cfg(test)]
+mod currency_tests {
+    use crate::models::account::CreateAccountRequest;
+    use crate::utils::currency::{find, format_amount, validate_currency_code, CURRENCIES};
+    use validator::Validate;
+
+    #[test]
+    fn test_currency_lookup() {
+        assert_eq!(find("USD").unwrap().minor_units, 2);
+        assert_eq!(find("JPY").unwrap().minor_units, 0);
+        assert_eq!(find("KWD").unwrap().minor_units, 3);
+        assert_eq!(find("CLF").unwrap().minor_units, 4);
+        assert!(find("XYZ").is_none());
+        assert!(find("usd").is_none());
+
+        assert!(validate_currency_code("EUR").is_ok());
+        assert!(validate_currency_code("XYZ").is_err());
+    }
+
+    #[test]
+    fn test_registry_has_unique_codes() {
+        for (index, currency) in CURRENCIES.iter().enumerate() {
+            assert_eq!(currency.code.len(), 3);
+            assert!(currency.code.chars().all(|c| c.is_ascii_uppercase()));
+            assert!(
+                CURRENCIES[index + 1..].iter().all(|other| other.code != currency.code),
+                "duplicate currency {}",
+                currency.code
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(1050, "USD").as_deref(), Some("10.50"));
+        assert_eq!(format_amount(5, "USD").as_deref(), Some("0.05"));
+        assert_eq!(format_amount(-1050, "EUR").as_deref(), Some("-10.50"));
+        assert_eq!(format_amount(1050, "JPY").as_deref(), Some("1050"));
+        assert_eq!(format_amount(1050, "KWD").as_deref(), Some("1.050"));
+        assert_eq!(format_amount(0, "USD").as_deref(), Some("0.00"));
+        assert_eq!(format_amount(1050, "XYZ"), None);
+    }
+
+    #[test]
+    fn test_account_currency_validation() {
+        let request = |currency: &str| CreateAccountRequest {
+            currency: currency.to_string(),
+            label: None,
+            purpose: Default::default(),
+        };
+
+        assert!(request("USD").validate().is_ok());
+        assert!(request("XYZ").validate().is_err());
+        assert!(request("US").validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod scheduled_transfer_tests {
+    use super::fixtures::{balances, held_event_data, parties, set_full_name, test_database, Parties, Screener};
+    use crate::db::scheduled_transfers::{self, RunOutcome};
+    use crate::db::transactions::{self, Screening};
+    use crate::models::risk::RiskPolicy;
+    use crate::models::scheduled_transfer::{
+        CreateScheduledTransferRequest, RunStatus, Schedule, ScheduleStatus, UpdateScheduledTransferRequest,
+    };
+    use crate::models::transaction::TransactionStatus;
+    use crate::models::watchlist::{parse_watchlist_csv, Watchlist};
+    use crate::utils::cron::CronSchedule;
+    use chrono::{DateTime, Duration, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    const RETRY_DELAY: i64 = 3600;
+    const MAX_FAILURES: i32 = 3;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> Option<DateTime<Utc>> {
+        expression.parse::<CronSchedule>().unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn test_cron_parsing() {
+        assert!("* * * * *".parse::<CronSchedule>().is_ok());
+        assert!("*/15 9-17 * JAN-JUN mon-fri".parse::<CronSchedule>().is_ok());
+        assert!("0 0 1,15 * 7".parse::<CronSchedule>().is_ok());
+
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * 0 * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * * FOO *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        // Monthly on the 1st at 09:00
+        assert_eq!(next("0 9 1 * *", "2026-01-15T12:00:00Z"), Some(at("2026-02-01T09:00:00Z")));
+        assert_eq!(next("0 9 1 * *", "2026-12-01T09:00:00Z"), Some(at("2027-01-01T09:00:00Z")));
+        // Strictly after, at minute resolution
+        assert_eq!(next("* * * * *", "2026-01-01T00:00:00Z"), Some(at("2026-01-01T00:01:00Z")));
+        assert_eq!(next("* * * * *", "2026-01-01T00:00:30Z"), Some(at("2026-01-01T00:01:00Z")));
+        // Steps
+        assert_eq!(next("*/15 * * * *", "2026-01-01T10:16:00Z"), Some(at("2026-01-01T10:30:00Z")));
+        // 2026-01-01 is a Thursday, so the next Monday is the 5th
+        assert_eq!(next("0 8 * * MON", "2026-01-01T00:00:00Z"), Some(at("2026-01-05T08:00:00Z")));
+        // Sunday as 7
+        assert_eq!(next("0 0 * * 7", "2026-01-01T00:00:00Z"), Some(at("2026-01-04T00:00:00Z")));
+        // Leap day
+        assert_eq!(next("0 0 29 2 *", "2026-01-01T00:00:00Z"), Some(at("2028-02-29T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_cron_day_fields_match_either() {
+        // The 15th or any Monday, whichever comes first
+        assert_eq!(next("0 0 15 * MON", "2026-01-01T00:00:00Z"), Some(at("2026-01-05T00:00:00Z")));
+        assert_eq!(next("0 0 15 * MON", "2026-01-12T00:00:00Z"), Some(at("2026-01-15T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_cron_impossible_date() {
+        assert_eq!(next("0 0 30 2 *", "2026-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_interval_schedule() {
+        let schedule = Schedule::new(None, Some(3600)).unwrap();
+        let start = at("2026-01-01T00:00:00Z");
+
+        assert_eq!(schedule.next_occurrence(start, None, start - Duration::days(1)), Some(start));
+        assert_eq!(schedule.next_occurrence(start, None, start), Some(at("2026-01-01T01:00:00Z")));
+        assert_eq!(
+            schedule.next_occurrence(start, None, at("2026-01-01T05:30:00Z")),
+            Some(at("2026-01-01T06:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_occurrence(start, Some(at("2026-01-01T06:00:00Z")), at("2026-01-01T05:30:00Z")),
+            Some(at("2026-01-01T06:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_occurrence(start, Some(at("2026-01-01T05:59:00Z")), at("2026-01-01T05:30:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cron_schedule_respects_start() {
+        let schedule = Schedule::new(Some("0 9 * * *"), None).unwrap();
+
+        assert_eq!(
+            schedule.next_occurrence(at("2026-03-01T09:00:00Z"), None, at("2026-01-01T00:00:00Z")),
+            Some(at("2026-03-01T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_create_request_validation() {
+        let request = |cron_expression: Option<&str>, interval_seconds: Option<i64>| CreateScheduledTransferRequest {
+            source_account_id: Uuid::new_v4(),
+            destination_account_id: Uuid::new_v4(),
+            amount: 1000,
+            currency: "USD".to_string(),
+            description: None,
+            cron_expression: cron_expression.map(str::to_string),
+            interval_seconds,
+            start_at: None,
+            end_at: None,
+            max_occurrences: None,
+        };
+
+        assert!(request(Some("0 9 1 * *"), None).validate().is_ok());
+        assert!(request(None, Some(86400)).validate().is_ok());
+        assert!(request(None, None).validate().is_err());
+        assert!(request(Some("0 9 1 * *"), Some(86400)).validate().is_err());
+        assert!(request(Some("not cron"), None).validate().is_err());
+        assert!(request(None, Some(30)).validate().is_err());
+
+        let mut ends_before_start = request(None, Some(86400));
+        ends_before_start.start_at = Some(at("2026-02-01T00:00:00Z"));
+        ends_before_start.end_at = Some(at("2026-01-01T00:00:00Z"));
+        assert!(ends_before_start.validate().is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_update_replaces_the_schedule_and_keeps_other_fields() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee, .. } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 400))
+            .await
+            .unwrap();
+        let update = |value| serde_json::from_value::<UpdateScheduledTransferRequest>(value).unwrap();
+
+        // Giving both kinds of schedule at once is ambiguous
+        assert!(update(json!({"cron_expression": "0 9 * * *", "interval_seconds": 7200})).validate().is_err());
+
+        // A cron expression replaces the interval
+        let updated = scheduled_transfers::update_scheduled_transfer(
+            &mut client,
+            payer_id,
+            schedule.id,
+            &update(json!({"cron_expression": "0 9 * * *"})),
+        )
+        .await
+        .unwrap();
+        assert_eq!((updated.cron_expression.as_deref(), updated.interval_seconds), (Some("0 9 * * *"), None));
+
+        // Other changes leave the schedule alone
+        let updated = scheduled_transfers::update_scheduled_transfer(
+            &mut client,
+            payer_id,
+            schedule.id,
+            &update(json!({"amount": 300})),
+        )
+        .await
+        .unwrap();
+        assert_eq!((updated.cron_expression.as_deref(), updated.interval_seconds), (Some("0 9 * * *"), None));
+        assert_eq!(updated.amount, 300);
+    }
+
+    fn hourly(source_account_id: Uuid, destination_account_id: Uuid, amount: i64) -> CreateScheduledTransferRequest {
+        CreateScheduledTransferRequest {
+            source_account_id,
+            destination_account_id,
+            amount,
+            currency: "USD".to_string(),
+            description: None,
+            cron_expression: None,
+            interval_seconds: Some(3600),
+            start_at: None,
+            end_at: None,
+            max_occurrences: None,
+        }
+    }
+
+    /// Runs due transfers until `id` has been run, since schedules left in the
+    /// shared database may come due first.
+    async fn run(client: &mut deadpool_postgres::Client, id: Uuid, screening: &Screening<'_>) -> RunOutcome {
+        client
+            .execute(
+                "UPDATE scheduled_transfers SET next_run_at = NOW() - INTERVAL '1 second' WHERE id = $1",
+                &[&id],
+            )
+            .await
+            .unwrap();
+        loop {
+            let outcome = scheduled_transfers::run_next_due(client, RETRY_DELAY, MAX_FAILURES, screening)
+                .await
+                .unwrap()
+                .expect("the schedule should be due");
+            if outcome.scheduled_transfer.id == id {
+                return outcome;
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_run_next_due_records_every_attempt() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee, .. } = parties(&db, 1_000, 100).await;
+        let screener = Screener::lenient();
+        let screening = screener.screening();
+        let mut client = db.pool.get().await.unwrap();
+
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 400))
+            .await
+            .unwrap();
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.run.status, RunStatus::Succeeded);
+        assert!(outcome.run.transaction_id.is_some());
+        assert_eq!(outcome.scheduled_transfer.occurrences, 1);
+        assert!(outcome.scheduled_transfer.next_run_at.unwrap() > Utc::now());
+        run(&mut client, schedule.id, &screening).await;
+        assert_eq!(balances(&client, payer).await, (200, 0));
+
+        // Running short of funds is retried later, until the schedule is paused
+        for failures in 1..MAX_FAILURES {
+            let outcome = run(&mut client, schedule.id, &screening).await;
+            assert_eq!(outcome.run.status, RunStatus::Failed);
+            assert_eq!(outcome.scheduled_transfer.consecutive_failures, failures);
+            assert_eq!(outcome.scheduled_transfer.status, ScheduleStatus::Active);
+            let retry_at = outcome.scheduled_transfer.next_run_at.unwrap();
+            assert!(retry_at > Utc::now() + Duration::seconds(RETRY_DELAY - 60));
+        }
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.scheduled_transfer.status, ScheduleStatus::Paused);
+        assert_eq!(outcome.scheduled_transfer.next_run_at, None);
+        assert_eq!(balances(&client, payer).await, (200, 0));
+
+        let runs = scheduled_transfers::get_runs(&client, schedule.id).await.unwrap();
+        let failed = runs.iter().filter(|run| run.status == RunStatus::Failed).count();
+        assert_eq!((runs.len(), failed), (2 + MAX_FAILURES as usize, MAX_FAILURES as usize));
+
+        // A server error is recorded and retried too, rather than blocking the worker
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 100))
+            .await
+            .unwrap();
+        let mut blocker = db.pool.get().await.unwrap();
+        let lock = blocker.transaction().await.unwrap();
+        lock.execute("SELECT 1 FROM accounts WHERE id = $1 FOR UPDATE", &[&payer])
+            .await
+            .unwrap();
+        client.execute("SET lock_timeout = '200ms'", &[]).await.unwrap();
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        client.execute("RESET lock_timeout", &[]).await.unwrap();
+        lock.rollback().await.unwrap();
+        assert_eq!(outcome.run.status, RunStatus::Failed);
+        assert!(outcome.run.error.unwrap().contains("lock timeout"));
+        assert_eq!(outcome.scheduled_transfer.consecutive_failures, 1);
+        assert_eq!(outcome.scheduled_transfer.status, ScheduleStatus::Active);
+
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.run.status, RunStatus::Succeeded);
+        assert_eq!(outcome.scheduled_transfer.consecutive_failures, 0);
+        assert_eq!(balances(&client, payer).await, (100, 0));
+        scheduled_transfers::cancel_scheduled_transfer(&mut client, payer_id, schedule.id)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_scheduled_transfers_are_risk_screened() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee, .. } = parties(&db, 10_000, 1).await;
+        let policy: RiskPolicy = serde_json::from_value(json!({
+            "rules": [
+                { "type": "amount_threshold", "min_amount": 1_000, "score": 60 },
+                { "type": "amount_threshold", "min_amount": 5_000, "score": 60 },
+            ],
+        }))
+        .unwrap();
+        let screener = Screener::new(&policy, Watchlist::default());
+        let screening = screener.screening();
+        let mut client = db.pool.get().await.unwrap();
+
+        // A held transfer counts as run, with its funds reserved until it is reviewed
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 2_000))
+            .await
+            .unwrap();
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.run.status, RunStatus::Succeeded);
+        assert_eq!(outcome.scheduled_transfer.status, ScheduleStatus::Active);
+        let held = transactions::get_transaction_by_id(&client, outcome.run.transaction_id.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(held.status, TransactionStatus::UnderReview);
+        assert!(held_event_data(&client, held.id).await["review_id"].is_string());
+        assert_eq!(balances(&client, payer).await, (10_000, 2_000));
+        scheduled_transfers::cancel_scheduled_transfer(&mut client, payer_id, schedule.id)
+            .await
+            .unwrap();
+
+        // A declined one pauses the schedule, since retrying will not help
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 6_000))
+            .await
+            .unwrap();
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.run.status, RunStatus::Failed);
+        assert!(outcome.run.error.unwrap().contains("declined by risk checks"));
+        assert_eq!(outcome.scheduled_transfer.status, ScheduleStatus::Paused);
+        assert_eq!(balances(&client, payer).await, (10_000, 2_000));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_scheduled_transfers_are_watchlist_screened() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 1).await;
+        let watchlist = Watchlist::new(parse_watchlist_csv("name\nMarta Kowalczyk\n").unwrap());
+        let screener = Screener::new(&RiskPolicy::default(), watchlist);
+        let screening = screener.screening();
+        let mut client = db.pool.get().await.unwrap();
+        set_full_name(&client, payee_id, "Marta Kowalczyk").await;
+
+        // A hit holds the transfer for compliance rather than failing the run
+        let schedule = scheduled_transfers::create_scheduled_transfer(&client, payer_id, &hourly(payer, payee, 300))
+            .await
+            .unwrap();
+        let outcome = run(&mut client, schedule.id, &screening).await;
+        assert_eq!(outcome.run.status, RunStatus::Succeeded);
+        let held = transactions::get_transaction_by_id(&client, outcome.run.transaction_id.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(held.status, TransactionStatus::UnderReview);
+        let event_data = held_event_data(&client, held.id).await;
+        assert_eq!(event_data["action"], "watchlist_hold");
+        assert_eq!(event_data["watchlist"]["matched_name"], "Marta Kowalczyk");
+        assert_eq!(balances(&client, payer).await, (1_000, 300));
+        scheduled_transfers::cancel_scheduled_transfer(&mut client, payer_id, schedule.id)
+            .await
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod webhook_tests {
+    use crate::models::webhook::{
+        check_endpoint_url, CreateWebhookEndpointRequest, RetryPolicy, WebhookDelivery, WebhookDeliveryStatus,
+        WebhookEndpoint, WebhookEventType,
+    };
+    use crate::services::webhook_service::{self, WebhookSender, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+    use axum::{
+        Router,
+        body::Bytes,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+    };
+    use chrono::Utc;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+    use validator::Validate;
+
+    fn endpoint(url: String) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            url,
+            description: None,
+            secret: "whsec_test".to_string(),
+            event_types: vec![WebhookEventType::TransactionCompleted],
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn delivery() -> WebhookDelivery {
+        WebhookDelivery {
+            id: Uuid::new_v4(),
+            endpoint_id: Uuid::new_v4(),
+            event_id: Uuid::new_v4(),
+            event_type: "transaction.completed".to_string(),
+            payload: json!({ "type": "transaction.completed", "data": { "amount": 1000 } }),
+            status: WebhookDeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: Utc::now(),
+            last_response_status: None,
+            last_error: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Starts a local receiver that records what it is sent and answers with
+    /// `status`, returning its URL.
+    async fn stand_in(status: StatusCode, received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>) -> String {
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                received.lock().unwrap().push((headers, body));
+                status
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}/hook", address)
+    }
+
+    #[test]
+    fn test_event_types() {
+        for event_type in [
+            WebhookEventType::TransactionCompleted,
+            WebhookEventType::TransactionFailed,
+            WebhookEventType::TransactionCancelled,
+            WebhookEventType::TransactionReversed,
+            WebhookEventType::AccountSuspended,
+        ] {
+            assert_eq!(WebhookEventType::try_from(event_type.to_string().as_str()), Ok(event_type));
+            assert_eq!(serde_json::to_value(event_type).unwrap(), json!(event_type.to_string()));
+        }
+        assert!(WebhookEventType::try_from("transaction.created").is_err());
+    }
+
+    #[test]
+    fn test_endpoint_request_validation() {
+        let request = |url: &str, event_types: &[&str]| CreateWebhookEndpointRequest {
+            url: url.to_string(),
+            description: None,
+            event_types: event_types.iter().map(|event_type| event_type.to_string()).collect(),
+        };
+
+        assert!(request("https://example.com/hooks", &["transaction.completed"]).validate().is_ok());
+        assert!(request("http://localhost:8080/hooks", &["account.suspended"]).validate().is_ok());
+        assert!(request("ftp://example.com/hooks", &["transaction.completed"]).validate().is_err());
+        assert!(request("not a url", &["transaction.completed"]).validate().is_err());
+        assert!(request("https://example.com/hooks", &[]).validate().is_err());
+        assert!(request("https://example.com/hooks", &["transaction.created"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_endpoint_urls_must_be_public_https() {
+        let allowed = vec!["localhost".to_string(), "::1".to_string()];
+
+        assert!(check_endpoint_url("https://example.com/hooks", &[]).is_ok());
+        assert!(check_endpoint_url("https://93.184.216.34/hooks", &[]).is_ok());
+        assert!(check_endpoint_url("http://example.com/hooks", &[]).is_err());
+        for url in [
+            "https://localhost/hooks",
+            "https://api.localhost./hooks",
+            "https://127.0.0.1/hooks",
+            "https://10.1.2.3/hooks",
+            "https://172.16.0.1/hooks",
+            "https://192.168.1.1/hooks",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hooks",
+            "https://0.0.0.0/hooks",
+            "https://[::1]/hooks",
+            "https://[fd00::1]/hooks",
+            "https://[fe80::1]/hooks",
+            "https://[::ffff:127.0.0.1]/hooks",
+        ] {
+            assert!(check_endpoint_url(url, &[]).is_err(), "{} should be refused", url);
+        }
+
+        // Configured hosts are exempt, over http too
+        assert!(check_endpoint_url("http://LOCALHOST:8080/hooks", &allowed).is_ok());
+        assert!(check_endpoint_url("http://[::1]:8080/hooks", &allowed).is_ok());
+        assert!(check_endpoint_url("http://127.0.0.1:8080/hooks", &allowed).is_err());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 8,
+            base_delay_seconds: 30,
+        };
+
+        assert_eq!(policy.delay_after(1).num_seconds(), 30);
+        assert_eq!(policy.delay_after(2).num_seconds(), 60);
+        assert_eq!(policy.delay_after(5).num_seconds(), 480);
+        assert_eq!(policy.delay_after(20).num_seconds(), 86400);
+        assert_eq!(policy.delay_after(i32::MAX).num_seconds(), 86400);
+    }
+
+    #[test]
+    fn test_signature() {
+        let body = br#"{"hello":"world"}"#;
+
+        assert_eq!(
+            webhook_service::sign("whsec_test", 1767225600, body),
+            "6e71b585e159805f050878ee829a4a61a8ca5280212d978c7a7f4dd927fc34aa"
+        );
+        assert_eq!(
+            webhook_service::signature_header("whsec_test", 1767225600, body),
+            "t=1767225600,v1=6e71b585e159805f050878ee829a4a61a8ca5280212d978c7a7f4dd927fc34aa"
+        );
+        assert_ne!(
+            webhook_service::sign("whsec_other", 1767225600, body),
+            webhook_service::sign("whsec_test", 1767225600, body)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_signs_delivery() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let url = stand_in(StatusCode::NO_CONTENT, received.clone()).await;
+        let delivery = delivery();
+
+        let result = webhook_service::send(&reqwest::Client::new(), &endpoint(url), &delivery).await;
+        assert_eq!(result.response_status, Some(204));
+        assert_eq!(result.error, None);
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[0];
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(body).unwrap(), delivery.payload);
+        assert_eq!(headers["webhook-id"], delivery.event_id.to_string().as_str());
+        assert_eq!(headers["webhook-event"], "transaction.completed");
+
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            webhook_service::signature_header("whsec_test", timestamp, body)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_failures() {
+        let url = stand_in(StatusCode::INTERNAL_SERVER_ERROR, Arc::default()).await;
+        let result = webhook_service::send(&reqwest::Client::new(), &endpoint(url), &delivery()).await;
+        assert_eq!(result.response_status, Some(500));
+        assert!(result.error.is_some());
+
+        // Nothing is listening on a port that was just released
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+        let result = webhook_service::send(&reqwest::Client::new(), &endpoint(url), &delivery()).await;
+        assert_eq!(result.response_status, None);
+        assert!(result.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sender_only_reaches_allowed_private_hosts() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let url = stand_in(StatusCode::NO_CONTENT, received.clone()).await.replace("127.0.0.1", "localhost");
+
+        // Endpoints saved before the URL rules are refused without a request
+        let result = WebhookSender::new(&[]).send(&endpoint(url.clone()), &delivery()).await;
+        assert_eq!(result.response_status, None);
+        assert!(result.error.unwrap().contains("https"));
+        assert!(received.lock().unwrap().is_empty());
+
+        let result = WebhookSender::new(&["localhost".to_string()])
+            .send(&endpoint(url), &delivery())
+            .await;
+        assert_eq!(result.response_status, Some(204));
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod outbox_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database};
+    use crate::db::outbox;
+    use crate::models::outbox::{parse_event_sinks, AggregateType, EventSinkKind, OutboxEvent};
+    use crate::services::event_relay::{self, BroadcastSink, EventSink};
+    use crate::utils::error::AppError;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    fn event(aggregate_id: Uuid) -> OutboxEvent {
+        OutboxEvent {
+            id: 1,
+            position: 1,
+            event_id: Uuid::new_v4(),
+            aggregate_type: AggregateType::Account,
+            aggregate_id,
+            user_ids: vec![Uuid::new_v4()],
+            event_type: "account.balance_updated".to_string(),
+            payload: json!({}),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Records every event it is given.
+    struct RecordingSink(Arc<Mutex<Vec<OutboxEvent>>>);
+
+    #[async_trait]
+    impl EventSink for RecordingSink {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError> {
+            self.0.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    /// Rejects events about one aggregate while `failing` is set.
+    struct FlakySink {
+        aggregate_id: Uuid,
+        failing: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl EventSink for FlakySink {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn publish(&self, event: &OutboxEvent) -> Result<(), AppError> {
+            if event.aggregate_id == self.aggregate_id && self.failing.load(Ordering::SeqCst) {
+                return Err(AppError::Internal("sink unavailable".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_event_sinks() {
+        assert_eq!(
+            parse_event_sinks("broadcast,webhooks").unwrap(),
+            vec![EventSinkKind::Broadcast, EventSinkKind::Webhooks]
+        );
+        assert_eq!(
+            parse_event_sinks(" Stdout , stdout,").unwrap(),
+            vec![EventSinkKind::Stdout]
+        );
+        assert!(parse_event_sinks("").unwrap().is_empty());
+        assert!(parse_event_sinks("broadcast,kafka").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_type_parsing() {
+        for aggregate_type in [AggregateType::Transaction, AggregateType::Account, AggregateType::PaymentIntent] {
+            assert_eq!(AggregateType::try_from(aggregate_type.to_string().as_str()), Ok(aggregate_type));
+        }
+        assert!(AggregateType::try_from("merchant").is_err());
+        assert!(AggregateType::try_from("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_sink() {
+        let (sender, mut receiver) = broadcast::channel(16);
+        let sink = BroadcastSink::new(sender);
+        let event = event(Uuid::new_v4());
+
+        sink.publish(&event).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().event_id, event.event_id);
+
+        // Publishing with nobody listening is not an error
+        drop(receiver);
+        assert!(sink.publish(&event).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn test_relay_holds_back_events_after_a_failure() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let blocked_account = create_funded_account(&db, user_id, "USD", 1_000).await;
+        let other_account = create_funded_account(&db, user_id, "EUR", 1_000).await;
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let failing = Arc::new(AtomicBool::new(true));
+        let sinks: Vec<Box<dyn EventSink>> = vec![
+            Box::new(FlakySink {
+                aggregate_id: blocked_account,
+                failing: failing.clone(),
+            }),
+            Box::new(RecordingSink(received.clone())),
+        ];
+
+        let mut client = db.pool.get().await.unwrap();
+        while event_relay::relay_batch(&mut client, &sinks, 100).await.unwrap() > 0 {}
+
+        let about = |aggregate_id: Uuid| -> Vec<OutboxEvent> {
+            received
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.aggregate_id == aggregate_id)
+                .cloned()
+                .collect()
+        };
+
+        // The other account's events went out in order; the blocked one's did not
+        let other_events = about(other_account);
+        assert_eq!(
+            other_events.iter().map(|event| event.event_type.as_str()).collect::<Vec<_>>(),
+            vec!["account.created", "account.balance_updated"]
+        );
+        assert!(about(blocked_account).is_empty());
+
+        failing.store(false, Ordering::SeqCst);
+        while event_relay::relay_batch(&mut client, &sinks, 100).await.unwrap() > 0 {}
+
+        let blocked_events = about(blocked_account);
+        assert_eq!(blocked_events.len(), 2);
+        assert!(blocked_events[0].id < blocked_events[1].id);
+        assert_eq!(blocked_events[0].event_type, "account.created");
+        assert!(blocked_events.iter().all(|event| event.user_ids == vec![user_id]));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_event_committed_late_is_positioned_after_earlier_ones() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let aggregate_id = Uuid::new_v4();
+
+        // The first event inserted is the last to commit
+        let mut late_client = db.pool.get().await.unwrap();
+        let late_tx = late_client.transaction().await.unwrap();
+        outbox::record(&late_tx, AggregateType::Account, aggregate_id, &[user_id], "test.late", json!({}))
+            .await
+            .unwrap();
+
+        let client = db.pool.get().await.unwrap();
+        outbox::record(&client, AggregateType::Account, aggregate_id, &[user_id], "test.early", json!({}))
+            .await
+            .unwrap();
+        outbox::assign_positions(&client, 1_000).await.unwrap();
+
+        let early = outbox::get_user_events_after(&client, user_id, 0, 100).await.unwrap();
+        assert_eq!(early.len(), 1);
+        assert_eq!(early[0].event_type, "test.early");
+
+        late_tx.commit().await.unwrap();
+        outbox::assign_positions(&client, 1_000).await.unwrap();
+
+        // A client resuming after the early event still receives the late one
+        let late = outbox::get_user_events_after(&client, user_id, early[0].position, 100).await.unwrap();
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].event_type, "test.late");
+        assert!(late[0].id < early[0].id);
+        assert!(late[0].position > early[0].position);
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database};
+    use super::test_config;
+    use crate::db::{outbox, transactions};
+    use crate::models::outbox::{AggregateType, OutboxEvent};
+    use crate::models::stream::{ClientMessage, ServerMessage, StreamSubscription};
+    use crate::models::user::UserRole;
+    use crate::services::event_relay::{BroadcastSink, EventSink};
+    use crate::utils::jwt::create_token;
+    use axum::extract::Extension;
+    use chrono::Utc;
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+    use tokio::sync::broadcast;
+    use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+    use uuid::Uuid;
+
+    type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    fn event(aggregate_type: AggregateType, aggregate_id: Uuid, user_ids: Vec<Uuid>, payload: serde_json::Value) -> OutboxEvent {
+        OutboxEvent {
+            id: 1,
+            position: 1,
+            event_id: Uuid::new_v4(),
+            aggregate_type,
+            aggregate_id,
+            user_ids,
+            event_type: "transaction.completed".to_string(),
+            payload,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_subscription_matches() {
+        let user_id = Uuid::new_v4();
+        let account_id = Uuid::new_v4();
+        let other_account_id = Uuid::new_v4();
+        let transfer = |source: Uuid, destination: Uuid| {
+            json!({ "transaction": { "source_account_id": source, "destination_account_id": destination } })
+        };
+
+        let everything = StreamSubscription::new(user_id, []);
+        assert!(everything.matches(&event(AggregateType::Account, account_id, vec![user_id], json!({}))));
+        assert!(!everything.matches(&event(AggregateType::Account, account_id, vec![Uuid::new_v4()], json!({}))));
+
+        let one_account = StreamSubscription::new(user_id, [account_id]);
+        assert!(one_account.matches(&event(AggregateType::Account, account_id, vec![user_id], json!({}))));
+        assert!(!one_account.matches(&event(AggregateType::Account, other_account_id, vec![user_id], json!({}))));
+        assert!(one_account.matches(&event(
+            AggregateType::Transaction,
+            Uuid::new_v4(),
+            vec![user_id],
+            transfer(other_account_id, account_id),
+        )));
+        assert!(!one_account.matches(&event(
+            AggregateType::Transaction,
+            Uuid::new_v4(),
+            vec![user_id],
+            json!({ "transaction": { "source_account_id": null, "destination_account_id": other_account_id } }),
+        )));
+    }
+
+    #[test]
+    fn test_client_messages() {
+        let account_id = Uuid::new_v4();
+        let message: ClientMessage =
+            serde_json::from_value(json!({ "type": "subscribe", "account_ids": [account_id] })).unwrap();
+        assert_eq!(message, ClientMessage::Subscribe { account_ids: vec![account_id] });
+
+        assert!(serde_json::from_value::<ClientMessage>(json!({ "type": "resubscribe" })).is_err());
+
+        let reply = serde_json::to_value(ServerMessage::Subscribed { account_ids: vec![] }).unwrap();
+        assert_eq!(reply, json!({ "type": "subscribed", "account_ids": [] }));
+    }
+
+    async fn serve(events: broadcast::Sender<OutboxEvent>) -> String {
+        let app = crate::api::create_router()
+            .with_state(test_config())
+            .layer(Extension(test_database()))
+            .layer(Extension(events));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        address.to_string()
+    }
+
+    fn token(user_id: Uuid) -> String {
+        create_token(user_id, "streamer", "streamer@example.com", &UserRole::User, &test_config()).unwrap()
+    }
+
+    async fn connect(url: &str, user_id: Uuid) -> Result<Socket, tokio_tungstenite::tungstenite::Error> {
+        let mut request = url.into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("authorization", format!("Bearer {}", token(user_id)).parse().unwrap());
+
+        tokio_tungstenite::connect_async(request).await.map(|(socket, _)| socket)
+    }
+
+    async fn next_message(socket: &mut Socket) -> ServerMessage {
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("timed out waiting for a stream message")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = message {
+                return serde_json::from_str(text.as_str()).unwrap();
+            }
+        }
+    }
+
+    async fn send(socket: &mut Socket, message: ClientMessage) {
+        let text = serde_json::to_string(&message).unwrap();
+        socket.send(Message::Text(text.into())).await.unwrap();
+    }
+
+    fn expect_event(message: ServerMessage) -> OutboxEvent {
+        match message {
+            ServerMessage::Event(event) => event,
+            other => panic!("expected an event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn test_stream_replays_then_follows_subscriptions() {
+        let db = test_database();
+        let (sender, _) = broadcast::channel(64);
+        let sink = BroadcastSink::new(sender.clone());
+        let url = format!("ws://{}/api/stream", serve(sender).await);
+
+        let user_id = create_test_user(&db).await;
+        let first_account = create_funded_account(&db, user_id, "USD", 1_000).await;
+        let client = db.pool.get().await.unwrap();
+        outbox::assign_positions(&client, 1_000).await.unwrap();
+        let first_events = outbox::get_user_events_after(&client, user_id, 0, 100).await.unwrap();
+
+        // Another user's account can neither be streamed from the start nor subscribed to
+        let stranger = create_test_user(&db).await;
+        let stranger_account = create_funded_account(&db, stranger, "USD", 1_000).await;
+        let forbidden = connect(&format!("{}?account_ids={}", url, stranger_account), user_id).await;
+        assert!(forbidden.is_err());
+
+        // Resuming from the start replays everything about the account
+        let mut socket = connect(&format!("{}?after=0&account_ids={}", url, first_account), user_id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_message(&mut socket).await,
+            ServerMessage::Subscribed { account_ids } if account_ids == vec![first_account]
+        ));
+        for expected in &first_events {
+            assert_eq!(expect_event(next_message(&mut socket).await).id, expected.id);
+        }
+
+        // Live copies of replayed events are skipped and other accounts filtered out
+        let second_account = create_funded_account(&db, user_id, "EUR", 1_000).await;
+        outbox::assign_positions(&client, 1_000).await.unwrap();
+        let all_events = outbox::get_user_events_after(&client, user_id, 0, 100).await.unwrap();
+        for event in &all_events {
+            sink.publish(event).await.unwrap();
+        }
+        let mut marker = event(AggregateType::Account, first_account, vec![user_id], json!({}));
+        marker.id = i64::MAX;
+        marker.position = i64::MAX;
+        sink.publish(&marker).await.unwrap();
+        assert_eq!(expect_event(next_message(&mut socket).await).id, i64::MAX);
+
+        send(&mut socket, ClientMessage::Subscribe { account_ids: vec![stranger_account] }).await;
+        assert!(matches!(next_message(&mut socket).await, ServerMessage::Error { .. }));
+
+        send(&mut socket, ClientMessage::Subscribe { account_ids: vec![second_account] }).await;
+        match next_message(&mut socket).await {
+            ServerMessage::Subscribed { account_ids } => {
+                assert_eq!(account_ids.len(), 2);
+                assert!(account_ids.contains(&second_account));
+            }
+            other => panic!("expected a subscription update, got {:?}", other),
+        }
+
+        let second_events: Vec<&OutboxEvent> = all_events
+            .iter()
+            .filter(|event| !first_events.iter().any(|first| first.id == event.id))
+            .collect();
+        assert!(!second_events.is_empty());
+        for event in &second_events {
+            sink.publish(event).await.unwrap();
+        }
+        for expected in second_events {
+            assert_eq!(expect_event(next_message(&mut socket).await).id, expected.id);
+        }
+    }
+
+    /// Reads server-sent events until `count` have arrived, returning their ids.
+    async fn read_event_ids(response: &mut reqwest::Response, count: usize) -> Vec<i64> {
+        let mut buffer = String::new();
+        loop {
+            let ids: Vec<i64> = buffer
+                .lines()
+                .filter_map(|line| line.strip_prefix("id: "))
+                .map(|id| id.parse().unwrap())
+                .collect();
+            if ids.len() >= count {
+                return ids;
+            }
+
+            let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+                .await
+                .expect("timed out waiting for a server-sent event")
+                .unwrap()
+                .expect("stream ended early");
+            buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn test_transaction_events_resume_then_go_live() {
+        let db = test_database();
+        let (sender, _) = broadcast::channel(64);
+        let address = serve(sender.clone()).await;
+
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 1_000).await;
+        let client = db.pool.get().await.unwrap();
+        let deposit: Uuid = client
+            .query_one(
+                "SELECT id FROM transactions WHERE destination_account_id = $1",
+                &[&account_id],
+            )
+            .await
+            .unwrap()
+            .get("id");
+        let existing = transactions::get_transaction_events_after(&client, deposit, 0).await.unwrap();
+        assert!(existing.len() >= 2);
+
+        let url = format!("http://{}/api/transactions/{}/events", address, deposit);
+        let http = reqwest::Client::new();
+
+        let stranger = create_test_user(&db).await;
+        let response = http.get(&url).bearer_auth(token(stranger)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+        // Resuming skips the events the client already has
+        let mut response = http
+            .get(&url)
+            .bearer_auth(token(user_id))
+            .header("Last-Event-ID", existing[0].sequence_number.to_string())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let expected: Vec<i64> = existing[1..].iter().map(|event| event.sequence_number).collect();
+        assert_eq!(read_event_ids(&mut response, expected.len()).await, expected);
+
+        // A new event is sent once the relay announces a change to the transaction
+        let sequence_number: i64 = client
+            .query_one(
+                "INSERT INTO transaction_events (transaction_id, previous_status, new_status)
+                 VALUES ($1, 'completed', 'reversed')
+                 RETURNING sequence_number",
+                &[&deposit],
+            )
+            .await
+            .unwrap()
+            .get("sequence_number");
+        sender
+            .send(event(AggregateType::Transaction, deposit, vec![user_id], json!({})))
+            .unwrap();
+
+        assert_eq!(read_event_ids(&mut response, 1).await, vec![sequence_number]);
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::fixtures::{
+        create_funded_account, create_test_user, parties, test_database, transfer, withdrawal, Parties,
+    };
+    use crate::db::transactions;
+    use crate::models::transaction::{SortDirection, TransactionFilter, TransactionType};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_transaction_history_and_account_activity() {
+        let db = test_database();
+        let Parties { payer_id, payer: source, payee: destination, .. } = parties(&db, 1_000, 1_000).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let transfer = transactions::create_transaction(&mut client, payer_id, &transfer(source, destination, 250))
+            .await
+            .unwrap();
+
+        // The timeline runs from creation to the final status
+        let (events, total) = transactions::get_transaction_history(&client, transfer.id, 1, 10).await.unwrap();
+        assert_eq!(total, events.len());
+        assert!(events.len() >= 2);
+        assert_eq!(events[0].previous_status, None);
+        assert_eq!(events.last().unwrap().new_status, "completed");
+        assert!(events.windows(2).all(|pair| pair[0].sequence_number < pair[1].sequence_number));
+
+        let (second_page, _) = transactions::get_transaction_history(&client, transfer.id, 2, 1).await.unwrap();
+        assert_eq!(second_page[0].id, events[1].id);
+
+        // Each account sees its own deposit and the transfer, newest first
+        let (activity, total) = transactions::get_account_activity(&client, source, 1, 50).await.unwrap();
+        assert_eq!(total, activity.len());
+        assert_eq!(activity[0].event.transaction_id, transfer.id);
+        assert_eq!(activity[0].amount, 250);
+        assert!(matches!(activity[0].transaction_type, TransactionType::Transfer));
+        assert!(activity.windows(2).all(|pair| pair[0].event.sequence_number > pair[1].event.sequence_number));
+        assert!(activity.iter().any(|item| matches!(item.transaction_type, TransactionType::Deposit)));
+
+        let (recipient_activity, _) = transactions::get_account_activity(&client, destination, 1, 50).await.unwrap();
+        let recipient_transactions: std::collections::HashSet<_> =
+            recipient_activity.iter().map(|item| item.event.transaction_id).collect();
+        assert_eq!(recipient_transactions.len(), 2);
+        assert!(recipient_transactions.contains(&transfer.id));
+        assert!(activity
+            .iter()
+            .filter(|item| item.event.transaction_id != transfer.id)
+            .all(|item| !recipient_transactions.contains(&item.event.transaction_id)));
+    }
+
+    /// Every page of a listing, following the cursors to the end.
+    async fn list_all(
+        client: &deadpool_postgres::Client,
+        user_id: Uuid,
+        filter: &TransactionFilter,
+        sort: SortDirection,
+        page_size: usize,
+    ) -> Vec<Uuid> {
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next_cursor) =
+                transactions::get_user_transactions(client, user_id, filter, cursor.as_ref(), sort, page_size)
+                    .await
+                    .unwrap();
+            assert!(page.len() <= page_size);
+            ids.extend(page.iter().map(|transaction| transaction.id));
+            match next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => return ids,
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_transaction_listing_pages_and_filters() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account = create_funded_account(&db, user_id, "USD", 10_000).await;
+        let savings = create_funded_account(&db, user_id, "EUR", 10_000).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        for (amount, description) in [(100, "Rent 100%"), (200, "rent_march"), (300, "Groceries"), (400, "Rent")] {
+            let mut withdrawal = withdrawal(account, amount);
+            withdrawal.description = Some(description.to_string());
+            transactions::create_transaction(&mut client, user_id, &withdrawal).await.unwrap();
+        }
+
+        // Paging visits every transaction exactly once, in either direction
+        let everything = TransactionFilter::default();
+        let newest_first = list_all(&client, user_id, &everything, SortDirection::Desc, 2).await;
+        assert_eq!(newest_first.len(), 6);
+        let mut oldest_first = list_all(&client, user_id, &everything, SortDirection::Asc, 4).await;
+        oldest_first.reverse();
+        assert_eq!(newest_first, oldest_first);
+
+        let by_account = TransactionFilter {
+            account_id: Some(savings),
+            ..Default::default()
+        };
+        assert_eq!(list_all(&client, user_id, &by_account, SortDirection::Desc, 10).await.len(), 1);
+
+        let withdrawals_between = TransactionFilter {
+            transaction_type: Some(TransactionType::Withdrawal),
+            min_amount: Some(200),
+            max_amount: Some(300),
+            ..Default::default()
+        };
+        assert_eq!(list_all(&client, user_id, &withdrawals_between, SortDirection::Desc, 10).await.len(), 2);
+
+        // Description matching ignores case and treats wildcards literally
+        let description = |text: &str| TransactionFilter {
+            description: Some(text.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(list_all(&client, user_id, &description("rent"), SortDirection::Desc, 10).await.len(), 3);
+        assert_eq!(list_all(&client, user_id, &description("0%"), SortDirection::Desc, 10).await.len(), 1);
+        assert_eq!(list_all(&client, user_id, &description("t_m"), SortDirection::Desc, 10).await.len(), 1);
+
+        // Other users' transactions never appear
+        let stranger = create_test_user(&db).await;
+        assert!(list_all(&client, stranger, &everything, SortDirection::Desc, 10).await.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod statement_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database, withdrawal};
+    use crate::db::{accounts, transactions};
+    use crate::models::ledger::EntryType;
+    use crate::models::statement::{StatementEntry, StatementFormat, StatementLine};
+    use crate::models::transaction::TransactionType;
+    use crate::services::statement_service;
+    use chrono::{TimeZone, Utc};
+    use futures_util::StreamExt;
+    use uuid::Uuid;
+
+    fn entry(amount: i64, description: Option<&str>) -> StatementEntry {
+        StatementEntry {
+            entry_id: Uuid::nil(),
+            transaction_id: Uuid::nil(),
+            transaction_type: TransactionType::Transfer,
+            description: description.map(str::to_string),
+            entry_type: if amount > 0 { EntryType::Credit } else { EntryType::Debit },
+            amount,
+            created_at: Utc.with_ymd_and_hms(2026, 3, 2, 9, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_csv_lines() {
+        let opening = StatementLine::OpeningBalance {
+            account_id: Uuid::nil(),
+            currency: "USD".to_string(),
+            from: Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap(),
+            to: Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap(),
+            balance: 1050,
+        };
+        assert_eq!(
+            opening.render(StatementFormat::Csv, "USD"),
+            "date,transaction_id,type,description,amount,balance\n2026-03-01T00:00:00+00:00,,opening_balance,,,10.50\n"
+        );
+
+        let line = StatementLine::Entry {
+            entry: entry(-250, Some("Rent, \"March\"")),
+            balance: 800,
+        };
+        assert_eq!(
+            line.render(StatementFormat::Csv, "USD"),
+            format!("2026-03-02T09:30:00+00:00,{},transfer,\"Rent, \"\"March\"\"\",-2.50,8.00\n", Uuid::nil())
+        );
+
+        // Formulas are neutralised so spreadsheets show them as text
+        let line = StatementLine::Entry {
+            entry: entry(100, Some("=HYPERLINK(\"x\")")),
+            balance: 100,
+        };
+        assert!(line.render(StatementFormat::Csv, "JPY").contains(",\"'=HYPERLINK(\"\"x\"\")\",100,100\n"));
+    }
+
+    #[test]
+    fn test_jsonl_and_text_lines() {
+        let closing = StatementLine::ClosingBalance {
+            to: Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap(),
+            total_credits: 500,
+            total_debits: 250,
+            balance: 1300,
+        };
+        let json = closing.render(StatementFormat::Jsonl, "USD");
+        assert!(json.ends_with('\n') && !json.trim_end().contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "closing_balance");
+        assert_eq!(value["balance"], 1300);
+
+        let text = StatementLine::Entry {
+            entry: entry(-250, Some("A description that is much too long\nto fit")),
+            balance: 800,
+        }
+        .render(StatementFormat::Txt, "USD");
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.contains("A description that is much to…"));
+        assert!(text.trim_end().ends_with("-2.50            8.00"));
+    }
+
+    async fn statement_lines(
+        db: &crate::db::Database,
+        account_id: Uuid,
+        from: chrono::DateTime<Utc>,
+        to: chrono::DateTime<Utc>,
+    ) -> Vec<StatementLine> {
+        let client = db.pool.get().await.unwrap();
+        let account = accounts::get_account(&client, account_id).await.unwrap();
+        let mut stream = Box::pin(statement_service::stream(db.clone(), account, from, to, StatementFormat::Jsonl));
+
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        String::from_utf8(body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_statement_reconciles() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 10_000).await;
+        let period_start = Utc::now();
+
+        let mut client = db.pool.get().await.unwrap();
+        for amount in [1_000, 2_500, 700] {
+            transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, amount))
+                .await
+                .unwrap();
+        }
+        let period_end = Utc::now();
+
+        let lines = statement_lines(&db, account_id, period_start, period_end).await;
+        let Some(StatementLine::OpeningBalance { balance: opening, .. }) = lines.first() else {
+            panic!("statement must start with the opening balance");
+        };
+        let Some(StatementLine::ClosingBalance { balance: closing, total_credits, total_debits, .. }) = lines.last() else {
+            panic!("statement must end with the closing balance");
+        };
+
+        // The deposit falls before the period and the withdrawals inside it
+        assert_eq!(*opening, 10_000);
+        assert_eq!(lines.len(), 5);
+        assert_eq!((*total_credits, *total_debits), (0, 4_200));
+        assert_eq!(*closing, opening + total_credits - total_debits);
+        assert!(matches!(lines[2], StatementLine::Entry { balance: 6_500, .. }));
+
+        let account = accounts::get_account(&client, account_id).await.unwrap();
+        assert_eq!(*closing, account.balance);
+
+        // An empty period still opens and closes at the same balance
+        let lines = statement_lines(&db, account_id, period_end, Utc::now()).await;
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[1], StatementLine::ClosingBalance { balance: 5_800, .. }));
+    }
+}
+
+#[cfg(test)]
+mod reconciliation_tests {
+    use super::fixtures::{parties, test_database, transfer, Parties};
+    use crate::db::{reconciliation, transactions};
+    use crate::models::hold::HoldStatus;
+    use crate::models::reconciliation::{BalanceDiscrepancy, OrphanReason};
+    use crate::models::transaction::{ReversalKind, ReverseTransactionRequest};
+    use crate::services::reconciliation_service;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_discrepancy_differences() {
+        let discrepancy = BalanceDiscrepancy::new(Uuid::nil(), "USD".to_string(), (1_000, 1_250), (300, 0));
+        assert_eq!(discrepancy.balance_difference, -250);
+        assert_eq!(discrepancy.held_balance_difference, 300);
+    }
+
+    #[test]
+    fn test_orphan_reasons() {
+        assert_eq!(OrphanReason::for_hold(None), OrphanReason::NoHold);
+        assert_eq!(OrphanReason::for_hold(Some(&HoldStatus::Active)), OrphanReason::HoldExpired);
+        assert_eq!(OrphanReason::for_hold(Some(&HoldStatus::Voided)), OrphanReason::HoldReleased);
+
+        for reason in [OrphanReason::NoHold, OrphanReason::HoldReleased, OrphanReason::HoldExpired] {
+            assert_eq!(OrphanReason::from(reason.to_string().as_str()), reason);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_reconciliation_finds_drift_and_orphans() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 10_000, 100).await;
+
+        // Every kind of balance movement should reconcile cleanly
+        let mut client = db.pool.get().await.unwrap();
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 3_000))
+            .await
+            .unwrap();
+        transactions::refund_transaction(&mut client, payee_id, payment.id, Some(1_000), None)
+            .await
+            .unwrap();
+        let reversal = ReverseTransactionRequest {
+            kind: ReversalKind::Chargeback,
+            reason_code: "fraud".to_string(),
+            note: None,
+        };
+        transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &reversal)
+            .await
+            .unwrap();
+        let authorization = transactions::authorize_transaction(&mut client, payer_id, &transfer(payer, payee, 500), 3600, None)
+            .await
+            .unwrap();
+
+        let report = reconciliation_service::reconcile(&db, 3600, false).await.unwrap();
+        assert!(report.id.is_none());
+        assert!(report.accounts_checked >= 2);
+        assert!(!report.discrepancies.iter().any(|d| d.account_id == payer || d.account_id == payee));
+
+        // Drift the stored balance and abandon a transaction half way
+        client
+            .execute("UPDATE accounts SET balance = balance + 7 WHERE id = $1", &[&payer])
+            .await
+            .unwrap();
+        let orphan_id: Uuid = client
+            .query_one(
+                "INSERT INTO transactions (destination_account_id, amount, currency, status, transaction_type, created_at)
+                 VALUES ($1, 250, 'USD', 'pending', 'deposit', NOW() - INTERVAL '2 hours')
+                 RETURNING id",
+                &[&payee],
+            )
+            .await
+            .unwrap()
+            .get("id");
+
+        let report = reconciliation_service::reconcile(&db, 3600, true).await.unwrap();
+        let discrepancy = report.discrepancies.iter().find(|d| d.account_id == payer).unwrap();
+        assert_eq!(discrepancy.balance_difference, 7);
+        assert_eq!((discrepancy.stored_held_balance, discrepancy.expected_held_balance), (500, 500));
+        assert!(!report.discrepancies.iter().any(|d| d.account_id == payee));
+
+        let orphan = report
+            .orphaned_transactions
+            .iter()
+            .find(|o| o.transaction_id == orphan_id)
+            .unwrap();
+        assert_eq!(orphan.reason, OrphanReason::NoHold);
+        assert!(!report.orphaned_transactions.iter().any(|o| o.transaction_id == authorization.id));
+
+        // The saved report reads back the same findings
+        let saved = reconciliation::get_report(&client, report.id.unwrap()).await.unwrap();
+        assert_eq!(saved.accounts_checked, report.accounts_checked);
+        assert_eq!(saved.discrepancies.len(), report.discrepancies.len());
+        assert_eq!(saved.orphaned_transactions.len(), report.orphaned_transactions.len());
+        assert!(saved.orphaned_transactions.iter().any(|o| o.transaction_id == orphan_id && o.reason == OrphanReason::NoHold));
+
+        // Leave the shared database consistent for other runs
+        client
+            .execute("UPDATE accounts SET balance = balance - 7 WHERE id = $1", &[&payer])
+            .await
+            .unwrap();
+        client
+            .execute("UPDATE transactions SET status = 'failed' WHERE id = $1", &[&orphan_id])
+            .await
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod settlement_tests {
+    use super::fixtures::{self, create_funded_account, create_test_user, test_database};
+    use crate::db::transactions;
+    use crate::models::settlement::{SettlementMapping, SettlementMatchStatus, SettlementRecord};
+    use crate::models::transaction::{CreateTransactionRequest, Transaction, TransactionStatus, TransactionType};
+    use crate::services::settlement_service;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use uuid::Uuid;
+
+    const CSV: &str = include_str!("fixtures/settlement.csv");
+    const CSV_MAPPING: &str = include_str!("fixtures/settlement_csv_mapping.json");
+    const FIXED_WIDTH: &str = include_str!("fixtures/settlement.txt");
+    const FIXED_WIDTH_MAPPING: &str = include_str!("fixtures/settlement_fixed_width_mapping.json");
+
+    fn mapping(json: &str) -> SettlementMapping {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn reference(n: u128) -> String {
+        Uuid::from_u128(n).to_string()
+    }
+
+    fn transaction(transaction_type: TransactionType, amount: i64) -> Transaction {
+        Transaction {
+            id: Uuid::from_u128(1),
+            source_account_id: None,
+            destination_account_id: Some(Uuid::new_v4()),
+            amount,
+            currency: "USD".to_string(),
+            status: TransactionStatus::Completed,
+            transaction_type,
+            description: None,
+            parent_transaction_id: None,
+            refunded_amount: 0,
+            destination_amount: None,
+            destination_currency: None,
+            fx_rate: None,
+            fx_spread_bps: None,
+            fx_spread_amount: None,
+            fx_quote_id: None,
+            settled_at: None,
+            created_at: Utc.with_ymd_and_hms(2026, 3, 2, 23, 30, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2026, 3, 2, 23, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_settlement_file() {
+        let records = mapping(CSV_MAPPING).parse(CSV).unwrap();
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records[0],
+            SettlementRecord {
+                line_number: 3,
+                reference: reference(1),
+                bank_reference: Some("BNK-1001".to_string()),
+                amount: 10_550,
+                currency: "USD".to_string(),
+                value_date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            }
+        );
+        // Debits are read without their sign
+        assert_eq!(records[1].amount, 2_000);
+        assert_eq!(records[3].reference, "ACME-FEE-0042");
+        assert_eq!(records[3].transaction_id(), None);
+    }
+
+    #[test]
+    fn test_parse_fixed_width_settlement_file() {
+        let records = mapping(FIXED_WIDTH_MAPPING).parse(FIXED_WIDTH).unwrap();
+        let csv_records = mapping(CSV_MAPPING).parse(CSV).unwrap();
+
+        // The header and trailer records are skipped
+        assert_eq!(records.len(), 3);
+        for (record, csv_record) in records.iter().zip(&csv_records) {
+            assert_eq!(record.reference, csv_record.reference);
+            assert_eq!(record.bank_reference, csv_record.bank_reference);
+            assert_eq!(record.amount, csv_record.amount);
+            assert_eq!(record.currency, csv_record.currency);
+            assert_eq!(record.value_date, csv_record.value_date);
+        }
+        assert_eq!(records[2].line_number, 4);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let csv_mapping = mapping(CSV_MAPPING);
+
+        let error = csv_mapping.parse(&CSV.replace("-20.00", "-20.001")).unwrap_err();
+        assert_eq!(error, "line 4: invalid amount '-20.001'");
+
+        let error = csv_mapping.parse(&CSV.replace(",usd", ",ABC")).unwrap_err();
+        assert_eq!(error, "line 3: unknown currency 'ABC'");
+
+        let error = csv_mapping.parse(&CSV.replace("02/03/2026,0", "2026-03-02,0")).unwrap_err();
+        assert_eq!(error, "line 3: invalid date '2026-03-02'");
+
+        let error = csv_mapping.parse(&CSV.replace("Our Reference", "Reference")).unwrap_err();
+        assert_eq!(error, "Missing reference column 'Our Reference'");
+
+        let error = mapping(FIXED_WIDTH_MAPPING.replace(r#"{"start": 1, "length": 36}"#, "1").as_str())
+            .parse(FIXED_WIDTH)
+            .unwrap_err();
+        assert!(error.contains("must be a start and a non-zero length"));
+
+        let error = csv_mapping.parse("Settlement report\n").unwrap_err();
+        assert_eq!(error, "File is empty");
+    }
+
+    #[test]
+    fn test_settlement_differences() {
+        let record = SettlementRecord {
+            line_number: 1,
+            reference: reference(1),
+            bank_reference: None,
+            amount: 10_550,
+            currency: "USD".to_string(),
+            value_date: NaiveDate::from_ymd_opt(2026, 3, 3).unwrap(),
+        };
+
+        assert!(record.differences(&transaction(TransactionType::Deposit, 10_550), 1).is_empty());
+
+        let differences = record.differences(&transaction(TransactionType::Deposit, 10_000), 0);
+        assert_eq!(differences.len(), 2);
+        assert!(differences[0].starts_with("Amount 10550 does not match"));
+        assert!(differences[1].starts_with("Settlement date 2026-03-03 is more than 0 days"));
+
+        let mut transfer = transaction(TransactionType::Transfer, 10_550);
+        transfer.status = TransactionStatus::Reversed;
+        transfer.settled_at = Some(Utc::now());
+        let differences = record.differences(&transfer, 1);
+        assert_eq!(differences.len(), 3);
+        assert_eq!(differences[1], "Transaction is reversed");
+    }
+
+    async fn create(client: &mut deadpool_postgres::Client, user_id: Uuid, request: CreateTransactionRequest) -> Uuid {
+        transactions::create_transaction(client, user_id, &request).await.unwrap().id
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_import_settles_matching_transactions() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 10_000).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let deposit = create(&mut client, user_id, fixtures::deposit(account_id, 10_550)).await;
+        let withdrawal = create(&mut client, user_id, fixtures::withdrawal(account_id, 2_000)).await;
+        let short_withdrawal = create(&mut client, user_id, fixtures::withdrawal(account_id, 2_500)).await;
+        client
+            .execute(
+                "UPDATE transactions SET created_at = '2026-03-02T12:00:00Z' WHERE id = ANY($1)",
+                &[&vec![deposit, withdrawal, short_withdrawal]],
+            )
+            .await
+            .unwrap();
+
+        let content = CSV
+            .replace(&reference(1), &deposit.to_string())
+            .replace(&reference(2), &withdrawal.to_string())
+            .replace(&reference(3), &short_withdrawal.to_string());
+        let report = settlement_service::import(&db, "settlement.csv", &mapping(CSV_MAPPING), &content, None)
+            .await
+            .unwrap();
+
+        assert_eq!(report.import.line_count, 4);
+        assert_eq!(report.import.format, "csv");
+        let matched: Vec<Option<Uuid>> = report.matched.iter().map(|line| line.transaction_id).collect();
+        assert_eq!(matched, vec![Some(deposit), Some(withdrawal)]);
+        assert_eq!(report.unmatched.len(), 1);
+        assert_eq!(report.unmatched[0].reference, "ACME-FEE-0042");
+        assert_eq!(report.unmatched[0].status, SettlementMatchStatus::Unmatched);
+        assert_eq!(report.mismatched.len(), 1);
+        assert_eq!(report.mismatched[0].transaction_id, Some(short_withdrawal));
+        assert_eq!(report.mismatched[0].reasons, vec!["Amount 3000 does not match the transaction amount 2500"]);
+
+        let settled = transactions::get_transaction_by_id(&client, deposit).await.unwrap();
+        assert!(settled.settled_at.is_some());
+        let unsettled = transactions::get_transaction_by_id(&client, short_withdrawal).await.unwrap();
+        assert!(unsettled.settled_at.is_none());
+        let history = transactions::get_transaction_events_after(&client, deposit, 0).await.unwrap();
+        assert_eq!(history.last().unwrap().event_data.as_ref().unwrap()["action"], "settled");
+
+        // Importing the same file again settles nothing twice
+        let report = settlement_service::import(&db, "settlement.csv", &mapping(CSV_MAPPING), &content, None)
+            .await
+            .unwrap();
+        assert!(report.matched.is_empty());
+        assert_eq!(report.mismatched.len(), 3);
+        assert!(report.mismatched[0].reasons[0].starts_with("Transaction was already settled"));
+
+        // A file that cannot be read stores nothing
+        let error = settlement_service::import(&db, "bad.csv", &mapping(CSV_MAPPING), "x\n", None).await;
+        assert!(error.is_err());
+    }
+}
+
+#[cfg(test)]
+mod payment_intent_tests {
+    use super::fixtures::{
+        balances, create_funded_account, create_test_user, deposit, held_event_data, set_full_name, test_database,
+        Screener,
+    };
+    use crate::db::{accounts, merchants, payment_intents, transactions};
+    use crate::models::merchant::CreateMerchantRequest;
+    use crate::models::payment_intent::{
+        ConfirmPaymentIntentRequest, CreatePaymentIntentRequest, PaymentIntent, PaymentIntentStatus,
+    };
+    use crate::models::risk::RiskPolicy;
+    use crate::models::watchlist::{parse_watchlist_csv, Watchlist};
+    use crate::utils::error::AppError;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    fn create_request(metadata: HashMap<String, String>) -> CreatePaymentIntentRequest {
+        CreatePaymentIntentRequest {
+            merchant_id: Uuid::new_v4(),
+            amount: 1_500,
+            currency: "USD".to_string(),
+            description: None,
+            metadata,
+        }
+    }
+
+    #[test]
+    fn test_metadata_limits() {
+        let metadata = |pairs: Vec<(String, String)>| pairs.into_iter().collect::<HashMap<_, _>>();
+
+        assert!(create_request(metadata(vec![("order_id".to_string(), "1234".to_string())])).validate().is_ok());
+        assert!(create_request(metadata(vec![("a".repeat(41), "x".to_string())])).validate().is_err());
+        assert!(create_request(metadata(vec![("order_id".to_string(), "x".repeat(501))])).validate().is_err());
+        assert!(create_request(metadata((0..21).map(|i| (i.to_string(), String::new())).collect())).validate().is_err());
+    }
+
+    #[test]
+    fn test_payment_intent_status_round_trip() {
+        for status in [
+            PaymentIntentStatus::RequiresConfirmation,
+            PaymentIntentStatus::Processing,
+            PaymentIntentStatus::Succeeded,
+            PaymentIntentStatus::Canceled,
+        ] {
+            assert_eq!(PaymentIntentStatus::from(status.to_string().as_str()), status);
+        }
+    }
+
+    #[test]
+    fn test_client_secret_is_verified_and_never_serialized() {
+        let payment_intent = PaymentIntent {
+            id: Uuid::new_v4(),
+            merchant_id: Uuid::new_v4(),
+            destination_account_id: Uuid::new_v4(),
+            amount: 1_500,
+            currency: "USD".to_string(),
+            description: None,
+            metadata: HashMap::new(),
+            client_secret: "pi_secret_abc".to_string(),
+            status: PaymentIntentStatus::RequiresConfirmation,
+            customer_user_id: None,
+            source_account_id: None,
+            transaction_id: None,
+            last_error: None,
+            cancellation_reason: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(payment_intent.verify_client_secret("pi_secret_abc"));
+        assert!(!payment_intent.verify_client_secret("pi_secret_abd"));
+        assert!(serde_json::to_value(&payment_intent).unwrap().get("client_secret").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_payment_intent_lifecycle() {
+        let db = test_database();
+        let merchant_user_id = create_test_user(&db).await;
+        let customer_user_id = create_test_user(&db).await;
+        let customer_account_id = create_funded_account(&db, customer_user_id, "USD", 1_000).await;
+
+        let screener = Screener::lenient();
+        let screening = screener.screening();
+        let mut client = db.pool.get().await.unwrap();
+        let merchant = merchants::create_merchant(
+            &mut client,
+            merchant_user_id,
+            &CreateMerchantRequest {
+                name: "Corner Shop".to_string(),
+                currency: "USD".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        // The settlement account does not stop the owner opening a personal one
+        create_funded_account(&db, merchant_user_id, "USD", 100).await;
+        let settlement_account = accounts::get_account(&client, merchant.settlement_account_id).await.unwrap();
+        assert_eq!(settlement_account.merchant_id, Some(merchant.id));
+
+        let mut request = create_request(HashMap::new());
+        request.merchant_id = merchant.id;
+        let payment_intent = payment_intents::create_payment_intent(&mut client, &merchant, &request)
+            .await
+            .unwrap();
+        assert_eq!(payment_intent.status, PaymentIntentStatus::RequiresConfirmation);
+
+        let confirm = ConfirmPaymentIntentRequest {
+            client_secret: payment_intent.client_secret.clone(),
+            source_account_id: customer_account_id,
+        };
+        let wrong_secret = ConfirmPaymentIntentRequest {
+            client_secret: "wrong".to_string(),
+            source_account_id: customer_account_id,
+        };
+        let error = payment_intents::confirm_payment_intent(&mut client, customer_user_id, payment_intent.id, &wrong_secret, &screening)
+            .await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+
+        // Too little money leaves the intent waiting, with the reason recorded
+        let error = payment_intents::confirm_payment_intent(&mut client, customer_user_id, payment_intent.id, &confirm, &screening)
+            .await;
+        assert!(matches!(error, Err(AppError::InsufficientFunds(_))));
+        let waiting = payment_intents::get_payment_intent(&client, payment_intent.id).await.unwrap();
+        assert_eq!(waiting.status, PaymentIntentStatus::RequiresConfirmation);
+        assert!(waiting.last_error.is_some());
+
+        transactions::create_transaction(&mut client, customer_user_id, &deposit(customer_account_id, 1_000))
+            .await
+            .unwrap();
+        let paid = payment_intents::confirm_payment_intent(&mut client, customer_user_id, payment_intent.id, &confirm, &screening)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, PaymentIntentStatus::Succeeded);
+        assert_eq!(paid.customer_user_id, Some(customer_user_id));
+        assert!(paid.transaction_id.is_some());
+        assert!(paid.last_error.is_none());
+        let settlement_account = accounts::get_account(&client, merchant.settlement_account_id).await.unwrap();
+        assert_eq!(settlement_account.balance, 1_500);
+
+        // A paid intent can neither be paid again nor canceled
+        let error = payment_intents::confirm_payment_intent(&mut client, customer_user_id, payment_intent.id, &confirm, &screening)
+            .await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+        let error = payment_intents::cancel_payment_intent(&mut client, payment_intent.id, None).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+    }
+
+    /// An intent to pay `amount` to a new merchant owned by `merchant_user_id`.
+    async fn intent_for(client: &mut deadpool_postgres::Client, merchant_user_id: Uuid, amount: i64) -> PaymentIntent {
+        let merchant = merchants::create_merchant(
+            client,
+            merchant_user_id,
+            &CreateMerchantRequest {
+                name: "Corner Shop".to_string(),
+                currency: "USD".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        let mut request = create_request(HashMap::new());
+        request.merchant_id = merchant.id;
+        request.amount = amount;
+
+        payment_intents::create_payment_intent(client, &merchant, &request).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_payment_intents_are_risk_screened() {
+        let db = test_database();
+        let merchant_user_id = create_test_user(&db).await;
+        let customer_user_id = create_test_user(&db).await;
+        let customer_account_id = create_funded_account(&db, customer_user_id, "USD", 10_000).await;
+        let policy: RiskPolicy = serde_json::from_value(json!({
+            "rules": [
+                { "type": "amount_threshold", "min_amount": 1_000, "score": 60 },
+                { "type": "amount_threshold", "min_amount": 5_000, "score": 60 },
+            ],
+        }))
+        .unwrap();
+        let screener = Screener::new(&policy, Watchlist::default());
+        let screening = screener.screening();
+        let operator_id = merchant_user_id;
+        let confirm = |payment_intent: &PaymentIntent| ConfirmPaymentIntentRequest {
+            client_secret: payment_intent.client_secret.clone(),
+            source_account_id: customer_account_id,
+        };
+
+        let mut client = db.pool.get().await.unwrap();
+
+        // A declined payment is refused and the intent keeps waiting
+        let declined = intent_for(&mut client, merchant_user_id, 6_000).await;
+        let error =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, declined.id, &confirm(&declined), &screening)
+                .await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+        let waiting = payment_intents::get_payment_intent(&client, declined.id).await.unwrap();
+        assert_eq!(waiting.status, PaymentIntentStatus::RequiresConfirmation);
+        assert!(waiting.last_error.unwrap().contains("declined by risk checks"));
+
+        // A held payment leaves the intent processing until the review is approved
+        let approved = intent_for(&mut client, merchant_user_id, 2_000).await;
+        let processing =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, approved.id, &confirm(&approved), &screening)
+                .await
+                .unwrap();
+        assert_eq!(processing.status, PaymentIntentStatus::Processing);
+        let transaction_id = processing.transaction_id.unwrap();
+        assert_eq!(balances(&client, customer_account_id).await, (10_000, 2_000));
+        let error = payment_intents::cancel_payment_intent(&mut client, approved.id, None).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+
+        let review_id = held_event_data(&client, transaction_id).await["review_id"].as_str().unwrap().parse().unwrap();
+        transactions::approve_review(&mut client, operator_id, review_id, None).await.unwrap();
+        let paid = payment_intents::get_payment_intent(&client, approved.id).await.unwrap();
+        assert_eq!(paid.status, PaymentIntentStatus::Succeeded);
+        assert_eq!(paid.transaction_id, Some(transaction_id));
+        assert_eq!(balances(&client, paid.destination_account_id).await, (2_000, 0));
+
+        // A rejected one sends the intent back to waiting, with the reason recorded
+        let rejected = intent_for(&mut client, merchant_user_id, 3_000).await;
+        let processing =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, rejected.id, &confirm(&rejected), &screening)
+                .await
+                .unwrap();
+        let transaction_id = processing.transaction_id.unwrap();
+        let review_id = held_event_data(&client, transaction_id).await["review_id"].as_str().unwrap().parse().unwrap();
+        transactions::reject_review(&mut client, operator_id, review_id, None).await.unwrap();
+        let waiting = payment_intents::get_payment_intent(&client, rejected.id).await.unwrap();
+        assert_eq!(waiting.status, PaymentIntentStatus::RequiresConfirmation);
+        assert_eq!(waiting.transaction_id, None);
+        assert!(waiting.last_error.unwrap().contains(&transaction_id.to_string()));
+        assert_eq!(balances(&client, customer_account_id).await, (8_000, 0));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_payment_intents_are_watchlist_screened() {
+        let db = test_database();
+        let merchant_user_id = create_test_user(&db).await;
+        let customer_user_id = create_test_user(&db).await;
+        let customer_account_id = create_funded_account(&db, customer_user_id, "USD", 10_000).await;
+        let watchlist = Watchlist::new(parse_watchlist_csv("name\nMarta Kowalczyk\n").unwrap());
+        let screener = Screener::new(&RiskPolicy::default(), watchlist);
+        let screening = screener.screening();
+        let operator_id = customer_user_id;
+        let confirm = |payment_intent: &PaymentIntent| ConfirmPaymentIntentRequest {
+            client_secret: payment_intent.client_secret.clone(),
+            source_account_id: customer_account_id,
+        };
+
+        let mut client = db.pool.get().await.unwrap();
+        set_full_name(&client, merchant_user_id, "Marta Kowalczyk").await;
+
+        // Paying a merchant whose owner is a hit waits for compliance, and a
+        // confirmed hit sends the intent back to waiting
+        let confirmed = intent_for(&mut client, merchant_user_id, 1_000).await;
+        let processing =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, confirmed.id, &confirm(&confirmed), &screening)
+                .await
+                .unwrap();
+        assert_eq!(processing.status, PaymentIntentStatus::Processing);
+        let case_id = held_event_data(&client, processing.transaction_id.unwrap()).await["case_id"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        transactions::confirm_screening_case(&mut client, operator_id, case_id, None).await.unwrap();
+        let waiting = payment_intents::get_payment_intent(&client, confirmed.id).await.unwrap();
+        assert_eq!(waiting.status, PaymentIntentStatus::RequiresConfirmation);
+        assert_eq!(balances(&client, customer_account_id).await, (10_000, 0));
+
+        // A cleared hit lets the payment through, and the owner is not flagged again
+        let cleared = intent_for(&mut client, merchant_user_id, 2_000).await;
+        let processing =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, cleared.id, &confirm(&cleared), &screening)
+                .await
+                .unwrap();
+        assert_eq!(processing.status, PaymentIntentStatus::Processing);
+        let case_id = held_event_data(&client, processing.transaction_id.unwrap()).await["case_id"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        transactions::clear_screening_case(&mut client, operator_id, case_id, None).await.unwrap();
+        let paid = payment_intents::get_payment_intent(&client, cleared.id).await.unwrap();
+        assert_eq!(paid.status, PaymentIntentStatus::Succeeded);
+
+        let again = intent_for(&mut client, merchant_user_id, 500).await;
+        let paid =
+            payment_intents::confirm_payment_intent(&mut client, customer_user_id, again.id, &confirm(&again), &screening)
+                .await
+                .unwrap();
+        assert_eq!(paid.status, PaymentIntentStatus::Succeeded);
+        assert_eq!(balances(&client, customer_account_id).await, (7_500, 0));
+    }
+}
+
+#[cfg(test)]
+mod wallet_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database};
+    use crate::db::accounts;
+    use crate::models::account::{Account, AccountPurpose, AccountStatus, CreateAccountRequest, CurrencyTotal};
+    use chrono::Utc;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    fn account(currency: &str, balance: i64, held_balance: i64) -> Account {
+        Account {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            balance,
+            held_balance,
+            currency: currency.to_string(),
+            label: "Main".to_string(),
+            purpose: AccountPurpose::General,
+            status: AccountStatus::Active,
+            merchant_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn request(label: Option<&str>, purpose: AccountPurpose) -> CreateAccountRequest {
+        CreateAccountRequest {
+            currency: "USD".to_string(),
+            label: label.map(str::to_string),
+            purpose,
+        }
+    }
+
+    #[test]
+    fn test_currency_totals() {
+        let totals = CurrencyTotal::from_accounts(&[
+            account("USD", 1_000, 200),
+            account("EUR", 500, 0),
+            account("USD", 250, 0),
+        ]);
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].currency, "EUR");
+        assert_eq!((totals[0].balance, totals[0].account_count), (500, 1));
+        assert_eq!(totals[1].currency, "USD");
+        assert_eq!((totals[1].balance, totals[1].available_balance, totals[1].account_count), (1_250, 1_050, 2));
+        assert!(CurrencyTotal::from_accounts(&[]).is_empty());
+
+        let usd = CurrencyTotal::from_accounts(&[account("USD", 1_250, 0)]).remove(0);
+        assert_eq!(usd.with_formatted_amounts().formatted_balance.as_deref(), Some("12.50"));
+    }
+
+    #[test]
+    fn test_create_account_request_defaults() {
+        let parsed: CreateAccountRequest = serde_json::from_str(r#"{"currency": "USD"}"#).unwrap();
+        assert_eq!(parsed.label(), "Main");
+        assert_eq!(parsed.purpose, AccountPurpose::General);
+
+        let parsed: CreateAccountRequest =
+            serde_json::from_str(r#"{"currency": "USD", "label": "Holiday", "purpose": "savings"}"#).unwrap();
+        assert_eq!(parsed.label(), "Holiday");
+        assert_eq!(parsed.purpose, AccountPurpose::Savings);
+
+        assert!(request(Some(""), AccountPurpose::General).validate().is_err());
+        assert!(request(Some(&"x".repeat(51)), AccountPurpose::General).validate().is_err());
+        assert!(serde_json::from_str::<CreateAccountRequest>(r#"{"currency": "USD", "purpose": "gambling"}"#).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_several_accounts_per_currency() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        create_funded_account(&db, user_id, "USD", 100).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let savings = accounts::create_account(&mut client, user_id, &request(Some("Savings"), AccountPurpose::Savings))
+            .await
+            .unwrap();
+        assert_eq!(savings.label, "Savings");
+        assert_eq!(savings.purpose, AccountPurpose::Savings);
+
+        // Labels are unique per currency, ignoring case
+        let duplicate = accounts::create_account(&mut client, user_id, &request(Some("savings"), AccountPurpose::General)).await;
+        assert!(duplicate.is_err());
+        let duplicate = accounts::create_account(&mut client, user_id, &request(None, AccountPurpose::General)).await;
+        assert!(duplicate.is_err());
+
+        let user_accounts = accounts::get_user_accounts(&client, user_id).await.unwrap();
+        let totals = CurrencyTotal::from_accounts(&user_accounts);
+        assert_eq!(totals.len(), 1);
+        assert_eq!((totals[0].balance, totals[0].account_count), (100, 2));
+    }
+}
+
+#[cfg(test)]
+mod account_lifecycle_tests {
+    use super::fixtures::{
+        create_funded_account, create_test_user, deposit, parties, test_database, transfer, withdrawal, Parties,
+    };
+    use crate::db::{accounts, limits, transactions};
+    use crate::models::account::{AccountPurpose, AccountStatus, CloseAccountRequest, CreateAccountRequest};
+    use crate::models::limit::{LimitScope, SpendingLimits};
+    use crate::models::transaction::{ReversalKind, ReverseTransactionRequest};
+    use crate::utils::error::AppError;
+    use uuid::Uuid;
+
+    async fn savings(client: &mut deadpool_postgres::Client, user_id: Uuid) -> Uuid {
+        accounts::create_account(
+            client,
+            user_id,
+            &CreateAccountRequest {
+                currency: "USD".to_string(),
+                label: Some("Savings".to_string()),
+                purpose: AccountPurpose::Savings,
+            },
+        )
+        .await
+        .unwrap()
+        .id
+    }
+
+    #[test]
+    fn test_account_status_parsing() {
+        for status in [AccountStatus::Active, AccountStatus::Suspended, AccountStatus::Closed] {
+            assert_eq!(AccountStatus::try_from(status.to_string().as_str()), Ok(status));
+        }
+        assert!(AccountStatus::try_from("frozen").is_err());
+        assert!(AccountStatus::try_from("").is_err());
+    }
+
+    #[test]
+    fn test_account_status_transitions() {
+        assert!(AccountStatus::Active.allowed_transitions().contains(&AccountStatus::Suspended));
+        assert!(AccountStatus::Active.allowed_transitions().contains(&AccountStatus::Closed));
+        assert_eq!(AccountStatus::Suspended.allowed_transitions(), &[AccountStatus::Active]);
+        assert!(AccountStatus::Closed.allowed_transitions().is_empty());
+
+        assert!(AccountStatus::Active.can_debit() && AccountStatus::Active.can_credit());
+        assert!(!AccountStatus::Suspended.can_debit() && AccountStatus::Suspended.can_credit());
+        assert!(!AccountStatus::Closed.can_debit() && !AccountStatus::Closed.can_credit());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_suspend_and_close_account() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 1_000).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let savings = accounts::create_account(
+            &mut client,
+            user_id,
+            &CreateAccountRequest {
+                currency: "USD".to_string(),
+                label: Some("Savings".to_string()),
+                purpose: AccountPurpose::Savings,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Suspended accounts take credits but refuse debits
+        let suspended = accounts::change_status(&mut client, user_id, account_id, AccountStatus::Suspended, "Fraud check")
+            .await
+            .unwrap();
+        assert_eq!(suspended.status, AccountStatus::Suspended);
+        let credit = transactions::create_transaction(&mut client, user_id, &deposit(account_id, 100)).await;
+        assert!(credit.is_ok());
+        let debit = transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 100)).await;
+        assert!(matches!(debit, Err(AppError::Conflict(_))));
+        let debit = transactions::create_transaction(&mut client, user_id, &transfer(account_id, savings.id, 100)).await;
+        assert!(matches!(debit, Err(AppError::Conflict(_))));
+
+        // A suspended account has to be reactivated before it can be closed
+        let close = CloseAccountRequest {
+            sweep_account_id: Some(savings.id),
+            reason: Some("Moving banks".to_string()),
+        };
+        let error = accounts::close_account(&mut client, user_id, account_id, &close).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+        accounts::change_status(&mut client, user_id, account_id, AccountStatus::Active, "Cleared")
+            .await
+            .unwrap();
+        let error = accounts::change_status(&mut client, user_id, account_id, AccountStatus::Active, "Again").await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+
+        let no_sweep = CloseAccountRequest {
+            sweep_account_id: None,
+            reason: None,
+        };
+        let error = accounts::close_account(&mut client, user_id, account_id, &no_sweep).await;
+        assert!(matches!(error, Err(AppError::BadRequest(_))));
+
+        let closed = accounts::close_account(&mut client, user_id, account_id, &close).await.unwrap();
+        assert_eq!(closed.status, AccountStatus::Closed);
+        assert_eq!(closed.balance, 0);
+        assert_eq!(accounts::get_account(&client, savings.id).await.unwrap().balance, 1_100);
+
+        let credit = transactions::create_transaction(&mut client, user_id, &deposit(account_id, 100)).await;
+        assert!(matches!(credit, Err(AppError::Conflict(_))));
+
+        let events = accounts::get_account_events(&client, account_id).await.unwrap();
+        let statuses: Vec<AccountStatus> = events.iter().map(|event| event.new_status.clone()).collect();
+        assert_eq!(statuses, vec![AccountStatus::Suspended, AccountStatus::Active, AccountStatus::Closed]);
+        assert_eq!(events[0].reason.as_deref(), Some("Fraud check"));
+        assert!(events[2].sweep_transaction_id.is_some());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_close_sweeps_past_limits_but_not_overdrafts() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 1_000, 100).await;
+        let mut client = db.pool.get().await.unwrap();
+        let close = |sweep_account_id| CloseAccountRequest {
+            sweep_account_id: Some(sweep_account_id),
+            reason: None,
+        };
+
+        // A chargeback after the payee spent the money leaves them overdrawn
+        let payment = transactions::create_transaction(&mut client, payer_id, &transfer(payer, payee, 400))
+            .await
+            .unwrap();
+        transactions::create_transaction(&mut client, payee_id, &withdrawal(payee, 450))
+            .await
+            .unwrap();
+        let chargeback = ReverseTransactionRequest {
+            kind: ReversalKind::Chargeback,
+            reason_code: "fraud".to_string(),
+            note: None,
+        };
+        transactions::reverse_transaction(&mut client, Uuid::new_v4(), payment.id, &chargeback)
+            .await
+            .unwrap();
+
+        let payee_savings = savings(&mut client, payee_id).await;
+        let error = accounts::close_account(&mut client, payee_id, payee, &close(payee_savings)).await;
+        assert!(matches!(error, Err(AppError::Conflict(ref message)) if message.contains("overdrawn by 350 USD")));
+        let account = accounts::get_account(&client, payee).await.unwrap();
+        assert_eq!((account.status, account.balance), (AccountStatus::Active, -350));
+
+        // Limits only cover money going to other users, so they never block the sweep
+        let tight = SpendingLimits {
+            max_transaction_amount: Some(10),
+            ..Default::default()
+        };
+        limits::set_limits(&client, LimitScope::Account, Some(payer), &tight, payer_id)
+            .await
+            .unwrap();
+        limits::set_limits(&client, LimitScope::User, Some(payer_id), &tight, payer_id)
+            .await
+            .unwrap();
+        let payer_savings = savings(&mut client, payer_id).await;
+        let closed = accounts::close_account(&mut client, payer_id, payer, &close(payer_savings))
+            .await
+            .unwrap();
+        assert_eq!((closed.status, closed.balance), (AccountStatus::Closed, 0));
+        assert_eq!(accounts::get_account(&client, payer_savings).await.unwrap().balance, 1_000);
+    }
+}
+
+#[cfg(test)]
+mod limit_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database, transfer, withdrawal};
+    use crate::db::{accounts, limits, transactions};
+    use crate::models::account::{AccountPurpose, CreateAccountRequest};
+    use crate::models::limit::{
+        LimitExceeded, LimitKind, LimitScope, LimitUsage, LimitWindows, SpendingLimits,
+    };
+    use crate::utils::error::AppError;
+    use chrono::{DateTime, Duration, Utc};
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_limit_windows() {
+        let windows = LimitWindows::at(at("2026-12-31T18:30:00Z"));
+        assert_eq!(windows.hour_start, at("2026-12-31T17:30:00Z"));
+        assert_eq!(windows.day_start, at("2026-12-31T00:00:00Z"));
+        assert_eq!(windows.next_day, at("2027-01-01T00:00:00Z"));
+        assert_eq!(windows.month_start, at("2026-12-01T00:00:00Z"));
+        assert_eq!(windows.next_month, at("2027-01-01T00:00:00Z"));
+        assert_eq!(windows.earliest(), windows.month_start);
+
+        // Just after midnight on the first, the rolling hour reaches into last month
+        let windows = LimitWindows::at(at("2026-03-01T00:10:00Z"));
+        assert_eq!(windows.next_month, at("2026-04-01T00:00:00Z"));
+        assert_eq!(windows.earliest(), at("2026-02-28T23:10:00Z"));
+    }
+
+    #[test]
+    fn test_limits_fall_back() {
+        let own = SpendingLimits {
+            daily_amount: Some(500),
+            ..Default::default()
+        };
+        let default = SpendingLimits {
+            daily_amount: Some(1_000),
+            hourly_count: Some(5),
+            ..Default::default()
+        };
+
+        let effective = own.or(default);
+        assert_eq!(effective.daily_amount, Some(500));
+        assert_eq!(effective.hourly_count, Some(5));
+        assert_eq!(effective.monthly_amount, None);
+        assert!(SpendingLimits::default().is_empty());
+        assert!(!effective.is_empty());
+    }
+
+    #[test]
+    fn test_limit_check() {
+        let windows = LimitWindows::at(at("2026-06-15T12:00:00Z"));
+        let limits = SpendingLimits {
+            max_transaction_amount: Some(1_000),
+            daily_amount: Some(2_000),
+            monthly_amount: Some(5_000),
+            hourly_count: Some(3),
+        };
+        let usage = LimitUsage {
+            daily_amount: 1_500,
+            monthly_amount: 4_000,
+            hourly_count: 2,
+            oldest_in_hour: Some(at("2026-06-15T11:20:00Z")),
+        };
+
+        assert!(usage.check(&limits, &windows, 500).is_ok());
+
+        let (remaining, requested) = usage.check(&limits, &windows, 1_001).unwrap_err();
+        assert_eq!((remaining.limit, requested), (LimitKind::MaxTransactionAmount, 1_001));
+        assert_eq!(remaining.resets_at, None);
+
+        let (remaining, _) = usage.check(&limits, &windows, 501).unwrap_err();
+        assert_eq!(remaining.limit, LimitKind::DailyAmount);
+        assert_eq!(remaining.remaining, 500);
+        assert_eq!(remaining.resets_at, Some(at("2026-06-16T00:00:00Z")));
+
+        let busy = LimitUsage {
+            hourly_count: 3,
+            ..usage.clone()
+        };
+        let (remaining, requested) = busy.check(&limits, &windows, 1).unwrap_err();
+        assert_eq!((remaining.limit, requested), (LimitKind::HourlyCount, 1));
+        assert_eq!(remaining.resets_at, Some(at("2026-06-15T12:20:00Z")));
+
+        let month = LimitUsage {
+            daily_amount: 0,
+            monthly_amount: 4_900,
+            ..usage.clone()
+        };
+        let (remaining, _) = month.check(&limits, &windows, 200).unwrap_err();
+        assert_eq!(remaining.limit, LimitKind::MonthlyAmount);
+        assert_eq!(remaining.resets_at, Some(at("2026-07-01T00:00:00Z")));
+
+        assert!(usage.check(&SpendingLimits::default(), &windows, i64::MAX).is_ok());
+        assert!(usage.remaining(&SpendingLimits::default(), &windows).is_empty());
+    }
+
+    #[test]
+    fn test_limit_exceeded_error() {
+        let windows = LimitWindows::at(at("2026-06-15T12:00:00Z"));
+        let limits = SpendingLimits {
+            daily_amount: Some(2_000),
+            ..Default::default()
+        };
+        let usage = LimitUsage {
+            daily_amount: 1_500,
+            ..Default::default()
+        };
+        let exceeded = LimitExceeded::new(LimitScope::Account, "USD", usage.check(&limits, &windows, 600).unwrap_err());
+
+        assert_eq!(
+            exceeded.to_string(),
+            "600 USD would exceed the account daily limit of 2000 USD, of which 1500 is used; \
+             resets at 2026-06-16T00:00:00+00:00"
+        );
+
+        let body = AppError::LimitExceeded(exceeded).body();
+        assert_eq!(body["error"]["code"], 422);
+        assert_eq!(body["error"]["limit"]["limit"], "daily_amount");
+        assert_eq!(body["error"]["limit"]["scope"], "account");
+        assert_eq!(body["error"]["limit"]["resets_at"], "2026-06-16T00:00:00Z");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_limits_are_enforced() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 10_000).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        let savings = accounts::create_account(
+            &mut client,
+            user_id,
+            &CreateAccountRequest {
+                currency: "USD".to_string(),
+                label: Some("Savings".to_string()),
+                purpose: AccountPurpose::Savings,
+            },
+        )
+        .await
+        .unwrap();
+
+        let account_limits = SpendingLimits {
+            max_transaction_amount: Some(500),
+            daily_amount: Some(800),
+            ..Default::default()
+        };
+        limits::set_limits(&client, LimitScope::Account, Some(account_id), &account_limits, user_id)
+            .await
+            .unwrap();
+
+        let error = transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 600)).await;
+        assert!(matches!(error, Err(AppError::LimitExceeded(ref e)) if e.limit == LimitKind::MaxTransactionAmount));
+
+        transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 400)).await.unwrap();
+        transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 400)).await.unwrap();
+        let error = transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 1)).await;
+        let Err(AppError::LimitExceeded(exceeded)) = error else {
+            panic!("expected the daily limit to be hit, got {:?}", error);
+        };
+        assert_eq!((exceeded.limit, exceeded.used), (LimitKind::DailyAmount, 800));
+        assert!(exceeded.resets_at.unwrap() <= Utc::now() + Duration::days(1));
+
+        // Moving money between the user's own accounts does not count
+        transactions::create_transaction(&mut client, user_id, &transfer(account_id, savings.id, 500))
+            .await
+            .unwrap();
+
+        // The user's limit covers their other accounts too
+        let user_limits = SpendingLimits {
+            hourly_count: Some(2),
+            ..Default::default()
+        };
+        limits::set_limits(&client, LimitScope::User, Some(user_id), &user_limits, user_id)
+            .await
+            .unwrap();
+        let error = transactions::create_transaction(&mut client, user_id, &withdrawal(savings.id, 100)).await;
+        assert!(matches!(error, Err(AppError::LimitExceeded(ref e)) if e.scope == LimitScope::User));
+
+        let windows = LimitWindows::at(Utc::now());
+        let account = accounts::get_account(&client, account_id).await.unwrap();
+        let usage = limits::get_usage(&client, LimitScope::Account, &account, &windows).await.unwrap();
+        assert_eq!((usage.daily_amount, usage.hourly_count), (800, 2));
+    }
+}
+
+#[cfg(test)]
+mod risk_tests {
+    use super::fixtures::{balances, parties, test_database, transfer, Parties};
+    use super::test_config;
+    use crate::db::transactions::Screening;
+    use crate::db::{accounts, risk, transactions};
+    use crate::models::risk::{
+        RiskContext, RiskDecision, RiskPolicy, RiskReview, RiskReviewStatus, RiskRuleConfig, RiskSignals, RuleHit,
+    };
+    use crate::models::transaction::TransactionStatus;
+    use crate::models::watchlist::Watchlist;
+    use crate::services::risk_service::{RiskEngine, RiskRule};
+    use crate::utils::error::AppError;
+    use axum::extract::FromRequestParts;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn policy(rules: serde_json::Value) -> RiskPolicy {
+        serde_json::from_value(json!({ "rules": rules })).unwrap()
+    }
+
+    fn context() -> RiskContext {
+        let now = Utc::now();
+        RiskContext {
+            amount: 1_000,
+            currency: "USD".to_string(),
+            source_account_created_at: now - Duration::days(30),
+            previous_outgoing_count: 10,
+            known_destination: true,
+            recent_outgoing: Vec::new(),
+            signals: RiskSignals {
+                ip: Some("203.0.113.7".to_string()),
+                country: Some("GB".to_string()),
+            },
+            previous_signals: Some(RiskSignals {
+                ip: Some("203.0.113.7".to_string()),
+                country: Some("GB".to_string()),
+            }),
+            now,
+        }
+    }
+
+    fn hit_rules(engine: &RiskEngine, context: &RiskContext) -> Vec<String> {
+        engine.assess(context).hits.into_iter().map(|hit| hit.rule).collect()
+    }
+
+    #[test]
+    fn test_policy_from_file() {
+        let policy: RiskPolicy = serde_json::from_str(include_str!("../../risk_rules.example.json")).unwrap();
+        assert_eq!(policy.rules.len(), 7);
+        assert_eq!(policy.velocity_window(), 3600);
+        assert!(matches!(policy.rules[0], RiskRuleConfig::AmountThreshold { min_amount: 100000, .. }));
+
+        assert_eq!(policy.decide(0), RiskDecision::Approve);
+        assert_eq!(policy.decide(49), RiskDecision::Approve);
+        assert_eq!(policy.decide(50), RiskDecision::Review);
+        assert_eq!(policy.decide(100), RiskDecision::Decline);
+
+        // Without rules nothing is ever held
+        let default = RiskPolicy::default();
+        assert!(default.rules.is_empty());
+        assert_eq!(RiskEngine::from_policy(&default).assess(&context()).decision, RiskDecision::Approve);
+    }
+
+    async fn signals(trusted_proxies: usize, forwarded: &str) -> RiskSignals {
+        let mut config = test_config();
+        config.risk_policy.ip_header = Some("x-forwarded-for".to_string());
+        config.risk_policy.trusted_proxies = trusted_proxies;
+        let request = axum::http::Request::builder()
+            .header("x-forwarded-for", forwarded)
+            .body(())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+
+        RiskSignals::from_request_parts(&mut parts, &config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_address_ignores_client_entries() {
+        // The client sent its own header, which our proxy appended to
+        let spoofed = "203.0.113.7, 198.51.100.20";
+        assert_eq!(signals(1, spoofed).await.ip.as_deref(), Some("198.51.100.20"));
+
+        // Behind two proxies the nearest one added the second proxy's address
+        let spoofed = "203.0.113.7, 198.51.100.20, 10.0.0.5";
+        assert_eq!(signals(2, spoofed).await.ip.as_deref(), Some("198.51.100.20"));
+
+        // Fewer entries than proxies leaves only the first to go on
+        assert_eq!(signals(2, "198.51.100.20").await.ip.as_deref(), Some("198.51.100.20"));
+        assert_eq!(signals(1, " , ").await.ip, None);
+    }
+
+    #[test]
+    fn test_rules() {
+        let engine = RiskEngine::from_policy(&policy(json!([
+            { "type": "amount_threshold", "min_amount": 5_000, "currency": "USD", "score": 10 },
+            { "type": "new_destination", "score": 10 },
+            { "type": "velocity", "window_seconds": 600, "max_count": 2, "score": 10 },
+            { "type": "country_mismatch", "score": 10 },
+            { "type": "ip_mismatch", "score": 10 },
+            { "type": "new_account", "max_age_seconds": 86_400, "score": 10 },
+        ])));
+
+        let quiet = context();
+        assert!(hit_rules(&engine, &quiet).is_empty());
+
+        let mut large = context();
+        large.amount = 5_000;
+        assert_eq!(hit_rules(&engine, &large), ["amount_threshold"]);
+        large.currency = "EUR".to_string();
+        assert!(hit_rules(&engine, &large).is_empty());
+
+        let mut stranger = context();
+        stranger.known_destination = false;
+        assert_eq!(hit_rules(&engine, &stranger), ["new_destination"]);
+
+        // Two earlier transfers in the window plus this one is more than two
+        let mut busy = context();
+        busy.recent_outgoing = vec![busy.now - Duration::minutes(20), busy.now - Duration::minutes(5)];
+        assert!(hit_rules(&engine, &busy).is_empty());
+        busy.recent_outgoing.push(busy.now - Duration::minutes(1));
+        assert_eq!(hit_rules(&engine, &busy), ["velocity"]);
+
+        let mut travelling = context();
+        travelling.signals = RiskSignals {
+            ip: Some("198.51.100.1".to_string()),
+            country: Some("gb".to_string()),
+        };
+        assert_eq!(hit_rules(&engine, &travelling), ["ip_mismatch"]);
+        travelling.signals.country = Some("FR".to_string());
+        assert_eq!(hit_rules(&engine, &travelling), ["country_mismatch", "ip_mismatch"]);
+        travelling.previous_signals = None;
+        assert!(hit_rules(&engine, &travelling).is_empty());
+
+        let mut fresh = context();
+        fresh.source_account_created_at = fresh.now - Duration::hours(1);
+        assert!(hit_rules(&engine, &fresh).is_empty());
+        fresh.previous_outgoing_count = 0;
+        assert_eq!(hit_rules(&engine, &fresh), ["new_account"]);
+    }
+
+    struct Blocklist(Uuid);
+
+    impl RiskRule for Blocklist {
+        fn name(&self) -> &'static str {
+            "blocklist"
+        }
+
+        fn evaluate(&self, context: &RiskContext) -> Option<RuleHit> {
+            (context.signals.ip.as_deref() == Some("192.0.2.1")).then(|| RuleHit {
+                rule: self.name().to_string(),
+                score: 100,
+                detail: self.0.to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_scores_add_up() {
+        let engine = RiskEngine::from_policy(&policy(json!([
+            { "type": "amount_threshold", "min_amount": 1_000, "score": 30 },
+            { "type": "new_destination", "score": 25 },
+        ])))
+        .with_rule(Box::new(Blocklist(Uuid::new_v4())));
+
+        let assessment = engine.assess(&context());
+        assert_eq!((assessment.score, assessment.decision), (30, RiskDecision::Approve));
+
+        let mut stranger = context();
+        stranger.known_destination = false;
+        let assessment = engine.assess(&stranger);
+        assert_eq!((assessment.score, assessment.decision), (55, RiskDecision::Review));
+        assert_eq!(assessment.signals, stranger.signals);
+
+        stranger.signals.ip = Some("192.0.2.1".to_string());
+        assert_eq!(engine.assess(&stranger).decision, RiskDecision::Decline);
+    }
+
+    async fn open_review(client: &deadpool_postgres::Client, transaction_id: Uuid) -> RiskReview {
+        risk::get_reviews(client, &RiskReviewStatus::Open)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|review| review.transaction_id == transaction_id)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_screened_transfers() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 10_000, 1).await;
+
+        let engine = RiskEngine::from_policy(&policy(json!([
+            { "type": "amount_threshold", "min_amount": 1_000, "score": 60 },
+            { "type": "amount_threshold", "min_amount": 5_000, "score": 60 },
+        ])));
+        let signals = RiskSignals::default();
+        let watchlist = Watchlist::default();
+        let screening = Screening {
+            engine: &engine,
+            signals: &signals,
+            watchlist: &watchlist,
+            match_threshold: 0.92,
+        };
+        let operator_id = payee_id;
+
+        let mut client = db.pool.get().await.unwrap();
+        let approved = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 500),
+            &screening,
+        )
+        .await
+        .unwrap();
+        assert_eq!(approved.status, TransactionStatus::Completed);
+        let (events, _) = transactions::get_transaction_history(&client, approved.id, 1, 10).await.unwrap();
+        let completed = events.iter().find(|e| e.new_status == "completed").unwrap();
+        assert_eq!(completed.event_data.as_ref().unwrap()["risk"]["decision"], "approve");
+
+        // Held transfers keep their funds reserved until they are decided
+        let held = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 2_000),
+            &screening,
+        )
+        .await
+        .unwrap();
+        assert_eq!(held.status, TransactionStatus::UnderReview);
+        assert_eq!(balances(&client, payer).await, (9_500, 2_000));
+
+        let review = open_review(&client, held.id).await;
+        assert_eq!(review.assessment.hits.len(), 1);
+        let error = transactions::capture_transaction(&mut client, payer_id, held.id, None).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+
+        let decided = transactions::approve_review(&mut client, operator_id, review.id, Some("Known customer"))
+            .await
+            .unwrap();
+        assert_eq!(decided.status, RiskReviewStatus::Approved);
+        let completed = transactions::get_transaction_by_id(&client, held.id).await.unwrap();
+        assert_eq!(completed.status, TransactionStatus::Completed);
+        assert_eq!(balances(&client, payer).await, (7_500, 0));
+        assert_eq!(accounts::get_account(&client, payee).await.unwrap().balance, 2_501);
+        let error = transactions::reject_review(&mut client, operator_id, review.id, None).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+
+        // Rejecting releases the funds
+        let held = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 1_500),
+            &screening,
+        )
+        .await
+        .unwrap();
+        let review = open_review(&client, held.id).await;
+        transactions::reject_review(&mut client, operator_id, review.id, None).await.unwrap();
+        let rejected = transactions::get_transaction_by_id(&client, held.id).await.unwrap();
+        assert_eq!(rejected.status, TransactionStatus::Failed);
+        assert_eq!(balances(&client, payer).await, (7_500, 0));
+
+        // Declined transfers are recorded as failed and refused
+        let error = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 6_000),
+            &screening,
+        )
+        .await;
+        assert!(matches!(error, Err(AppError::Forbidden(ref message)) if message.contains("declined by risk checks")));
+        assert_eq!(accounts::get_account(&client, payer).await.unwrap().balance, 7_500);
+
+        // An approved authorization goes back to pending and can then be captured
+        let authorization = transactions::authorize_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 1_200),
+            3600,
+            Some(&screening),
+        )
+        .await
+        .unwrap();
+        assert_eq!(authorization.status, TransactionStatus::UnderReview);
+        let review = open_review(&client, authorization.id).await;
+        assert!(review.authorize_only);
+        transactions::approve_review(&mut client, operator_id, review.id, None).await.unwrap();
+        let approved = transactions::get_transaction_by_id(&client, authorization.id).await.unwrap();
+        assert_eq!(approved.status, TransactionStatus::Pending);
+        let captured = transactions::capture_transaction(&mut client, payer_id, authorization.id, None).await.unwrap();
+        assert_eq!(captured.status, TransactionStatus::Completed);
+        assert_eq!(accounts::get_account(&client, payer).await.unwrap().balance, 6_300);
+    }
+}
+
+#[cfg(test)]
+mod screening_tests {
+    use super::fixtures::{
+        balances, create_funded_account, create_test_user, parties, set_full_name, test_database, transfer, Parties,
+        Screener,
+    };
+    use crate::db::transactions::Screening;
+    use super::test_config;
+    use crate::db::{accounts, risk, screening, transactions};
+    use crate::handlers;
+    use crate::middleware::auth::CurrentUser;
+    use crate::models::account::{AccountPurpose, AccountStatus, CloseAccountRequest, CreateAccountRequest};
+    use crate::models::risk::{RiskPolicy, RiskReviewStatus, RiskSignals};
+    use crate::models::user::{CreateUserRequest, UpdateUserRequest, UserRole};
+    use crate::models::transaction::TransactionStatus;
+    use crate::models::watchlist::{
+        jaro_winkler, normalize_name, parse_watchlist_csv, ScreenedPerson, ScreeningCase, ScreeningCaseStatus,
+        ScreeningSubject, Watchlist, WatchlistEntry,
+    };
+    use crate::services::risk_service::RiskEngine;
+    use crate::services::screening_service::{self, WatchlistHandle};
+    use crate::utils::error::AppError;
+    use axum::{
+        extract::{Extension, State},
+        Json,
+    };
+    use chrono::NaiveDate;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const THRESHOLD: f64 = 0.92;
+
+    fn entry(name: &str, aliases: &[&str], date_of_birth: Option<&str>) -> WatchlistEntry {
+        WatchlistEntry {
+            id: String::new(),
+            name: name.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            date_of_birth: date_of_birth.map(|value| value.parse().unwrap()),
+            list: Some("TEST".to_string()),
+        }
+    }
+
+    fn date(value: &str) -> Option<NaiveDate> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("  José  García-Márquez "), "garcia jose marquez");
+        assert_eq!(normalize_name("GARCÍA MÁRQUEZ, José"), "garcia jose marquez");
+        assert_eq!(normalize_name("O'Brien"), "brien o");
+        assert_eq!(normalize_name(" -- "), "");
+    }
+
+    #[test]
+    fn test_jaro_winkler() {
+        assert_eq!(jaro_winkler("martha", "martha"), 1.0);
+        assert_eq!(jaro_winkler("abc", "xyz"), 0.0);
+        assert_eq!(jaro_winkler("", ""), 1.0);
+        assert!((jaro_winkler("martha", "marhta") - 0.961).abs() < 0.001);
+        assert!((jaro_winkler("dixon", "dicksonx") - 0.813).abs() < 0.001);
+        assert_eq!(jaro_winkler("dwayne", "duane"), jaro_winkler("duane", "dwayne"));
+    }
+
+    #[test]
+    fn test_parse_watchlist_csv() {
+        let entries = parse_watchlist_csv(
+            "# exported list\n\
+             id,name,aliases,date_of_birth,list\n\
+             \n\
+             SDN-1,\"Petrov, Ivan\",Ivan Petrow; I. Petrov,1970-03-01,SDN\n\
+             ,Anna Smirnova,,,\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "SDN-1");
+        assert_eq!(entries[0].name, "Petrov, Ivan");
+        assert_eq!(entries[0].aliases, vec!["Ivan Petrow", "I. Petrov"]);
+        assert_eq!(entries[0].date_of_birth, date("1970-03-01"));
+        assert_eq!(entries[0].list.as_deref(), Some("SDN"));
+        assert_eq!(entries[1].id, "");
+        assert!(entries[1].aliases.is_empty() && entries[1].list.is_none());
+
+        assert!(parse_watchlist_csv("id,aliases\n1,x\n").unwrap_err().contains("name"));
+        assert!(parse_watchlist_csv("name,list\nIvan Petrov\n").unwrap_err().contains("line 2"));
+        assert!(parse_watchlist_csv("name,date_of_birth\nIvan Petrov,1970-13-01\n").is_err());
+        assert!(parse_watchlist_csv("name\n\"\"\n").is_err());
+    }
+
+    #[test]
+    fn test_screen_matches_names_and_aliases() {
+        let watchlist = Watchlist::new(vec![
+            entry("Ivan Petrov", &["Ivan Petrow"], Some("1970-03-01")),
+            entry("Anna Smirnova", &[], None),
+        ]);
+        assert_eq!(watchlist.len(), 2);
+
+        let matches = watchlist.screen("PETROV, Iván", None, THRESHOLD);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry_id, "ivan petrov");
+        assert_eq!(matches[0].score, 1.0);
+
+        let matches = watchlist.screen("Ivan Petrof", date("1970-03-01"), THRESHOLD);
+        assert_eq!(matches[0].entry_name, "Ivan Petrov");
+        assert!(matches[0].score >= THRESHOLD && matches[0].score < 1.0);
+
+        // A different date of birth rules the entry out; an unknown one does not
+        assert!(watchlist.screen("Ivan Petrov", date("1985-06-15"), THRESHOLD).is_empty());
+        assert_eq!(watchlist.screen("Anna Smirnova", date("1985-06-15"), THRESHOLD).len(), 1);
+
+        assert!(watchlist.screen("Ivana Peters", None, THRESHOLD).is_empty());
+        assert!(watchlist.screen("", None, THRESHOLD).is_empty());
+        assert_eq!(watchlist.screen("Ivana Peters", None, 0.5)[0].entry_id, "ivan petrov");
+        assert!(Watchlist::default().screen("Ivan Petrov", None, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_watchlist_reload_keeps_list_on_error() {
+        let path = std::env::temp_dir().join(format!("watchlist_{}.json", Uuid::new_v4().simple()));
+        let write = |contents: serde_json::Value| std::fs::write(&path, contents.to_string()).unwrap();
+        write(json!([{ "name": "Ivan Petrov" }]));
+
+        let handle = WatchlistHandle::load(Some(path.to_string_lossy().into_owned())).unwrap();
+        assert_eq!(handle.current().len(), 1);
+
+        write(json!([{ "name": "Ivan Petrov" }, { "name": "Anna Smirnova", "aliases": ["Anya Smirnova"] }]));
+        assert_eq!(handle.reload().unwrap().len(), 2);
+        assert_eq!(handle.current().screen("Anya Smirnova", None, THRESHOLD).len(), 1);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(handle.reload(), Err(AppError::BadRequest(_))));
+        assert_eq!(handle.current().len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(WatchlistHandle::load(None).unwrap().reload(), Err(AppError::BadRequest(_))));
+    }
+
+    async fn open_case(client: &deadpool_postgres::Client, transaction_id: Uuid) -> ScreeningCase {
+        screening::get_cases(client, &ScreeningCaseStatus::Open)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|case| case.transaction_id == Some(transaction_id))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_watchlist_holds_transfers_and_blocks_registrations() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee_id, payee } = parties(&db, 10_000, 1).await;
+        let other_payee_id = create_test_user(&db).await;
+        let other_payee = create_funded_account(&db, other_payee_id, "USD", 1).await;
+
+        let mut client = db.pool.get().await.unwrap();
+        set_full_name(&client, payee_id, "Ivan Petrov").await;
+        set_full_name(&client, other_payee_id, "Iván Petrow").await;
+
+        let policy: RiskPolicy = serde_json::from_value(json!({
+            "rules": [{ "type": "amount_threshold", "min_amount": 5_000, "score": 100 }],
+        }))
+        .unwrap();
+        let engine = RiskEngine::from_policy(&policy);
+        let signals = RiskSignals::default();
+        let watchlist = Watchlist::new(vec![entry("Ivan Petrov", &["Ivan Petrow"], None)]);
+        let screening = Screening {
+            engine: &engine,
+            signals: &signals,
+            watchlist: &watchlist,
+            match_threshold: THRESHOLD,
+        };
+        let operator_id = payer_id;
+
+        // A hit holds the transfer with its funds reserved
+        let held = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 500),
+            &screening,
+        )
+        .await
+        .unwrap();
+        assert_eq!(held.status, TransactionStatus::UnderReview);
+        assert_eq!(balances(&client, payer).await, (10_000, 500));
+        let case = open_case(&client, held.id).await;
+        assert_eq!((case.subject, case.user_id), (ScreeningSubject::Transfer, Some(payee_id)));
+        assert_eq!(case.watchlist_match.entry_id, "ivan petrov");
+
+        // Clearing it lets the transfer complete, and the payee is not flagged again
+        let cleared = transactions::clear_screening_case(&mut client, operator_id, case.id, Some("Different person"))
+            .await
+            .unwrap();
+        assert_eq!(cleared.status, ScreeningCaseStatus::Cleared);
+        let completed = transactions::get_transaction_by_id(&client, held.id).await.unwrap();
+        assert_eq!(completed.status, TransactionStatus::Completed);
+        assert_eq!(accounts::get_account(&client, payee).await.unwrap().balance, 501);
+        let error = transactions::confirm_screening_case(&mut client, operator_id, case.id, None).await;
+        assert!(matches!(error, Err(AppError::Conflict(_))));
+
+        let again = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, payee, 300),
+            &screening,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.status, TransactionStatus::Completed);
+
+        // Confirming a hit fails the transfer and releases its funds
+        let held = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, other_payee, 700),
+            &screening,
+        )
+        .await
+        .unwrap();
+        let case = open_case(&client, held.id).await;
+        assert_eq!(case.watchlist_match.matched_name, "Ivan Petrow");
+        transactions::confirm_screening_case(&mut client, operator_id, case.id, None).await.unwrap();
+        let failed = transactions::get_transaction_by_id(&client, held.id).await.unwrap();
+        assert_eq!(failed.status, TransactionStatus::Failed);
+        assert_eq!(balances(&client, payer).await, (9_200, 0));
+
+        // A hit holds a transfer the risk engine would decline, and clearing it
+        // hands the transfer to a risk review
+        let held = transactions::create_screened_transaction(
+            &mut client,
+            payer_id,
+            &transfer(payer, other_payee, 6_000),
+            &screening,
+        )
+        .await
+        .unwrap();
+        assert_eq!(held.status, TransactionStatus::UnderReview);
+        let case = open_case(&client, held.id).await;
+        assert!(case.risk_assessment.is_some());
+        transactions::clear_screening_case(&mut client, operator_id, case.id, None).await.unwrap();
+        let under_review = transactions::get_transaction_by_id(&client, held.id).await.unwrap();
+        assert_eq!(under_review.status, TransactionStatus::UnderReview);
+        let review = risk::get_reviews(&client, &RiskReviewStatus::Open)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|review| review.transaction_id == held.id)
+            .unwrap();
+        transactions::reject_review(&mut client, operator_id, review.id, None).await.unwrap();
+        assert_eq!(balances(&client, payer).await, (9_200, 0));
+
+        // Registrations matching the list are refused until their case is cleared
+        let person = ScreenedPerson {
+            user_id: None,
+            email: format!("{}@example.com", Uuid::new_v4().simple()),
+            name: "Petrov Ivan".to_string(),
+            date_of_birth: None,
+        };
+        let error = screening_service::screen_registration(&client, &watchlist, THRESHOLD, &person).await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+        let case = screening::get_cases(&client, &ScreeningCaseStatus::Open)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|case| case.email.as_deref() == Some(person.email.as_str()))
+            .unwrap();
+        assert_eq!(case.subject, ScreeningSubject::Registration);
+        assert_eq!(case.transaction_id, None);
+        transactions::clear_screening_case(&mut client, operator_id, case.id, None).await.unwrap();
+        screening_service::screen_registration(&client, &watchlist, THRESHOLD, &person).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_registrations_and_name_changes_are_screened() {
+        let db = test_database();
+        let client = db.pool.get().await.unwrap();
+        // A listed name no earlier run has registered or cleared
+        let surname: String = Uuid::new_v4()
+            .simple()
+            .to_string()
+            .chars()
+            .map(|digit| (b'a' + digit.to_digit(16).unwrap() as u8) as char)
+            .collect();
+        let listed = format!("Ivan {}", surname);
+        let path = std::env::temp_dir().join(format!("watchlist_{}.json", surname));
+        std::fs::write(&path, json!([{ "name": listed }]).to_string()).unwrap();
+        let watchlist = WatchlistHandle::load(Some(path.to_string_lossy().into_owned())).unwrap();
+
+        let register = |username: &str| {
+            handlers::auth::register(
+                Extension(db.clone()),
+                Extension(watchlist.clone()),
+                State(test_config()),
+                Json(CreateUserRequest {
+                    email: format!("{}@example.com", Uuid::new_v4().simple()),
+                    username: username.to_string(),
+                    password: "password123".to_string(),
+                    full_name: None,
+                    date_of_birth: None,
+                }),
+            )
+        };
+
+        // Without a full name the username is screened instead
+        let error = register(&listed).await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+
+        let Json(user) = register(&format!("jane_{}", &Uuid::new_v4().simple().to_string()[..12])).await.unwrap();
+        let current_user = CurrentUser {
+            user_id: user.id,
+            username: user.username.clone(),
+            email: user.email.clone(),
+            role: UserRole::User,
+        };
+        let update = |full_name: Option<&str>, username: Option<&str>| {
+            handlers::users::update_profile(
+                current_user.clone(),
+                Extension(db.clone()),
+                Extension(watchlist.clone()),
+                State(test_config()),
+                Json(UpdateUserRequest {
+                    email: None,
+                    username: username.map(str::to_string),
+                    full_name: full_name.map(str::to_string),
+                }),
+            )
+        };
+
+        // Taking a listed name later is refused, as a full name or a username
+        let error = update(Some(&listed), None).await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+        let error = update(None, Some(&listed)).await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+        let cases: Vec<ScreeningCase> = screening::get_cases(&client, &ScreeningCaseStatus::Open)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|case| case.user_id == Some(user.id))
+            .collect();
+        assert_eq!(cases.len(), 2);
+        assert!(cases.iter().all(|case| case.subject == ScreeningSubject::Profile));
+
+        // Other names are unaffected
+        let Json(updated) = update(Some("Jane Doe"), None).await.unwrap();
+        assert_eq!((updated.full_name.as_deref(), updated.username), (Some("Jane Doe"), user.username));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Moves between a user's own accounts, and the sweep when one is closed,
+    /// make unscreened transfers. That is only safe while screening exempts
+    /// them and a sweep cannot reach anyone else.
+    #[tokio::test]
+    #[ignore]
+    async fn test_own_account_moves_are_not_screened() {
+        let db = test_database();
+        let Parties { payer_id, payer, payee, .. } = parties(&db, 1_000, 1).await;
+        let mut client = db.pool.get().await.unwrap();
+        set_full_name(&client, payer_id, "Ivan Petrov").await;
+        let savings = accounts::create_account(
+            &mut client,
+            payer_id,
+            &CreateAccountRequest {
+                currency: "USD".to_string(),
+                label: Some("Savings".to_string()),
+                purpose: AccountPurpose::Savings,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Neither a declining score nor a hit on the owner stops the move
+        let policy: RiskPolicy = serde_json::from_value(json!({
+            "rules": [{ "type": "amount_threshold", "min_amount": 1, "score": 100 }],
+        }))
+        .unwrap();
+        let screener = Screener::new(&policy, Watchlist::new(vec![entry("Ivan Petrov", &[], None)]));
+        let moved =
+            transactions::create_screened_transaction(&mut client, payer_id, &transfer(payer, savings.id, 400), &screener.screening())
+                .await
+                .unwrap();
+        assert_eq!(moved.status, TransactionStatus::Completed);
+        assert_eq!(balances(&client, payer).await, (600, 0));
+
+        let close = |sweep_account_id| CloseAccountRequest {
+            sweep_account_id: Some(sweep_account_id),
+            reason: None,
+        };
+        let error = accounts::close_account(&mut client, payer_id, payer, &close(payee)).await;
+        assert!(matches!(error, Err(AppError::Forbidden(_))));
+        let closed = accounts::close_account(&mut client, payer_id, payer, &close(savings.id)).await.unwrap();
+        assert_eq!(closed.status, AccountStatus::Closed);
+        assert_eq!(balances(&client, savings.id).await, (1_000, 0));
+    }
+}
+
+/// These tests need a migrated PostgreSQL database and are skipped by default.
+/// Run them with `DATABASE_URL=... cargo test -- --ignored`.
+#[cfg(test)]
+mod concurrency_tests {
+    use super::fixtures::{create_funded_account, create_test_user, test_database, transfer, withdrawal};
+    use crate::db::{accounts, transactions};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore]
+    async fn test_concurrent_withdrawals_never_overdraw() {
+        let db = test_database();
+        let user_id = create_test_user(&db).await;
+        let account_id = create_funded_account(&db, user_id, "USD", 1_000).await;
+
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let mut client = db.pool.get().await.unwrap();
+                    transactions::create_transaction(&mut client, user_id, &withdrawal(account_id, 100))
+                        .await
+                        .is_ok()
+                })
+            })
+            .collect();
+
+        let mut succeeded = 0;
+        for handle in handles {
+            if handle.await.unwrap() {
+                succeeded += 1;
+            }
+        }
+
+        let client = db.pool.get().await.unwrap();
+        let account = accounts::get_account(&client, account_id).await.unwrap();
+        assert_eq!(succeeded, 10);
+        assert_eq!(account.balance, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore]
+    async fn test_opposing_transfers_do_not_deadlock() {
+        let db = test_database();
+        let first_user = create_test_user(&db).await;
+        let second_user = create_test_user(&db).await;
+        let first = create_funded_account(&db, first_user, "USD", 5_000).await;
+        let second = create_funded_account(&db, second_user, "USD", 5_000).await;
+
+        let handles: Vec<_> = (0..40)
+            .map(|i| {
+                let db = db.clone();
+                let (user_id, source, destination) = if i % 2 == 0 {
+                    (first_user, first, second)
+                } else {
+                    (second_user, second, first)
+                };
+                tokio::spawn(async move {
+                    let mut client = db.pool.get().await.unwrap();
+                    transactions::create_transaction(&mut client, user_id, &transfer(source, destination, 10)).await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.await.unwrap().expect("transfer should not fail or deadlock");
+        }
+
+        let client = db.pool.get().await.unwrap();
+        let first_account = accounts::get_account(&client, first).await.unwrap();
+        let second_account = accounts::get_account(&client, second).await.unwrap();
+        assert_eq!(first_account.balance, 5_000);
+        assert_eq!(second_account.balance, 5_000);
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, TimeZone, Timelike, Utc};
+use std::str::FromStr;
+
+/// How far ahead to look for a match before deciding an expression such as
+/// `0 0 30 2 *` can never fire.
+const SEARCH_LIMIT_YEARS: i64 = 5;
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A standard five-field cron expression (minute, hour, day of month, month,
+/// day of week) evaluated in UTC. Fields accept `*`, single values, ranges
+/// (`1-5`), steps (`*/15`, `0-30/10`), comma-separated lists, and three-letter
+/// month and day names. As in Vixie cron, when both day fields are restricted
+/// a day matches if either does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "Cron expression must have 5 fields, found {}",
+                fields.len()
+            ));
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7, DAY_NAMES, 0)?;
+        // Both 0 and 7 mean Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(CronSchedule {
+            minutes: parse_field(minute, 0, 59, &[], 0)?,
+            hours: parse_field(hour, 0, 23, &[], 0)?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[], 0)?,
+            months: parse_field(month, 1, 12, MONTH_NAMES, 1)?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], first_name_value: u32) -> Result<u64, String> {
+    let parse_value = |value: &str| -> Result<u32, String> {
+        let parsed = match names.iter().position(|name| name.eq_ignore_ascii_case(value)) {
+            Some(index) => index as u32 + first_name_value,
+            None => value
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid cron value '{}'", value))?,
+        };
+        if parsed < min || parsed > max {
+            return Err(format!("Cron value {} is outside {}-{}", parsed, min, max));
+        }
+        Ok(parsed)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid cron step '{}'", step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                // `5/10` runs from 5 to the end of the range
+                None if step > 1 => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("Invalid cron range '{}'", range));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+impl CronSchedule {
+    /// The first whole minute strictly after `after` that matches the expression,
+    /// or `None` if it never matches within the next few years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let limit = after + Duration::days(366 * SEARCH_LIMIT_YEARS);
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+
+        while time <= limit {
+            if !matches(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+                continue;
+            }
+
+            if !self.matches_day(time) {
+                time = Utc
+                    .with_ymd_and_hms(time.year(), time.month(), time.day(), 0, 0, 0)
+                    .single()?
+                    + Duration::days(1);
+                continue;
+            }
+
+            if !matches(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+
+            if !matches(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+                continue;
+            }
+
+            return Some(time);
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = matches(self.days_of_month, time.day());
+        let day_of_week = matches(self.days_of_week, time.weekday().num_days_from_sunday());
+
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+}
+
+fn matches(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
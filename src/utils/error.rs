@@ -7,6 +7,9 @@ use serde_json::json;
 use thiserror::Error;
 use validator::ValidationErrors;
 
+use crate::models::limit::LimitExceeded;
+use crate::models::transaction_state::StateError;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Authentication error: {0}")]
@@ -21,9 +24,21 @@ pub enum AppError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Insufficient funds: {0}")]
+    InsufficientFunds(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(LimitExceeded),
+
     #[error("Validation error: {0}")]
     Validation(#[from] ValidationErrors),
 
+    #[error("Invalid state: {0}")]
+    InvalidState(#[from] StateError),
+
     #[error("Database error: {0}")]
     Database(String),
 
@@ -35,13 +50,17 @@ pub enum AppError {
     RateLimitExceeded,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::Auth(message) => (StatusCode::UNAUTHORIZED, message),
-            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
-            AppError::NotFound(message) => (StatusCode::NOT_FOUND, message),
-            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+impl AppError {
+    /// Status code and client-facing message for this error.
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            AppError::Auth(message) => (StatusCode::UNAUTHORIZED, message.clone()),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, message.clone()),
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, message.clone()),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message.clone()),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, message.clone()),
+            AppError::InsufficientFunds(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            AppError::LimitExceeded(_) => (StatusCode::UNPROCESSABLE_ENTITY, self.to_string()),
             AppError::Validation(errors) => {
                 let validation_errors = errors
                     .field_errors()
@@ -61,22 +80,44 @@ impl IntoResponse for AppError {
                     format!("Validation error: {:?}", validation_errors),
                 )
             }
-            AppError::Database(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
-            AppError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+            AppError::InvalidState(error) => match error {
+                StateError::InvalidTransition { .. } => (StatusCode::CONFLICT, error.to_string()),
+                StateError::UnknownStatus(_) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
+            },
+            AppError::Database(message) => (StatusCode::INTERNAL_SERVER_ERROR, message.clone()),
+            AppError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message.clone()),
             AppError::RateLimitExceeded => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Rate limit exceeded".to_string(),
             ),
-        };
+        }
+    }
 
-        let body = Json(json!({
+    /// JSON body returned to the client for this error.
+    pub fn body(&self) -> serde_json::Value {
+        let (status, error_message) = self.status_and_message();
+
+        let mut body = json!({
             "error": {
                 "message": error_message,
                 "code": status.as_u16()
             }
-        }));
+        });
+
+        // Say which limit was hit, so clients can show when to try again
+        if let AppError::LimitExceeded(exceeded) = self {
+            body["error"]["limit"] = json!(exceeded);
+        }
+
+        body
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, _) = self.status_and_message();
 
-        (status, body).into_response()
+        (status, Json(self.body())).into_response()
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::models::user::UserRole;
 use crate::utils::error::AppError;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
@@ -11,12 +12,15 @@ pub struct Claims {
     pub exp: i64,
     pub username: String,
     pub email: String,
+    #[serde(default)]
+    pub role: String,
 }
 
 pub fn create_token(
     user_id: Uuid,
     username: &str,
     email: &str,
+    role: &UserRole,
     config: &Config,
 ) -> Result<String, AppError> {
     let expiration = Utc::now()
@@ -29,6 +33,7 @@ pub fn create_token(
         exp: expiration,
         username: username.to_string(),
         email: email.to_string(),
+        role: role.to_string(),
     };
 
     encode(
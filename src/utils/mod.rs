@@ -1,2 +1,4 @@
+pub mod cron;
+pub mod currency;
 pub mod error;
 pub mod jwt; 
\ No newline at end of file
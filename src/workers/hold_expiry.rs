@@ -0,0 +1,40 @@
+use crate::db::{transactions, Database};
+use std::time::Duration;
+
+const BATCH_SIZE: i64 = 100;
+
+/// Periodically releases authorization holds that were neither captured nor
+/// voided before their expiry.
+pub async fn run(db: Database, interval_seconds: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        let mut client = match db.pool.get().await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Hold expiry worker could not get a connection: {}", e);
+                continue;
+            }
+        };
+
+        // Keep going while full batches come back so a backlog drains quickly
+        loop {
+            match transactions::expire_holds(&mut client, BATCH_SIZE).await {
+                Ok(expired) => {
+                    if expired > 0 {
+                        tracing::info!("Expired {} authorization holds", expired);
+                    }
+                    if (expired as i64) < BATCH_SIZE {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to expire authorization holds: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod hold_expiry;
+pub mod scheduled_transfers;
+pub mod webhook_delivery;
+pub mod outbox_relay;
@@ -0,0 +1,58 @@
+use crate::db::{outbox, Database};
+use crate::services::event_relay::{self, EventSink};
+use std::time::Duration;
+
+const BATCH_SIZE: i64 = 500;
+
+/// How often published events older than the retention period are deleted.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Publishes outbox events to the configured sinks as they are committed, and
+/// periodically deletes published events past their retention.
+pub async fn run(db: Database, interval_seconds: u64, retention_seconds: i64, sinks: Vec<Box<dyn EventSink>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+    let mut cleanup = tokio::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let mut client = match db.pool.get().await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        tracing::error!("Outbox relay could not get a connection: {}", e);
+                        continue;
+                    }
+                };
+
+                // Keep going while full batches come back so a backlog drains quickly
+                loop {
+                    match event_relay::relay_batch(&mut client, &sinks, BATCH_SIZE).await {
+                        Ok(published) => {
+                            if published > 0 {
+                                tracing::debug!("Published {} outbox events", published);
+                            }
+                            if (published as i64) < BATCH_SIZE {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to relay outbox events: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            _ = cleanup.tick() => {
+                let result = match db.pool.get().await {
+                    Ok(client) => outbox::delete_published_before(&client, retention_seconds).await,
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(deleted) if deleted > 0 => tracing::info!("Deleted {} published outbox events", deleted),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Failed to clean up the outbox: {}", e),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::db::{scheduled_transfers, Database};
+use crate::models::risk::RiskSignals;
+use crate::models::scheduled_transfer::RunStatus;
+use crate::services::screening_service::TransferScreener;
+use std::time::Duration;
+
+const BATCH_SIZE: usize = 100;
+
+/// Periodically executes scheduled transfers that have come due, screening
+/// them like transfers made by request.
+pub async fn run(
+    db: Database,
+    interval_seconds: u64,
+    retry_delay_seconds: i64,
+    max_failures: i32,
+    screener: TransferScreener,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        let mut client = match db.pool.get().await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Scheduled transfer worker could not get a connection: {}", e);
+                continue;
+            }
+        };
+
+        // No request is behind a scheduled transfer, so there are no signals to screen
+        let signals = RiskSignals::default();
+        let watchlist = screener.watchlist();
+        let screening = screener.screening(&watchlist, &signals);
+
+        // Each transfer commits on its own, so one failure does not hold up the rest
+        for _ in 0..BATCH_SIZE {
+            match scheduled_transfers::run_next_due(&mut client, retry_delay_seconds, max_failures, &screening).await {
+                Ok(Some(outcome)) => match outcome.run.status {
+                    RunStatus::Succeeded => tracing::info!(
+                        "Scheduled transfer {} executed as transaction {}",
+                        outcome.scheduled_transfer.id,
+                        outcome.run.transaction_id.unwrap_or_default()
+                    ),
+                    RunStatus::Failed => tracing::warn!(
+                        "Scheduled transfer {} failed ({}), now {}",
+                        outcome.scheduled_transfer.id,
+                        outcome.run.error.unwrap_or_default(),
+                        outcome.scheduled_transfer.status
+                    ),
+                },
+                Ok(None) => break,
+                // Failed transfers are recorded as runs, so an error here means the
+                // database itself is unavailable and the next tick should try again
+                Err(e) => {
+                    tracing::error!("Failed to execute scheduled transfer: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::db::{webhooks, Database};
+use crate::models::webhook::RetryPolicy;
+use crate::services::webhook_service::{WebhookSender, DELIVERY_TIMEOUT};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+const BATCH_SIZE: i64 = 50;
+
+/// Claimed deliveries are hidden from other workers for this long, comfortably
+/// more than one request can take.
+const LEASE_SECONDS: i64 = DELIVERY_TIMEOUT.as_secs() as i64 + 60;
+
+/// Periodically sends webhook deliveries that are due, retrying failures with
+/// exponential backoff.
+pub async fn run(db: Database, interval_seconds: u64, policy: RetryPolicy, sender: WebhookSender) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        // Keep going while full batches come back so a backlog drains quickly
+        loop {
+            let claimed = match db.pool.get().await {
+                Ok(client) => webhooks::claim_due_deliveries(&client, BATCH_SIZE, LEASE_SECONDS).await,
+                Err(e) => {
+                    tracing::error!("Webhook delivery worker could not get a connection: {}", e);
+                    break;
+                }
+            };
+            let claimed = match claimed {
+                Ok(claimed) => claimed,
+                Err(e) => {
+                    tracing::error!("Failed to claim webhook deliveries: {}", e);
+                    break;
+                }
+            };
+            let full_batch = claimed.len() as i64 == BATCH_SIZE;
+
+            // Send concurrently so one slow endpoint does not hold up the rest
+            let mut tasks = JoinSet::new();
+            for (delivery, endpoint) in claimed {
+                let db = db.clone();
+                let sender = sender.clone();
+                tasks.spawn(async move {
+                    let mut client = db.pool.get().await?;
+                    sender
+                        .deliver(&mut client, &endpoint, &delivery, &policy)
+                        .await
+                        .map(|attempt| (delivery, attempt))
+                });
+            }
+
+            while let Some(result) = tasks.join_next().await {
+                match result {
+                    Ok(Ok((delivery, attempt))) => match attempt.error {
+                        None => tracing::debug!("Delivered webhook {} on attempt {}", delivery.id, attempt.attempt),
+                        Some(error) => tracing::warn!(
+                            "Webhook delivery {} attempt {} failed: {}",
+                            delivery.id,
+                            attempt.attempt,
+                            error
+                        ),
+                    },
+                    Ok(Err(e)) => tracing::error!("Failed to record webhook delivery: {}", e),
+                    Err(e) => tracing::error!("Webhook delivery task panicked: {}", e),
+                }
+            }
+
+            if !full_batch {
+                break;
+            }
+        }
+    }
+}